@@ -45,6 +45,14 @@ pub enum Error {
         /// The invalid value provided.
         value: String,
     },
+    /// Time or duration is not parsable, or does not fit into the ROS time format.
+    #[error("bad time value `{value}`, {reason}")]
+    BadTime {
+        /// The invalid value provided.
+        value: String,
+        /// Reason for the failure.
+        reason: String,
+    },
 }
 
 /// Convenience type for shorter return value syntax of this crate's errors.
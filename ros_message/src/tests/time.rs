@@ -214,3 +214,178 @@ fn display_decimals() {
     let time = Duration { sec: -1, nsec: -1 };
     assert_eq!(format!("{}", time), "-1.000000001");
 }
+
+#[test]
+fn time_arithmetic_is_checked() {
+    let time = Time::from_seconds(1);
+    assert_eq!(time.checked_sub(Duration::from_nanos(1_000_000_001)), None);
+    assert_eq!(
+        time.checked_sub(Duration::from_seconds(1)),
+        Some(Time::new())
+    );
+    assert_eq!(Time::MAX.checked_add(Duration::from_nanos(1)), None);
+    assert_eq!(
+        Time::MAX.checked_add(Duration::from_nanos(-1)),
+        Some(Time {
+            sec: u32::MAX,
+            nsec: 999_999_998
+        })
+    );
+    assert_eq!(
+        Time::from_seconds(3).checked_duration_since(Time::from_seconds(1)),
+        Some(Duration::from_seconds(2))
+    );
+    assert_eq!(Time::new().checked_duration_since(Time::MAX), None);
+}
+
+#[test]
+fn time_arithmetic_saturates() {
+    let time = Time::from_seconds(1);
+    assert_eq!(time.saturating_sub(Duration::from_seconds(5)), Time::new());
+    assert_eq!(
+        time.saturating_add(Duration::MAX),
+        Time::from_nanos(Duration::MAX.nanos() + 1_000_000_000)
+    );
+    assert_eq!(Time::MAX.saturating_add(Duration::MAX), Time::MAX);
+    assert_eq!(Time::MAX.saturating_sub(Duration::MIN), Time::MAX);
+}
+
+#[test]
+fn duration_arithmetic_is_checked() {
+    assert_eq!(Duration::MAX.checked_add(Duration::from_nanos(1)), None);
+    assert_eq!(Duration::MIN.checked_sub(Duration::from_nanos(1)), None);
+    assert_eq!(
+        Duration::MAX.checked_add(Duration::MIN),
+        Some(Duration::from_seconds(-1))
+    );
+    assert_eq!(Duration::from_seconds(i32::MAX).checked_mul(2), None);
+    assert_eq!(Duration::from_seconds(3).checked_div(0), None);
+    assert_eq!(Duration::MAX.saturating_add(Duration::MAX), Duration::MAX);
+    assert_eq!(Duration::MIN.saturating_sub(Duration::MAX), Duration::MIN);
+    assert_eq!(Duration::MIN.saturating_mul(3), Duration::MIN);
+}
+
+#[test]
+fn duration_scales() {
+    let duration = Duration::from_nanos(1_500_000_000);
+    assert_eq!(duration * 3, Duration::from_nanos(4_500_000_000));
+    assert_eq!(-2 * duration, Duration::from_seconds(-3));
+    assert_eq!(duration / 2, Duration::from_nanos(750_000_000));
+    assert_eq!(-duration / 4, Duration::from_nanos(-375_000_000));
+    assert_eq!(
+        duration.mul_f64(1.0 / 3.0),
+        Duration::from_nanos(500_000_000)
+    );
+    assert_eq!(duration.div_f64(-0.5), Duration::from_seconds(-3));
+    assert_eq!(duration.div_duration_f64(Duration::from_seconds(3)), 0.5);
+}
+
+#[test]
+#[should_panic(expected = "overflow when multiplying duration")]
+fn duration_multiplication_panics_on_overflow() {
+    let _ = Duration::MAX * 2;
+}
+
+#[test]
+fn duration_parses() {
+    let parse = |value: &str| value.parse::<Duration>().unwrap();
+    assert_eq!(parse("1.5s"), Duration::from_nanos(1_500_000_000));
+    assert_eq!(parse("200ms"), Duration::from_nanos(200_000_000));
+    assert_eq!(parse(" -200 ms "), Duration::from_nanos(-200_000_000));
+    assert_eq!(parse("+3us"), Duration::from_nanos(3_000));
+    assert_eq!(parse("0.5ns"), Duration::new());
+    assert_eq!(parse("1.25min"), Duration::from_seconds(75));
+    assert_eq!(parse("2h"), Duration::from_seconds(7200));
+    assert_eq!(parse(".5"), Duration::from_nanos(500_000_000));
+    assert_eq!(parse("12.000000345"), Duration { sec: 12, nsec: 345 });
+    assert_eq!(
+        parse("-12.000000345"),
+        Duration {
+            sec: -12,
+            nsec: -345
+        }
+    );
+    assert_eq!(
+        parse("1.0000000019999999999999"),
+        Duration::from_nanos(1_000_000_001)
+    );
+    assert_eq!(parse(&Duration::MIN.to_string()), Duration::MIN);
+
+    for bad in &[
+        "",
+        "-",
+        ".",
+        "1.2.3",
+        "5 parsecs",
+        "1e9",
+        "2147483648s",
+        "--1",
+    ] {
+        assert!(bad.parse::<Duration>().is_err(), "{} should fail", bad);
+    }
+}
+
+#[test]
+fn time_parses() {
+    let parse = |value: &str| value.parse::<Time>().unwrap();
+    assert_eq!(parse("12.000000345"), Time { sec: 12, nsec: 345 });
+    assert_eq!(parse("1500ms"), Time::from_nanos(1_500_000_000));
+    assert_eq!(parse(&Time::MAX.to_string()), Time::MAX);
+    assert!("-1".parse::<Time>().is_err());
+    assert!("4294967296".parse::<Time>().is_err());
+}
+
+#[test]
+fn serde_round_trips() {
+    let time = Time { sec: 12, nsec: 345 };
+    let json = serde_json::to_string(&time).unwrap();
+    assert_eq!(json, r#"{"sec":12,"nsec":345}"#);
+    assert_eq!(serde_json::from_str::<Time>(&json).unwrap(), time);
+
+    let duration = Duration::from_nanos(-12_000_000_345);
+    let json = serde_json::to_string(&duration).unwrap();
+    assert_eq!(json, r#"{"sec":-12,"nsec":-345}"#);
+    assert_eq!(serde_json::from_str::<Duration>(&json).unwrap(), duration);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn time_converts_with_chrono() {
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::convert::TryFrom;
+
+    let time = Time { sec: 12, nsec: 345 };
+    let date: DateTime<Utc> = time.into();
+    assert_eq!(date, Utc.timestamp_opt(12, 345).unwrap());
+    assert_eq!(Time::try_from(date).unwrap(), time);
+
+    let leap_second = Utc.timestamp_opt(12, 1_000_000_345).unwrap();
+    assert_eq!(
+        Time::try_from(leap_second).unwrap(),
+        Time { sec: 13, nsec: 345 }
+    );
+
+    let before_epoch = Utc.timestamp_opt(-1, 999_999_999).unwrap();
+    assert!(Time::try_from(before_epoch).is_err());
+    let after_max = Utc.timestamp_opt(i64::from(u32::MAX) + 1, 0).unwrap();
+    assert!(Time::try_from(after_max).is_err());
+    assert_eq!(
+        Time::try_from(DateTime::<Utc>::from(Time::MAX)).unwrap(),
+        Time::MAX
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn duration_converts_with_chrono() {
+    use std::convert::TryFrom;
+
+    let duration = Duration::from_nanos(-12_000_000_345);
+    let chrono_duration: chrono::Duration = duration.into();
+    assert_eq!(
+        chrono_duration,
+        chrono::Duration::nanoseconds(-12_000_000_345)
+    );
+    assert_eq!(Duration::try_from(chrono_duration).unwrap(), duration);
+    assert!(Duration::try_from(chrono::Duration::days(30_000)).is_err());
+}
@@ -1,3 +1,4 @@
+use crate::{Error, Result};
 use serde_derive::{Deserialize, Serialize};
 use std::cmp;
 use std::convert::TryInto;
@@ -5,10 +6,95 @@ use std::fmt;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
 use std::ops;
+use std::str::FromStr;
 use std::time;
 
 const BILLION: i64 = 1_000_000_000;
 
+const MAX_TIME_NANOS: i128 = (u32::MAX as i128 + 1) * BILLION as i128 - 1;
+const MIN_DURATION_NANOS: i128 = (i32::MIN as i128 - 1) * BILLION as i128 + 1;
+const MAX_DURATION_NANOS: i128 = (i32::MAX as i128 + 1) * BILLION as i128 - 1;
+
+fn time_from_nanos(t: i128) -> Option<Time> {
+    if !(0..=MAX_TIME_NANOS).contains(&t) {
+        return None;
+    }
+    Some(Time {
+        sec: (t / i128::from(BILLION)) as u32,
+        nsec: (t % i128::from(BILLION)) as u32,
+    })
+}
+
+fn duration_from_nanos(t: i128) -> Option<Duration> {
+    if !(MIN_DURATION_NANOS..=MAX_DURATION_NANOS).contains(&t) {
+        return None;
+    }
+    Some(Duration {
+        sec: (t / i128::from(BILLION)) as i32,
+        nsec: (t % i128::from(BILLION)) as i32,
+    })
+}
+
+fn bad_time(value: &str, reason: &str) -> Error {
+    Error::BadTime {
+        value: value.into(),
+        reason: reason.into(),
+    }
+}
+
+/// Parses values like `1.5s`, `200ms` or `12.000000345` into nanoseconds.
+///
+/// Values without units are in seconds. Digits past nanosecond precision get truncated.
+fn parse_nanos(value: &str) -> Result<i128> {
+    let trimmed = value.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let split_point = unsigned
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(unsigned.len());
+    let (number, unit) = unsigned.split_at(split_point);
+    let multiplier: i128 = match unit.trim() {
+        "" | "s" | "sec" => 1_000_000_000,
+        "ms" => 1_000_000,
+        "us" | "\u{b5}s" => 1_000,
+        "ns" => 1,
+        "min" => 60_000_000_000,
+        "h" => 3_600_000_000_000,
+        _ => return Err(bad_time(value, "unknown unit")),
+    };
+    let (whole, fraction) = match number.find('.') {
+        Some(point) => (&number[..point], &number[point + 1..]),
+        None => (number, ""),
+    };
+    if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return Err(bad_time(value, "expected a decimal number"));
+    }
+    let whole: i128 = if whole.is_empty() {
+        0
+    } else {
+        whole
+            .parse()
+            .map_err(|_| bad_time(value, "number is out of range"))?
+    };
+    let fraction = &fraction[..fraction.len().min(18)];
+    let digits: i128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction
+            .parse()
+            .map_err(|_| bad_time(value, "expected a decimal number"))?
+    };
+    let nanos = whole
+        .checked_mul(multiplier)
+        .and_then(|nanos| {
+            nanos.checked_add(digits * multiplier / 10i128.pow(fraction.len() as u32))
+        })
+        .ok_or_else(|| bad_time(value, "number is out of range"))?;
+    Ok(if negative { -nanos } else { nanos })
+}
+
 /// ROS representation of time, with nanosecond precision
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, Eq)]
 pub struct Time {
@@ -31,6 +117,12 @@ impl Default for Time {
 }
 
 impl Time {
+    /// Latest time that fits into the ROS time format.
+    pub const MAX: Time = Time {
+        sec: u32::MAX,
+        nsec: 999_999_999,
+    };
+
     /// Creates a new time of zero value.
     ///
     /// # Examples
@@ -102,6 +194,113 @@ impl Time {
     pub fn seconds(self) -> f64 {
         f64::from(self.sec) + f64::from(self.nsec) / BILLION as f64
     }
+
+    /// Adds a duration, returning `None` if the result does not fit into the ROS time format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{Duration, Time};
+    /// assert_eq!(
+    ///     Time::from_seconds(5).checked_add(Duration::from_seconds(-2)),
+    ///     Some(Time::from_seconds(3)),
+    /// );
+    /// assert_eq!(Time::MAX.checked_add(Duration::from_nanos(1)), None);
+    /// ```
+    #[inline]
+    pub fn checked_add(self, rhs: Duration) -> Option<Time> {
+        time_from_nanos(i128::from(self.nanos()) + i128::from(rhs.nanos()))
+    }
+
+    /// Subtracts a duration, returning `None` if the result does not fit into the ROS time format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{Duration, Time};
+    /// assert_eq!(
+    ///     Time::from_seconds(5).checked_sub(Duration::from_seconds(2)),
+    ///     Some(Time::from_seconds(3)),
+    /// );
+    /// assert_eq!(Time::from_seconds(1).checked_sub(Duration::from_seconds(2)), None);
+    /// ```
+    #[inline]
+    pub fn checked_sub(self, rhs: Duration) -> Option<Time> {
+        time_from_nanos(i128::from(self.nanos()) - i128::from(rhs.nanos()))
+    }
+
+    /// Returns the duration since an earlier time, or `None` if it does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{Duration, Time};
+    /// assert_eq!(
+    ///     Time::from_seconds(2).checked_duration_since(Time::from_seconds(5)),
+    ///     Some(Duration::from_seconds(-3)),
+    /// );
+    /// assert_eq!(Time::MAX.checked_duration_since(Time::new()), None);
+    /// ```
+    #[inline]
+    pub fn checked_duration_since(self, earlier: Time) -> Option<Duration> {
+        duration_from_nanos(i128::from(self.nanos()) - i128::from(earlier.nanos()))
+    }
+
+    /// Adds a duration, stopping at zero or the maximum time instead of overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{Duration, Time};
+    /// assert_eq!(Time::MAX.saturating_add(Duration::from_seconds(1)), Time::MAX);
+    /// assert_eq!(Time::from_seconds(1).saturating_add(Duration::from_seconds(-2)), Time::new());
+    /// ```
+    #[inline]
+    pub fn saturating_add(self, rhs: Duration) -> Time {
+        saturate_time(i128::from(self.nanos()) + i128::from(rhs.nanos()))
+    }
+
+    /// Subtracts a duration, stopping at zero or the maximum time instead of overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{Duration, Time};
+    /// assert_eq!(Time::from_seconds(1).saturating_sub(Duration::from_seconds(2)), Time::new());
+    /// ```
+    #[inline]
+    pub fn saturating_sub(self, rhs: Duration) -> Time {
+        saturate_time(i128::from(self.nanos()) - i128::from(rhs.nanos()))
+    }
+}
+
+fn saturate_time(t: i128) -> Time {
+    if t < 0 {
+        Time::new()
+    } else if t > MAX_TIME_NANOS {
+        Time::MAX
+    } else {
+        Time::from_nanos(t as i64)
+    }
+}
+
+impl FromStr for Time {
+    type Err = Error;
+
+    /// Parses times in the `sec.nsec` format, with an optional unit like `s` or `ms`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Time;
+    /// assert_eq!("12.000000345".parse::<Time>().unwrap(), Time { sec: 12, nsec: 345 });
+    /// assert_eq!("1500ms".parse::<Time>().unwrap(), Time { sec: 1, nsec: 500_000_000 });
+    /// assert!("-1".parse::<Time>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        time_from_nanos(parse_nanos(s)?)
+            .ok_or_else(|| bad_time(s, "time does not fit into the ROS time format"))
+    }
 }
 
 fn display_nanos(nanos: &str, f: &mut Formatter<'_>) -> fmt::Result {
@@ -197,6 +396,18 @@ impl fmt::Display for Duration {
 }
 
 impl Duration {
+    /// Most negative duration that fits into the ROS duration format.
+    pub const MIN: Duration = Duration {
+        sec: i32::MIN,
+        nsec: -999_999_999,
+    };
+
+    /// Longest duration that fits into the ROS duration format.
+    pub const MAX: Duration = Duration {
+        sec: i32::MAX,
+        nsec: 999_999_999,
+    };
+
     /// Creates a new duration of zero value.
     ///
     /// # Examples
@@ -272,6 +483,202 @@ impl Duration {
     pub fn seconds(self) -> f64 {
         f64::from(self.sec) + f64::from(self.nsec) / BILLION as f64
     }
+
+    /// Adds two durations, returning `None` if the result does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(
+    ///     Duration::from_seconds(1).checked_add(Duration::from_seconds(2)),
+    ///     Some(Duration::from_seconds(3)),
+    /// );
+    /// assert_eq!(Duration::MAX.checked_add(Duration::from_nanos(1)), None);
+    /// ```
+    #[inline]
+    pub fn checked_add(self, rhs: Duration) -> Option<Duration> {
+        duration_from_nanos(i128::from(self.nanos()) + i128::from(rhs.nanos()))
+    }
+
+    /// Subtracts two durations, returning `None` if the result does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(
+    ///     Duration::from_seconds(1).checked_sub(Duration::from_seconds(2)),
+    ///     Some(Duration::from_seconds(-1)),
+    /// );
+    /// assert_eq!(Duration::MIN.checked_sub(Duration::from_nanos(1)), None);
+    /// ```
+    #[inline]
+    pub fn checked_sub(self, rhs: Duration) -> Option<Duration> {
+        duration_from_nanos(i128::from(self.nanos()) - i128::from(rhs.nanos()))
+    }
+
+    /// Multiplies the duration, returning `None` if the result does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(
+    ///     Duration::from_nanos(1_500_000_000).checked_mul(-3),
+    ///     Some(Duration::from_nanos(-4_500_000_000)),
+    /// );
+    /// assert_eq!(Duration::MAX.checked_mul(2), None);
+    /// ```
+    #[inline]
+    pub fn checked_mul(self, rhs: i32) -> Option<Duration> {
+        duration_from_nanos(i128::from(self.nanos()) * i128::from(rhs))
+    }
+
+    /// Divides the duration, returning `None` when dividing by zero.
+    ///
+    /// The result is rounded towards zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::from_seconds(1).checked_div(3), Some(Duration::from_nanos(333_333_333)));
+    /// assert_eq!(Duration::from_seconds(1).checked_div(0), None);
+    /// ```
+    #[inline]
+    pub fn checked_div(self, rhs: i32) -> Option<Duration> {
+        if rhs == 0 {
+            return None;
+        }
+        duration_from_nanos(i128::from(self.nanos()) / i128::from(rhs))
+    }
+
+    /// Adds two durations, stopping at the minimum or maximum duration instead of overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::MAX.saturating_add(Duration::from_seconds(1)), Duration::MAX);
+    /// ```
+    #[inline]
+    pub fn saturating_add(self, rhs: Duration) -> Duration {
+        saturate_duration(i128::from(self.nanos()) + i128::from(rhs.nanos()))
+    }
+
+    /// Subtracts two durations, stopping at the minimum or maximum duration instead of overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::MIN.saturating_sub(Duration::from_seconds(1)), Duration::MIN);
+    /// ```
+    #[inline]
+    pub fn saturating_sub(self, rhs: Duration) -> Duration {
+        saturate_duration(i128::from(self.nanos()) - i128::from(rhs.nanos()))
+    }
+
+    /// Multiplies the duration, stopping at the minimum or maximum duration instead of overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::MAX.saturating_mul(-2), Duration::MIN);
+    /// ```
+    #[inline]
+    pub fn saturating_mul(self, rhs: i32) -> Duration {
+        saturate_duration(i128::from(self.nanos()) * i128::from(rhs))
+    }
+
+    /// Multiplies the duration by a floating point number, rounding to the nearest nanosecond.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is not finite or does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::from_seconds(2).mul_f64(0.25), Duration::from_nanos(500_000_000));
+    /// ```
+    #[inline]
+    pub fn mul_f64(self, rhs: f64) -> Duration {
+        let nanos = (self.nanos() as f64 * rhs).round();
+        assert!(
+            nanos.is_finite()
+                && nanos >= MIN_DURATION_NANOS as f64
+                && nanos <= MAX_DURATION_NANOS as f64,
+            "overflow when multiplying duration by float"
+        );
+        Duration::from_nanos(nanos as i64)
+    }
+
+    /// Divides the duration by a floating point number, rounding to the nearest nanosecond.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is not finite or does not fit into a duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!(Duration::from_seconds(1).div_f64(4.0), Duration::from_nanos(250_000_000));
+    /// ```
+    #[inline]
+    pub fn div_f64(self, rhs: f64) -> Duration {
+        self.mul_f64(1.0 / rhs)
+    }
+
+    /// Returns how many times the other duration fits into this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// let period = Duration::from_nanos(400_000_000);
+    /// assert_eq!(Duration::from_seconds(1).div_duration_f64(period), 2.5);
+    /// ```
+    #[inline]
+    pub fn div_duration_f64(self, rhs: Duration) -> f64 {
+        self.nanos() as f64 / rhs.nanos() as f64
+    }
+}
+
+fn saturate_duration(t: i128) -> Duration {
+    if t < MIN_DURATION_NANOS {
+        Duration::MIN
+    } else if t > MAX_DURATION_NANOS {
+        Duration::MAX
+    } else {
+        Duration::from_nanos(t as i64)
+    }
+}
+
+impl FromStr for Duration {
+    type Err = Error;
+
+    /// Parses durations like `1.5s`, `-200ms` or `12.000000345`, which is in seconds.
+    ///
+    /// Supported units are `ns`, `us`, `ms`, `s`, `min` and `h`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Duration;
+    /// assert_eq!("1.5s".parse::<Duration>().unwrap(), Duration::from_nanos(1_500_000_000));
+    /// assert_eq!("-200ms".parse::<Duration>().unwrap(), Duration::from_nanos(-200_000_000));
+    /// assert_eq!("12.000000345".parse::<Duration>().unwrap(), Duration { sec: 12, nsec: 345 });
+    /// assert!("12 parsecs".parse::<Duration>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        duration_from_nanos(parse_nanos(s)?)
+            .ok_or_else(|| bad_time(s, "duration does not fit into the ROS duration format"))
+    }
 }
 
 impl cmp::PartialEq for Duration {
@@ -327,6 +734,29 @@ impl ops::Sub<Duration> for Duration {
     }
 }
 
+impl ops::Mul<i32> for Duration {
+    type Output = Duration;
+    fn mul(self, rhs: i32) -> Self::Output {
+        self.checked_mul(rhs)
+            .expect("overflow when multiplying duration by scalar")
+    }
+}
+
+impl ops::Mul<Duration> for i32 {
+    type Output = Duration;
+    fn mul(self, rhs: Duration) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl ops::Div<i32> for Duration {
+    type Output = Duration;
+    fn div(self, rhs: i32) -> Self::Output {
+        self.checked_div(rhs)
+            .expect("divide by zero error when dividing duration by scalar")
+    }
+}
+
 impl ops::Neg for Duration {
     type Output = Duration;
     fn neg(self) -> Self::Output {
@@ -362,3 +792,61 @@ impl From<Duration> for time::Duration {
         Self::new((other.sec + extra_sec).try_into().unwrap(), nsec as u32)
     }
 }
+
+#[cfg(feature = "chrono")]
+impl From<Time> for chrono::DateTime<chrono::Utc> {
+    fn from(other: Time) -> Self {
+        use chrono::TimeZone;
+        chrono::Utc
+            .timestamp_opt(other.sec.into(), other.nsec)
+            .single()
+            .expect("All ROS times are valid dates")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::convert::TryFrom<chrono::DateTime<chrono::Utc>> for Time {
+    type Error = Error;
+
+    fn try_from(other: chrono::DateTime<chrono::Utc>) -> Result<Self> {
+        // Leap seconds are represented by nanoseconds past one billion
+        let nanos = i128::from(other.timestamp()) * i128::from(BILLION)
+            + i128::from(other.timestamp_subsec_nanos());
+        if nanos < 0 {
+            return Err(bad_time(
+                &other.to_rfc3339(),
+                "dates before 1970 are not supported by the ROS time format",
+            ));
+        }
+        time_from_nanos(nanos).ok_or_else(|| {
+            bad_time(
+                &other.to_rfc3339(),
+                "dates after 2106 are not supported by the ROS time format",
+            )
+        })
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Duration> for chrono::Duration {
+    fn from(other: Duration) -> Self {
+        chrono::Duration::nanoseconds(other.nanos())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::convert::TryFrom<chrono::Duration> for Duration {
+    type Error = Error;
+
+    fn try_from(other: chrono::Duration) -> Result<Self> {
+        other
+            .num_nanoseconds()
+            .and_then(|nanos| duration_from_nanos(nanos.into()))
+            .ok_or_else(|| {
+                bad_time(
+                    &other.to_string(),
+                    "duration does not fit into the ROS duration format",
+                )
+            })
+    }
+}
@@ -1,9 +1,10 @@
 use crate::util::FAILED_TO_LOCK;
 use crossbeam::sync::{Parker, Unparker};
+use log::warn;
 use ros_message::{Duration, Time};
 use std::cell::Cell;
 use std::cmp;
-use std::collections::BinaryHeap;
+use std::collections::{BTreeMap, BinaryHeap};
 use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
@@ -20,15 +21,37 @@ impl Delay {
         Self { clock, delay }
     }
 
+    /// Sleeps for the delay, ending early if the clock jumps backwards meanwhile.
     pub fn sleep(self) {
         self.clock.sleep(self.delay);
     }
 }
 
+/// What a `Rate` does after a cycle overran its deadline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverrunPolicy {
+    /// Keeps the original schedule, running the missed cycles without sleeping until the loop
+    /// has caught up.
+    #[default]
+    CatchUp,
+    /// Drops the missed cycles, sleeping until the next deadline of the original schedule.
+    SkipMissed,
+}
+
 pub struct Rate {
     clock: Arc<dyn Clock>,
     next: Cell<Time>,
     delay: Duration,
+    cycle_time: Cell<Duration>,
+    overruns: Cell<u64>,
+    policy: OverrunPolicy,
+    warning: Option<OverrunWarning>,
+    logger: Option<Box<dyn Fn(String) + Send>>,
+}
+
+struct OverrunWarning {
+    period: Duration,
+    next: Cell<Time>,
 }
 
 impl Rate {
@@ -38,13 +61,176 @@ impl Rate {
             clock,
             next: Cell::new(start),
             delay,
+            cycle_time: Cell::new(Duration::default()),
+            overruns: Cell::new(0),
+            policy: OverrunPolicy::default(),
+            warning: None,
+            logger: None,
         }
     }
 
+    /// Sets the function that overrun warnings get written with, instead of the `log` crate.
+    pub(crate) fn with_logger(mut self, logger: impl Fn(String) + Send + 'static) -> Self {
+        self.logger = Some(Box::new(logger));
+        self
+    }
+
+    #[inline]
+    pub fn with_overrun_policy(mut self, policy: OverrunPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Warns about missed deadlines, at most once per period.
+    ///
+    /// Rates created by a node send the warnings to `/rosout`.
+    #[inline]
+    pub fn warn_on_overrun(mut self, period: Duration) -> Self {
+        self.warning = Some(OverrunWarning {
+            period,
+            next: Cell::new(Time::default()),
+        });
+        self
+    }
+
+    /// Sleeps until the end of the current cycle.
+    ///
+    /// If the clock jumps backwards, the rate restarts from the new time instead of waiting
+    /// for the time of the old cycle to come around again.
     pub fn sleep(&self) {
-        let new_time = self.next.get() + self.delay;
+        let now = self.clock.now();
+        if now < self.next.get() {
+            self.next.set(now);
+        }
+        self.cycle_time.set(now - self.next.get());
+        let mut new_time = self.next.get() + self.delay;
+        if now > new_time {
+            self.overrun(now, new_time);
+            if self.policy == OverrunPolicy::SkipMissed && self.delay > Duration::default() {
+                let missed = (now - new_time).nanos() / self.delay.nanos() + 1;
+                new_time = new_time + Duration::from_nanos(self.delay.nanos() * missed);
+            }
+        }
         self.next.set(new_time);
         self.clock.wait_until(new_time);
+        let now = self.clock.now();
+        if now < new_time {
+            self.next.set(now);
+        }
+    }
+
+    /// Starts a new cycle at the current time, dropping any time owed to earlier cycles.
+    pub fn reset(&self) {
+        self.next.set(self.clock.now());
+    }
+
+    /// Time between the start of the last cycle and the call to `sleep` that ended it.
+    #[inline]
+    pub fn cycle_time(&self) -> Duration {
+        self.cycle_time.get()
+    }
+
+    #[inline]
+    pub fn expected_cycle_time(&self) -> Duration {
+        self.delay
+    }
+
+    /// Number of cycles that ended after their deadline.
+    #[inline]
+    pub fn overruns(&self) -> u64 {
+        self.overruns.get()
+    }
+
+    fn overrun(&self, now: Time, deadline: Time) {
+        self.overruns.set(self.overruns.get() + 1);
+        let warning = match &self.warning {
+            Some(warning) if now >= warning.next.get() => warning,
+            _ => return,
+        };
+        warning.next.set(now + warning.period);
+        let message = format!(
+            "Loop with cycle time {:.6}s missed its deadline by {:.6}s ({} missed so far)",
+            self.delay.seconds(),
+            (now - deadline).seconds(),
+            self.overruns.get()
+        );
+        match &self.logger {
+            Some(logger) => logger(message),
+            None => warn!("{}", message),
+        }
+    }
+}
+
+/// Jump of a clock, from the time before the jump to the time after it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeJump {
+    pub previous: Time,
+    pub current: Time,
+}
+
+impl TimeJump {
+    #[inline]
+    pub fn delta(&self) -> Duration {
+        self.current - self.previous
+    }
+
+    #[inline]
+    pub fn is_backward(&self) -> bool {
+        self.current < self.previous
+    }
+}
+
+/// Smallest jumps that a jump callback gets called for, with `None` ignoring that direction.
+///
+/// Both thresholds are magnitudes, so the backward one is positive too.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct JumpThreshold {
+    pub min_forward: Option<Duration>,
+    pub min_backward: Option<Duration>,
+}
+
+impl JumpThreshold {
+    /// Threshold matching every backward jump and no forward jumps.
+    pub fn backward() -> Self {
+        Self {
+            min_forward: None,
+            min_backward: Some(Duration::default()),
+        }
+    }
+
+    pub fn matches(&self, jump: &TimeJump) -> bool {
+        if jump.is_backward() {
+            matches!(self.min_backward, Some(min) if -jump.delta() >= min)
+        } else {
+            matches!(self.min_forward, Some(min) if jump.delta() >= min)
+        }
+    }
+}
+
+pub type JumpCallback = Arc<dyn Fn(&TimeJump) + Send + Sync>;
+
+/// Registration of a jump callback, which gets removed when the handle is dropped.
+pub struct JumpHandle {
+    clock: Arc<dyn Clock>,
+    id: Option<usize>,
+}
+
+impl JumpHandle {
+    pub(crate) fn new(
+        clock: Arc<dyn Clock>,
+        threshold: JumpThreshold,
+        callback: JumpCallback,
+    ) -> Self {
+        let id = clock.add_jump_callback(threshold, callback);
+        Self { clock, id }
+    }
+}
+
+impl Drop for JumpHandle {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.clock.remove_jump_callback(id);
+        }
     }
 }
 
@@ -53,6 +239,31 @@ pub trait Clock: Send + Sync {
     fn sleep(&self, d: Duration);
     fn wait_until(&self, t: Time);
     fn await_init(&self) {}
+
+    /// Blocks until the given time, or until the parker gets unparked.
+    ///
+    /// Wakeups can be spurious, so callers need to check the time afterwards. The default
+    /// implementation assumes that the clock moves at the speed of wall time.
+    fn park_until(&self, t: Time, parker: &Parker) {
+        let remaining = t - self.now();
+        if remaining > Duration::default() {
+            parker.park_timeout(remaining.into());
+        }
+    }
+
+    /// Calls the callback whenever the clock jumps by at least the threshold.
+    ///
+    /// Returns an ID for removing the callback, or `None` for clocks that never jump, which is
+    /// what the default implementation assumes.
+    fn add_jump_callback(
+        &self,
+        _threshold: JumpThreshold,
+        _callback: JumpCallback,
+    ) -> Option<usize> {
+        None
+    }
+
+    fn remove_jump_callback(&self, _id: usize) {}
 }
 
 #[derive(Clone, Default)]
@@ -126,19 +337,57 @@ pub struct SimData {
 #[derive(Default)]
 pub struct SimulatedClock {
     pub data: Mutex<SimData>,
+    jump_callbacks: Mutex<JumpCallbacks>,
+}
+
+#[derive(Default)]
+struct JumpCallbacks {
+    callbacks: BTreeMap<usize, (JumpThreshold, JumpCallback)>,
+    next_id: usize,
 }
 
 impl SimulatedClock {
+    /// Moves the clock to the given time, which can be in the past.
+    ///
+    /// Jumping backwards ends all pending waits early, as their times could otherwise take
+    /// arbitrarily long to come around again. Jump callbacks get called afterwards, except
+    /// when the clock gets its first time.
     pub fn trigger(&self, time: Time) {
-        let mut data = self.data.lock().expect(FAILED_TO_LOCK);
-        data.current = time;
-        loop {
-            match data.timeouts.peek() {
-                None => break,
-                Some(next) if next.timestamp > data.current => break,
-                _ => {}
+        let previous = {
+            let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+            let previous = data.current;
+            data.current = time;
+            if time < previous {
+                data.timeouts.clear();
             }
-            data.timeouts.pop();
+            loop {
+                match data.timeouts.peek() {
+                    None => break,
+                    Some(next) if next.timestamp > data.current => break,
+                    _ => {}
+                }
+                data.timeouts.pop();
+            }
+            previous
+        };
+        if previous == Time::default() {
+            return;
+        }
+        let jump = TimeJump {
+            previous,
+            current: time,
+        };
+        let callbacks = self
+            .jump_callbacks
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .callbacks
+            .values()
+            .filter(|(threshold, _)| threshold.matches(&jump))
+            .map(|(_, callback)| Arc::clone(callback))
+            .collect::<Vec<_>>();
+        for callback in callbacks {
+            callback(&jump);
         }
     }
 }
@@ -160,17 +409,21 @@ impl Clock for SimulatedClock {
 
     #[inline]
     fn wait_until(&self, timestamp: Time) {
-        let parker = Parker::new();
+        self.park_until(timestamp, &Parker::new())
+    }
+
+    fn park_until(&self, timestamp: Time, parker: &Parker) {
         let unparker = parker.unparker().clone();
         {
-            self.data
-                .lock()
-                .expect(FAILED_TO_LOCK)
-                .timeouts
-                .push(Timeout {
-                    timestamp,
-                    unparker,
-                });
+            let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+            // The time could have been reached since the caller last checked it
+            if timestamp <= data.current {
+                return;
+            }
+            data.timeouts.push(Timeout {
+                timestamp,
+                unparker,
+            });
         }
         parker.park()
     }
@@ -180,4 +433,20 @@ impl Clock for SimulatedClock {
             self.wait_until(Time::from_nanos(1));
         }
     }
+
+    fn add_jump_callback(&self, threshold: JumpThreshold, callback: JumpCallback) -> Option<usize> {
+        let mut jump_callbacks = self.jump_callbacks.lock().expect(FAILED_TO_LOCK);
+        let id = jump_callbacks.next_id;
+        jump_callbacks.next_id += 1;
+        jump_callbacks.callbacks.insert(id, (threshold, callback));
+        Some(id)
+    }
+
+    fn remove_jump_callback(&self, id: usize) {
+        self.jump_callbacks
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .callbacks
+            .remove(&id);
+    }
 }
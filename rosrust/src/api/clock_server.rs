@@ -0,0 +1,255 @@
+use super::clock::SimulatedClock;
+use super::error::{ErrorKind, Result};
+use super::raii::Publisher;
+use crate::msg::rosgraph_msgs::Clock as ClockMsg;
+use crate::util::FAILED_TO_LOCK;
+use crossbeam::sync::{Parker, Unparker};
+use error_chain::bail;
+use log::error;
+use ros_message::{Duration, Time};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
+
+// Bounds the echoes kept around when the `/clock` subscriber drops messages
+const MAX_PENDING_ECHOES: usize = 1024;
+
+/// Settings of a clock server, with defaults matching a real time simulation.
+#[derive(Clone, Debug)]
+pub struct ClockServerOptions {
+    /// Simulated time that the server starts at.
+    pub start: Time,
+    /// How often the time gets published, per second of wall time.
+    pub publish_rate: f64,
+    /// Seconds of simulated time that pass per second of wall time, which must not be negative.
+    pub real_time_factor: f64,
+    /// Whether the server starts paused, only moving forward with `step`.
+    pub paused: bool,
+}
+
+impl Default for ClockServerOptions {
+    fn default() -> Self {
+        Self {
+            start: Time::default(),
+            publish_rate: 100.0,
+            real_time_factor: 1.0,
+            paused: false,
+        }
+    }
+}
+
+/// Times that clock servers of a node have already set its clock to.
+///
+/// The node skips these when they come back on `/clock`, so late echoes don't move its clock
+/// backwards, while times published by anyone else still get through.
+#[derive(Default)]
+pub(crate) struct ClockEchoes {
+    pending: Mutex<VecDeque<Time>>,
+}
+
+impl ClockEchoes {
+    fn expect(&self, time: Time) {
+        let mut pending = self.pending.lock().expect(FAILED_TO_LOCK);
+        if pending.len() >= MAX_PENDING_ECHOES {
+            pending.pop_front();
+        }
+        pending.push_back(time);
+    }
+
+    /// Consumes the echo of the given time, if one is expected.
+    ///
+    /// Echoes arrive in order, so older ones that never came back get dropped with it.
+    pub(crate) fn take(&self, time: Time) -> bool {
+        let mut pending = self.pending.lock().expect(FAILED_TO_LOCK);
+        match pending.iter().position(|&expected| expected == time) {
+            Some(index) => {
+                pending.drain(..=index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Publisher of simulated time on `/clock`, for simulators and other sources of time.
+///
+/// If the node itself runs on simulated time, its clock gets set directly, without waiting for
+/// the published messages to come back. Publishing stops once all clones are dropped.
+#[derive(Clone)]
+pub struct ClockServer {
+    handle: Arc<ClockServerHandle>,
+}
+
+struct ClockServerHandle {
+    shared: Arc<ClockServerShared>,
+}
+
+impl Drop for ClockServerHandle {
+    fn drop(&mut self) {
+        self.shared.state().alive = false;
+        self.shared.unparker.unpark();
+    }
+}
+
+struct ClockServerShared {
+    publisher: Publisher<ClockMsg>,
+    local_clock: Option<Arc<SimulatedClock>>,
+    echoes: Option<Arc<ClockEchoes>>,
+    state: Mutex<ClockServerState>,
+    // Keeps times in order when publishing from several threads
+    emit_lock: Mutex<()>,
+    unparker: Unparker,
+}
+
+struct ClockServerState {
+    alive: bool,
+    paused: bool,
+    real_time_factor: f64,
+    anchor_time: Time,
+    anchor_instant: Instant,
+}
+
+impl ClockServerState {
+    fn now(&self) -> Time {
+        if self.paused {
+            return self.anchor_time;
+        }
+        let elapsed = self.anchor_instant.elapsed().as_secs_f64() * self.real_time_factor;
+        self.anchor_time
+            .saturating_add(Duration::from_nanos((elapsed * 1e9) as i64))
+    }
+
+    fn reanchor(&mut self, time: Time) {
+        self.anchor_time = time;
+        self.anchor_instant = Instant::now();
+    }
+}
+
+impl ClockServerShared {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, ClockServerState> {
+        self.state.lock().expect(FAILED_TO_LOCK)
+    }
+
+    fn emit(&self) {
+        let _order = self.emit_lock.lock().expect(FAILED_TO_LOCK);
+        let time = self.state().now();
+        if let Some(clock) = &self.local_clock {
+            clock.trigger(time);
+        }
+        if let Some(echoes) = &self.echoes {
+            echoes.expect(time);
+        }
+        if let Err(err) = self.publisher.send(ClockMsg { clock: time }) {
+            error!("Failed to publish simulated time: {}", err);
+        }
+    }
+}
+
+impl ClockServer {
+    pub(crate) fn new(
+        publisher: Publisher<ClockMsg>,
+        local_clock: Option<Arc<SimulatedClock>>,
+        echoes: Option<Arc<ClockEchoes>>,
+        options: ClockServerOptions,
+    ) -> Self {
+        let parker = Parker::new();
+        let shared = Arc::new(ClockServerShared {
+            publisher,
+            local_clock,
+            echoes,
+            state: Mutex::new(ClockServerState {
+                alive: true,
+                paused: options.paused,
+                real_time_factor: options.real_time_factor,
+                anchor_time: options.start,
+                anchor_instant: Instant::now(),
+            }),
+            emit_lock: Mutex::new(()),
+            unparker: parker.unparker().clone(),
+        });
+        let period = StdDuration::from_secs_f64(1.0 / options.publish_rate);
+        let thread_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            while thread_shared.state().alive {
+                thread_shared.emit();
+                parker.park_timeout(period);
+            }
+        });
+        Self {
+            handle: Arc::new(ClockServerHandle { shared }),
+        }
+    }
+
+    /// Current simulated time of the server.
+    pub fn now(&self) -> Time {
+        self.handle.shared.state().now()
+    }
+
+    /// Whether the time only moves with `step` and `set_time`.
+    pub fn is_paused(&self) -> bool {
+        self.handle.shared.state().paused
+    }
+
+    /// Stops the time from moving on its own.
+    pub fn pause(&self) {
+        let mut state = self.handle.shared.state();
+        let now = state.now();
+        state.reanchor(now);
+        state.paused = true;
+    }
+
+    /// Lets the time move on its own again, from where it was paused.
+    pub fn resume(&self) {
+        let mut state = self.handle.shared.state();
+        let now = state.now();
+        state.reanchor(now);
+        state.paused = false;
+    }
+
+    /// Seconds of simulated time that pass per second of wall time while running.
+    pub fn real_time_factor(&self) -> f64 {
+        self.handle.shared.state().real_time_factor
+    }
+
+    /// Changes how fast the time moves, starting from the current time.
+    ///
+    /// Fails for negative factors, since simulated time never runs backwards on its own.
+    pub fn set_real_time_factor(&self, real_time_factor: f64) -> Result<()> {
+        check_real_time_factor(real_time_factor)?;
+        let mut state = self.handle.shared.state();
+        let now = state.now();
+        state.reanchor(now);
+        state.real_time_factor = real_time_factor;
+        Ok(())
+    }
+
+    /// Moves the time forward, and publishes the new time right away.
+    ///
+    /// This is how paused simulations advance, but it works while running too. Negative
+    /// durations move the time back, stopping at zero.
+    pub fn step(&self, duration: Duration) {
+        {
+            let mut state = self.handle.shared.state();
+            let time = state.now().saturating_add(duration);
+            state.reanchor(time);
+        }
+        self.handle.shared.emit();
+    }
+
+    /// Moves the time to the given value, and publishes it right away.
+    ///
+    /// Moving backwards is allowed, and shows up as a time jump on subscribed nodes.
+    pub fn set_time(&self, time: Time) {
+        self.handle.shared.state().reanchor(time);
+        self.handle.shared.emit();
+    }
+}
+
+pub(crate) fn check_real_time_factor(real_time_factor: f64) -> Result<()> {
+    if !(real_time_factor >= 0.0 && real_time_factor.is_finite()) {
+        bail!(ErrorKind::InvalidRealTimeFactor(real_time_factor));
+    }
+    Ok(())
+}
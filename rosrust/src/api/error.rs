@@ -33,10 +33,26 @@ error_chain::error_chain! {
             description("Bad YAML data provided")
             display("Bad YAML data provided: {}", details)
         }
+        UnsupportedParamValue(details: String) {
+            description("Unsupported parameter value")
+            display("Unsupported parameter value: {}", details)
+        }
         CannotResolveName(name: String) {
             description("Failed to resolve name")
             display("Failed to resolve name: {}", name)
         }
+        InvalidPublishRate(rate: f64) {
+            description("Publish rate must be positive")
+            display("Publish rate must be positive, but got {}", rate)
+        }
+        InvalidRealTimeFactor(factor: f64) {
+            description("Real time factor must not be negative")
+            display("Real time factor must not be negative, but got {}", factor)
+        }
+        InvalidLoggerLevel(details: String) {
+            description("Invalid logger level")
+            display("Invalid logger level '{}', expected a level like 'info', or a logger and level like 'ros.planner=debug'", details)
+        }
         CommunicationIssue(details: String) {
             description("Failure in communication with ROS API")
             display("Failure in communication with ROS API: {}", details)
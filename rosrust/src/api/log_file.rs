@@ -0,0 +1,94 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Rotation settings of a node's log file, with defaults matching `rospy`.
+#[derive(Clone, Debug)]
+pub struct LogFileOptions {
+    /// Size in bytes that the file gets rotated at, or zero to never rotate it.
+    pub max_size: u64,
+    /// Number of rotated files to keep, named like `node.log.1` for the most recent one.
+    pub max_backups: usize,
+}
+
+impl Default for LogFileOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 50_000_000,
+            max_backups: 4,
+        }
+    }
+}
+
+/// Log file of a node, appending one line per record and rotating by size.
+pub(crate) struct LogFile {
+    path: PathBuf,
+    options: LogFileOptions,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens the file for appending, creating it and its directories if needed.
+    pub fn open(path: PathBuf, options: LogFileOptions) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            options,
+            file,
+            size,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let line = format!("{}\n", line);
+        let length = line.len() as u64;
+        if self.options.max_size > 0 && self.size > 0 && self.size + length > self.options.max_size
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += length;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let max_backups = self.options.max_backups;
+        if max_backups > 0 {
+            let oldest = backup_path(&self.path, max_backups);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for index in (1..max_backups).rev() {
+                let backup = backup_path(&self.path, index);
+                if backup.exists() {
+                    fs::rename(backup, backup_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, backup_path(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
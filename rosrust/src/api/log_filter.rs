@@ -0,0 +1,47 @@
+/// Message of a `ros_*_filter!` call, which filters can inspect and change before it gets
+/// logged.
+#[derive(Clone, Debug)]
+pub struct FilterParams {
+    pub file: &'static str,
+    pub line: u32,
+    /// Path of the calling function.
+    pub function: &'static str,
+    pub message: String,
+    /// Level of the message, like `Log::INFO`.
+    pub level: i8,
+    /// Logger that the message goes through, like `ros` or `ros.planner`.
+    pub logger: String,
+}
+
+/// Filter of the `ros_*_filter!` macros, deciding whether their messages get logged.
+///
+/// Closures taking `&mut FilterParams` work as filters too.
+///
+/// ```no_run
+/// use rosrust::FilterParams;
+///
+/// let no_secrets = |params: &mut FilterParams| {
+///     params.message = params.message.replace("hunter2", "*******");
+///     true
+/// };
+/// rosrust::ros_info_filter!(no_secrets, "Logging in with {}", "hunter2");
+/// ```
+pub trait LogFilter {
+    /// Checked before the message gets formatted, so that disabled messages never are.
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// Checked with the formatted message, which the filter may change, along with its
+    /// level and logger.
+    fn is_enabled_for(&self, params: &mut FilterParams) -> bool;
+}
+
+impl<F> LogFilter for F
+where
+    F: Fn(&mut FilterParams) -> bool,
+{
+    fn is_enabled_for(&self, params: &mut FilterParams) -> bool {
+        self(params)
+    }
+}
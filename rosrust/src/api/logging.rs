@@ -0,0 +1,245 @@
+use super::rosout::{call_site, LogSite, NodeLog, ROOT_LOGGER};
+use crate::msg::rosgraph_msgs::Log;
+use log::{Level, LevelFilter, Metadata, Record, SetLoggerError};
+use std::cell::Cell;
+
+thread_local! {
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Forwards records of the `log` facade to `/rosout` and the terminal, the same way
+/// `ros_info!` and the other logging macros do.
+///
+/// Records get filtered by level, with optional levels for targets and their submodules.
+/// The record's module path fills the `function` field of `rosgraph_msgs/Log`, and records
+/// with an explicit target get it prepended to their message, like `[planner] no path found`.
+///
+/// ```no_run
+/// rosrust::init("talker");
+/// rosrust::rosout_logger()
+///     .with_level(log::LevelFilter::Debug)
+///     .with_target_level("hyper", log::LevelFilter::Warn)
+///     .install()
+///     .unwrap();
+/// log::info!("This reaches /rosout");
+/// ```
+#[derive(Clone)]
+pub struct RosLogger {
+    log: NodeLog,
+    level: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+    throttle_identical: Option<f64>,
+}
+
+impl RosLogger {
+    pub(crate) fn new(log: NodeLog) -> Self {
+        Self {
+            log,
+            level: LevelFilter::Info,
+            targets: vec![],
+            throttle_identical: None,
+        }
+    }
+
+    /// Level of records to forward, for targets without a level of their own.
+    ///
+    /// Defaults to `Info`.
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Level of records to forward for the target, and the modules within it.
+    ///
+    /// The longest matching target decides the level.
+    pub fn with_target_level(mut self, target: &str, level: LevelFilter) -> Self {
+        self.targets.retain(|(item, _)| item != target);
+        self.targets.push((target.into(), level));
+        self
+    }
+
+    /// Forward identical messages of a call site at most once per period, in seconds.
+    ///
+    /// Call sites are told apart by their file, line and module path. Their state is separate
+    /// from that of `ros_info_throttle_identical!` and the related macros.
+    pub fn with_throttle_identical(mut self, period: f64) -> Self {
+        self.throttle_identical = Some(period);
+        self
+    }
+
+    /// Most verbose level that any target gets forwarded at.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Ord::max)
+    }
+
+    /// Sets this as the logger of the `log` facade, which can only happen once per process.
+    pub fn install(self) -> std::result::Result<(), SetLoggerError> {
+        let max_level = self.max_level();
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    /// Layer that forwards `tracing` events the same way, for use with `tracing-subscriber`.
+    #[cfg(feature = "tracing")]
+    pub fn into_layer(self) -> RosoutLayer {
+        RosoutLayer { logger: self }
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| is_within(target, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    fn forward(&self, level: Level, target: &str, msg: String, location: Location<'_>) {
+        let logger = format!("{}.{}", ROOT_LOGGER, target.replace("::", "."));
+        if level > self.level_for(target) || !self.log.enabled(&logger, ros_level(level)) {
+            return;
+        }
+        // Logs from within forwarding, like failures to publish to `/rosout`, would loop
+        if FORWARDING.with(|forwarding| forwarding.replace(true)) {
+            return;
+        }
+        let site = LogSite {
+            file: location.file.unwrap_or(""),
+            line: location.line.unwrap_or(0),
+            function: location.module_path.unwrap_or(target),
+        };
+        let msg = match location.module_path {
+            Some(path) if path != target => format!("[{}] {}", target, msg),
+            _ => msg,
+        };
+        let throttled = match self.throttle_identical {
+            Some(period) => !call_site(&site).throttle_identical(self.log.now(), period, &msg),
+            None => false,
+        };
+        if !throttled {
+            self.log.write(&logger, ros_level(level), msg, &site);
+        }
+        FORWARDING.with(|forwarding| forwarding.set(false));
+    }
+}
+
+impl log::Log for RosLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let location = Location {
+            file: record.file(),
+            line: record.line(),
+            module_path: record.module_path(),
+        };
+        self.forward(
+            record.level(),
+            record.target(),
+            record.args().to_string(),
+            location,
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+struct Location<'a> {
+    file: Option<&'a str>,
+    line: Option<u32>,
+    module_path: Option<&'a str>,
+}
+
+fn is_within(target: &str, prefix: &str) -> bool {
+    target == prefix || (target.starts_with(prefix) && target[prefix.len()..].starts_with("::"))
+}
+
+fn ros_level(level: Level) -> i8 {
+    match level {
+        Level::Error => Log::ERROR,
+        Level::Warn => Log::WARN,
+        Level::Info => Log::INFO,
+        Level::Debug | Level::Trace => Log::DEBUG,
+    }
+}
+
+/// `tracing` layer forwarding events to `/rosout`, created with `RosLogger::into_layer`.
+///
+/// The `message` field of events becomes the log message, and their other fields get
+/// appended to it, like `planning done duration=1.5`.
+#[cfg(feature = "tracing")]
+pub struct RosoutLayer {
+    logger: RosLogger,
+}
+
+#[cfg(feature = "tracing")]
+impl<S: tracing_core::Subscriber> tracing_subscriber::Layer<S> for RosoutLayer {
+    fn on_event(
+        &self,
+        event: &tracing_core::Event<'_>,
+        _context: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            tracing_core::Level::ERROR => Level::Error,
+            tracing_core::Level::WARN => Level::Warn,
+            tracing_core::Level::INFO => Level::Info,
+            tracing_core::Level::DEBUG => Level::Debug,
+            tracing_core::Level::TRACE => Level::Trace,
+        };
+        if level > self.logger.level_for(metadata.target()) {
+            return;
+        }
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        let location = Location {
+            file: metadata.file(),
+            line: metadata.line(),
+            module_path: metadata.module_path(),
+        };
+        self.logger
+            .forward(level, metadata.target(), visitor.finish(), location);
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: String,
+}
+
+#[cfg(feature = "tracing")]
+impl EventVisitor {
+    fn finish(mut self) -> String {
+        if self.message.is_empty() {
+            return self.fields.trim_start().into();
+        }
+        self.message.push_str(&self.fields);
+        self.message
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl tracing_core::field::Visit for EventVisitor {
+    fn record_str(&mut self, field: &tracing_core::Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing_core::Field, value: &dyn std::fmt::Debug) {
+        use std::fmt::Write;
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
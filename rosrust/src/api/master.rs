@@ -1,13 +1,47 @@
 use super::super::rosxmlrpc::{self, Response as Result};
+#[cfg(feature = "testing")]
+use super::testing::MemoryMaster;
+use crate::rosxmlrpc::client::{bad_request_structure, bad_response_structure};
 use serde::{Deserialize, Serialize};
-use xml_rpc;
+use std::sync::Arc;
+use xml_rpc::{self, Params, Value};
 
 pub struct Master {
-    client: rosxmlrpc::Client,
+    client: MasterClient,
     client_id: String,
     caller_api: String,
 }
 
+enum MasterClient {
+    Remote(rosxmlrpc::Client),
+    #[cfg(feature = "testing")]
+    Memory(Arc<MemoryMaster>),
+}
+
+impl MasterClient {
+    fn request_tree_with_tree(&self, name: &str, params: Params) -> Result<Value> {
+        match self {
+            MasterClient::Remote(client) => client.request_tree_with_tree(name, params),
+            #[cfg(feature = "testing")]
+            MasterClient::Memory(master) => master.call(name, params),
+        }
+    }
+
+    fn request_tree<S: Serialize>(&self, name: &str, params: &S) -> Result<Value> {
+        let params = xml_rpc::into_params(params).map_err(bad_request_structure)?;
+        self.request_tree_with_tree(name, params)
+    }
+
+    fn request<'a, S, D>(&self, name: &str, params: &S) -> Result<D>
+    where
+        S: Serialize,
+        D: Deserialize<'a>,
+    {
+        let data = self.request_tree(name, params)?;
+        Deserialize::deserialize(data).map_err(bad_response_structure)
+    }
+}
+
 macro_rules! request {
     ($s:expr; $name:ident; $($item:expr),*)=> ({
         $s.client.request(stringify!($name),&(&$s.client_id,
@@ -35,12 +69,25 @@ impl Master {
         caller_api: &str,
     ) -> rosxmlrpc::error::Result<Master> {
         Ok(Master {
-            client: rosxmlrpc::Client::new(master_uri)?,
+            client: MasterClient::Remote(rosxmlrpc::Client::new(master_uri)?),
             client_id: client_id.to_owned(),
             caller_api: caller_api.to_owned(),
         })
     }
 
+    #[cfg(feature = "testing")]
+    pub(crate) fn new_in_memory(
+        master: Arc<MemoryMaster>,
+        client_id: &str,
+        caller_api: &str,
+    ) -> Master {
+        Master {
+            client: MasterClient::Memory(master),
+            client_id: client_id.to_owned(),
+            caller_api: caller_api.to_owned(),
+        }
+    }
+
     pub fn register_service(&self, service: &str, service_api: &str) -> Result<i32> {
         request!(self; registerService; service, service_api, &self.caller_api)
     }
@@ -1,17 +1,38 @@
-pub use self::clock::{Clock, Delay, Rate};
+pub use self::clock::{Clock, Delay, JumpHandle, JumpThreshold, OverrunPolicy, Rate, TimeJump};
+pub use self::clock_server::{ClockServer, ClockServerOptions};
+pub use self::log_file::LogFileOptions;
+pub use self::log_filter::{FilterParams, LogFilter};
+pub use self::logging::RosLogger;
+#[cfg(feature = "tracing")]
+pub use self::logging::RosoutLayer;
 pub use self::master::{Master, SystemState, Topic};
-pub use self::ros::{Parameter, Ros};
+pub use self::param_value::ParamValue;
+pub use self::ros::{NodeHandle, Parameter, Ros};
+#[doc(hidden)]
+pub use self::rosout::{function_path, LogCallSite, LogSite};
+pub use self::timer::{Timer, TimerEvent, WeakTimer};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 mod clock;
+mod clock_server;
 pub mod error;
 pub mod handlers;
+mod log_file;
+mod log_filter;
+mod logging;
 mod master;
 mod naming;
+mod param_value;
 pub mod raii;
 pub mod resolve;
 mod ros;
+mod rosout;
 mod slave;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timer;
+mod transport;
+mod yaml;
 
 pub struct ShutdownManager {
     handler: Box<dyn Fn() + Send + Sync>,
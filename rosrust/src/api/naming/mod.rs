@@ -6,6 +6,7 @@ pub mod error;
 mod mapper;
 mod path;
 
+#[derive(Clone)]
 pub struct Resolver {
     path: path::Buffer,
     namespace: path::Buffer,
@@ -30,6 +31,22 @@ impl Resolver {
         Ok(())
     }
 
+    /// Resolver for names within the namespace, keeping the node's name and remappings.
+    pub fn child(&self, namespace: &str) -> Result<Resolver, Error> {
+        Ok(Resolver {
+            path: self.path.clone(),
+            namespace: self.resolve(namespace)?,
+            mapper: self.mapper.clone(),
+        })
+    }
+
+    pub fn namespace(&self) -> String {
+        match self.namespace.get() {
+            [] => String::from("/"),
+            _ => format!("{}", self.namespace),
+        }
+    }
+
     fn resolve(&self, name: &str) -> Result<path::Buffer, Error> {
         let first_char = *name.as_bytes().first().ok_or(ErrorKind::EmptyName)?;
         if first_char == b'/' {
@@ -219,4 +236,42 @@ mod tests {
             r.translate("other").expect(FAILED_TO_RESOLVE)
         );
     }
+
+    #[test]
+    fn children_resolve_within_namespace_and_keep_remappings() {
+        let mut r = Resolver::new("/some/long/path").expect(FAILED_TO_RESOLVE);
+        r.map("/some/arm/a", "/b").expect(FAILED_TO_RESOLVE);
+        r.map("/some/arm/c", "/d").expect(FAILED_TO_RESOLVE);
+        let mut child = r.child("/some/arm").expect(FAILED_TO_RESOLVE);
+        child.map("c", "/e").expect(FAILED_TO_RESOLVE);
+        assert_eq!(String::from("/some/arm"), child.namespace());
+        assert_eq!(
+            String::from("/b"),
+            child.translate("a").expect(FAILED_TO_RESOLVE)
+        );
+        assert_eq!(
+            String::from("/some/long/path/x"),
+            child.translate("~x").expect(FAILED_TO_RESOLVE)
+        );
+        assert_eq!(
+            String::from("/some/arm/x"),
+            child.translate("x").expect(FAILED_TO_RESOLVE)
+        );
+        assert_eq!(
+            String::from("/e"),
+            child.translate("c").expect(FAILED_TO_RESOLVE)
+        );
+        assert_eq!(
+            String::from("/d"),
+            r.translate("/some/arm/c").expect(FAILED_TO_RESOLVE)
+        );
+        assert_eq!(
+            String::from("/some/long/path/sub"),
+            r.child("~sub").expect(FAILED_TO_RESOLVE).namespace()
+        );
+        assert_eq!(
+            String::from("/"),
+            r.child("/").expect(FAILED_TO_RESOLVE).namespace()
+        );
+    }
 }
@@ -0,0 +1,361 @@
+use super::error::{Error, ErrorKind, Result};
+use super::yaml;
+use error_chain::bail;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use yaml_rust::Yaml;
+
+/// Largest magnitude up to which every integer is exactly representable as a double.
+const MAX_EXACT_DOUBLE: u64 = 1 << 53;
+
+/// Value of a parameter, or of a whole namespace of parameters.
+///
+/// Integers are kept as 64-bit values. XML-RPC only has 32-bit integers, so larger values
+/// get stored as doubles while that is exact, and as decimal strings otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+    /// Binary data, stored as base64 in XML-RPC and as `!!binary` in YAML.
+    Binary(Vec<u8>),
+    /// Date and time in the ISO 8601 format used by XML-RPC.
+    DateTime(String),
+    Array(Vec<ParamValue>),
+    Struct(BTreeMap<String, ParamValue>),
+}
+
+impl ParamValue {
+    /// Parses a YAML document, with the same tags and conventions as `rosparam`.
+    pub fn from_yaml(data: &str) -> Result<ParamValue> {
+        yaml::parse_value(data)
+    }
+
+    /// Serializes the value as a block style YAML document.
+    pub fn to_yaml(&self) -> String {
+        yaml::dump(self)
+    }
+
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParamValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ParamValue::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns doubles, and integers converted to doubles.
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParamValue::Double(v) => Some(*v),
+            ParamValue::Int(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParamValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> Option<&[ParamValue]> {
+        match self {
+            ParamValue::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_struct(&self) -> Option<&BTreeMap<String, ParamValue>> {
+        match self {
+            ParamValue::Struct(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Name of the value's type, for use in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ParamValue::Bool(_) => "bool",
+            ParamValue::Int(_) => "int",
+            ParamValue::Double(_) => "double",
+            ParamValue::String(_) => "string",
+            ParamValue::Binary(_) => "binary",
+            ParamValue::DateTime(_) => "datetime",
+            ParamValue::Array(_) => "array",
+            ParamValue::Struct(_) => "struct",
+        }
+    }
+}
+
+impl From<xml_rpc::Value> for ParamValue {
+    fn from(value: xml_rpc::Value) -> Self {
+        match value {
+            xml_rpc::Value::Int(v) => ParamValue::Int(v.into()),
+            xml_rpc::Value::Bool(v) => ParamValue::Bool(v),
+            xml_rpc::Value::String(v) => ParamValue::String(v),
+            xml_rpc::Value::Double(v) => ParamValue::Double(v),
+            xml_rpc::Value::DateTime(v) => ParamValue::DateTime(v),
+            xml_rpc::Value::Base64(v) => ParamValue::Binary(v),
+            xml_rpc::Value::Array(v) => ParamValue::Array(v.into_iter().map(Into::into).collect()),
+            xml_rpc::Value::Struct(v) => ParamValue::Struct(
+                v.into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<ParamValue> for xml_rpc::Value {
+    fn from(value: ParamValue) -> Self {
+        match value {
+            ParamValue::Int(v) => match i32::try_from(v) {
+                Ok(v) => xml_rpc::Value::Int(v),
+                Err(_) if v.unsigned_abs() <= MAX_EXACT_DOUBLE => xml_rpc::Value::Double(v as f64),
+                Err(_) => xml_rpc::Value::String(v.to_string()),
+            },
+            ParamValue::Bool(v) => xml_rpc::Value::Bool(v),
+            ParamValue::String(v) => xml_rpc::Value::String(v),
+            ParamValue::Double(v) => xml_rpc::Value::Double(v),
+            ParamValue::DateTime(v) => xml_rpc::Value::DateTime(v),
+            ParamValue::Binary(v) => xml_rpc::Value::Base64(v),
+            ParamValue::Array(v) => xml_rpc::Value::Array(v.into_iter().map(Into::into).collect()),
+            ParamValue::Struct(v) => xml_rpc::Value::Struct(
+                v.into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<Yaml> for ParamValue {
+    type Error = Error;
+
+    /// Converts already parsed YAML, which has lost the tags that `from_yaml` handles.
+    fn try_from(value: Yaml) -> Result<Self> {
+        Ok(match value {
+            Yaml::Real(v) => ParamValue::Double(yaml::parse_float(&v)?),
+            Yaml::Integer(v) => ParamValue::Int(v),
+            Yaml::String(v) => ParamValue::String(v),
+            Yaml::Boolean(v) => ParamValue::Bool(v),
+            Yaml::Array(v) => ParamValue::Array(
+                v.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_>>()?,
+            ),
+            Yaml::Hash(v) => ParamValue::Struct(
+                v.into_iter()
+                    .map(|(key, value)| Ok((yaml_key(key)?, value.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Yaml::Null => bail!(ErrorKind::UnsupportedParamValue(
+                "null values can't be stored, as XML-RPC has no representation for them".into()
+            )),
+            Yaml::Alias(_) => bail!(ErrorKind::UnsupportedParamValue(
+                "aliases need to be resolved before conversion".into()
+            )),
+            Yaml::BadValue => bail!(ErrorKind::UnsupportedParamValue(
+                "YAML value is invalid".into()
+            )),
+        })
+    }
+}
+
+fn yaml_key(key: Yaml) -> Result<String> {
+    Ok(match key {
+        Yaml::Real(v) | Yaml::String(v) => v,
+        Yaml::Integer(v) => v.to_string(),
+        Yaml::Boolean(v) => v.to_string(),
+        key => bail!(ErrorKind::UnsupportedParamValue(format!(
+            "struct keys need to be strings, but got {:?}",
+            key
+        ))),
+    })
+}
+
+impl From<bool> for ParamValue {
+    #[inline]
+    fn from(v: bool) -> Self {
+        ParamValue::Bool(v)
+    }
+}
+
+impl From<i32> for ParamValue {
+    #[inline]
+    fn from(v: i32) -> Self {
+        ParamValue::Int(v.into())
+    }
+}
+
+impl From<i64> for ParamValue {
+    #[inline]
+    fn from(v: i64) -> Self {
+        ParamValue::Int(v)
+    }
+}
+
+impl From<f64> for ParamValue {
+    #[inline]
+    fn from(v: f64) -> Self {
+        ParamValue::Double(v)
+    }
+}
+
+impl From<&str> for ParamValue {
+    #[inline]
+    fn from(v: &str) -> Self {
+        ParamValue::String(v.into())
+    }
+}
+
+impl From<String> for ParamValue {
+    #[inline]
+    fn from(v: String) -> Self {
+        ParamValue::String(v)
+    }
+}
+
+impl From<Vec<ParamValue>> for ParamValue {
+    #[inline]
+    fn from(v: Vec<ParamValue>) -> Self {
+        ParamValue::Array(v)
+    }
+}
+
+impl From<BTreeMap<String, ParamValue>> for ParamValue {
+    #[inline]
+    fn from(v: BTreeMap<String, ParamValue>) -> Self {
+        ParamValue::Struct(v)
+    }
+}
+
+impl Serialize for ParamValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            ParamValue::Bool(v) => serializer.serialize_bool(*v),
+            ParamValue::Int(v) => serializer.serialize_i64(*v),
+            ParamValue::Double(v) => serializer.serialize_f64(*v),
+            ParamValue::String(v) | ParamValue::DateTime(v) => serializer.serialize_str(v),
+            ParamValue::Binary(v) => serializer.serialize_bytes(v),
+            ParamValue::Array(v) => {
+                let mut seq = serializer.serialize_seq(Some(v.len()))?;
+                for item in v {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            ParamValue::Struct(v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (key, value) in v {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ParamValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(ParamValueVisitor)
+    }
+}
+
+struct ParamValueVisitor;
+
+impl<'de> Visitor<'de> for ParamValueVisitor {
+    type Value = ParamValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a value that can be stored on the parameter server")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<ParamValue, E> {
+        i64::try_from(v).map(ParamValue::Int).map_err(|_| {
+            E::custom(format!(
+                "integer {} is too large, as parameters are limited to 64-bit signed integers",
+                v
+            ))
+        })
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::Double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::String(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::Binary(v.into()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<ParamValue, E> {
+        Ok(ParamValue::Binary(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<ParamValue, E> {
+        Err(E::custom(
+            "null values can't be stored, as XML-RPC has no representation for them",
+        ))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<ParamValue, E> {
+        self.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<ParamValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<ParamValue, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(ParamValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<ParamValue, A::Error> {
+        let mut fields = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry::<String, ParamValue>()? {
+            fields.insert(key, value);
+        }
+        Ok(ParamValue::Struct(fields))
+    }
+}
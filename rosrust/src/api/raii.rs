@@ -1,11 +1,13 @@
 use super::clock::Clock;
 use super::error::Result;
 use super::master::Master;
-use super::slave::Slave;
+use super::slave::{ParamCache, ParamCallback};
+use super::transport::{TopicStream, Transport};
 use crate::api::SystemState;
 use crate::error::ErrorKind;
 use crate::rosxmlrpc::Response;
-use crate::tcpros::{Message, PublisherStream, ServicePair, ServiceResult};
+use crate::tcpros::{Message, ServicePair, ServiceResult};
+use crate::util::FAILED_TO_LOCK;
 use crate::{RawMessageDescription, SubscriptionHandler};
 use log::error;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
@@ -15,14 +17,14 @@ use std::sync::Arc;
 pub struct Publisher<T: Message> {
     clock: Arc<dyn Clock>,
     seq: Arc<AtomicUsize>,
-    stream: PublisherStream<T>,
+    stream: TopicStream<T>,
     raii: Arc<InteractorRaii<PublisherInfo>>,
 }
 
 impl<T: Message> Publisher<T> {
     pub(crate) fn new(
         master: Arc<Master>,
-        slave: Arc<Slave>,
+        transport: Transport,
         clock: Arc<dyn Clock>,
         hostname: &str,
         name: &str,
@@ -31,12 +33,16 @@ impl<T: Message> Publisher<T> {
     ) -> Result<Self> {
         let message_description =
             message_description.unwrap_or_else(RawMessageDescription::from_message::<T>);
-        let stream =
-            slave.add_publication::<T>(hostname, name, queue_size, message_description.clone())?;
+        let stream = transport.add_publication::<T>(
+            hostname,
+            name,
+            queue_size,
+            message_description.clone(),
+        )?;
 
         let raii = Arc::new(InteractorRaii::new(PublisherInfo {
             master,
-            slave,
+            transport,
             name: name.into(),
         }));
 
@@ -116,13 +122,13 @@ impl<T: Message> Publisher<T> {
 
 struct PublisherInfo {
     master: Arc<Master>,
-    slave: Arc<Slave>,
+    transport: Transport,
     name: String,
 }
 
 impl Interactor for PublisherInfo {
     fn unregister(&mut self) -> Response<()> {
-        self.slave.remove_publication(&self.name);
+        self.transport.remove_publication(&self.name);
         self.master.unregister_publisher(&self.name).map(|_| ())
     }
 }
@@ -135,7 +141,7 @@ pub struct Subscriber {
 impl Subscriber {
     pub(crate) fn new<T, H>(
         master: Arc<Master>,
-        slave: Arc<Slave>,
+        transport: Transport,
         name: &str,
         queue_size: usize,
         handler: H,
@@ -145,11 +151,12 @@ impl Subscriber {
         H: SubscriptionHandler<T>,
     {
         let unsub_signal = Arc::new(AtomicBool::new(false));
-        let id = slave.add_subscription::<T, H>(name, queue_size, handler, unsub_signal.clone())?;
+        let id =
+            transport.add_subscription::<T, H>(name, queue_size, handler, unsub_signal.clone())?;
 
         let info = Arc::new(InteractorRaii::new(SubscriberInfo {
             master,
-            slave,
+            transport,
             name: name.into(),
             id,
             unsub_signal,
@@ -162,7 +169,7 @@ impl Subscriber {
 
         if let Err(err) = info
             .interactor
-            .slave
+            .transport
             .add_publishers_to_subscription(name, publishers.into_iter())
         {
             error!(
@@ -178,22 +185,22 @@ impl Subscriber {
     pub fn publisher_count(&self) -> usize {
         self.info
             .interactor
-            .slave
-            .get_publisher_count_of_subscription(&self.info.interactor.name)
+            .transport
+            .publisher_count(&self.info.interactor.name)
     }
 
     #[inline]
     pub fn publisher_uris(&self) -> Vec<String> {
         self.info
             .interactor
-            .slave
-            .get_publisher_uris_of_subscription(&self.info.interactor.name)
+            .transport
+            .publisher_uris(&self.info.interactor.name)
     }
 }
 
 struct SubscriberInfo {
     master: Arc<Master>,
-    slave: Arc<Slave>,
+    transport: Transport,
     name: String,
     id: usize,
     unsub_signal: Arc<AtomicBool>,
@@ -203,7 +210,7 @@ impl Interactor for SubscriberInfo {
     fn unregister(&mut self) -> Response<()> {
         self.unsub_signal.store(true, std::sync::atomic::Ordering::Relaxed);
 
-        self.slave.remove_subscription(&self.name, self.id);
+        self.transport.remove_subscription(&self.name, self.id);
         self.master.unregister_subscriber(&self.name).map(|_| ())
     }
 }
@@ -216,7 +223,7 @@ pub struct Service {
 impl Service {
     pub(crate) fn new<T, F>(
         master: Arc<Master>,
-        slave: Arc<Slave>,
+        transport: Transport,
         hostname: &str,
         bind_address: &str,
         name: &str,
@@ -226,11 +233,11 @@ impl Service {
         T: ServicePair,
         F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
     {
-        let api = slave.add_service::<T, F>(hostname, bind_address, name, handler)?;
+        let api = transport.add_service::<T, F>(hostname, bind_address, name, handler)?;
 
         let raii = Arc::new(InteractorRaii::new(ServiceInfo {
             master,
-            slave,
+            transport,
             api,
             name: name.into(),
         }));
@@ -244,20 +251,76 @@ impl Service {
 
 struct ServiceInfo {
     master: Arc<Master>,
-    slave: Arc<Slave>,
+    transport: Transport,
     name: String,
     api: String,
 }
 
 impl Interactor for ServiceInfo {
     fn unregister(&mut self) -> Response<()> {
-        self.slave.remove_service(&self.name);
+        self.transport.remove_service(&self.name);
         self.master
             .unregister_service(&self.name, &self.api)
             .map(|_| ())
     }
 }
 
+/// Watch of a parameter's changes, which stops once all of its clones get dropped.
+#[derive(Clone)]
+pub struct ParamWatch {
+    _raii: Arc<InteractorRaii<ParamWatchInfo>>,
+}
+
+impl ParamWatch {
+    pub(crate) fn new(
+        master: Arc<Master>,
+        param_cache: ParamCache,
+        name: &str,
+        callback: ParamCallback,
+    ) -> Result<Self> {
+        let id = param_cache
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .add_watcher(name, callback);
+
+        let raii = Arc::new(InteractorRaii::new(ParamWatchInfo {
+            master,
+            param_cache,
+            name: name.into(),
+            id,
+        }));
+
+        let value = raii.interactor.master.subscribe_param_any(name)?;
+        raii.interactor
+            .param_cache
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .set_watched_value(id, value);
+        Ok(Self { _raii: raii })
+    }
+}
+
+struct ParamWatchInfo {
+    master: Arc<Master>,
+    param_cache: ParamCache,
+    name: String,
+    id: usize,
+}
+
+impl Interactor for ParamWatchInfo {
+    fn unregister(&mut self) -> Response<()> {
+        let unused = self
+            .param_cache
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .remove_watcher(self.id);
+        if !unused {
+            return Ok(());
+        }
+        self.master.unsubscribe_param(&self.name).map(|_| ())
+    }
+}
+
 trait Interactor {
     fn unregister(&mut self) -> Response<()>;
 }
@@ -1,4 +1,5 @@
-use std::{self, env};
+use std::path::PathBuf;
+use std::{self, env, process};
 
 pub fn master() -> String {
     if let Some(v) = find_with_prefix("__master:=") {
@@ -34,6 +35,30 @@ pub fn name(default: &str) -> String {
     find_with_prefix("__name:=").unwrap_or_else(|| String::from(default))
 }
 
+pub fn log_level() -> Option<String> {
+    find_with_prefix("__log_level:=")
+}
+
+/// Log file of the node, from `__log:=`, or named after the node and process in the log
+/// directory, which is `ROS_LOG_DIR`, `ROS_HOME/log` or `~/.ros/log`.
+pub fn log_file(node_name: &str) -> Option<PathBuf> {
+    if let Some(v) = find_with_prefix("__log:=") {
+        return Some(PathBuf::from(v));
+    }
+    let name = node_name.trim_start_matches('/').replace('/', "_");
+    Some(log_directory()?.join(format!("{}-{}.log", name, process::id())))
+}
+
+fn log_directory() -> Option<PathBuf> {
+    if let Some(v) = env::var_os("ROS_LOG_DIR") {
+        return Some(PathBuf::from(v));
+    }
+    if let Some(v) = env::var_os("ROS_HOME") {
+        return Some(PathBuf::from(v).join("log"));
+    }
+    env::var_os("HOME").map(|v| PathBuf::from(v).join(".ros").join("log"))
+}
+
 pub fn mappings() -> Vec<(String, String)> {
     args()
         .skip(1)
@@ -444,4 +469,54 @@ mod tests {
         set_args(&["__hostname:=host2", "__ip:=127.0.0.1"]);
         assert_eq!(String::from("host2"), hostname());
     }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn log_file_uses_home_directory_by_default() {
+        let testcase = TESTCASE.lock().expect(FAILED_TO_LOCK);
+        set_args(&[]);
+        env::remove_var("ROS_LOG_DIR");
+        env::remove_var("ROS_HOME");
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let file = home.map(|v| {
+            v.join(".ros")
+                .join("log")
+                .join(format!("ns_node-{}.log", process::id()))
+        });
+        assert_eq!(file, log_file("/ns/node"));
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn log_file_uses_environment_when_passed() {
+        let testcase = TESTCASE.lock().expect(FAILED_TO_LOCK);
+        set_args(&[]);
+        env::remove_var("ROS_LOG_DIR");
+        env::set_var("ROS_HOME", "/tmp/roshome");
+        let name = format!("node-{}.log", process::id());
+        assert_eq!(
+            Some(PathBuf::from("/tmp/roshome/log").join(&name)),
+            log_file("/node")
+        );
+        env::set_var("ROS_LOG_DIR", "/tmp/roslog");
+        assert_eq!(
+            Some(PathBuf::from("/tmp/roslog").join(&name)),
+            log_file("/node")
+        );
+        env::remove_var("ROS_LOG_DIR");
+        env::remove_var("ROS_HOME");
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn log_file_prioritizes_argument_when_passed() {
+        let testcase = TESTCASE.lock().expect(FAILED_TO_LOCK);
+        env::set_var("ROS_LOG_DIR", "/tmp/roslog");
+        set_args(&["__log:=/tmp/custom/node.log"]);
+        assert_eq!(
+            Some(PathBuf::from("/tmp/custom/node.log")),
+            log_file("/node")
+        );
+        env::remove_var("ROS_LOG_DIR");
+    }
 }
@@ -1,46 +1,63 @@
 use super::super::rosxmlrpc::Response;
-use super::clock::{Clock, Rate, RealClock, SimulatedClock};
+use super::clock::{Clock, JumpHandle, JumpThreshold, Rate, RealClock, SimulatedClock, TimeJump};
+use super::clock_server::{check_real_time_factor, ClockEchoes, ClockServer, ClockServerOptions};
 use super::error::{Error, ErrorKind, Result, ResultExt};
+use super::log_file::{LogFile, LogFileOptions};
+use super::log_filter::{FilterParams, LogFilter};
+use super::logging::RosLogger;
 use super::master::{self, Master, Topic};
 use super::naming::{self, Resolver};
-use super::raii::{Publisher, Service, Subscriber};
+use super::param_value::ParamValue;
+use super::raii::{ParamWatch, Publisher, Service, Subscriber};
 use super::resolve;
+use super::rosout::{
+    call_site, level_name, parse_level, LogCallSite, LogSite, NodeLog, RosoutPublisher,
+    INTERNAL_LOGGER, ROOT_LOGGER,
+};
 use super::slave::Slave;
+#[cfg(feature = "testing")]
+use super::testing::{MemoryBus, MemoryMaster, MemoryNode};
+use super::timer::{Timer, TimerEvent};
+use super::transport::Transport;
+use super::yaml::{self, Assignment};
 use crate::api::clock::Delay;
 use crate::api::handlers::CallbackSubscriptionHandler;
 use crate::api::slave::ParamCache;
 use crate::api::ShutdownManager;
+use crate::msg::roscpp::{GetLoggers, GetLoggersRes, Logger, SetLoggerLevel, SetLoggerLevelRes};
 use crate::msg::rosgraph_msgs::{Clock as ClockMsg, Log};
-use crate::msg::std_msgs::Header;
 use crate::rosxmlrpc::client::bad_response_structure;
 use crate::tcpros::{Client, Message, ServicePair, ServiceResult};
 use crate::util::FAILED_TO_LOCK;
 use crate::{RawMessage, RawMessageDescription, SubscriptionHandler};
 use error_chain::bail;
-use lazy_static::lazy_static;
 use log::error;
 use ros_message::{Duration, Time};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::ops::Deref;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread::sleep;
 use std::time::Instant;
 use xml_rpc;
-use yaml_rust::{Yaml, YamlLoader};
 
 pub struct Ros {
     master: Arc<Master>,
-    slave: Arc<Slave>,
+    transport: Transport,
     param_cache: ParamCache,
     hostname: String,
     bind_address: String,
-    resolver: Resolver,
+    resolver: Arc<Resolver>,
     name: String,
     clock: Arc<dyn Clock>,
+    sim_clock: Option<Arc<SimulatedClock>>,
+    clock_echoes: Option<Arc<ClockEchoes>>,
     static_subs: Vec<Subscriber>,
-    logger: Arc<Mutex<Option<Publisher<Log>>>>,
+    log: NodeLog,
+    static_services: Vec<Service>,
     shutdown_manager: Arc<ShutdownManager>,
 }
 
@@ -58,13 +75,9 @@ impl Ros {
             ros.map(&src, &dest)?;
         }
         for (src, dest) in resolve::params() {
-            let data = YamlLoader::load_from_str(&dest)
-                .chain_err(|| ErrorKind::BadYamlData(dest.clone()))?
-                .into_iter()
-                .next()
-                .ok_or_else(|| ErrorKind::BadYamlData(dest.clone()))?;
+            let data = yaml::parse_value(&dest)?;
             let param = ros.param(&src).ok_or(ErrorKind::CannotResolveName(src))?;
-            param.set_raw(yaml_to_xmlrpc(data)?)?;
+            param.set_value(data)?;
         }
 
         if ros
@@ -74,29 +87,83 @@ impl Ros {
         {
             let clock = Arc::new(SimulatedClock::default());
             let ros_clock = Arc::clone(&clock);
+            let echoes = Arc::new(ClockEchoes::default());
+            let sub_echoes = Arc::clone(&echoes);
             let sub = ros
-                .subscribe::<ClockMsg, _>("/clock", 1, move |v| clock.trigger(v.clock))
+                .subscribe::<ClockMsg, _>("/clock", 1, move |v| {
+                    // Clock servers of this node set the clock directly
+                    if !sub_echoes.take(v.clock) {
+                        clock.trigger(v.clock)
+                    }
+                })
                 .chain_err(|| {
                     ErrorKind::CommunicationIssue("Failed to subscribe to simulated clock".into())
                 })?;
             ros.static_subs.push(sub);
+            ros.sim_clock = Some(Arc::clone(&ros_clock));
+            ros.clock_echoes = Some(echoes);
+            ros.log.set_clock(Arc::clone(&ros_clock) as Arc<dyn Clock>);
             ros.clock = ros_clock;
         }
 
-        *ros.logger.lock().unwrap() = Some(ros.publish("/rosout", 100)?);
+        ros.init_logging(resolve::log_level())?;
+        if let Some(path) = resolve::log_file(&ros.name) {
+            if let Err(err) = ros.log_to_file(&path, LogFileOptions::default()) {
+                let msg = format!("Failed to open log file {}: {}", path.display(), err);
+                ros.log
+                    .write(INTERNAL_LOGGER, Log::WARN, msg, &crate::ros_log_site!());
+            }
+        }
 
         Ok(ros)
     }
 
-    fn new_raw(master_uri: &str, hostname: &str, namespace: &str, name: &str) -> Result<Ros> {
-        let namespace = namespace.trim_end_matches('/');
+    #[cfg(feature = "testing")]
+    pub(crate) fn new_in_memory(
+        memory_master: Arc<MemoryMaster>,
+        memory_bus: Arc<MemoryBus>,
+        clock: Arc<SimulatedClock>,
+        name: &str,
+    ) -> Result<Ros> {
+        let name = full_name("", name)?;
+        let resolver = Resolver::new(&name)?;
+        let (logger, shutdown_manager) = create_logger_and_shutdown_manager();
 
-        if name.contains('/') {
-            bail!(ErrorKind::Naming(
-                naming::error::ErrorKind::IllegalCharacter(name.into()),
-            ));
-        }
+        let param_cache: ParamCache = Arc::new(Mutex::new(Default::default()));
+        let node = MemoryNode::new(memory_bus, &name);
+        memory_master.add_node(&name, node.uri(), Arc::clone(&param_cache));
+        let master = Master::new_in_memory(memory_master, &name, node.uri());
+        let transport = Transport::Memory(Arc::new(node));
+        let log = NodeLog::new(
+            logger,
+            transport.clone(),
+            name.clone(),
+            Arc::clone(&clock) as Arc<dyn Clock>,
+        );
 
+        let mut ros = Ros {
+            master: Arc::new(master),
+            transport,
+            param_cache,
+            hostname: String::from("localhost"),
+            bind_address: String::from("localhost"),
+            resolver: Arc::new(resolver),
+            name,
+            clock: Arc::clone(&clock) as Arc<dyn Clock>,
+            sim_clock: Some(clock),
+            clock_echoes: None,
+            static_subs: Vec::new(),
+            log,
+            static_services: Vec::new(),
+            shutdown_manager,
+        };
+
+        ros.init_logging(None)?;
+
+        Ok(ros)
+    }
+
+    fn new_raw(master_uri: &str, hostname: &str, namespace: &str, name: &str) -> Result<Ros> {
         let bind_host = {
             if hostname == "localhost" || hostname.starts_with("127.") {
                 hostname
@@ -105,14 +172,9 @@ impl Ros {
             }
         };
 
-        let name = format!("{}/{}", namespace, name);
+        let name = full_name(namespace, name)?;
         let resolver = Resolver::new(&name)?;
-
-        let logger = Arc::new(Mutex::new(None));
-        let shutdown_manager = Arc::new(ShutdownManager::new({
-            let logger = Arc::clone(&logger);
-            move || drop(logger.lock().unwrap().take())
-        }));
+        let (logger, shutdown_manager) = create_logger_and_shutdown_manager();
 
         let param_cache = Arc::new(Mutex::new(Default::default()));
         let slave = Slave::new(
@@ -125,29 +187,95 @@ impl Ros {
             Arc::clone(&shutdown_manager),
         )?;
         let master = Master::new(master_uri, &name, slave.uri())?;
+        let transport = Transport::Tcpros(Arc::new(slave));
+        let clock: Arc<dyn Clock> = Arc::new(RealClock::default());
+        let log = NodeLog::new(logger, transport.clone(), name.clone(), Arc::clone(&clock));
 
         Ok(Ros {
             master: Arc::new(master),
-            slave: Arc::new(slave),
+            transport,
             param_cache,
             hostname: String::from(hostname),
             bind_address: String::from(bind_host),
-            resolver,
+            resolver: Arc::new(resolver),
             name,
-            clock: Arc::new(RealClock::default()),
+            clock,
+            sim_clock: None,
+            clock_echoes: None,
             static_subs: Vec::new(),
-            logger,
+            log,
+            static_services: Vec::new(),
             shutdown_manager,
         })
     }
 
+    /// Starts publishing to `/rosout`, and serving the logger level services.
+    fn init_logging(&mut self, level_arg: Option<String>) -> Result<()> {
+        self.log.set_publisher(self.publish("/rosout", 100)?);
+
+        let level_param = self
+            .param("~log_level")
+            .and_then(|param| param.get::<String>().ok());
+        for levels in level_param.iter().chain(level_arg.iter()) {
+            self.log.levels().apply(levels)?;
+        }
+
+        let log = self.log.clone();
+        let get_loggers = self.service::<GetLoggers, _>("~get_loggers", move |_| {
+            let loggers = log
+                .levels()
+                .loggers()
+                .into_iter()
+                .map(|(name, level)| Logger {
+                    name,
+                    level: level_name(level).into(),
+                })
+                .collect();
+            Ok(GetLoggersRes { loggers })
+        })?;
+        let log = self.log.clone();
+        let set_logger_level =
+            self.service::<SetLoggerLevel, _>("~set_logger_level", move |req| {
+                let level = parse_level(&req.level)
+                    .ok_or_else(|| format!("Unknown logger level '{}'", req.level))?;
+                log.levels().set(&req.logger, level);
+                Ok(SetLoggerLevelRes {})
+            })?;
+        self.static_services.push(get_loggers);
+        self.static_services.push(set_logger_level);
+        Ok(())
+    }
+
     fn map(&mut self, source: &str, destination: &str) -> Result<()> {
-        self.resolver.map(source, destination).map_err(Into::into)
+        Arc::make_mut(&mut self.resolver)
+            .map(source, destination)
+            .map_err(Into::into)
+    }
+
+    /// Handle for names within the namespace, which may be relative, absolute or private.
+    ///
+    /// Relative namespaces are within the node's namespace, and `~` refers to the node's
+    /// private namespace, so `~planner` resolves to `/robot/node/planner` for `/robot/node`.
+    pub fn node_handle(&self, namespace: &str) -> Result<NodeHandle> {
+        self.root_handle().child(namespace)
+    }
+
+    fn root_handle(&self) -> NodeHandle {
+        NodeHandle {
+            master: Arc::clone(&self.master),
+            transport: self.transport.clone(),
+            param_cache: Arc::clone(&self.param_cache),
+            hostname: self.hostname.clone(),
+            bind_address: self.bind_address.clone(),
+            resolver: Arc::clone(&self.resolver),
+            name: self.name.clone(),
+            clock: Arc::clone(&self.clock),
+        }
     }
 
     #[inline]
     pub fn uri(&self) -> &str {
-        self.slave.uri()
+        self.transport.uri()
     }
 
     #[inline]
@@ -184,7 +312,77 @@ impl Ros {
     pub fn rate(&self, rate: f64) -> Rate {
         self.clock.await_init();
         let nanos = 1_000_000_000.0 / rate;
-        Rate::new(Arc::clone(&self.clock), Duration::from_nanos(nanos as i64))
+        let log = self.log.clone();
+        Rate::new(Arc::clone(&self.clock), Duration::from_nanos(nanos as i64)).with_logger(
+            move |msg| log.write(INTERNAL_LOGGER, Log::WARN, msg, &crate::ros_log_site!()),
+        )
+    }
+
+    /// Starts publishing simulated time on `/clock`, until the returned server gets dropped.
+    ///
+    /// A node running on simulated time has its own clock set by the server directly.
+    pub fn clock_server(&self, options: ClockServerOptions) -> Result<ClockServer> {
+        if !(options.publish_rate > 0.0 && options.publish_rate.is_finite()) {
+            bail!(ErrorKind::InvalidPublishRate(options.publish_rate));
+        }
+        check_real_time_factor(options.real_time_factor)?;
+        let publisher = self.publish::<ClockMsg>("/clock", 1)?;
+        Ok(ClockServer::new(
+            publisher,
+            self.sim_clock.clone(),
+            self.clock_echoes.clone(),
+            options,
+        ))
+    }
+
+    /// Calls the callback whenever the node's clock jumps by at least the threshold.
+    ///
+    /// Only simulated time jumps, for example when a bag starts playing again from the
+    /// beginning. The callback stays registered until the returned handle gets dropped.
+    pub fn add_jump_callback<F>(&self, threshold: JumpThreshold, callback: F) -> JumpHandle
+    where
+        F: Fn(&TimeJump) + Send + Sync + 'static,
+    {
+        JumpHandle::new(Arc::clone(&self.clock), threshold, Arc::new(callback))
+    }
+
+    /// Calls the callback once every period, until the returned timer gets dropped.
+    ///
+    /// The timer follows the node's clock, so it runs on simulated time when `/use_sim_time`
+    /// is set. A period that is not positive calls the callback just once.
+    pub fn create_timer<F>(&self, period: Duration, callback: F) -> Timer
+    where
+        F: FnMut(TimerEvent) + Send + 'static,
+    {
+        self.clock.await_init();
+        Timer::new(Arc::clone(&self.clock), period, false, callback)
+    }
+
+    /// Calls the callback once, after the period has passed on the node's clock.
+    ///
+    /// Starting the returned timer again schedules another call.
+    pub fn create_oneshot_timer<F>(&self, period: Duration, callback: F) -> Timer
+    where
+        F: FnMut(TimerEvent) + Send + 'static,
+    {
+        self.clock.await_init();
+        Timer::new(Arc::clone(&self.clock), period, true, callback)
+    }
+
+    /// Like `create_timer`, but always following wall time, even in simulation.
+    pub fn create_wall_timer<F>(&self, period: Duration, callback: F) -> Timer
+    where
+        F: FnMut(TimerEvent) + Send + 'static,
+    {
+        Timer::new(Arc::new(RealClock::default()), period, false, callback)
+    }
+
+    /// Like `create_oneshot_timer`, but always following wall time, even in simulation.
+    pub fn create_oneshot_wall_timer<F>(&self, period: Duration, callback: F) -> Timer
+    where
+        F: FnMut(TimerEvent) + Send + 'static,
+    {
+        Timer::new(Arc::new(RealClock::default()), period, true, callback)
     }
 
     #[inline]
@@ -200,11 +398,30 @@ impl Ros {
     }
 
     pub fn param(&self, name: &str) -> Option<Parameter> {
-        self.resolver.translate(name).ok().map(|v| Parameter {
-            param_cache: Arc::clone(&self.param_cache),
-            master: Arc::clone(&self.master),
-            name: v,
-        })
+        self.root_handle().param(name)
+    }
+
+    /// Calls the callback with the parameter's new value, whenever the parameter changes.
+    ///
+    /// Changes of parameters within the watched namespace, and of namespaces containing the
+    /// watched parameter, are delivered too. Unset parameters are delivered as `None`. The master
+    /// keeps sending updates until the returned watch gets dropped.
+    pub fn watch_param<F>(&self, name: &str, callback: F) -> Result<ParamWatch>
+    where
+        F: Fn(Option<ParamValue>) + Send + Sync + 'static,
+    {
+        self.root_handle().watch_param(name, callback)
+    }
+
+    /// Like `watch_param`, with values deserialized into the given type.
+    ///
+    /// Values that fail to deserialize get logged and skipped.
+    pub fn watch_param_typed<T, F>(&self, name: &str, callback: F) -> Result<ParamWatch>
+    where
+        T: DeserializeOwned,
+        F: Fn(Option<T>) + Send + Sync + 'static,
+    {
+        self.root_handle().watch_param_typed(name, callback)
     }
 
     pub fn parameters(&self) -> Response<Vec<String>> {
@@ -221,9 +438,399 @@ impl Ros {
             .map(|v| v.into_iter().map(Into::into).collect())
     }
 
+    pub fn client<T: ServicePair>(&self, service: &str) -> Result<Client<T>> {
+        self.root_handle().client::<T>(service)
+    }
+
+    pub fn wait_for_service(
+        &self,
+        service: &str,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<()> {
+        self.root_handle().wait_for_service(service, timeout)
+    }
+
+    pub fn service<T, F>(&self, service: &str, handler: F) -> Result<Service>
+    where
+        T: ServicePair,
+        F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        self.root_handle().service::<T, F>(service, handler)
+    }
+
+    #[inline]
+    pub fn subscribe<T, F>(&self, topic: &str, queue_size: usize, callback: F) -> Result<Subscriber>
+    where
+        T: Message,
+        F: Fn(T) + Send + 'static,
+    {
+        self.root_handle()
+            .subscribe::<T, F>(topic, queue_size, callback)
+    }
+
+    pub fn subscribe_with_ids<T, F>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        callback: F,
+    ) -> Result<Subscriber>
+    where
+        T: Message,
+        F: Fn(T, &str) + Send + 'static,
+    {
+        self.root_handle()
+            .subscribe_with_ids::<T, F>(topic, queue_size, callback)
+    }
+
+    pub fn subscribe_with_ids_and_headers<T, F, G>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        on_message: F,
+        on_connect: G,
+    ) -> Result<Subscriber>
+    where
+        T: Message,
+        F: Fn(T, &str) + Send + 'static,
+        G: Fn(HashMap<String, String>) + Send + 'static,
+    {
+        self.root_handle()
+            .subscribe_with_ids_and_headers::<T, F, G>(topic, queue_size, on_message, on_connect)
+    }
+
+    pub fn subscribe_with<T, H>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        handler: H,
+    ) -> Result<Subscriber>
+    where
+        T: Message,
+        H: SubscriptionHandler<T>,
+    {
+        self.root_handle()
+            .subscribe_with::<T, H>(topic, queue_size, handler)
+    }
+
+    pub fn publish<T>(&self, topic: &str, queue_size: usize) -> Result<Publisher<T>>
+    where
+        T: Message,
+    {
+        self.root_handle().publish::<T>(topic, queue_size)
+    }
+
+    pub fn publish_with_description<T>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        message_description: RawMessageDescription,
+    ) -> Result<Publisher<T>>
+    where
+        T: Message,
+    {
+        self.root_handle()
+            .publish_with_description::<T>(topic, queue_size, message_description)
+    }
+
+    pub fn log(&self, level: i8, msg: String, file: &str, line: u32) {
+        self.log_at(level, msg, &unknown_function(file, line));
+    }
+
+    #[doc(hidden)]
+    pub fn log_at(&self, level: i8, msg: String, site: &LogSite<'_>) {
+        self.log.write(ROOT_LOGGER, level, msg, site);
+    }
+
+    /// Logs through the named logger, like `planner` for the `ros.planner` logger.
+    pub fn log_named(&self, name: &str, level: i8, msg: String, file: &str, line: u32) {
+        self.log_named_at(name, level, msg, &unknown_function(file, line));
+    }
+
+    #[doc(hidden)]
+    pub fn log_named_at(&self, name: &str, level: i8, msg: String, site: &LogSite<'_>) {
+        let logger = format!("{}.{}", ROOT_LOGGER, name);
+        self.log.write(&logger, level, msg, site);
+    }
+
+    /// Logs once per file and line.
+    #[deprecated(note = "use `log_once_at`, as calls on one line share their state")]
+    pub fn log_once(&self, level: i8, msg: String, file: &str, line: u32) {
+        let site = unknown_function(file, line);
+        let state = call_site(&site);
+        self.log_once_at(level, msg, &site, &state);
+    }
+
+    /// Logs only the first call with the given call site state.
+    pub fn log_once_at(&self, level: i8, msg: String, site: &LogSite<'_>, state: &LogCallSite) {
+        if self.log.enabled(ROOT_LOGGER, level) && state.once() {
+            self.log_at(level, msg, site);
+        }
+    }
+
+    /// Logs at most once per period, in seconds, per file and line.
+    #[deprecated(note = "use `log_throttle_at`, as calls on one line share their state")]
+    pub fn log_throttle(&self, period: f64, level: i8, msg: String, file: &str, line: u32) {
+        let site = unknown_function(file, line);
+        let state = call_site(&site);
+        self.log_throttle_at(period, level, msg, &site, &state);
+    }
+
+    /// Logs at most once per period, in seconds, of the node's clock per call site state.
+    pub fn log_throttle_at(
+        &self,
+        period: f64,
+        level: i8,
+        msg: String,
+        site: &LogSite<'_>,
+        state: &LogCallSite,
+    ) {
+        if self.log.enabled(ROOT_LOGGER, level) && state.throttle(self.now(), period) {
+            self.log_at(level, msg, site);
+        }
+    }
+
+    /// Like `log_throttle_at`, but skips the first log, so logs start one period after it.
+    pub fn log_delayed_throttle_at(
+        &self,
+        period: f64,
+        level: i8,
+        msg: String,
+        site: &LogSite<'_>,
+        state: &LogCallSite,
+    ) {
+        if self.log.enabled(ROOT_LOGGER, level) && state.delayed_throttle(self.now(), period) {
+            self.log_at(level, msg, site);
+        }
+    }
+
+    /// Logs identical messages at most once per period, in seconds, per file and line.
+    #[deprecated(note = "use `log_throttle_identical_at`, as calls on one line share their state")]
+    pub fn log_throttle_identical(
+        &self,
+        period: f64,
+        level: i8,
+        msg: String,
+        file: &str,
+        line: u32,
+    ) {
+        let site = unknown_function(file, line);
+        let state = call_site(&site);
+        self.log_throttle_identical_at(period, level, msg, &site, &state);
+    }
+
+    /// Logs identical messages at most once per period, in seconds, per call site state.
+    pub fn log_throttle_identical_at(
+        &self,
+        period: f64,
+        level: i8,
+        msg: String,
+        site: &LogSite<'_>,
+        state: &LogCallSite,
+    ) {
+        if !self.log.enabled(ROOT_LOGGER, level) {
+            return;
+        }
+        if state.throttle_identical(self.now(), period, &msg) {
+            self.log_at(level, msg, site);
+        }
+    }
+
+    /// Logs all but the first `count` calls with the given call site state.
+    pub fn log_skip_first_at(
+        &self,
+        count: u64,
+        level: i8,
+        msg: String,
+        site: &LogSite<'_>,
+        state: &LogCallSite,
+    ) {
+        if self.log.enabled(ROOT_LOGGER, level) && state.skip_first(count) {
+            self.log_at(level, msg, site);
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn log_filter_at(
+        &self,
+        filter: &dyn LogFilter,
+        level: i8,
+        msg: String,
+        site: &LogSite<'static>,
+    ) {
+        let mut params = FilterParams {
+            file: site.file,
+            line: site.line,
+            function: site.function,
+            message: msg,
+            level,
+            logger: ROOT_LOGGER.into(),
+        };
+        if filter.is_enabled_for(&mut params) {
+            self.log
+                .write(&params.logger, params.level, params.message, site);
+        }
+    }
+
+    /// Minimum level of the logger, like `ros` for the default logger or `ros.planner`.
+    pub fn logger_level(&self, logger: &str) -> i8 {
+        self.log.levels().level(logger)
+    }
+
+    /// Sets the minimum level of the logger, and the loggers within it without their own.
+    pub fn set_logger_level(&self, logger: &str, level: i8) {
+        self.log.levels().set(logger, level);
+    }
+
+    /// Writes logs to the file from now on, instead of any previous log file.
+    ///
+    /// Nodes connected to a master start out logging to a file in the log directory, like
+    /// `~/.ros/log`, or the path set with `__log:=`.
+    pub fn log_to_file<P: AsRef<Path>>(&self, path: P, options: LogFileOptions) -> Result<()> {
+        let file = LogFile::open(path.as_ref().to_path_buf(), options)?;
+        self.log.set_file(Some(file));
+        Ok(())
+    }
+
+    /// Stops writing logs to a file.
+    pub fn stop_log_file(&self) {
+        self.log.set_file(None);
+    }
+
+    /// Path of the file that logs get written to, if any.
+    pub fn log_file_path(&self) -> Option<PathBuf> {
+        self.log.file_path()
+    }
+
+    /// Logger for the `log` facade, forwarding records to `/rosout` on behalf of this node.
+    ///
+    /// Records of a target, like `my_crate::planner`, go through the `ros.my_crate.planner`
+    /// logger. The logger still needs to be installed, with `RosLogger::install`.
+    pub fn rosout_logger(&self) -> RosLogger {
+        RosLogger::new(self.log.clone())
+    }
+}
+
+/// Handle of a node within a namespace of its own, with remappings of its own.
+///
+/// Publishers, subscribers, services, clients and parameters of a handle resolve their
+/// relative names within its namespace, so components can live in a namespace without
+/// knowing about it. Names remapped by the handle skip the node's remappings, and all other
+/// names go through them as usual.
+///
+/// ```no_run
+/// # fn main() -> rosrust::error::Result<()> {
+/// rosrust::init("robot");
+/// let arm = rosrust::node_handle("arm")?.with_remap("command", "/safety/command")?;
+/// assert_eq!("/arm/state", arm.resolve_name("state")?);
+/// assert_eq!("/safety/command", arm.resolve_name("command")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct NodeHandle {
+    master: Arc<Master>,
+    transport: Transport,
+    param_cache: ParamCache,
+    hostname: String,
+    bind_address: String,
+    resolver: Arc<Resolver>,
+    name: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl NodeHandle {
+    /// Handle for names within the namespace, relative to this handle's namespace.
+    ///
+    /// Remappings of this handle carry over to the child.
+    pub fn child(&self, namespace: &str) -> Result<NodeHandle> {
+        Ok(NodeHandle {
+            resolver: Arc::new(self.resolver.child(namespace)?),
+            ..self.clone()
+        })
+    }
+
+    /// Remaps the name, with both names resolved within the handle's namespace.
+    pub fn with_remap(mut self, source: &str, destination: &str) -> Result<NodeHandle> {
+        Arc::make_mut(&mut self.resolver).map(source, destination)?;
+        Ok(self)
+    }
+
+    /// Namespace of the handle, like `/robot/arm`.
+    pub fn namespace(&self) -> String {
+        self.resolver.namespace()
+    }
+
+    /// Full name that the name resolves to, after remapping.
+    pub fn resolve_name(&self, name: &str) -> Result<String> {
+        self.resolver.translate(name).map_err(Into::into)
+    }
+
+    pub fn param(&self, name: &str) -> Option<Parameter> {
+        self.resolver.translate(name).ok().map(|v| {
+            self.param_cache
+                .lock()
+                .expect(FAILED_TO_LOCK)
+                .add_handle(&v);
+            Parameter {
+                param_cache: Arc::clone(&self.param_cache),
+                master: Arc::clone(&self.master),
+                name: v,
+                cached: true,
+            }
+        })
+    }
+
+    /// Calls the callback with the parameter's new value, whenever the parameter changes.
+    ///
+    /// Changes of parameters within the watched namespace, and of namespaces containing the
+    /// watched parameter, are delivered too. Unset parameters are delivered as `None`. The master
+    /// keeps sending updates until the returned watch gets dropped.
+    pub fn watch_param<F>(&self, name: &str, callback: F) -> Result<ParamWatch>
+    where
+        F: Fn(Option<ParamValue>) + Send + Sync + 'static,
+    {
+        self.watch_param_raw(name, move |value| callback(value.map(Into::into)))
+    }
+
+    /// Like `watch_param`, with values deserialized into the given type.
+    ///
+    /// Values that fail to deserialize get logged and skipped.
+    pub fn watch_param_typed<T, F>(&self, name: &str, callback: F) -> Result<ParamWatch>
+    where
+        T: DeserializeOwned,
+        F: Fn(Option<T>) + Send + Sync + 'static,
+    {
+        let param_name = String::from(name);
+        self.watch_param_raw(name, move |value| match value.map(T::deserialize) {
+            Some(Ok(value)) => callback(Some(value)),
+            Some(Err(err)) => error!("Bad value of watched parameter '{}': {}", param_name, err),
+            None => callback(None),
+        })
+    }
+
+    fn watch_param_raw<F>(&self, name: &str, callback: F) -> Result<ParamWatch>
+    where
+        F: Fn(Option<xml_rpc::Value>) + Send + Sync + 'static,
+    {
+        let name = self.resolver.translate(name)?;
+        ParamWatch::new(
+            Arc::clone(&self.master),
+            Arc::clone(&self.param_cache),
+            &name,
+            Arc::new(callback),
+        )
+    }
+
     pub fn client<T: ServicePair>(&self, service: &str) -> Result<Client<T>> {
         let name = self.resolver.translate(service)?;
-        Ok(Client::new(Arc::clone(&self.master), &self.name, &name))
+        let master = Arc::clone(&self.master);
+        Ok(match &self.transport {
+            Transport::Tcpros(_) => Client::new(master, &self.name, &name),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => {
+                Client::new_in_memory(master, Arc::clone(node.bus()), &self.name, &name)
+            }
+        })
     }
 
     pub fn wait_for_service(
@@ -258,7 +865,7 @@ impl Ros {
         let name = self.resolver.translate(service)?;
         Service::new::<T, F>(
             Arc::clone(&self.master),
-            Arc::clone(&self.slave),
+            self.transport.clone(),
             &self.hostname,
             &self.bind_address,
             &name,
@@ -311,7 +918,7 @@ impl Ros {
         let name = self.resolver.translate(topic)?;
         Subscriber::new::<T, _>(
             Arc::clone(&self.master),
-            Arc::clone(&self.slave),
+            self.transport.clone(),
             &name,
             queue_size,
             CallbackSubscriptionHandler::new(on_message, on_connect),
@@ -334,7 +941,7 @@ impl Ros {
         let name = self.resolver.translate(topic)?;
         Subscriber::new::<T, H>(
             Arc::clone(&self.master),
-            Arc::clone(&self.slave),
+            self.transport.clone(),
             &name,
             queue_size,
             handler,
@@ -375,7 +982,7 @@ impl Ros {
         let name = self.resolver.translate(topic)?;
         Publisher::new(
             Arc::clone(&self.master),
-            Arc::clone(&self.slave),
+            self.transport.clone(),
             Arc::clone(&self.clock),
             &self.bind_address,
             &name,
@@ -383,122 +990,17 @@ impl Ros {
             message_description,
         )
     }
-
-    fn log_to_terminal(&self, level: i8, msg: &str, file: &str, line: u32) {
-        use colored::{Color, Colorize};
-
-        // TODO colorise without allocation
-        macro_rules! format_string {
-            ($prefix:expr, $color:expr) => {
-                format_args!("[{} @ {}:{}]: {}", $prefix, file, line, msg)
-            };
-        }
-
-        match level {
-            Log::DEBUG => println!("{}", format_string!("DEBUG", Color::White)),
-            Log::INFO => println!("{}", format_string!("INFO", Color::White)),
-            Log::WARN => eprintln!("{}", format_string!("WARN", Color::Yellow)),
-            Log::ERROR => eprintln!("{}", format_string!("ERROR", Color::Red)),
-            Log::FATAL => eprintln!("{}", format_string!("FATAL", Color::Red)),
-            _ => {}
-        }
-    }
-
-    pub fn log(&self, level: i8, msg: String, file: &str, line: u32) {
-        self.log_to_terminal(level, &msg, file, line);
-        let maybe_logger = self.logger.lock().unwrap();
-        if let Some(logger) = maybe_logger.deref() {
-            let topics = self.slave.publications.get_topic_names();
-            let message = Log {
-                header: Header::default(),
-                level,
-                msg,
-                name: self.name.clone(),
-                line,
-                file: file.into(),
-                function: String::default(),
-                topics,
-            };
-
-            if let Err(err) = logger.send(message) {
-                error!("Logging error: {}", err);
-            }
-        }
-    }
-
-    pub fn log_once(&self, level: i8, msg: String, file: &str, line: u32) {
-        lazy_static! {
-            static ref UNIQUE_LOGS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
-        }
-        let key = format!("{}:{}", file, line);
-        let mut unique_logs = UNIQUE_LOGS.lock().expect(FAILED_TO_LOCK);
-        if !unique_logs.contains(&key) {
-            unique_logs.insert(key);
-            self.log(level, msg, file, line);
-        }
-    }
-
-    pub fn log_throttle(&self, period: f64, level: i8, msg: String, file: &str, line: u32) {
-        lazy_static! {
-            static ref PERIODIC_LOGS: Mutex<HashMap<String, Time>> = Mutex::new(HashMap::new());
-        }
-        let now = self.now();
-        let key = format!("{}:{}", file, line);
-        let get_next_log_time = |now, period| now + Duration::from_nanos((period * 1e9) as i64);
-        let mut period_logs = PERIODIC_LOGS.lock().expect(FAILED_TO_LOCK);
-        match period_logs.get_mut(&key) {
-            Some(next_log_time) => {
-                if now >= *next_log_time {
-                    *next_log_time = get_next_log_time(*next_log_time, period);
-                    self.log(level, msg, file, line);
-                }
-            }
-            None => {
-                period_logs.insert(key, get_next_log_time(now, period));
-                self.log(level, msg, file, line);
-            }
-        }
-    }
-
-    pub fn log_throttle_identical(
-        &self,
-        period: f64,
-        level: i8,
-        msg: String,
-        file: &str,
-        line: u32,
-    ) {
-        lazy_static! {
-            static ref IDENTICAL_LOGS: Mutex<HashMap<String, (Time, String)>> =
-                Mutex::new(HashMap::new());
-        }
-        let now = self.now();
-        let key = format!("{}:{}", file, line);
-        let get_next_log_time = |now, period| now + Duration::from_nanos((period * 1e9) as i64);
-        let mut identical_logs = IDENTICAL_LOGS.lock().expect(FAILED_TO_LOCK);
-        match identical_logs.get_mut(&key) {
-            Some((next_log_time, previous_msg)) => {
-                if &msg != previous_msg {
-                    *previous_msg = msg.clone();
-                    *next_log_time = get_next_log_time(now, period);
-                    self.log(level, msg, file, line);
-                } else if now >= *next_log_time {
-                    *next_log_time = get_next_log_time(*next_log_time, period);
-                    self.log(level, msg, file, line);
-                }
-            }
-            None => {
-                identical_logs.insert(key, (get_next_log_time(now, period), msg.clone()));
-                self.log(level, msg, file, line);
-            }
-        }
-    }
 }
 
+/// Handle of a parameter on the master.
+///
+/// Reads are cached, and the cached value is kept up to date until the last handle of the
+/// parameter gets dropped, which cancels the parameter's subscription.
 pub struct Parameter {
     param_cache: ParamCache,
     master: Arc<Master>,
     name: String,
+    cached: bool,
 }
 
 impl Parameter {
@@ -512,43 +1014,74 @@ impl Parameter {
     }
 
     pub fn get_raw(&self) -> Response<xml_rpc::Value> {
-        let subscribed;
-        {
-            let cache = self.param_cache.lock().expect(FAILED_TO_LOCK);
-            if let Some(data) = cache.data.get(&self.name) {
-                return data.clone();
-            }
-            subscribed = cache.subscribed;
+        if !self.cached {
+            return self.master.get_param_any(&self.name);
         }
-        if !subscribed {
-            self.master.subscribe_param_any("/")?;
-            self.param_cache.lock().expect(FAILED_TO_LOCK).subscribed = true;
+        let cached = {
+            let mut cache = self.param_cache.lock().expect(FAILED_TO_LOCK);
+            let cached = cache.get(&self.name);
+            if cached.is_none() {
+                cache.reserve(&self.name);
+            }
+            cached
+        };
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                // Subscribing returns the current value, and keeps the cached one up to date
+                let value = self.master.subscribe_param_any(&self.name);
+                let mut cache = self.param_cache.lock().expect(FAILED_TO_LOCK);
+                match value {
+                    Ok(value) => cache.insert(&self.name, value.clone()),
+                    Err(err) => {
+                        cache.release(&self.name);
+                        return Err(err);
+                    }
+                }
+                cache.get(&self.name).unwrap_or(None)
+            }
+        };
+        match value {
+            Some(value) => Ok(value),
+            // The master reports unset parameters as empty namespaces, so it gets asked directly
+            None => self.master.get_param_any(&self.name),
         }
-        let data = self.master.get_param_any(&self.name);
-        self.param_cache
-            .lock()
-            .expect(FAILED_TO_LOCK)
-            .data
-            .insert(self.name.clone(), data.clone());
-        data
+    }
+
+    /// Disables caching, so every read asks the master and no parameter subscription is made.
+    ///
+    /// Cached parameters subscribe to updates of their key, which costs a subscription per key.
+    /// Parameters read only once, or read from many different keys, are better left uncached.
+    pub fn uncached(mut self) -> Self {
+        self.release_cache();
+        self.cached = false;
+        self
+    }
+
+    /// Gets the value without converting it, with integers widened to 64 bits.
+    pub fn get_value(&self) -> Response<ParamValue> {
+        self.get_raw().map(Into::into)
     }
 
     pub fn set<T: Serialize>(&self, value: &T) -> Response<()> {
         self.master.set_param::<T>(&self.name, value)?;
-        self.clear_param_cache();
-        Ok(())
+        self.invalidate_cache(&self.name)
     }
 
     pub fn set_raw(&self, value: xml_rpc::Value) -> Response<()> {
         self.master.set_param_any(&self.name, value)?;
-        self.clear_param_cache();
-        Ok(())
+        self.invalidate_cache(&self.name)
+    }
+
+    /// Sets the value, with integers outside of the 32-bit range stored as doubles while that
+    /// is exact, and as strings otherwise.
+    pub fn set_value(&self, value: ParamValue) -> Response<()> {
+        self.set_raw(value.into())
     }
 
     pub fn delete(&self) -> Response<()> {
         self.master.delete_param(&self.name)?;
-        self.clear_param_cache();
-        Ok(())
+        self.invalidate_cache(&self.name)
     }
 
     pub fn exists(&self) -> Response<bool> {
@@ -559,44 +1092,102 @@ impl Parameter {
         self.master.search_param(&self.name)
     }
 
-    fn clear_param_cache(&self) {
-        self.param_cache.lock().expect(FAILED_TO_LOCK).data.clear();
+    /// Loads a YAML document into the parameter, treating it as a namespace like `rosparam load`.
+    ///
+    /// Mappings are merged into existing namespaces key by key, keys starting with `/` are
+    /// global, and null values delete the parameters they are assigned to. Angles can be
+    /// written as `!degrees 90`, `!radians pi/2`, `deg(90)` or `rad(pi/2)`, and binary data as
+    /// `!!binary` base64 strings.
+    pub fn load_yaml_str(&self, data: &str) -> Result<()> {
+        let assignments = yaml::assignments(&self.name, data)?;
+        for assignment in assignments {
+            let (name, outcome) = match assignment {
+                Assignment::Set(name, value) => {
+                    let outcome = self.master.set_param_any(&name, value.into());
+                    (name, outcome)
+                }
+                Assignment::Delete(name) => {
+                    let outcome = match self.master.has_param(&name) {
+                        Ok(true) => self.master.delete_param(&name).map(|_| ()),
+                        Ok(false) => Ok(()),
+                        Err(err) => Err(err),
+                    };
+                    (name, outcome)
+                }
+            };
+            self.invalidate_cache(&name)?;
+            outcome?;
+        }
+        Ok(())
+    }
+
+    /// Loads a YAML file into the parameter, like `load_yaml_str` does.
+    pub fn load_yaml_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let data = fs::read_to_string(path)?;
+        self.load_yaml_str(&data)
     }
-}
 
-fn yaml_to_xmlrpc(val: Yaml) -> Result<xml_rpc::Value> {
-    Ok(match val {
-        Yaml::Real(v) => xml_rpc::Value::Double(
-            v.parse()
-                .chain_err(|| ErrorKind::BadYamlData("Failed to parse float".into()))?,
-        ),
-        Yaml::Integer(v) => xml_rpc::Value::Int(v as i32),
-        Yaml::String(v) => xml_rpc::Value::String(v),
-        Yaml::Boolean(v) => xml_rpc::Value::Bool(v),
-        Yaml::Array(v) => {
-            xml_rpc::Value::Array(v.into_iter().map(yaml_to_xmlrpc).collect::<Result<_>>()?)
+    /// Dumps the parameter and all parameters within its namespace as a YAML document.
+    pub fn dump_yaml(&self) -> Result<String> {
+        Ok(yaml::dump(&self.get_value()?))
+    }
+
+    /// Drops cached values affected by a local change, along with their subscriptions.
+    ///
+    /// The master pushes local changes too, but only after the call returns, so reads right
+    /// after a change would otherwise see the old value.
+    fn invalidate_cache(&self, name: &str) -> Response<()> {
+        let unused = self
+            .param_cache
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .invalidate(name);
+        for key in unused {
+            self.master.unsubscribe_param(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Drops this handle's claim on the cache, unsubscribing if it was the last one.
+    fn release_cache(&self) {
+        if !self.cached {
+            return;
+        }
+        let unused = self
+            .param_cache
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .remove_handle(&self.name);
+        if unused {
+            if let Err(err) = self.master.unsubscribe_param(&self.name) {
+                error!("Failed to unsubscribe from {}: {:?}", self.name, err);
+            }
         }
-        Yaml::Hash(v) => xml_rpc::Value::Struct(
-            v.into_iter()
-                .map(|(k, v)| Ok((yaml_to_string(k)?, yaml_to_xmlrpc(v)?)))
-                .collect::<Result<_>>()?,
-        ),
-        Yaml::Alias(_) => bail!(ErrorKind::BadYamlData("Alias is not supported".into())),
-        Yaml::Null => bail!(ErrorKind::BadYamlData("Illegal null value".into())),
-        Yaml::BadValue => bail!(ErrorKind::BadYamlData("Bad value provided".into())),
-    })
+    }
+}
+
+impl Drop for Parameter {
+    fn drop(&mut self) {
+        self.release_cache();
+    }
 }
 
-fn yaml_to_string(val: Yaml) -> Result<String> {
-    Ok(match val {
-        Yaml::Real(v) | Yaml::String(v) => v,
-        Yaml::Integer(v) => v.to_string(),
-        Yaml::Boolean(true) => "true".into(),
-        Yaml::Boolean(false) => "false".into(),
-        _ => bail!(ErrorKind::BadYamlData(
-            "Hash keys need to be strings".into()
-        )),
-    })
+fn full_name(namespace: &str, name: &str) -> Result<String> {
+    if name.contains('/') {
+        bail!(ErrorKind::Naming(
+            naming::error::ErrorKind::IllegalCharacter(name.into()),
+        ));
+    }
+    Ok(format!("{}/{}", namespace.trim_end_matches('/'), name))
+}
+
+fn create_logger_and_shutdown_manager() -> (RosoutPublisher, Arc<ShutdownManager>) {
+    let logger: RosoutPublisher = Arc::new(Mutex::new(None));
+    let shutdown_manager = Arc::new(ShutdownManager::new({
+        let logger = Arc::clone(&logger);
+        move || drop(logger.lock().unwrap().take())
+    }));
+    (logger, shutdown_manager)
 }
 
 pub struct Spinner {
@@ -610,3 +1201,11 @@ impl Drop for Spinner {
         }
     }
 }
+
+fn unknown_function<'a>(file: &'a str, line: u32) -> LogSite<'a> {
+    LogSite {
+        file,
+        line,
+        function: "",
+    }
+}
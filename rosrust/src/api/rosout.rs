@@ -0,0 +1,629 @@
+use super::clock::Clock;
+use super::error::{ErrorKind, Result};
+use super::log_file::LogFile;
+use super::raii::Publisher;
+use super::transport::Transport;
+use crate::msg::rosgraph_msgs::Log;
+use crate::msg::std_msgs::Header;
+use crate::util::FAILED_TO_LOCK;
+use error_chain::bail;
+use lazy_static::lazy_static;
+use log::error;
+use ros_message::{Duration, Time};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::{env, thread};
+
+/// Logger that all other loggers descend from, and that the logging macros use by default.
+pub(crate) const ROOT_LOGGER: &str = "ros";
+/// Logger of the library's own messages, like `Rate` overrun warnings.
+pub(crate) const INTERNAL_LOGGER: &str = "ros.rosrust";
+
+/// Terminal output format, unless overridden with `ROSCONSOLE_FORMAT`.
+const DEFAULT_FORMAT: &str = "[${severity} @ ${file}:${line}]: ${message}";
+
+pub(crate) type RosoutPublisher = Arc<Mutex<Option<Publisher<Log>>>>;
+
+/// Location of a logging call, as captured by the logging macros.
+#[derive(Clone, Copy, Debug)]
+pub struct LogSite<'a> {
+    pub file: &'a str,
+    pub line: u32,
+    /// Path of the calling function, or an empty string if unknown.
+    pub function: &'a str,
+}
+
+/// Path of the function that the item is defined in, used by `ros_function_name!`.
+#[doc(hidden)]
+pub fn function_path<T>(_item: T) -> &'static str {
+    let mut name = std::any::type_name::<T>();
+    name = name.strip_suffix("::f").unwrap_or(name);
+    while let Some(outer) = name.strip_suffix("::{{closure}}") {
+        name = outer;
+    }
+    name
+}
+
+/// Destination of a node's logs, writing them to the terminal, the log file and `/rosout`,
+/// as long as their logger's level allows it.
+#[derive(Clone)]
+pub(crate) struct NodeLog {
+    publisher: RosoutPublisher,
+    transport: Transport,
+    name: String,
+    clock: Arc<dyn Clock>,
+    levels: Arc<LoggerLevels>,
+    file: Arc<Mutex<Option<LogFile>>>,
+}
+
+impl NodeLog {
+    pub fn new(
+        publisher: RosoutPublisher,
+        transport: Transport,
+        name: String,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            publisher,
+            transport,
+            name,
+            clock,
+            levels: Arc::new(LoggerLevels::new()),
+            file: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Switches to the clock that stamps records, which only affects clones made afterwards.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    #[inline]
+    pub fn now(&self) -> Time {
+        self.clock.now()
+    }
+
+    pub fn set_publisher(&self, publisher: Publisher<Log>) {
+        *self.publisher.lock().expect(FAILED_TO_LOCK) = Some(publisher);
+    }
+
+    /// Replaces the log file, or stops writing to one with `None`.
+    pub fn set_file(&self, file: Option<LogFile>) {
+        *self.file.lock().expect(FAILED_TO_LOCK) = file;
+    }
+
+    pub fn file_path(&self) -> Option<PathBuf> {
+        self.file
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .as_ref()
+            .map(|file| file.path().to_path_buf())
+    }
+
+    #[inline]
+    pub fn levels(&self) -> &LoggerLevels {
+        &self.levels
+    }
+
+    #[inline]
+    pub fn enabled(&self, logger: &str, level: i8) -> bool {
+        level >= self.levels.level(logger)
+    }
+
+    /// Writes the message to the terminal, the log file and `/rosout`, on behalf of the node.
+    pub fn write(&self, logger: &str, level: i8, msg: String, site: &LogSite<'_>) {
+        if !self.enabled(logger, level) {
+            return;
+        }
+        let stamp = self.now();
+        let record = Record {
+            level,
+            stamp,
+            node: &self.name,
+            logger,
+            msg: &msg,
+            site,
+        };
+        log_to_terminal(&record);
+        let file_result = match self.file.lock().expect(FAILED_TO_LOCK).as_mut() {
+            Some(file) => file.write_line(&file_line(&record)),
+            None => Ok(()),
+        };
+        let result = {
+            let maybe_publisher = self.publisher.lock().expect(FAILED_TO_LOCK);
+            match maybe_publisher.as_ref() {
+                Some(publisher) => {
+                    let topics = self.transport.publication_names();
+                    let message = Log {
+                        header: Header {
+                            stamp,
+                            ..Header::default()
+                        },
+                        level,
+                        msg,
+                        name: self.name.clone(),
+                        line: site.line,
+                        file: site.file.into(),
+                        function: site.function.into(),
+                        topics,
+                    };
+                    publisher.send(message)
+                }
+                None => Ok(()),
+            }
+        };
+        // Logged outside of the locks, since the `log` facade might be forwarding here
+        if let Err(err) = file_result {
+            error!("Log file error: {}", err);
+        }
+        if let Err(err) = result {
+            error!("Logging error: {}", err);
+        }
+    }
+}
+
+/// Minimum levels of a node's named loggers.
+///
+/// Loggers form a hierarchy by their dot separated names, like `ros.planner` within `ros`.
+/// Loggers without a level of their own use the level of their closest ancestor that has one.
+pub(crate) struct LoggerLevels {
+    levels: Mutex<BTreeMap<String, Option<i8>>>,
+}
+
+impl LoggerLevels {
+    fn new() -> Self {
+        let mut levels = BTreeMap::new();
+        // Like roscpp and rospy, only info and above gets logged by default
+        levels.insert(ROOT_LOGGER.into(), Some(Log::INFO));
+        Self {
+            levels: Mutex::new(levels),
+        }
+    }
+
+    /// Minimum level of the logger, which gets listed among the known loggers from now on.
+    pub fn level(&self, logger: &str) -> i8 {
+        let mut levels = self.levels.lock().expect(FAILED_TO_LOCK);
+        if !levels.contains_key(logger) {
+            levels.insert(logger.into(), None);
+        }
+        effective_level(&levels, logger)
+    }
+
+    pub fn set(&self, logger: &str, level: i8) {
+        self.levels
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .insert(logger.into(), Some(level));
+    }
+
+    /// Known loggers, with their minimum levels.
+    pub fn loggers(&self) -> Vec<(String, i8)> {
+        let levels = self.levels.lock().expect(FAILED_TO_LOCK);
+        levels
+            .keys()
+            .map(|logger| (logger.clone(), effective_level(&levels, logger)))
+            .collect()
+    }
+
+    /// Applies levels like `info,ros.planner=debug`, where a bare level is for the root logger.
+    pub fn apply(&self, spec: &str) -> Result<()> {
+        let mut assignments = vec![];
+        for item in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let (logger, level) = match item.find('=') {
+                Some(index) => (item[..index].trim(), item[index + 1..].trim()),
+                None => (ROOT_LOGGER, item),
+            };
+            match parse_level(level) {
+                Some(level) if !logger.is_empty() => assignments.push((logger, level)),
+                _ => bail!(ErrorKind::InvalidLoggerLevel(item.into())),
+            }
+        }
+        for (logger, level) in assignments {
+            self.set(logger, level);
+        }
+        Ok(())
+    }
+}
+
+fn effective_level(levels: &BTreeMap<String, Option<i8>>, logger: &str) -> i8 {
+    let mut name = logger;
+    loop {
+        if let Some(Some(level)) = levels.get(name) {
+            return *level;
+        }
+        match name.rfind('.') {
+            Some(index) => name = &name[..index],
+            None => return levels[ROOT_LOGGER].unwrap_or(Log::INFO),
+        }
+    }
+}
+
+/// Parses level names used by `rqt_logger_level`, ignoring their case.
+pub(crate) fn parse_level(name: &str) -> Option<i8> {
+    match name.to_lowercase().as_str() {
+        "debug" => Some(Log::DEBUG),
+        "info" => Some(Log::INFO),
+        "warn" => Some(Log::WARN),
+        "error" => Some(Log::ERROR),
+        "fatal" => Some(Log::FATAL),
+        _ => None,
+    }
+}
+
+pub(crate) fn level_name(level: i8) -> &'static str {
+    match level {
+        Log::DEBUG => "debug",
+        Log::INFO => "info",
+        Log::WARN => "warn",
+        Log::ERROR => "error",
+        _ => "fatal",
+    }
+}
+
+/// State of a logging call site, for logging it once, throttling it or skipping it.
+///
+/// The logging macros keep one in a static of each call site, so calls on the same line
+/// don't share it. Calls of functions like `log_once_at` need one of their own, which can be a
+/// `static` as well, since `new` is a `const fn`.
+pub struct LogCallSite {
+    state: Mutex<CallSiteState>,
+}
+
+struct CallSiteState {
+    hits: u64,
+    next_log_time: Option<Time>,
+    previous_msg: Option<String>,
+}
+
+impl LogCallSite {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(CallSiteState {
+                hits: 0,
+                next_log_time: None,
+                previous_msg: None,
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CallSiteState> {
+        self.state.lock().expect(FAILED_TO_LOCK)
+    }
+
+    /// Whether this is the first log of the call site.
+    pub(crate) fn once(&self) -> bool {
+        self.hit() == 1
+    }
+
+    /// Whether a log of the call site is due, with the first `count` of them skipped.
+    pub(crate) fn skip_first(&self, count: u64) -> bool {
+        self.hit() > count
+    }
+
+    /// Counts a log of the call site, returning how many there have been.
+    fn hit(&self) -> u64 {
+        let mut state = self.state();
+        state.hits = state.hits.saturating_add(1);
+        state.hits
+    }
+
+    /// Whether a log of the call site is due, with logs written at most once per period.
+    pub(crate) fn throttle(&self, now: Time, period: f64) -> bool {
+        let mut state = self.state();
+        match state.next_log_time {
+            Some(next_log_time) => {
+                if now >= next_log_time {
+                    state.next_log_time = Some(next_log_time + period_duration(period));
+                    true
+                } else {
+                    false
+                }
+            }
+            None => {
+                state.next_log_time = Some(now + period_duration(period));
+                true
+            }
+        }
+    }
+
+    /// Like `throttle`, but with the first log skipped, so logs start a period after it.
+    pub(crate) fn delayed_throttle(&self, now: Time, period: f64) -> bool {
+        {
+            let mut state = self.state();
+            if state.next_log_time.is_none() {
+                state.next_log_time = Some(now + period_duration(period));
+                return false;
+            }
+        }
+        self.throttle(now, period)
+    }
+
+    /// Whether a log of the call site is due, with identical messages written at most once per
+    /// period.
+    pub(crate) fn throttle_identical(&self, now: Time, period: f64, msg: &str) -> bool {
+        let mut state = self.state();
+        if state.previous_msg.as_deref() != Some(msg) {
+            state.previous_msg = Some(msg.into());
+            state.next_log_time = Some(now + period_duration(period));
+            return true;
+        }
+        match state.next_log_time {
+            Some(next_log_time) if now >= next_log_time => {
+                state.next_log_time = Some(next_log_time + period_duration(period));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for LogCallSite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn period_duration(period: f64) -> Duration {
+    Duration::from_nanos((period * 1e9) as i64)
+}
+
+/// State of a call site without a static of its own, like logs of the `log` facade, known by
+/// its file, line and function.
+pub(crate) fn call_site(site: &LogSite<'_>) -> Arc<LogCallSite> {
+    lazy_static! {
+        static ref CALL_SITES: Mutex<HashMap<(String, u32, String), Arc<LogCallSite>>> =
+            Mutex::new(HashMap::new());
+    }
+    let key = (site.file.into(), site.line, site.function.into());
+    let mut call_sites = CALL_SITES.lock().expect(FAILED_TO_LOCK);
+    Arc::clone(call_sites.entry(key).or_default())
+}
+
+/// Log message, with everything that the terminal format can refer to.
+struct Record<'a> {
+    level: i8,
+    stamp: Time,
+    node: &'a str,
+    logger: &'a str,
+    msg: &'a str,
+    site: &'a LogSite<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+enum FormatToken {
+    Text(String),
+    Severity,
+    Time,
+    WallTime,
+    Thread,
+    Node,
+    Logger,
+    Message,
+    File,
+    Line,
+    Function,
+}
+
+/// Splits a `ROSCONSOLE_FORMAT` string into tokens, keeping unknown `${...}` tokens as text.
+fn parse_format(format: &str) -> Vec<FormatToken> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let token = match &rest[start + 2..end] {
+            "severity" => FormatToken::Severity,
+            "time" => FormatToken::Time,
+            "walltime" => FormatToken::WallTime,
+            "thread" => FormatToken::Thread,
+            "node" => FormatToken::Node,
+            "logger" => FormatToken::Logger,
+            "message" => FormatToken::Message,
+            "file" => FormatToken::File,
+            "line" => FormatToken::Line,
+            "function" => FormatToken::Function,
+            _ => {
+                text.push_str(&rest[..=end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        };
+        text.push_str(&rest[..start]);
+        if !text.is_empty() {
+            tokens.push(FormatToken::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(token);
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(FormatToken::Text(text));
+    }
+    tokens
+}
+
+fn format_record(tokens: &[FormatToken], record: &Record<'_>) -> String {
+    use std::fmt::Write;
+
+    let mut output = String::new();
+    for token in tokens {
+        let _ = match token {
+            FormatToken::Text(text) => write!(output, "{}", text),
+            FormatToken::Severity => write!(output, "{}", severity_name(record.level)),
+            FormatToken::Time => write!(output, "{}", record.stamp),
+            FormatToken::WallTime => write!(output, "{}", crate::wall_time::now()),
+            FormatToken::Thread => write!(output, "{:?}", thread::current().id()),
+            FormatToken::Node => write!(output, "{}", record.node),
+            FormatToken::Logger => write!(output, "{}", record.logger),
+            FormatToken::Message => write!(output, "{}", record.msg),
+            FormatToken::File => write!(output, "{}", record.site.file),
+            FormatToken::Line => write!(output, "{}", record.site.line),
+            FormatToken::Function => write!(output, "{}", record.site.function),
+        };
+    }
+    output
+}
+
+/// Line of a log file, with newlines escaped to keep one record per line.
+fn file_line(record: &Record<'_>) -> String {
+    let mut line = format!(
+        "[{}] [{}] [{}] [{}] [{}:{}]",
+        severity_name(record.level),
+        record.stamp,
+        record.node,
+        record.logger,
+        record.site.file,
+        record.site.line,
+    );
+    if !record.site.function.is_empty() {
+        line.push_str(&format!(" [{}]", record.site.function));
+    }
+    line.push_str(": ");
+    line.push_str(&record.msg.replace('\\', "\\\\").replace('\n', "\\n"));
+    line
+}
+
+fn severity_name(level: i8) -> &'static str {
+    match level {
+        Log::DEBUG => "DEBUG",
+        Log::INFO => "INFO",
+        Log::WARN => "WARN",
+        Log::ERROR => "ERROR",
+        _ => "FATAL",
+    }
+}
+
+fn log_to_terminal(record: &Record<'_>) {
+    use colored::{Color, Colorize};
+
+    lazy_static! {
+        static ref FORMAT: Vec<FormatToken> =
+            parse_format(&env::var("ROSCONSOLE_FORMAT").unwrap_or_else(|_| DEFAULT_FORMAT.into()));
+    }
+
+    let output = format_record(&FORMAT, record);
+    match record.level {
+        Log::DEBUG => println!("{}", output.color(Color::Green)),
+        Log::INFO => println!("{}", output),
+        Log::WARN => eprintln!("{}", output.color(Color::Yellow)),
+        Log::ERROR => eprintln!("{}", output.color(Color::Red)),
+        Log::FATAL => eprintln!("{}", output.color(Color::Red)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record<'a>(site: &'a LogSite<'a>) -> Record<'a> {
+        Record {
+            level: Log::WARN,
+            stamp: Time::from_nanos(12_500_000_000),
+            node: "/talker",
+            logger: "ros.planner",
+            msg: "no path",
+            site,
+        }
+    }
+
+    #[test]
+    fn default_format_matches_previous_output() {
+        let site = LogSite {
+            file: "src/main.rs",
+            line: 12,
+            function: "talker::main",
+        };
+        let tokens = parse_format(DEFAULT_FORMAT);
+        assert_eq!(
+            "[WARN @ src/main.rs:12]: no path",
+            format_record(&tokens, &record(&site))
+        );
+    }
+
+    #[test]
+    fn formats_all_tokens() {
+        let site = LogSite {
+            file: "src/main.rs",
+            line: 12,
+            function: "talker::main",
+        };
+        let tokens = parse_format(
+            "${severity} ${time} ${node} ${logger} ${file}:${line} ${function}: ${message}",
+        );
+        assert_eq!(
+            "WARN 12.500000000 /talker ros.planner src/main.rs:12 talker::main: no path",
+            format_record(&tokens, &record(&site))
+        );
+    }
+
+    #[test]
+    fn file_lines_hold_one_record_each() {
+        let site = LogSite {
+            file: "src/main.rs",
+            line: 12,
+            function: "talker::main",
+        };
+        let mut record = record(&site);
+        record.msg = "no path\\nto goal\nretrying";
+        assert_eq!(
+            "[WARN] [12.500000000] [/talker] [ros.planner] [src/main.rs:12] [talker::main]: \
+             no path\\\\nto goal\\nretrying",
+            file_line(&record)
+        );
+    }
+
+    #[test]
+    fn call_sites_keep_separate_state() {
+        let first = LogCallSite::new();
+        let second = LogCallSite::new();
+        let at = |seconds: f64| Time::from_nanos((seconds * 1e9) as i64);
+        assert!(first.throttle(at(1.0), 1.0));
+        assert!(!first.throttle(at(1.5), 1.0));
+        assert!(second.throttle(at(1.5), 1.0));
+        assert!(first.throttle(at(2.0), 1.0));
+
+        let delayed = LogCallSite::new();
+        assert!(!delayed.delayed_throttle(at(1.0), 1.0));
+        assert!(!delayed.delayed_throttle(at(1.5), 1.0));
+        assert!(delayed.delayed_throttle(at(2.0), 1.0));
+
+        let identical = LogCallSite::new();
+        assert!(identical.throttle_identical(at(1.0), 1.0, "a"));
+        assert!(!identical.throttle_identical(at(1.5), 1.0, "a"));
+        assert!(identical.throttle_identical(at(1.5), 1.0, "b"));
+    }
+
+    #[test]
+    fn keeps_unknown_tokens_as_text() {
+        assert_eq!(
+            vec![
+                FormatToken::Text("<${unknown} ".into()),
+                FormatToken::Message,
+                FormatToken::Text("> ${unclosed".into()),
+            ],
+            parse_format("<${unknown} ${message}> ${unclosed")
+        );
+    }
+
+    #[test]
+    fn function_path_skips_closures() {
+        let in_closure = || {
+            fn f() {}
+            function_path(f)
+        };
+        let name = in_closure();
+        assert_eq!(
+            "rosrust::api::rosout::tests::function_path_skips_closures",
+            name
+        );
+    }
+}
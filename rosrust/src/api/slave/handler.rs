@@ -1,8 +1,9 @@
+use super::param_cache::{self, ParamCache};
 use super::publications::PublicationsTracker;
 use super::subscriptions::SubscriptionsTracker;
 use crate::rosxmlrpc::{self, Response, ResponseError, Server};
 use crate::tcpros::Service;
-use crate::util::{kill, FAILED_TO_LOCK};
+use crate::util::kill;
 use log::{error, info};
 use std::collections::HashMap;
 use std::net::SocketAddr;
@@ -23,14 +24,6 @@ fn unwrap_array_case(params: Params) -> Params {
     params
 }
 
-#[derive(Default)]
-pub struct ParamCacheState {
-    pub data: HashMap<String, Response<Value>>,
-    pub subscribed: bool,
-}
-
-pub type ParamCache = Arc<Mutex<ParamCacheState>>;
-
 impl SlaveHandler {
     pub fn new(
         master_uri: &str,
@@ -127,20 +120,15 @@ impl SlaveHandler {
                     ))
                 }
             };
-            let _parameter_value = match args.next() {
+            let parameter_value = match args.next() {
                 Some(parameter_value) => parameter_value,
                 _ => {
                     return Err(ResponseError::Client(
-                        "Missing argument 'parameter_key'".into(),
+                        "Missing argument 'parameter_value'".into(),
                     ))
                 }
             };
-            let key = parameter_key.trim_end_matches('/');
-            param_cache
-                .lock()
-                .expect(FAILED_TO_LOCK)
-                .data
-                .retain(|k, _| !k.starts_with(key) && !key.starts_with(k));
+            param_cache::update(&param_cache, &parameter_key, parameter_value);
             Ok(Value::Int(0))
         });
 
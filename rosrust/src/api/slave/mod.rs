@@ -1,9 +1,10 @@
 mod handler;
+mod param_cache;
 mod publications;
 mod subscriptions;
 
-pub use self::handler::ParamCache;
 use self::handler::SlaveHandler;
+pub use self::param_cache::{update as update_param_cache, ParamCache, ParamCallback};
 use super::error::{self, ErrorKind, Result};
 use crate::api::ShutdownManager;
 use crate::tcpros::{Message, PublisherStream, Service, ServicePair, ServiceResult};
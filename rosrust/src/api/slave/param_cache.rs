@@ -0,0 +1,371 @@
+use crate::util::FAILED_TO_LOCK;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use xml_rpc::Value;
+
+/// Callback of a parameter watch, receiving the parameter's new value.
+pub type ParamCallback = Arc<dyn Fn(Option<Value>) + Send + Sync>;
+
+struct ParamWatcher {
+    key: String,
+    value: Option<Value>,
+    // Set by the first update, which is newer than the value the watch subscribed with
+    updated: bool,
+    callback: ParamCallback,
+}
+
+/// Cached parameter values and watchers of a node.
+///
+/// The node is subscribed to every key that has a cached value or a watcher, and the master
+/// pushes updates of those keys, so cached values never go stale. Values stay cached while
+/// parameter handles of their key exist.
+#[derive(Default)]
+pub struct ParamCacheState {
+    data: HashMap<String, Option<Value>>,
+    handles: HashMap<String, usize>,
+    // Updates that arrive while subscribing, which can be newer than the subscription's result
+    pending: HashMap<String, Vec<(String, Value)>>,
+    watchers: BTreeMap<usize, ParamWatcher>,
+    next_watcher_id: usize,
+}
+
+impl ParamCacheState {
+    /// Cached value of the key, with `Some(None)` meaning that the parameter is unset.
+    pub fn get(&self, key: &str) -> Option<Option<Value>> {
+        self.data.get(&with_trailing_slash(key)).cloned()
+    }
+
+    /// Marks a key as about to be cached, so its subscription is kept while subscribing.
+    pub fn reserve(&mut self, key: &str) {
+        self.pending.entry(with_trailing_slash(key)).or_default();
+    }
+
+    /// Drops the reservation of a key whose subscription failed.
+    pub fn release(&mut self, key: &str) {
+        self.pending.remove(&with_trailing_slash(key));
+    }
+
+    /// Caches the value of a key that the node just subscribed to.
+    ///
+    /// Updates pushed while subscribing get applied on top of the value, and a value cached by
+    /// a concurrent subscription is kept, as both are at least as new as the given one.
+    pub fn insert(&mut self, key: &str, value: Value) {
+        let key = with_trailing_slash(key);
+        let updates = self.pending.remove(&key).unwrap_or_default();
+        if self.data.contains_key(&key) {
+            return;
+        }
+        let value = updates
+            .into_iter()
+            .fold(non_empty(value), |current, (updated_key, update)| {
+                updated_value(&key, &current, &updated_key, &update).unwrap_or(current)
+            });
+        self.data.insert(key, value);
+    }
+
+    /// Drops cached values affected by a change of the given key.
+    ///
+    /// Returns the dropped keys that no watcher needs, so their subscriptions can be cancelled.
+    pub fn invalidate(&mut self, key: &str) -> Vec<String> {
+        let key = with_trailing_slash(key);
+        let dropped = self
+            .data
+            .keys()
+            .filter(|k| k.starts_with(&key) || key.starts_with(k.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        for k in &dropped {
+            self.data.remove(k);
+        }
+        dropped
+            .into_iter()
+            .filter(|k| !self.is_subscription_needed(k))
+            .map(|k| master_key(&k))
+            .collect()
+    }
+
+    /// Counts a new parameter handle of the key.
+    pub fn add_handle(&mut self, key: &str) {
+        *self.handles.entry(with_trailing_slash(key)).or_default() += 1;
+    }
+
+    /// Drops a parameter handle of the key, along with the cached value once no handles are left.
+    ///
+    /// Returns `true` if a value got dropped and no watcher needs the key, so its subscription
+    /// can be cancelled.
+    pub fn remove_handle(&mut self, key: &str) -> bool {
+        let key = with_trailing_slash(key);
+        let count = match self.handles.get_mut(&key) {
+            Some(count) => count,
+            None => return false,
+        };
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.handles.remove(&key);
+        self.data.remove(&key).is_some() && !self.is_subscription_needed(&key)
+    }
+
+    fn is_subscription_needed(&self, key: &str) -> bool {
+        self.data.contains_key(key)
+            || self.pending.contains_key(key)
+            || self.watchers.values().any(|watcher| watcher.key == key)
+    }
+
+    /// Registers a callback for changes of the given parameter, returning the watcher's ID.
+    pub fn add_watcher(&mut self, key: &str, callback: ParamCallback) -> usize {
+        let id = self.next_watcher_id;
+        self.next_watcher_id += 1;
+        self.watchers.insert(
+            id,
+            ParamWatcher {
+                key: with_trailing_slash(key),
+                value: None,
+                updated: false,
+                callback,
+            },
+        );
+        id
+    }
+
+    /// Sets the value that a watcher compares updates against, unless it got an update already.
+    pub fn set_watched_value(&mut self, id: usize, value: Value) {
+        if let Some(watcher) = self.watchers.get_mut(&id) {
+            if !watcher.updated {
+                watcher.value = non_empty(value);
+            }
+        }
+    }
+
+    /// Removes a watcher, returning `true` if its key isn't needed by the cache or other watchers.
+    pub fn remove_watcher(&mut self, id: usize) -> bool {
+        match self.watchers.remove(&id) {
+            Some(watcher) => !self.is_subscription_needed(&watcher.key),
+            None => false,
+        }
+    }
+
+    /// Applies an update to cached values of the key, its namespaces and its children.
+    ///
+    /// Keys that are still being subscribed to keep the update for when their value arrives.
+    fn update_data(&mut self, key: &str, value: &Value) {
+        let key = with_trailing_slash(key);
+        for (cached_key, cached_value) in self.data.iter_mut() {
+            if let Some(new_value) = updated_value(cached_key, cached_value, &key, value) {
+                *cached_value = new_value;
+            }
+        }
+        for (pending_key, updates) in self.pending.iter_mut() {
+            if pending_key.starts_with(&key) || key.starts_with(pending_key.as_str()) {
+                updates.push((key.clone(), value.clone()));
+            }
+        }
+    }
+
+    /// Applies an update to all affected watchers, returning callbacks that need to be called.
+    fn update_watchers(&mut self, key: &str, value: &Value) -> Vec<(ParamCallback, Option<Value>)> {
+        let key = with_trailing_slash(key);
+        let mut notifications = vec![];
+        for watcher in self.watchers.values_mut() {
+            let new_value = match updated_value(&watcher.key, &watcher.value, &key, value) {
+                Some(new_value) => new_value,
+                None => continue,
+            };
+            watcher.updated = true;
+            // Nodes get the same update once per matching subscription, so repeats are skipped
+            if new_value != watcher.value {
+                watcher.value = new_value.clone();
+                notifications.push((Arc::clone(&watcher.callback), new_value));
+            }
+        }
+        notifications
+    }
+}
+
+pub type ParamCache = Arc<Mutex<ParamCacheState>>;
+
+/// Handles a parameter update pushed by the master.
+///
+/// Cached values get updated, and watchers get called with their parameter's new value.
+/// Callbacks are called after releasing the cache, so they can access parameters themselves.
+pub fn update(param_cache: &ParamCache, key: &str, value: Value) {
+    let notifications = {
+        let mut cache = param_cache.lock().expect(FAILED_TO_LOCK);
+        cache.update_data(key, &value);
+        cache.update_watchers(key, &value)
+    };
+    for (callback, value) in notifications {
+        callback(value);
+    }
+}
+
+/// Computes the new value of a subscribed key after an update of the given key.
+///
+/// Returns `None` if the update doesn't affect the subscribed key.
+fn updated_value(
+    subscribed_key: &str,
+    current: &Option<Value>,
+    key: &str,
+    value: &Value,
+) -> Option<Option<Value>> {
+    if let Some(path) = subscribed_key.strip_prefix(key) {
+        Some(
+            split(path)
+                .into_iter()
+                .try_fold(value, |value, part| match value {
+                    Value::Struct(children) => children.get(part),
+                    _ => None,
+                })
+                .cloned()
+                .and_then(non_empty),
+        )
+    } else {
+        key.strip_prefix(subscribed_key)
+            .map(|path| replace_child(current.clone(), &split(path), value))
+    }
+}
+
+/// Returns the value with the child at the given path replaced by the update.
+///
+/// The master reports deleted parameters with an empty struct, so those get removed instead.
+fn replace_child(value: Option<Value>, path: &[&str], update: &Value) -> Option<Value> {
+    let (last, namespaces) = match path.split_last() {
+        Some(v) => v,
+        None => return non_empty(update.clone()),
+    };
+    let mut root = match value {
+        Some(Value::Struct(children)) => children,
+        _ => HashMap::new(),
+    };
+    let mut node = &mut root;
+    for namespace in namespaces {
+        let entry = node
+            .entry(String::from(*namespace))
+            .or_insert_with(|| Value::Struct(HashMap::new()));
+        if !matches!(entry, Value::Struct(_)) {
+            *entry = Value::Struct(HashMap::new());
+        }
+        node = match entry {
+            Value::Struct(children) => children,
+            _ => unreachable!(),
+        };
+    }
+    match non_empty(update.clone()) {
+        Some(update) => node.insert(String::from(*last), update),
+        None => node.remove(*last),
+    };
+    non_empty(Value::Struct(root))
+}
+
+/// Maps empty structs, which the master uses for unset parameters, to `None`.
+fn non_empty(value: Value) -> Option<Value> {
+    match value {
+        Value::Struct(ref children) if children.is_empty() => None,
+        value => Some(value),
+    }
+}
+
+fn split(key: &str) -> Vec<&str> {
+    key.split('/').filter(|v| !v.is_empty()).collect()
+}
+
+/// Converts an internal key into the name that the master expects.
+fn master_key(key: &str) -> String {
+    match key.trim_end_matches('/') {
+        "" => "/".into(),
+        key => key.into(),
+    }
+}
+
+fn with_trailing_slash(key: &str) -> String {
+    format!("{}/", key.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_with_pending(key: &str) -> ParamCache {
+        let cache: ParamCache = Default::default();
+        cache.lock().unwrap().reserve(key);
+        cache
+    }
+
+    #[test]
+    fn keeps_updates_that_arrive_while_subscribing() {
+        let cache = cache_with_pending("/a");
+        update(&cache, "/a", Value::Int(2));
+        cache.lock().unwrap().insert("/a", Value::Int(1));
+        assert_eq!(Some(Some(Value::Int(2))), cache.lock().unwrap().get("/a"));
+    }
+
+    #[test]
+    fn applies_child_updates_that_arrive_while_subscribing() {
+        let cache = cache_with_pending("/a");
+        update(&cache, "/a/b", Value::Int(2));
+        update(&cache, "/a/c", Value::Struct(HashMap::new()));
+        let mut snapshot = HashMap::new();
+        snapshot.insert(String::from("b"), Value::Int(1));
+        snapshot.insert(String::from("c"), Value::Int(3));
+        cache.lock().unwrap().insert("/a", Value::Struct(snapshot));
+
+        let mut expected = HashMap::new();
+        expected.insert(String::from("b"), Value::Int(2));
+        assert_eq!(
+            Some(Some(Value::Struct(expected))),
+            cache.lock().unwrap().get("/a")
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_updates_while_subscribing() {
+        let cache = cache_with_pending("/a");
+        update(&cache, "/ab", Value::Int(2));
+        cache.lock().unwrap().insert("/a", Value::Int(1));
+        assert_eq!(Some(Some(Value::Int(1))), cache.lock().unwrap().get("/a"));
+    }
+
+    #[test]
+    fn keeps_watched_values_from_updates_that_arrive_while_subscribing() {
+        let cache: ParamCache = Default::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let id = cache.lock().unwrap().add_watcher(
+            "/a",
+            Arc::new(move |value| tx.lock().unwrap().send(value).unwrap()),
+        );
+        update(&cache, "/a", Value::Int(2));
+        cache.lock().unwrap().set_watched_value(id, Value::Int(1));
+        update(&cache, "/a", Value::Int(2));
+        assert_eq!(vec![Some(Value::Int(2))], rx.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keeps_values_cached_by_concurrent_subscriptions() {
+        let cache = cache_with_pending("/a");
+        cache.lock().unwrap().reserve("/a");
+        cache.lock().unwrap().insert("/a", Value::Int(1));
+        update(&cache, "/a", Value::Int(2));
+        cache.lock().unwrap().insert("/a", Value::Int(1));
+        assert_eq!(Some(Some(Value::Int(2))), cache.lock().unwrap().get("/a"));
+    }
+
+    #[test]
+    fn drops_values_with_their_last_handle() {
+        let mut cache = ParamCacheState::default();
+        cache.add_handle("/a");
+        cache.add_handle("/a");
+        cache.insert("/a", Value::Int(1));
+        assert!(!cache.remove_handle("/a"));
+        assert_eq!(Some(Some(Value::Int(1))), cache.get("/a"));
+        assert!(cache.remove_handle("/a"));
+        assert_eq!(None, cache.get("/a"));
+
+        cache.add_handle("/b");
+        cache.insert("/b", Value::Int(1));
+        cache.add_watcher("/b", Arc::new(|_| {}));
+        assert!(!cache.remove_handle("/b"));
+        assert_eq!(None, cache.get("/b"));
+    }
+}
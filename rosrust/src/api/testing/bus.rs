@@ -0,0 +1,496 @@
+use crate::api::error::{self, ErrorKind, Result};
+use crate::rosmsg::RosMsg;
+use crate::tcpros::{self, handle_data, MessageInfo, ServicePair, ServiceResult};
+use crate::util::lossy_channel::{lossy_channel, LossySender};
+use crate::util::FAILED_TO_LOCK;
+use crate::{Message, RawMessageDescription, SubscriptionHandler};
+use crossbeam::channel::{unbounded, Sender};
+use error_chain::bail;
+use log::error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type ServiceHandler =
+    Arc<dyn Fn(&[u8]) -> std::io::Result<ServiceResult<Vec<u8>>> + Send + Sync + 'static>;
+
+/// Replacement for TCPROS connections, passing data between nodes of a test kit directly.
+#[derive(Default)]
+pub struct MemoryBus {
+    state: Mutex<BusState>,
+}
+
+#[derive(Default)]
+struct BusState {
+    topics: BTreeMap<String, TopicState>,
+    services: BTreeMap<String, ServiceState>,
+    next_subscription_id: usize,
+}
+
+#[derive(Default)]
+struct TopicState {
+    publications: BTreeMap<String, Publication>,
+    subscriptions: BTreeMap<usize, Subscription>,
+}
+
+struct Publication {
+    message_description: RawMessageDescription,
+    last_message: Option<Arc<Vec<u8>>>,
+}
+
+struct Subscription {
+    caller_id: String,
+    msg_type: String,
+    md5sum: String,
+    data: LossySender<MessageInfo>,
+    connections: Sender<HashMap<String, String>>,
+}
+
+struct ServiceState {
+    caller_id: String,
+    md5sum: String,
+    handler: ServiceHandler,
+}
+
+impl Subscription {
+    fn accepts(&self, message_description: &RawMessageDescription) -> bool {
+        header_matches(&self.md5sum, &message_description.md5sum)
+            && header_matches(&self.msg_type, &message_description.msg_type)
+    }
+
+    fn connect(&self, caller_id: &str, topic: &str, publication: &Publication) {
+        let description = &publication.message_description;
+        let mut headers = HashMap::new();
+        headers.insert(String::from("callerid"), String::from(caller_id));
+        headers.insert(String::from("topic"), String::from(topic));
+        headers.insert(String::from("type"), description.msg_type.clone());
+        headers.insert(String::from("md5sum"), description.md5sum.clone());
+        headers.insert(
+            String::from("message_definition"),
+            description.msg_definition.clone(),
+        );
+        if self.connections.send(headers).is_err() {
+            error!("Failed to send connection info for subscriber");
+        }
+        if let Some(data) = &publication.last_message {
+            self.deliver(caller_id, data);
+        }
+    }
+
+    fn deliver(&self, caller_id: &str, data: &[u8]) {
+        let info = MessageInfo::new(Arc::new(caller_id.into()), data.to_vec());
+        if self.data.try_send(info).is_err() {
+            error!("Failed to send data to subscriber");
+        }
+    }
+}
+
+fn header_matches(first: &str, second: &str) -> bool {
+    first == "*" || second == "*" || first == second
+}
+
+/// URI under which a node of a test kit is known to the in-memory master.
+pub fn node_uri(caller_id: &str) -> String {
+    format!("memory://{}", caller_id.trim_start_matches('/'))
+}
+
+impl MemoryBus {
+    fn add_publication(
+        &self,
+        caller_id: &str,
+        topic: &str,
+        message_description: RawMessageDescription,
+    ) -> tcpros::error::Result<()> {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        let topic_state = state.topics.entry(topic.into()).or_default();
+        if let Some(publication) = topic_state.publications.get(caller_id) {
+            let msg_type = &publication.message_description.msg_type;
+            if *msg_type != message_description.msg_type {
+                bail!(tcpros::error::ErrorKind::MessageTypeMismatch(
+                    msg_type.clone(),
+                    message_description.msg_type,
+                ));
+            }
+            return Ok(());
+        }
+        let publication = Publication {
+            message_description,
+            last_message: None,
+        };
+        for subscription in topic_state.subscriptions.values() {
+            if subscription.accepts(&publication.message_description) {
+                subscription.connect(caller_id, topic, &publication);
+            }
+        }
+        topic_state
+            .publications
+            .insert(caller_id.into(), publication);
+        Ok(())
+    }
+
+    fn remove_publication(&self, caller_id: &str, topic: &str) {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        if let Some(topic_state) = state.topics.get_mut(topic) {
+            topic_state.publications.remove(caller_id);
+        }
+    }
+
+    fn publish(&self, caller_id: &str, topic: &str, data: Vec<u8>, latching: bool) {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        let topic_state = match state.topics.get_mut(topic) {
+            Some(v) => v,
+            None => return,
+        };
+        let publication = match topic_state.publications.get_mut(caller_id) {
+            Some(v) => v,
+            None => return,
+        };
+        for subscription in topic_state.subscriptions.values() {
+            if subscription.accepts(&publication.message_description) {
+                subscription.deliver(caller_id, &data);
+            }
+        }
+        if latching {
+            publication.last_message = Some(Arc::new(data));
+        }
+    }
+
+    fn subscriber_names(&self, caller_id: &str, topic: &str) -> Vec<String> {
+        let state = self.state.lock().expect(FAILED_TO_LOCK);
+        let topic_state = match state.topics.get(topic) {
+            Some(v) => v,
+            None => return vec![],
+        };
+        let publication = match topic_state.publications.get(caller_id) {
+            Some(v) => v,
+            None => return vec![],
+        };
+        topic_state
+            .subscriptions
+            .values()
+            .filter(|subscription| subscription.accepts(&publication.message_description))
+            .map(|subscription| subscription.caller_id.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn add_subscription<T, H>(
+        &self,
+        caller_id: &str,
+        topic: &str,
+        queue_size: usize,
+        handler: H,
+    ) -> Result<usize>
+    where
+        T: Message,
+        H: SubscriptionHandler<T>,
+    {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        state.next_subscription_id += 1;
+        let id = state.next_subscription_id;
+        let topic_state = state.topics.entry(topic.into()).or_default();
+
+        let msg_type = T::msg_type();
+        let md5sum = T::md5sum();
+        if let Some(existing) = topic_state
+            .subscriptions
+            .values()
+            .find(|subscription| subscription.caller_id == caller_id)
+        {
+            if !header_matches(&existing.msg_type, &msg_type)
+                || !header_matches(&existing.md5sum, &md5sum)
+            {
+                error!(
+                    "Attempted to connect to {} topic '{}' with message type {}",
+                    existing.msg_type, topic, msg_type
+                );
+                bail!(ErrorKind::MismatchedType(
+                    topic.into(),
+                    existing.msg_type.clone(),
+                    msg_type,
+                ));
+            }
+        }
+
+        let (data_tx, data_rx) = lossy_channel(queue_size);
+        let (connection_tx, connection_rx) = unbounded();
+        thread::spawn(move || handle_data::<T, H>(data_rx, connection_rx, handler));
+
+        let subscription = Subscription {
+            caller_id: caller_id.into(),
+            msg_type,
+            md5sum,
+            data: data_tx,
+            connections: connection_tx,
+        };
+        for (publisher_id, publication) in &topic_state.publications {
+            if subscription.accepts(&publication.message_description) {
+                subscription.connect(publisher_id, topic, publication);
+            }
+        }
+        topic_state.subscriptions.insert(id, subscription);
+        Ok(id)
+    }
+
+    fn remove_subscription(&self, topic: &str, id: usize) {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        let subscription = state
+            .topics
+            .get_mut(topic)
+            .and_then(|topic_state| topic_state.subscriptions.remove(&id));
+        if let Some(mut subscription) = subscription {
+            if subscription.data.close().is_err() {
+                error!("Subscriber data stream to topic has already been killed");
+            }
+        }
+    }
+
+    fn publisher_uris(&self, caller_id: &str, topic: &str) -> Vec<String> {
+        let state = self.state.lock().expect(FAILED_TO_LOCK);
+        let topic_state = match state.topics.get(topic) {
+            Some(v) => v,
+            None => return vec![],
+        };
+        let subscription = match topic_state
+            .subscriptions
+            .values()
+            .find(|subscription| subscription.caller_id == caller_id)
+        {
+            Some(v) => v,
+            None => return vec![],
+        };
+        topic_state
+            .publications
+            .iter()
+            .filter(|(_, publication)| subscription.accepts(&publication.message_description))
+            .map(|(publisher_id, _)| node_uri(publisher_id))
+            .collect()
+    }
+
+    fn add_service<T, F>(&self, caller_id: &str, service: &str, handler: F) -> Result<String>
+    where
+        T: ServicePair,
+        F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        use std::collections::btree_map::Entry;
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        match state.services.entry(service.into()) {
+            Entry::Occupied(..) => {
+                error!("Duplicate initiation of service '{}' attempted", service);
+                Err(ErrorKind::Duplicate("service".into()).into())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(ServiceState {
+                    caller_id: caller_id.into(),
+                    md5sum: T::md5sum(),
+                    handler: Arc::new(move |data| {
+                        Ok(match handler(RosMsg::decode_slice(data)?) {
+                            Ok(response) => Ok(response.encode_vec()?),
+                            Err(message) => Err(message),
+                        })
+                    }),
+                });
+                Ok(format!("{}{}", node_uri(caller_id), service))
+            }
+        }
+    }
+
+    fn remove_service(&self, caller_id: &str, service: &str) {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        let owned = state
+            .services
+            .get(service)
+            .is_some_and(|service_state| service_state.caller_id == caller_id);
+        let removed = if owned {
+            state.services.remove(service)
+        } else {
+            None
+        };
+        // The handler can own publishers, which need the lock when dropped
+        drop(state);
+        drop(removed);
+    }
+
+    pub fn probe_service(&self, service: &str) -> tcpros::error::Result<()> {
+        if self
+            .state
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .services
+            .contains_key(service)
+        {
+            Ok(())
+        } else {
+            bail!(tcpros::error::ErrorKind::ServiceConnectionFail(
+                service.into()
+            ))
+        }
+    }
+
+    pub fn call_service<T: ServicePair>(
+        &self,
+        service: &str,
+        request: &T::Request,
+    ) -> tcpros::error::Result<ServiceResult<T::Response>> {
+        let (md5sum, handler) = {
+            let state = self.state.lock().expect(FAILED_TO_LOCK);
+            match state.services.get(service) {
+                Some(service_state) => (
+                    service_state.md5sum.clone(),
+                    Arc::clone(&service_state.handler),
+                ),
+                None => bail!(tcpros::error::ErrorKind::ServiceConnectionFail(
+                    service.into()
+                )),
+            }
+        };
+        if !header_matches(&md5sum, &T::md5sum()) {
+            bail!(tcpros::error::ErrorKind::HeaderMismatch(
+                "md5sum".into(),
+                T::md5sum(),
+                md5sum,
+            ));
+        }
+        // The handler is called without holding the lock, so it can use the bus too
+        Ok(match handler(&request.encode_vec()?)? {
+            Ok(response) => Ok(RosMsg::decode_slice(&response)?),
+            Err(message) => Err(message),
+        })
+    }
+}
+
+/// Node's view of the in-memory bus, mirroring the parts of `Slave` used by node handles.
+pub struct MemoryNode {
+    bus: Arc<MemoryBus>,
+    name: String,
+    uri: String,
+}
+
+impl MemoryNode {
+    pub fn new(bus: Arc<MemoryBus>, name: &str) -> Self {
+        Self {
+            bus,
+            name: name.into(),
+            uri: node_uri(name),
+        }
+    }
+
+    #[inline]
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    #[inline]
+    pub fn bus(&self) -> &Arc<MemoryBus> {
+        &self.bus
+    }
+
+    pub fn publication_names(&self) -> Vec<String> {
+        let state = self.bus.state.lock().expect(FAILED_TO_LOCK);
+        state
+            .topics
+            .iter()
+            .filter(|(_, topic_state)| topic_state.publications.contains_key(&self.name))
+            .map(|(topic, _)| topic.clone())
+            .collect()
+    }
+
+    pub fn add_publication<T: Message>(
+        &self,
+        topic: &str,
+        message_description: RawMessageDescription,
+    ) -> tcpros::error::Result<MemoryPublisherStream<T>> {
+        self.bus
+            .add_publication(&self.name, topic, message_description)?;
+        Ok(MemoryPublisherStream {
+            bus: Arc::clone(&self.bus),
+            caller_id: self.name.clone(),
+            topic: topic.into(),
+            latching: false,
+            datatype: std::marker::PhantomData,
+        })
+    }
+
+    #[inline]
+    pub fn remove_publication(&self, topic: &str) {
+        self.bus.remove_publication(&self.name, topic)
+    }
+
+    #[inline]
+    pub fn add_subscription<T, H>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        handler: H,
+    ) -> Result<usize>
+    where
+        T: Message,
+        H: SubscriptionHandler<T>,
+    {
+        self.bus
+            .add_subscription::<T, H>(&self.name, topic, queue_size, handler)
+    }
+
+    #[inline]
+    pub fn remove_subscription(&self, topic: &str, id: usize) {
+        self.bus.remove_subscription(topic, id)
+    }
+
+    #[inline]
+    pub fn publisher_count(&self, topic: &str) -> usize {
+        self.publisher_uris(topic).len()
+    }
+
+    #[inline]
+    pub fn publisher_uris(&self, topic: &str) -> Vec<String> {
+        self.bus.publisher_uris(&self.name, topic)
+    }
+
+    #[inline]
+    pub fn add_service<T, F>(&self, service: &str, handler: F) -> Result<String>
+    where
+        T: ServicePair,
+        F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        self.bus.add_service::<T, F>(&self.name, service, handler)
+    }
+
+    #[inline]
+    pub fn remove_service(&self, service: &str) {
+        self.bus.remove_service(&self.name, service)
+    }
+}
+
+#[derive(Clone)]
+pub struct MemoryPublisherStream<T: Message> {
+    bus: Arc<MemoryBus>,
+    caller_id: String,
+    topic: String,
+    latching: bool,
+    datatype: std::marker::PhantomData<T>,
+}
+
+impl<T: Message> MemoryPublisherStream<T> {
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriber_names().len()
+    }
+
+    #[inline]
+    pub fn subscriber_names(&self) -> Vec<String> {
+        self.bus.subscriber_names(&self.caller_id, &self.topic)
+    }
+
+    #[inline]
+    pub fn set_latching(&mut self, latching: bool) {
+        self.latching = latching;
+    }
+
+    pub fn send(&self, message: &T) -> error::tcpros::Result<()> {
+        let data = message.encode_vec()?;
+        self.bus
+            .publish(&self.caller_id, &self.topic, data, self.latching);
+        Ok(())
+    }
+}
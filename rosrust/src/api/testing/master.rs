@@ -0,0 +1,481 @@
+use crate::api::slave::{self, ParamCache};
+use crate::rosxmlrpc::{Response, ResponseError};
+use crate::util::FAILED_TO_LOCK;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use xml_rpc::{Params, Value};
+
+pub static MASTER_URI: &str = "memory://master";
+
+/// Master and parameter server shared by all nodes of a test kit.
+///
+/// Requests use the same method names and arguments as the XML-RPC Master API, so the regular
+/// `Master` client can talk to it without any changes.
+#[derive(Default)]
+pub struct MemoryMaster {
+    state: Mutex<MasterState>,
+}
+
+#[derive(Default)]
+struct MasterState {
+    nodes: BTreeMap<String, String>,
+    param_caches: HashMap<String, ParamCache>,
+    publishers: BTreeMap<String, BTreeSet<String>>,
+    subscribers: BTreeMap<String, BTreeSet<String>>,
+    topic_types: BTreeMap<String, String>,
+    services: BTreeMap<String, (String, String)>,
+    params: HashMap<String, Value>,
+    param_subscriptions: BTreeMap<String, BTreeSet<String>>,
+}
+
+struct Arguments<'a> {
+    method: &'a str,
+    items: std::vec::IntoIter<Value>,
+}
+
+impl<'a> Arguments<'a> {
+    fn value(&mut self, name: &str) -> Response<Value> {
+        self.items.next().ok_or_else(|| {
+            ResponseError::Client(format!(
+                "Missing argument '{}' in call to '{}'",
+                name, self.method
+            ))
+        })
+    }
+
+    fn string(&mut self, name: &str) -> Response<String> {
+        match self.value(name)? {
+            Value::String(v) => Ok(v),
+            _ => Err(ResponseError::Client(format!(
+                "Argument '{}' in call to '{}' must be a string",
+                name, self.method
+            ))),
+        }
+    }
+}
+
+impl MemoryMaster {
+    /// Registers a node's parameter cache, so it gets parameter updates.
+    pub fn add_node(&self, caller_id: &str, caller_api: &str, param_cache: ParamCache) {
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        state.nodes.insert(caller_id.into(), caller_api.into());
+        state.param_caches.insert(caller_api.into(), param_cache);
+    }
+
+    /// Names of the nodes subscribed to updates of exactly the given parameter key.
+    pub fn param_subscribers(&self, key: &str) -> Vec<String> {
+        let state = self.state.lock().expect(FAILED_TO_LOCK);
+        let caller_apis = match state.param_subscriptions.get(&with_trailing_slash(key)) {
+            Some(caller_apis) => caller_apis,
+            None => return vec![],
+        };
+        state
+            .nodes
+            .iter()
+            .filter(|(_, caller_api)| caller_apis.contains(*caller_api))
+            .map(|(caller_id, _)| caller_id.clone())
+            .collect()
+    }
+
+    pub fn call(&self, method: &str, params: Params) -> Response<Value> {
+        let mut args = Arguments {
+            method,
+            items: params.into_iter(),
+        };
+        let caller_id = args.string("caller_id")?;
+        let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+        match method {
+            "registerService" => {
+                let service = args.string("service")?;
+                let service_api = args.string("service_api")?;
+                let caller_api = args.string("caller_api")?;
+                state.nodes.insert(caller_id.clone(), caller_api);
+                state.services.insert(service, (caller_id, service_api));
+                Ok(Value::Int(1))
+            }
+            "unregisterService" => {
+                let service = args.string("service")?;
+                let service_api = args.string("service_api")?;
+                let removed = match state.services.get(&service) {
+                    Some((_, api)) if *api == service_api => {
+                        state.services.remove(&service);
+                        1
+                    }
+                    _ => 0,
+                };
+                Ok(Value::Int(removed))
+            }
+            "registerSubscriber" => {
+                let topic = args.string("topic")?;
+                let topic_type = args.string("topic_type")?;
+                let caller_api = args.string("caller_api")?;
+                state.nodes.insert(caller_id.clone(), caller_api);
+                if topic_type != "*" {
+                    state.topic_types.entry(topic.clone()).or_insert(topic_type);
+                }
+                state
+                    .subscribers
+                    .entry(topic.clone())
+                    .or_default()
+                    .insert(caller_id);
+                let apis = state.apis(state.publishers.get(&topic));
+                Ok(apis)
+            }
+            "unregisterSubscriber" => {
+                let topic = args.string("topic")?;
+                Ok(Value::Int(remove_entry(
+                    &mut state.subscribers,
+                    &topic,
+                    &caller_id,
+                )))
+            }
+            "registerPublisher" => {
+                let topic = args.string("topic")?;
+                let topic_type = args.string("topic_type")?;
+                let caller_api = args.string("caller_api")?;
+                state.nodes.insert(caller_id.clone(), caller_api);
+                if topic_type != "*" || !state.topic_types.contains_key(&topic) {
+                    state.topic_types.insert(topic.clone(), topic_type);
+                }
+                state
+                    .publishers
+                    .entry(topic.clone())
+                    .or_default()
+                    .insert(caller_id);
+                let apis = state.apis(state.subscribers.get(&topic));
+                Ok(apis)
+            }
+            "unregisterPublisher" => {
+                let topic = args.string("topic")?;
+                Ok(Value::Int(remove_entry(
+                    &mut state.publishers,
+                    &topic,
+                    &caller_id,
+                )))
+            }
+            "lookupNode" => {
+                let node_name = args.string("node_name")?;
+                match state.nodes.get(&node_name) {
+                    Some(api) => Ok(Value::String(api.clone())),
+                    None => Err(ResponseError::Client(format!(
+                        "Unknown node [{}]",
+                        node_name
+                    ))),
+                }
+            }
+            "getPublishedTopics" => {
+                let subgraph = args.string("subgraph")?;
+                let topics = state
+                    .publishers
+                    .keys()
+                    .filter(|topic| topic.starts_with(&subgraph))
+                    .filter_map(|topic| {
+                        state
+                            .topic_types
+                            .get(topic)
+                            .map(|datatype| (topic.clone(), datatype.clone()))
+                    })
+                    .collect::<Vec<_>>();
+                Ok(topic_pairs(topics))
+            }
+            "getTopicTypes" => Ok(topic_pairs(
+                state
+                    .topic_types
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            )),
+            "getSystemState" => {
+                let services = state
+                    .services
+                    .iter()
+                    .map(|(name, (caller_id, _))| {
+                        (name.clone(), std::iter::once(caller_id.clone()).collect())
+                    })
+                    .collect();
+                Ok(Value::Array(vec![
+                    topic_nodes(&state.publishers),
+                    topic_nodes(&state.subscribers),
+                    topic_nodes(&services),
+                ]))
+            }
+            "getUri" => Ok(Value::String(MASTER_URI.into())),
+            "lookupService" => {
+                let service = args.string("service")?;
+                match state.services.get(&service) {
+                    Some((_, api)) => Ok(Value::String(api.clone())),
+                    None => Err(ResponseError::Client(format!(
+                        "No provider for service [{}]",
+                        service
+                    ))),
+                }
+            }
+            "setParam" => {
+                let key = args.string("key")?;
+                let value = args.value("value")?;
+                state.set_param(&key, value)?;
+                let updates = state.param_updates(&key);
+                drop(state);
+                send_param_updates(updates);
+                Ok(Value::Int(0))
+            }
+            "getParam" => {
+                let key = args.string("key")?;
+                state.get_param(&key).ok_or_else(|| not_set(&key))
+            }
+            "deleteParam" => {
+                let key = args.string("key")?;
+                if !state.delete_param(&key) {
+                    return Err(not_set(&key));
+                }
+                let updates = state.param_updates(&key);
+                drop(state);
+                send_param_updates(updates);
+                Ok(Value::Int(0))
+            }
+            "hasParam" => {
+                let key = args.string("key")?;
+                Ok(Value::Bool(state.get_param(&key).is_some()))
+            }
+            "searchParam" => {
+                let key = args.string("key")?;
+                match state.search_param(&caller_id, &key) {
+                    Some(found) => Ok(Value::String(found)),
+                    None => Err(ResponseError::Client(format!(
+                        "Cannot find parameter [{}] in an upwards search",
+                        key
+                    ))),
+                }
+            }
+            "subscribeParam" => {
+                let caller_api = args.string("caller_api")?;
+                let key = args.string("key")?;
+                state
+                    .param_subscriptions
+                    .entry(with_trailing_slash(&key))
+                    .or_default()
+                    .insert(caller_api);
+                Ok(state
+                    .get_param(&key)
+                    .unwrap_or_else(|| Value::Struct(HashMap::new())))
+            }
+            "unsubscribeParam" => {
+                let caller_api = args.string("caller_api")?;
+                let key = args.string("key")?;
+                Ok(Value::Int(remove_entry(
+                    &mut state.param_subscriptions,
+                    &with_trailing_slash(&key),
+                    &caller_api,
+                )))
+            }
+            "getParamNames" => {
+                let mut names = vec![];
+                collect_names("", &state.params, &mut names);
+                names.sort();
+                Ok(Value::Array(names.into_iter().map(Value::String).collect()))
+            }
+            _ => Err(ResponseError::Client(format!(
+                "Method '{}' is not supported by the in-memory master",
+                method
+            ))),
+        }
+    }
+}
+
+impl MasterState {
+    fn apis(&self, caller_ids: Option<&BTreeSet<String>>) -> Value {
+        Value::Array(
+            caller_ids
+                .into_iter()
+                .flatten()
+                .filter_map(|caller_id| self.nodes.get(caller_id))
+                .cloned()
+                .map(Value::String)
+                .collect(),
+        )
+    }
+
+    fn get_param(&self, key: &str) -> Option<Value> {
+        let mut value = None;
+        for part in split(key) {
+            let children = match value {
+                None => &self.params,
+                Some(Value::Struct(ref children)) => children,
+                Some(_) => return None,
+            };
+            value = Some(children.get(part)?.clone());
+        }
+        Some(value.unwrap_or_else(|| Value::Struct(self.params.clone())))
+    }
+
+    fn set_param(&mut self, key: &str, value: Value) -> Response<()> {
+        let parts = split(key);
+        let (last, namespaces) = match parts.split_last() {
+            Some(v) => v,
+            None => match value {
+                Value::Struct(params) => {
+                    self.params = params;
+                    return Ok(());
+                }
+                _ => {
+                    return Err(ResponseError::Client(
+                        "Cannot set root of parameter tree to non-struct".into(),
+                    ))
+                }
+            },
+        };
+        let mut node = &mut self.params;
+        for namespace in namespaces {
+            let entry = node
+                .entry(String::from(*namespace))
+                .or_insert_with(|| Value::Struct(HashMap::new()));
+            if !matches!(entry, Value::Struct(_)) {
+                *entry = Value::Struct(HashMap::new());
+            }
+            node = match entry {
+                Value::Struct(children) => children,
+                _ => unreachable!(),
+            };
+        }
+        node.insert(String::from(*last), value);
+        Ok(())
+    }
+
+    fn delete_param(&mut self, key: &str) -> bool {
+        let parts = split(key);
+        let (last, namespaces) = match parts.split_last() {
+            Some(v) => v,
+            None => {
+                self.params.clear();
+                return true;
+            }
+        };
+        let mut node = &mut self.params;
+        for namespace in namespaces {
+            node = match node.get_mut(*namespace) {
+                Some(Value::Struct(children)) => children,
+                _ => return false,
+            };
+        }
+        node.remove(*last).is_some()
+    }
+
+    fn search_param(&self, caller_id: &str, key: &str) -> Option<String> {
+        if key.starts_with('/') {
+            return self
+                .get_param(key)
+                .map(|_| key.trim_end_matches('/').into());
+        }
+        let key_parts = split(key);
+        let (first, rest) = key_parts.split_first()?;
+        let namespace_parts = split(caller_id);
+        let namespace_parts = &namespace_parts[..namespace_parts.len().saturating_sub(1)];
+        (0..=namespace_parts.len()).rev().find_map(|depth| {
+            let mut search_key = namespace_parts[..depth].to_vec();
+            search_key.push(first);
+            let search_key = format!("/{}", search_key.join("/"));
+            self.get_param(&search_key)?;
+            Some(
+                std::iter::once(search_key.as_str())
+                    .chain(rest.iter().cloned())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            )
+        })
+    }
+
+    /// Collects the updates that subscribers need to get after the given key changed.
+    ///
+    /// Like with the ROS master, unset parameters are sent as empty structs.
+    fn param_updates(&self, key: &str) -> Vec<(ParamCache, String, Value)> {
+        let key = with_trailing_slash(key);
+        let mut updates = vec![];
+        for (subscribed_key, caller_apis) in &self.param_subscriptions {
+            let update_key = if key.starts_with(subscribed_key.as_str()) {
+                &key
+            } else if subscribed_key.starts_with(key.as_str()) {
+                subscribed_key
+            } else {
+                continue;
+            };
+            let value = self
+                .get_param(update_key)
+                .unwrap_or_else(|| Value::Struct(HashMap::new()));
+            for caller_api in caller_apis {
+                if let Some(param_cache) = self.param_caches.get(caller_api) {
+                    updates.push((param_cache.clone(), update_key.clone(), value.clone()));
+                }
+            }
+        }
+        updates
+    }
+}
+
+/// Delivers parameter updates, which needs to happen without holding the master's state,
+/// since watch callbacks can call back into the master.
+fn send_param_updates(updates: Vec<(ParamCache, String, Value)>) {
+    for (param_cache, key, value) in updates {
+        slave::update_param_cache(&param_cache, &key, value);
+    }
+}
+
+fn not_set(key: &str) -> ResponseError {
+    ResponseError::Client(format!("Parameter [{}] is not set", key))
+}
+
+fn split(key: &str) -> Vec<&str> {
+    key.split('/').filter(|v| !v.is_empty()).collect()
+}
+
+fn with_trailing_slash(key: &str) -> String {
+    format!("{}/", key.trim_end_matches('/'))
+}
+
+fn remove_entry(map: &mut BTreeMap<String, BTreeSet<String>>, key: &str, item: &str) -> i32 {
+    let entries = match map.get_mut(key) {
+        Some(v) => v,
+        None => return 0,
+    };
+    let removed = entries.remove(item);
+    if entries.is_empty() {
+        map.remove(key);
+    }
+    removed as i32
+}
+
+fn collect_names(prefix: &str, params: &HashMap<String, Value>, output: &mut Vec<String>) {
+    for (key, value) in params {
+        let name = format!("{}/{}", prefix, key);
+        match value {
+            Value::Struct(children) if !children.is_empty() => {
+                collect_names(&name, children, output)
+            }
+            _ => output.push(name),
+        }
+    }
+}
+
+fn topic_pairs(topics: Vec<(String, String)>) -> Value {
+    Value::Array(
+        topics
+            .into_iter()
+            .map(|(name, datatype)| {
+                Value::Array(vec![Value::String(name), Value::String(datatype)])
+            })
+            .collect(),
+    )
+}
+
+fn topic_nodes(topics: &BTreeMap<String, BTreeSet<String>>) -> Value {
+    Value::Array(
+        topics
+            .iter()
+            .map(|(name, nodes)| {
+                Value::Array(vec![
+                    Value::String(name.clone()),
+                    Value::Array(nodes.iter().cloned().map(Value::String).collect()),
+                ])
+            })
+            .collect(),
+    )
+}
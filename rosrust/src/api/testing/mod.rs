@@ -0,0 +1,205 @@
+//! In-memory ROS graph for unit testing nodes.
+//!
+//! A [`TestKit`] replaces the master, the parameter server and TCPROS connections with in-memory
+//! equivalents, so node logic can be tested within a single process, without `roscore` and
+//! without touching the network. Nodes created by the kit are ordinary [`Ros`] instances, so the
+//! code under test keeps using `publish`, `subscribe`, `service`, `client` and `param`.
+//!
+//! The kit is only built with the `testing` feature, which is meant to be enabled from
+//! `[dev-dependencies]`.
+//!
+//! ```no_run
+//! # rosrust::rosmsg_include!(std_msgs / String);
+//! use rosrust::api::testing::TestKit;
+//! use std::time::Duration;
+//!
+//! let kit = TestKit::new().unwrap();
+//! let node = kit.node("talker").unwrap();
+//! let chatter = kit.capture::<std_msgs::String>("/chatter").unwrap();
+//!
+//! let publisher = node.publish::<std_msgs::String>("chatter", 10).unwrap();
+//! publisher.send(std_msgs::String { data: "hello".into() }).unwrap();
+//!
+//! let message = chatter.recv_timeout(Duration::from_secs(1)).unwrap();
+//! assert_eq!("hello", message.data);
+//! ```
+
+pub(crate) use self::bus::{MemoryBus, MemoryNode, MemoryPublisherStream};
+pub(crate) use self::master::MemoryMaster;
+
+mod bus;
+mod master;
+
+use super::clock::{Clock, SimulatedClock};
+use super::error::Result;
+use super::raii::{Publisher, Service, Subscriber};
+use super::{Parameter, Ros};
+use crate::tcpros::{Message, ServicePair, ServiceResult};
+use crate::util::FAILED_TO_LOCK;
+use crossbeam::channel::{unbounded, Receiver};
+use ros_message::{Duration, Time};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Name of the node that the test kit uses for its own interactions with the graph.
+pub static TEST_KIT_NAME: &str = "test_kit";
+
+/// In-memory ROS graph, shared by all nodes that it creates.
+///
+/// The kit has a node of its own, named [`TEST_KIT_NAME`], which injects messages, captures
+/// published messages, provides stubbed services and accesses parameters. All nodes share a
+/// manually driven clock. Like simulated time, the clock starts at zero, which nodes treat as
+/// not yet initialized, so call [`TestKit::set_time`] before relying on rates or delays.
+#[derive(Clone)]
+pub struct TestKit {
+    inner: Arc<TestKitInner>,
+}
+
+struct TestKitInner {
+    master: Arc<MemoryMaster>,
+    bus: Arc<MemoryBus>,
+    clock: Arc<SimulatedClock>,
+    node: Ros,
+    // Keyed by message type too, so injecting another type never replaces a publisher
+    injectors: Mutex<HashMap<(String, TypeId), Box<dyn Any + Send + Sync>>>,
+}
+
+impl TestKit {
+    pub fn new() -> Result<TestKit> {
+        let master = Arc::new(MemoryMaster::default());
+        let bus = Arc::new(MemoryBus::default());
+        let clock = Arc::new(SimulatedClock::default());
+        let node = Ros::new_in_memory(
+            Arc::clone(&master),
+            Arc::clone(&bus),
+            Arc::clone(&clock),
+            TEST_KIT_NAME,
+        )?;
+        Ok(TestKit {
+            inner: Arc::new(TestKitInner {
+                master,
+                bus,
+                clock,
+                node,
+                injectors: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Creates a node in the root namespace, connected to this kit's graph.
+    pub fn node(&self, name: &str) -> Result<Ros> {
+        Ros::new_in_memory(
+            Arc::clone(&self.inner.master),
+            Arc::clone(&self.inner.bus),
+            Arc::clone(&self.inner.clock),
+            name,
+        )
+    }
+
+    /// Publishes a message on a topic, as if it came from another node.
+    ///
+    /// Messages are handed to subscriber queues before returning, but subscriber callbacks
+    /// still run on their own threads.
+    pub fn inject<T: Message>(&self, topic: &str, message: T) -> Result<()> {
+        let mut injectors = self.inner.injectors.lock().expect(FAILED_TO_LOCK);
+        let key = (String::from(topic), TypeId::of::<T>());
+        let existing = injectors
+            .get(&key)
+            .and_then(|injector| injector.downcast_ref::<Publisher<T>>())
+            .cloned();
+        let publisher = match existing {
+            Some(publisher) => publisher,
+            None => {
+                let publisher = self.inner.node.publish::<T>(topic, 0)?;
+                injectors.insert(key, Box::new(publisher.clone()));
+                publisher
+            }
+        };
+        publisher.send(message)
+    }
+
+    /// Records all messages published on a topic from now on.
+    ///
+    /// The capture counts as a subscriber, and receives latched messages too.
+    pub fn capture<T: Message>(&self, topic: &str) -> Result<Capture<T>> {
+        let (tx, rx) = unbounded();
+        let subscriber = self
+            .inner
+            .node
+            .subscribe::<T, _>(topic, 0, move |message| {
+                // The receiver only goes away together with the subscription
+                let _ = tx.send(message);
+            })?;
+        Ok(Capture {
+            _subscriber: subscriber,
+            messages: rx,
+        })
+    }
+
+    /// Provides a service with the given handler, until the returned handle is dropped.
+    pub fn stub_service<T, F>(&self, service: &str, handler: F) -> Result<Service>
+    where
+        T: ServicePair,
+        F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        self.inner.node.service::<T, F>(service, handler)
+    }
+
+    /// Accesses a parameter, with relative names resolved from the root namespace.
+    #[inline]
+    pub fn param(&self, name: &str) -> Option<Parameter> {
+        self.inner.node.param(name)
+    }
+
+    /// Names of the nodes subscribed to updates of exactly the given parameter key.
+    ///
+    /// Nodes subscribe to the parameters they watch, and to the parameters they have cached.
+    pub fn param_subscribers(&self, key: &str) -> Vec<String> {
+        self.inner.master.param_subscribers(key)
+    }
+
+    #[inline]
+    pub fn now(&self) -> Time {
+        self.inner.clock.now()
+    }
+
+    /// Moves the shared clock to the given time, waking up everything sleeping until then.
+    #[inline]
+    pub fn set_time(&self, time: Time) {
+        self.inner.clock.trigger(time);
+    }
+
+    /// Moves the shared clock forward by the given duration.
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        self.set_time(self.now() + duration);
+    }
+}
+
+/// Messages captured on a topic by a [`TestKit`].
+///
+/// Capturing stops once this is dropped.
+pub struct Capture<T> {
+    _subscriber: Subscriber,
+    messages: Receiver<T>,
+}
+
+impl<T> Capture<T> {
+    /// Waits for the next captured message.
+    #[inline]
+    pub fn recv_timeout(&self, timeout: std::time::Duration) -> Option<T> {
+        self.messages.recv_timeout(timeout).ok()
+    }
+
+    #[inline]
+    pub fn try_recv(&self) -> Option<T> {
+        self.messages.try_recv().ok()
+    }
+
+    /// Takes all messages captured so far.
+    #[inline]
+    pub fn drain(&self) -> Vec<T> {
+        self.messages.try_iter().collect()
+    }
+}
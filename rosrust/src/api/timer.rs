@@ -0,0 +1,248 @@
+use super::clock::Clock;
+use crate::util::FAILED_TO_LOCK;
+use crossbeam::sync::{Parker, Unparker};
+use ros_message::{Duration, Time};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::Instant;
+
+/// Timing information passed to timer callbacks.
+///
+/// Expected times are the ones the timer was scheduled for, while real times are the ones at
+/// which the callbacks actually got called. All of them are zero before the first call.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimerEvent {
+    pub last_expected: Time,
+    pub last_real: Time,
+    pub current_expected: Time,
+    pub current_real: Time,
+    /// Wall time that the previous callback took to run.
+    pub last_duration: Duration,
+    /// The timer that is calling back, for stopping it or changing its period from the callback.
+    pub timer: WeakTimer,
+}
+
+/// Calls a callback periodically, or once, on a thread of its own.
+///
+/// Periodic timers with a period that is not positive get called only once, like oneshot
+/// timers. The timer stops once all of its clones get dropped.
+///
+/// A callback that captures a clone of its own timer keeps it alive forever. Callbacks should
+/// control their timer through `TimerEvent::timer` instead.
+#[derive(Clone)]
+pub struct Timer {
+    handle: Arc<TimerHandle>,
+}
+
+/// Handle of a timer that doesn't keep it from stopping once all of its clones get dropped.
+#[derive(Clone, Default)]
+pub struct WeakTimer {
+    handle: Weak<TimerHandle>,
+}
+
+impl WeakTimer {
+    /// Accesses the timer, unless all of its clones got dropped.
+    pub fn upgrade(&self) -> Option<Timer> {
+        self.handle.upgrade().map(|handle| Timer { handle })
+    }
+}
+
+impl fmt::Debug for WeakTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakTimer")
+            .field("alive", &(self.handle.strong_count() > 0))
+            .finish()
+    }
+}
+
+impl PartialEq for WeakTimer {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle.ptr_eq(&other.handle)
+    }
+}
+
+struct TimerHandle {
+    shared: Arc<TimerShared>,
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.shared.state().alive = false;
+        self.shared.unparker.unpark();
+    }
+}
+
+struct TimerShared {
+    clock: Arc<dyn Clock>,
+    state: Mutex<TimerState>,
+    unparker: Unparker,
+}
+
+impl TimerShared {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, TimerState> {
+        self.state.lock().expect(FAILED_TO_LOCK)
+    }
+}
+
+struct TimerState {
+    period: Duration,
+    oneshot: bool,
+    running: bool,
+    alive: bool,
+    next_expected: Time,
+    last_checked: Time,
+    last_expected: Time,
+    last_real: Time,
+    last_duration: Duration,
+}
+
+impl Timer {
+    pub(crate) fn new<F>(
+        clock: Arc<dyn Clock>,
+        period: Duration,
+        oneshot: bool,
+        callback: F,
+    ) -> Self
+    where
+        F: FnMut(TimerEvent) + Send + 'static,
+    {
+        let parker = Parker::new();
+        let now = clock.now();
+        let shared = Arc::new(TimerShared {
+            clock,
+            state: Mutex::new(TimerState {
+                period,
+                oneshot,
+                running: true,
+                alive: true,
+                next_expected: now + period,
+                last_checked: now,
+                last_expected: Time::default(),
+                last_real: Time::default(),
+                last_duration: Duration::default(),
+            }),
+            unparker: parker.unparker().clone(),
+        });
+        let handle = Arc::new(TimerHandle {
+            shared: Arc::clone(&shared),
+        });
+        let timer = WeakTimer {
+            handle: Arc::downgrade(&handle),
+        };
+        thread::spawn(move || run(&shared, &timer, &parker, callback));
+        Self { handle }
+    }
+
+    /// Creates a handle that doesn't keep the timer running.
+    pub fn downgrade(&self) -> WeakTimer {
+        WeakTimer {
+            handle: Arc::downgrade(&self.handle),
+        }
+    }
+
+    /// Restarts a stopped timer, with the next call one period from now.
+    ///
+    /// Starting a running timer has no effect.
+    pub fn start(&self) {
+        let shared = &self.handle.shared;
+        let mut state = shared.state();
+        if state.running {
+            return;
+        }
+        state.running = true;
+        state.next_expected = shared.clock.now() + state.period;
+        shared.unparker.unpark();
+    }
+
+    /// Stops calling the callback, until the timer gets started again.
+    ///
+    /// A callback that is already running gets to finish.
+    pub fn stop(&self) {
+        let shared = &self.handle.shared;
+        shared.state().running = false;
+        shared.unparker.unpark();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.shared.state().running
+    }
+
+    #[inline]
+    pub fn period(&self) -> Duration {
+        self.handle.shared.state().period
+    }
+
+    /// Changes the period of the timer.
+    ///
+    /// With `reset`, the next call happens one new period from now. Otherwise, it happens one
+    /// new period after the previous call was expected, or after the timer got started.
+    /// A period that is not positive makes a periodic timer stop after its next call.
+    pub fn set_period(&self, period: Duration, reset: bool) {
+        let shared = &self.handle.shared;
+        let mut state = shared.state();
+        state.next_expected = if reset {
+            shared.clock.now() + period
+        } else {
+            state.next_expected - state.period + period
+        };
+        state.period = period;
+        shared.unparker.unpark();
+    }
+}
+
+fn run<F>(shared: &TimerShared, timer: &WeakTimer, parker: &Parker, mut callback: F)
+where
+    F: FnMut(TimerEvent),
+{
+    loop {
+        let event = {
+            let mut state = shared.state();
+            if !state.alive {
+                return;
+            }
+            if !state.running {
+                drop(state);
+                parker.park();
+                continue;
+            }
+            let now = shared.clock.now();
+            // After the clock jumps backwards, the schedule restarts from the new time
+            if now < state.last_checked {
+                state.next_expected = now + state.period;
+            }
+            state.last_checked = now;
+            if now < state.next_expected {
+                let next_expected = state.next_expected;
+                drop(state);
+                shared.clock.park_until(next_expected, parker);
+                continue;
+            }
+            let event = TimerEvent {
+                last_expected: state.last_expected,
+                last_real: state.last_real,
+                current_expected: state.next_expected,
+                current_real: now,
+                last_duration: state.last_duration,
+                timer: timer.clone(),
+            };
+            state.last_expected = event.current_expected;
+            state.last_real = now;
+            // Periodic timers without a positive period would call back in a busy loop
+            if state.oneshot || state.period <= Duration::default() {
+                state.running = false;
+            } else {
+                state.next_expected = state.next_expected + state.period;
+                // Calls that were missed because of slow callbacks get skipped
+                if state.next_expected <= now {
+                    state.next_expected = now + state.period;
+                }
+            }
+            event
+        };
+        let start = Instant::now();
+        callback(event);
+        shared.state().last_duration = start.elapsed().into();
+    }
+}
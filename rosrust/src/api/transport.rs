@@ -0,0 +1,213 @@
+use super::error::Result;
+use super::slave::Slave;
+#[cfg(feature = "testing")]
+use super::testing::{MemoryNode, MemoryPublisherStream};
+use crate::tcpros::{self, Message, PublisherStream, ServicePair, ServiceResult};
+use crate::{RawMessageDescription, SubscriptionHandler};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Carrier of a node's topic and service data.
+///
+/// Nodes normally talk TCPROS through their slave API, while nodes created by a test kit
+/// exchange data in memory.
+#[derive(Clone)]
+pub enum Transport {
+    Tcpros(Arc<Slave>),
+    #[cfg(feature = "testing")]
+    Memory(Arc<MemoryNode>),
+}
+
+impl Transport {
+    #[inline]
+    pub fn uri(&self) -> &str {
+        match self {
+            Transport::Tcpros(slave) => slave.uri(),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.uri(),
+        }
+    }
+
+    pub fn publication_names(&self) -> Vec<String> {
+        match self {
+            Transport::Tcpros(slave) => slave.publications.get_topic_names(),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.publication_names(),
+        }
+    }
+
+    pub fn add_publication<T: Message>(
+        &self,
+        hostname: &str,
+        topic: &str,
+        queue_size: usize,
+        message_description: RawMessageDescription,
+    ) -> tcpros::error::Result<TopicStream<T>> {
+        Ok(match self {
+            Transport::Tcpros(slave) => TopicStream::Tcpros(slave.add_publication(
+                hostname,
+                topic,
+                queue_size,
+                message_description,
+            )?),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) =>
+            {
+                #[cfg(feature = "testing")]
+                TopicStream::Memory(node.add_publication(topic, message_description)?)
+            }
+        })
+    }
+
+    #[inline]
+    pub fn remove_publication(&self, topic: &str) {
+        match self {
+            Transport::Tcpros(slave) => slave.remove_publication(topic),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.remove_publication(topic),
+        }
+    }
+
+    pub fn add_subscription<T, H>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        handler: H,
+        unsub_signal: Arc<AtomicBool>,
+    ) -> Result<usize>
+    where
+        T: Message,
+        H: SubscriptionHandler<T>,
+    {
+        match self {
+            Transport::Tcpros(slave) => {
+                slave.add_subscription::<T, H>(topic, queue_size, handler, unsub_signal)
+            }
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.add_subscription::<T, H>(topic, queue_size, handler),
+        }
+    }
+
+    #[inline]
+    pub fn remove_subscription(&self, topic: &str, id: usize) {
+        match self {
+            Transport::Tcpros(slave) => slave.remove_subscription(topic, id),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.remove_subscription(topic, id),
+        }
+    }
+
+    pub fn add_publishers_to_subscription<I>(&self, topic: &str, publishers: I) -> Result<()>
+    where
+        I: Iterator<Item = String>,
+    {
+        match self {
+            Transport::Tcpros(slave) => slave.add_publishers_to_subscription(topic, publishers),
+            #[cfg(feature = "testing")]
+            // In-memory subscriptions connect to publications as soon as either side appears
+            Transport::Memory(_) => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn publisher_count(&self, topic: &str) -> usize {
+        match self {
+            Transport::Tcpros(slave) => slave.get_publisher_count_of_subscription(topic),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.publisher_count(topic),
+        }
+    }
+
+    #[inline]
+    pub fn publisher_uris(&self, topic: &str) -> Vec<String> {
+        match self {
+            Transport::Tcpros(slave) => slave.get_publisher_uris_of_subscription(topic),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.publisher_uris(topic),
+        }
+    }
+
+    pub fn add_service<T, F>(
+        &self,
+        hostname: &str,
+        bind_address: &str,
+        service: &str,
+        handler: F,
+    ) -> Result<String>
+    where
+        T: ServicePair,
+        F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        match self {
+            Transport::Tcpros(slave) => {
+                slave.add_service::<T, F>(hostname, bind_address, service, handler)
+            }
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.add_service::<T, F>(service, handler),
+        }
+    }
+
+    #[inline]
+    pub fn remove_service(&self, service: &str) {
+        match self {
+            Transport::Tcpros(slave) => slave.remove_service(service),
+            #[cfg(feature = "testing")]
+            Transport::Memory(node) => node.remove_service(service),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum TopicStream<T: Message> {
+    Tcpros(PublisherStream<T>),
+    #[cfg(feature = "testing")]
+    Memory(MemoryPublisherStream<T>),
+}
+
+impl<T: Message> TopicStream<T> {
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        match self {
+            TopicStream::Tcpros(stream) => stream.subscriber_count(),
+            #[cfg(feature = "testing")]
+            TopicStream::Memory(stream) => stream.subscriber_count(),
+        }
+    }
+
+    #[inline]
+    pub fn subscriber_names(&self) -> Vec<String> {
+        match self {
+            TopicStream::Tcpros(stream) => stream.subscriber_names(),
+            #[cfg(feature = "testing")]
+            TopicStream::Memory(stream) => stream.subscriber_names(),
+        }
+    }
+
+    #[inline]
+    pub fn set_latching(&mut self, latching: bool) {
+        match self {
+            TopicStream::Tcpros(stream) => stream.set_latching(latching),
+            #[cfg(feature = "testing")]
+            TopicStream::Memory(stream) => stream.set_latching(latching),
+        }
+    }
+
+    #[inline]
+    pub fn set_queue_size(&mut self, queue_size: usize) {
+        match self {
+            TopicStream::Tcpros(stream) => stream.set_queue_size(queue_size),
+            #[cfg(feature = "testing")]
+            // In-memory messages are handed to subscriber queues without an outgoing queue
+            TopicStream::Memory(_) => {}
+        }
+    }
+
+    #[inline]
+    pub fn send(&self, message: &T) -> tcpros::error::Result<()> {
+        match self {
+            TopicStream::Tcpros(stream) => stream.send(message),
+            #[cfg(feature = "testing")]
+            TopicStream::Memory(stream) => stream.send(message),
+        }
+    }
+}
@@ -0,0 +1,486 @@
+//! Conversion between YAML and parameter values, following the conventions of `rosparam`.
+
+use super::error::{ErrorKind, Result, ResultExt};
+use super::param_value::ParamValue;
+use error_chain::bail;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle, TokenType};
+use yaml_rust::Yaml;
+
+/// Change of the parameter server caused by loading a YAML document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Assignment {
+    Set(String, ParamValue),
+    Delete(String),
+}
+
+/// Parses a single value, like the ones passed as `_param:=value` arguments.
+pub fn parse_value(data: &str) -> Result<ParamValue> {
+    let document = load(data)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ErrorKind::BadYamlData(data.into()))?;
+    document.into_value()
+}
+
+/// Parses all documents, and lists the changes they make within the namespace.
+///
+/// Mappings update the namespace key by key instead of replacing it, and null values delete
+/// the parameter they are assigned to.
+pub fn assignments(namespace: &str, data: &str) -> Result<Vec<Assignment>> {
+    let mut assignments = vec![];
+    for document in load(data)? {
+        document.collect_assignments(namespace.into(), &mut assignments)?;
+    }
+    Ok(assignments)
+}
+
+/// Serializes the value as a block style YAML document.
+pub fn dump(value: &ParamValue) -> String {
+    let mut out = String::new();
+    match value {
+        ParamValue::Struct(v) if !v.is_empty() => emit_mapping(&mut out, v, 0, false),
+        ParamValue::Array(v) if !v.is_empty() => emit_sequence(&mut out, v, 0, false),
+        v => {
+            out.push_str(&scalar(v));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Null,
+    Scalar(ParamValue),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn into_value(self) -> Result<ParamValue> {
+        Ok(match self {
+            Node::Null => bail!(ErrorKind::UnsupportedParamValue(
+                "null values can't be stored, as XML-RPC has no representation for them".into()
+            )),
+            Node::Scalar(v) => v,
+            Node::Sequence(v) => {
+                ParamValue::Array(v.into_iter().map(Node::into_value).collect::<Result<_>>()?)
+            }
+            Node::Mapping(v) => ParamValue::Struct(
+                v.into_iter()
+                    .map(|(key, value)| Ok((key, value.into_value()?)))
+                    .collect::<Result<BTreeMap<_, _>>>()?,
+            ),
+        })
+    }
+
+    fn collect_assignments(self, name: String, assignments: &mut Vec<Assignment>) -> Result<()> {
+        match self {
+            Node::Mapping(v) => {
+                for (key, value) in v {
+                    value.collect_assignments(join(&name, &key), assignments)?;
+                }
+            }
+            Node::Null => assignments.push(Assignment::Delete(name)),
+            node => assignments.push(Assignment::Set(name, node.into_value()?)),
+        }
+        Ok(())
+    }
+
+    fn into_key(self) -> Result<String> {
+        Ok(match self {
+            Node::Scalar(ParamValue::String(v)) => v,
+            Node::Scalar(ParamValue::Int(v)) => v.to_string(),
+            Node::Scalar(ParamValue::Bool(v)) => v.to_string(),
+            Node::Scalar(ParamValue::Double(v)) => v.to_string(),
+            _ => bail!(ErrorKind::BadYamlData(
+                "Hash keys need to be strings".into()
+            )),
+        })
+    }
+}
+
+fn join(namespace: &str, key: &str) -> String {
+    if key.starts_with('/') {
+        return key.into();
+    }
+    format!("{}/{}", namespace.trim_end_matches('/'), key)
+}
+
+fn load(data: &str) -> Result<Vec<Node>> {
+    let mut loader = Loader::default();
+    Parser::new(data.chars())
+        .load(&mut loader, true)
+        .chain_err(|| ErrorKind::BadYamlData(data.into()))?;
+    if let Some(err) = loader.error {
+        return Err(err);
+    }
+    Ok(loader.documents)
+}
+
+/// Builds documents from parser events, keeping the tags that `YamlLoader` discards.
+#[derive(Default)]
+struct Loader {
+    documents: Vec<Node>,
+    stack: Vec<(Partial, usize)>,
+    anchors: BTreeMap<usize, Node>,
+    error: Option<super::error::Error>,
+}
+
+enum Partial {
+    Sequence(Vec<Node>),
+    Mapping(Vec<(String, Node)>, Option<String>),
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, event: Event, _: Marker) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.handle(event) {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl Loader {
+    fn handle(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::SequenceStart(anchor) => self.stack.push((Partial::Sequence(vec![]), anchor)),
+            Event::MappingStart(anchor) => {
+                self.stack.push((Partial::Mapping(vec![], None), anchor))
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let node = match self.stack.pop() {
+                    Some((Partial::Sequence(v), anchor)) => (Node::Sequence(v), anchor),
+                    Some((Partial::Mapping(v, _), anchor)) => (Node::Mapping(v), anchor),
+                    None => bail!(ErrorKind::BadYamlData("Unbalanced collection".into())),
+                };
+                self.insert(node)?;
+            }
+            Event::Scalar(value, style, anchor, tag) => {
+                let node = resolve_scalar(value, style, tag)?;
+                self.insert((node, anchor))?;
+            }
+            Event::Alias(anchor) => match self.anchors.get(&anchor) {
+                Some(node) => self.insert((node.clone(), 0))?,
+                None => bail!(ErrorKind::BadYamlData("Unknown alias".into())),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, (node, anchor): (Node, usize)) -> Result<()> {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            None => self.documents.push(node),
+            Some((Partial::Sequence(items), _)) => items.push(node),
+            Some((Partial::Mapping(_, key @ None), _)) => *key = Some(node.into_key()?),
+            Some((Partial::Mapping(entries, key), _)) => {
+                let key = key.take().unwrap_or_default();
+                entries.retain(|(existing, _)| *existing != key);
+                entries.push((key, node));
+            }
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref ANGLE: Regex = Regex::new(r"^(deg|rad)\(([^)]*)\)$").unwrap();
+}
+
+fn resolve_scalar(value: String, style: TScalarStyle, tag: Option<TokenType>) -> Result<Node> {
+    if let Some(TokenType::Tag(handle, suffix)) = tag {
+        return Ok(Node::Scalar(match (handle.as_str(), suffix.as_str()) {
+            ("!", "degrees") => ParamValue::Double(evaluate_angle(&value)? * PI / 180.0),
+            ("!", "radians") => ParamValue::Double(evaluate_angle(&value)?),
+            ("!!", "binary") => {
+                let data = value.split_whitespace().collect::<String>();
+                ParamValue::Binary(
+                    base64::decode(&data)
+                        .chain_err(|| ErrorKind::BadYamlData("Bad binary data".into()))?,
+                )
+            }
+            ("!!", "str") => ParamValue::String(value),
+            ("!!", "timestamp") => ParamValue::DateTime(value),
+            ("!!", "null") => return Ok(Node::Null),
+            ("!!", "bool") | ("!!", "int") | ("!!", "float") => {
+                return match resolve_plain(value)? {
+                    Node::Scalar(ParamValue::String(v)) => bail!(ErrorKind::BadYamlData(format!(
+                        "Value '{}' does not match tag !!{}",
+                        v, suffix
+                    ))),
+                    node => Ok(node),
+                };
+            }
+            _ => bail!(ErrorKind::BadYamlData(format!(
+                "Unsupported tag {}{}",
+                handle, suffix
+            ))),
+        }));
+    }
+    if style != TScalarStyle::Plain {
+        return Ok(Node::Scalar(ParamValue::String(value)));
+    }
+    resolve_plain(value)
+}
+
+fn resolve_plain(value: String) -> Result<Node> {
+    if let Some(captures) = ANGLE.captures(&value) {
+        let angle = evaluate_angle(&captures[2])?;
+        return Ok(Node::Scalar(ParamValue::Double(match &captures[1] {
+            "deg" => angle * PI / 180.0,
+            _ => angle,
+        })));
+    }
+    // YAML 1.1 booleans, which rosparam accepts through PyYAML
+    match value.as_str() {
+        "yes" | "Yes" | "YES" | "on" | "On" | "ON" => {
+            return Ok(Node::Scalar(ParamValue::Bool(true)))
+        }
+        "no" | "No" | "NO" | "off" | "Off" | "OFF" => {
+            return Ok(Node::Scalar(ParamValue::Bool(false)))
+        }
+        _ => {}
+    }
+    Ok(Node::Scalar(match Yaml::from_str(&value) {
+        Yaml::Integer(v) => ParamValue::Int(v),
+        Yaml::Real(v) => ParamValue::Double(parse_float(&v)?),
+        Yaml::Boolean(v) => ParamValue::Bool(v),
+        Yaml::Null => return Ok(Node::Null),
+        _ => ParamValue::String(value),
+    }))
+}
+
+pub fn parse_float(value: &str) -> Result<f64> {
+    Ok(match value {
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => f64::INFINITY,
+        "-.inf" | "-.Inf" | "-.INF" => f64::NEG_INFINITY,
+        ".nan" | "NaN" | ".NAN" => f64::NAN,
+        _ => value
+            .parse()
+            .chain_err(|| ErrorKind::BadYamlData("Failed to parse float".into()))?,
+    })
+}
+
+/// Evaluates arithmetic over numbers and `pi`, which is what `rosparam` allows in angles.
+fn evaluate_angle(expression: &str) -> Result<f64> {
+    let mut parser = AngleParser {
+        expression,
+        tokens: tokenize(expression)?,
+        position: 0,
+    };
+    let value = parser.sum()?;
+    if parser.position != parser.tokens.len() {
+        return Err(parser.error());
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Operator(char),
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = expression.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            ' ' => {}
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(Token::Operator(c)),
+            'p' if expression[start..].starts_with("pi") => {
+                chars.next();
+                tokens.push(Token::Number(PI));
+            }
+            '0'..='9' | '.' => {
+                let mut end = start + 1;
+                while let Some((index, '0'..='9' | '.')) = chars.peek() {
+                    end = index + 1;
+                    chars.next();
+                }
+                tokens.push(Token::Number(expression[start..end].parse().chain_err(
+                    || ErrorKind::BadYamlData(format!("Bad angle expression '{}'", expression)),
+                )?));
+            }
+            _ => bail!(ErrorKind::BadYamlData(format!(
+                "Bad angle expression '{}'",
+                expression
+            ))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct AngleParser<'a> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl<'a> AngleParser<'a> {
+    fn error(&self) -> super::error::Error {
+        ErrorKind::BadYamlData(format!("Bad angle expression '{}'", self.expression)).into()
+    }
+
+    fn next_operator(&mut self, operators: &str) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(c)) if operators.contains(*c) => {
+                self.position += 1;
+                Some(*c)
+            }
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<f64> {
+        let mut value = self.product()?;
+        while let Some(operator) = self.next_operator("+-") {
+            let rhs = self.product()?;
+            value = if operator == '+' {
+                value + rhs
+            } else {
+                value - rhs
+            };
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<f64> {
+        let mut value = self.factor()?;
+        while let Some(operator) = self.next_operator("*/") {
+            let rhs = self.factor()?;
+            value = if operator == '*' {
+                value * rhs
+            } else {
+                value / rhs
+            };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<f64> {
+        if let Some(operator) = self.next_operator("+-(") {
+            return match operator {
+                '+' => self.factor(),
+                '-' => Ok(-self.factor()?),
+                _ => {
+                    let value = self.sum()?;
+                    self.next_operator(")").ok_or_else(|| self.error())?;
+                    Ok(value)
+                }
+            };
+        }
+        match self.tokens.get(self.position) {
+            Some(Token::Number(v)) => {
+                self.position += 1;
+                Ok(*v)
+            }
+            _ => Err(self.error()),
+        }
+    }
+}
+
+fn emit_mapping(
+    out: &mut String,
+    entries: &BTreeMap<String, ParamValue>,
+    indent: usize,
+    inline_first: bool,
+) {
+    for (index, (key, value)) in entries.iter().enumerate() {
+        if index > 0 || !inline_first {
+            out.push_str(&" ".repeat(indent));
+        }
+        out.push_str(&string(key));
+        out.push(':');
+        match value {
+            ParamValue::Struct(v) if !v.is_empty() => {
+                out.push('\n');
+                emit_mapping(out, v, indent + 2, false);
+            }
+            ParamValue::Array(v) if !v.is_empty() => {
+                out.push('\n');
+                emit_sequence(out, v, indent, false);
+            }
+            v => {
+                out.push(' ');
+                out.push_str(&scalar(v));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn emit_sequence(out: &mut String, items: &[ParamValue], indent: usize, inline_first: bool) {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 || !inline_first {
+            out.push_str(&" ".repeat(indent));
+        }
+        out.push_str("- ");
+        match item {
+            ParamValue::Struct(v) if !v.is_empty() => emit_mapping(out, v, indent + 2, true),
+            ParamValue::Array(v) if !v.is_empty() => emit_sequence(out, v, indent + 2, true),
+            v => {
+                out.push_str(&scalar(v));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn scalar(value: &ParamValue) -> String {
+    match value {
+        ParamValue::Int(v) => v.to_string(),
+        ParamValue::Bool(v) => v.to_string(),
+        ParamValue::Double(v) if v.is_nan() => ".nan".into(),
+        ParamValue::Double(v) if v.is_infinite() && *v > 0.0 => ".inf".into(),
+        ParamValue::Double(v) if v.is_infinite() => "-.inf".into(),
+        ParamValue::Double(v) => format!("{:?}", v),
+        ParamValue::String(v) => string(v),
+        ParamValue::DateTime(v) => format!("!!timestamp {}", string(v)),
+        ParamValue::Binary(v) => format!("!!binary {}", base64::encode(v)),
+        ParamValue::Struct(_) => "{}".into(),
+        ParamValue::Array(_) => "[]".into(),
+    }
+}
+
+/// Writes strings plainly when they read back as the same string, and quoted otherwise.
+fn string(value: &str) -> String {
+    let plain_safe = value.starts_with(|c: char| c.is_alphanumeric() || "_/.".contains(c))
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-./()+=~^$@ ".contains(c))
+        && !value.ends_with(' ')
+        && matches!(
+            resolve_plain(value.into()),
+            Ok(Node::Scalar(ParamValue::String(_)))
+        );
+    if plain_safe {
+        return value.into();
+    }
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
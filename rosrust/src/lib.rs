@@ -1,13 +1,21 @@
 #![recursion_limit = "1024"]
 
 pub use crate::api::handlers::SubscriptionHandler;
-pub use crate::api::raii::{Publisher, Service, Subscriber};
-pub use crate::api::{error, Clock, Parameter};
+pub use crate::api::raii::{ParamWatch, Publisher, Service, Subscriber};
+pub use crate::api::{
+    error, Clock, FilterParams, LogFilter, NodeHandle, ParamValue, Parameter, RosLogger, Timer,
+    TimerEvent, WeakTimer,
+};
+#[doc(hidden)]
+pub use crate::api::{function_path, LogCallSite, LogSite};
 pub use crate::raw_message::{RawMessage, RawMessageDescription};
 #[doc(hidden)]
 pub use crate::rosmsg::RosMsg;
+/// XML-RPC server following the ROS response conventions, which `rosrust_master` serves with.
+#[doc(hidden)]
+pub use crate::rosxmlrpc::Server as XmlRpcServer;
 pub use crate::singleton::*;
-pub use crate::tcpros::{Client, ClientResponse, Message, ServicePair};
+pub use crate::tcpros::{Client, ClientResponse, Message, ServicePair, Stamped};
 pub use dynamic_msg::DynamicMsg;
 pub use ros_message::{Duration, MessageValue as MsgMessage, Time, Value as MsgValue};
 #[doc(hidden)]
@@ -17,6 +25,7 @@ pub mod wall_time;
 pub mod api;
 mod dynamic_msg;
 mod log_macros;
+pub mod message_filters;
 #[doc(hidden)]
 pub mod msg;
 mod raw_message;
@@ -1,170 +1,466 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ros_function_name {
+    () => {{
+        fn f() {}
+        $crate::function_path(f)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ros_log_site {
+    () => {
+        $crate::LogSite {
+            file: file!(),
+            line: line!(),
+            function: $crate::ros_function_name!(),
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! ros_log {
-    ($level:expr, $($arg:tt)+) => {
+    ($level:expr, $($arg:tt)+) => {{
         let msg = format!($($arg)*);
-        $crate::log($level, msg, file!(), line!());
-    }
+        $crate::log_at($level, msg, &$crate::ros_log_site!());
+    }};
 }
 
 #[macro_export]
 macro_rules! ros_debug {
     ($($arg:tt)*) => {
-        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*);
-    }
+        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_info {
     ($($arg:tt)*) => {
-        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::INFO, $($arg)*);
-    }
+        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_warn {
     ($($arg:tt)*) => {
-        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::WARN, $($arg)*);
-    }
+        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_err {
     ($($arg:tt)*) => {
-        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*);
-    }
+        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_fatal {
     ($($arg:tt)*) => {
-        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*);
-    }
+        $crate::ros_log!($crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_log_once {
-    ($level:expr, $($arg:tt)+) => {
+    ($level:expr, $($arg:tt)+) => {{
+        static STATE: $crate::LogCallSite = $crate::LogCallSite::new();
         let msg = format!($($arg)*);
-        $crate::log_once($level, msg, file!(), line!());
-    }
+        $crate::log_once_at($level, msg, &$crate::ros_log_site!(), &STATE);
+    }};
 }
 
 #[macro_export]
 macro_rules! ros_debug_once {
     ($($arg:tt)*) => {
-        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*);
-    }
+        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_info_once {
     ($($arg:tt)*) => {
-        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::INFO, $($arg)*);
-    }
+        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_warn_once {
     ($($arg:tt)*) => {
-        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::WARN, $($arg)*);
-    }
+        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_err_once {
     ($($arg:tt)*) => {
-        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*);
-    }
+        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_fatal_once {
     ($($arg:tt)*) => {
-        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*);
-    }
+        $crate::ros_log_once!($crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_log_throttle {
-    ($period:expr, $level:expr, $($arg:tt)+) => {
+    ($period:expr, $level:expr, $($arg:tt)+) => {{
+        static STATE: $crate::LogCallSite = $crate::LogCallSite::new();
         let msg = format!($($arg)*);
-        $crate::log_throttle($period, $level, msg, file!(), line!());
-    }
+        $crate::log_throttle_at($period, $level, msg, &$crate::ros_log_site!(), &STATE);
+    }};
 }
 
 #[macro_export]
 macro_rules! ros_debug_throttle {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*);
-    }
+        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_info_throttle {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*);
-    }
+        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_warn_throttle {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*);
-    }
+        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_err_throttle {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*);
-    }
+        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_fatal_throttle {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*);
-    }
+        $crate::ros_log_throttle!($period, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
 }
+
 #[macro_export]
 macro_rules! ros_log_throttle_identical {
-    ($period:expr, $level:expr, $($arg:tt)+) => {
+    ($period:expr, $level:expr, $($arg:tt)+) => {{
+        static STATE: $crate::LogCallSite = $crate::LogCallSite::new();
         let msg = format!($($arg)*);
-        $crate::log_throttle_identical($period, $level, msg, file!(), line!());
-    }
+        $crate::log_throttle_identical_at($period, $level, msg, &$crate::ros_log_site!(), &STATE);
+    }};
 }
 
 #[macro_export]
 macro_rules! ros_debug_throttle_identical {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*);
-    }
+        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_info_throttle_identical {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*);
-    }
+        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_warn_throttle_identical {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*);
-    }
+        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_err_throttle_identical {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*);
-    }
+        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
 }
 
 #[macro_export]
 macro_rules! ros_fatal_throttle_identical {
     ($period:expr, $($arg:tt)*) => {
-        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*);
-    }
+        $crate::ros_log_throttle_identical!($period, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Logs only when the condition holds, without formatting the message otherwise.
+#[macro_export]
+macro_rules! ros_log_cond {
+    ($cond:expr, $level:expr, $($arg:tt)+) => {
+        if $cond {
+            $crate::ros_log!($level, $($arg)+)
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! ros_debug_cond {
+    ($cond:expr, $($arg:tt)*) => {
+        $crate::ros_log_cond!($cond, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_cond {
+    ($cond:expr, $($arg:tt)*) => {
+        $crate::ros_log_cond!($cond, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_cond {
+    ($cond:expr, $($arg:tt)*) => {
+        $crate::ros_log_cond!($cond, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_cond {
+    ($cond:expr, $($arg:tt)*) => {
+        $crate::ros_log_cond!($cond, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_cond {
+    ($cond:expr, $($arg:tt)*) => {
+        $crate::ros_log_cond!($cond, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Logs through the named logger, like `planner` for the `ros.planner` logger.
+#[macro_export]
+macro_rules! ros_log_named {
+    ($name:expr, $level:expr, $($arg:tt)+) => {{
+        let msg = format!($($arg)*);
+        $crate::log_named_at($name, $level, msg, &$crate::ros_log_site!());
+    }};
+}
+
+#[macro_export]
+macro_rules! ros_debug_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($name, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($name, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($name, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($name, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($name, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Logs at most once per period, starting one period after the first call.
+#[macro_export]
+macro_rules! ros_log_delayed_throttle {
+    ($period:expr, $level:expr, $($arg:tt)+) => {{
+        static STATE: $crate::LogCallSite = $crate::LogCallSite::new();
+        let msg = format!($($arg)*);
+        $crate::log_delayed_throttle_at($period, $level, msg, &$crate::ros_log_site!(), &STATE);
+    }};
+}
+
+#[macro_export]
+macro_rules! ros_debug_delayed_throttle {
+    ($period:expr, $($arg:tt)*) => {
+        $crate::ros_log_delayed_throttle!($period, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_delayed_throttle {
+    ($period:expr, $($arg:tt)*) => {
+        $crate::ros_log_delayed_throttle!($period, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_delayed_throttle {
+    ($period:expr, $($arg:tt)*) => {
+        $crate::ros_log_delayed_throttle!($period, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_delayed_throttle {
+    ($period:expr, $($arg:tt)*) => {
+        $crate::ros_log_delayed_throttle!($period, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_delayed_throttle {
+    ($period:expr, $($arg:tt)*) => {
+        $crate::ros_log_delayed_throttle!($period, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Skips the first `count` calls, and logs every call after them.
+#[macro_export]
+macro_rules! ros_log_skip_first {
+    ($count:expr, $level:expr, $($arg:tt)+) => {{
+        static STATE: $crate::LogCallSite = $crate::LogCallSite::new();
+        let msg = format!($($arg)*);
+        $crate::log_skip_first_at($count, $level, msg, &$crate::ros_log_site!(), &STATE);
+    }};
+}
+
+#[macro_export]
+macro_rules! ros_debug_skip_first {
+    ($count:expr, $($arg:tt)*) => {
+        $crate::ros_log_skip_first!($count, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_skip_first {
+    ($count:expr, $($arg:tt)*) => {
+        $crate::ros_log_skip_first!($count, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_skip_first {
+    ($count:expr, $($arg:tt)*) => {
+        $crate::ros_log_skip_first!($count, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_skip_first {
+    ($count:expr, $($arg:tt)*) => {
+        $crate::ros_log_skip_first!($count, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_skip_first {
+    ($count:expr, $($arg:tt)*) => {
+        $crate::ros_log_skip_first!($count, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Logs when the filter, a `LogFilter` or a closure taking `&mut FilterParams`, allows it.
+#[macro_export]
+macro_rules! ros_log_filter {
+    ($filter:expr, $level:expr, $($arg:tt)+) => {{
+        let filter: &dyn $crate::LogFilter = &$filter;
+        if filter.is_enabled() {
+            let msg = format!($($arg)*);
+            $crate::log_filter_at(filter, $level, msg, &$crate::ros_log_site!());
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! ros_debug_filter {
+    ($filter:expr, $($arg:tt)*) => {
+        $crate::ros_log_filter!($filter, $crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_filter {
+    ($filter:expr, $($arg:tt)*) => {
+        $crate::ros_log_filter!($filter, $crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_filter {
+    ($filter:expr, $($arg:tt)*) => {
+        $crate::ros_log_filter!($filter, $crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_filter {
+    ($filter:expr, $($arg:tt)*) => {
+        $crate::ros_log_filter!($filter, $crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_filter {
+    ($filter:expr, $($arg:tt)*) => {
+        $crate::ros_log_filter!($filter, $crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
+}
+
+/// Logs the expressions one after another, like `ros_info_stream!("x = ", x, ", y = ", y)`.
+#[macro_export]
+macro_rules! ros_log_stream {
+    ($level:expr, $($item:expr),+ $(,)?) => {{
+        let mut msg = ::std::string::String::new();
+        $(msg.push_str(&::std::string::ToString::to_string(&$item));)+
+        $crate::log_at($level, msg, &$crate::ros_log_site!());
+    }};
+}
+
+#[macro_export]
+macro_rules! ros_debug_stream {
+    ($($arg:tt)*) => {
+        $crate::ros_log_stream!($crate::msg::rosgraph_msgs::Log::DEBUG, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_info_stream {
+    ($($arg:tt)*) => {
+        $crate::ros_log_stream!($crate::msg::rosgraph_msgs::Log::INFO, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_warn_stream {
+    ($($arg:tt)*) => {
+        $crate::ros_log_stream!($crate::msg::rosgraph_msgs::Log::WARN, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_err_stream {
+    ($($arg:tt)*) => {
+        $crate::ros_log_stream!($crate::msg::rosgraph_msgs::Log::ERROR, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! ros_fatal_stream {
+    ($($arg:tt)*) => {
+        $crate::ros_log_stream!($crate::msg::rosgraph_msgs::Log::FATAL, $($arg)*)
+    };
 }
@@ -0,0 +1,335 @@
+use super::sync::{Item, Matcher};
+use ros_message::{Duration, Time};
+use std::collections::VecDeque;
+
+/// Synchronizer policy that matches messages with stamps close to each other.
+///
+/// This is the adaptive algorithm of ROS's `ApproximateTime` policy. Every message gets used
+/// in at most one set, and each set is chosen to minimize the spread of its stamps, with
+/// a penalty for waiting on newer messages. Sets are only output once no future message can
+/// produce a better one, so the algorithm needs messages on every input to make progress.
+#[derive(Clone, Debug)]
+pub struct ApproximateTime {
+    queue_size: usize,
+    max_interval_duration: Option<Duration>,
+    age_penalty: f64,
+    inter_message_lower_bounds: Vec<(usize, Duration)>,
+}
+
+impl ApproximateTime {
+    /// Creates the policy, keeping up to `queue_size` messages per input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the queue size is zero.
+    pub fn new(queue_size: usize) -> Self {
+        assert!(queue_size > 0, "Queue size must be positive");
+        Self {
+            queue_size,
+            max_interval_duration: None,
+            age_penalty: 0.1,
+            inter_message_lower_bounds: vec![],
+        }
+    }
+
+    /// Never match sets with stamps spread further apart than the given duration.
+    pub fn with_max_interval_duration(mut self, duration: Duration) -> Self {
+        self.max_interval_duration = Some(duration);
+        self
+    }
+
+    /// How much a set is penalized for its age, compared to its spread. Defaults to `0.1`.
+    ///
+    /// Higher penalties output sets sooner, possibly missing better matches.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the penalty is negative.
+    pub fn with_age_penalty(mut self, age_penalty: f64) -> Self {
+        assert!(age_penalty >= 0.0, "Age penalty must not be negative");
+        self.age_penalty = age_penalty;
+        self
+    }
+
+    /// Smallest possible duration between the stamps of consecutive messages on an input.
+    ///
+    /// Knowing the bound lets sets be output without waiting for the input's next message.
+    pub fn with_inter_message_lower_bound(mut self, index: usize, bound: Duration) -> Self {
+        self.inter_message_lower_bounds.push((index, bound));
+        self
+    }
+}
+
+struct Candidate {
+    items: Vec<Item>,
+    start: Time,
+    end: Time,
+}
+
+struct Pivot {
+    index: usize,
+    time: Time,
+}
+
+pub(crate) struct ApproximateTimeMatcher {
+    queue_size: usize,
+    max_interval_duration: Option<Duration>,
+    age_penalty: f64,
+    inter_message_lower_bounds: Vec<Duration>,
+    deques: Vec<VecDeque<Item>>,
+    past: Vec<Vec<Item>>,
+    has_dropped_messages: Vec<bool>,
+    num_non_empty_deques: usize,
+    candidate: Option<Candidate>,
+    pivot: Option<Pivot>,
+    published: Vec<Vec<Item>>,
+}
+
+impl ApproximateTimeMatcher {
+    pub fn new(policy: ApproximateTime, size: usize) -> Self {
+        let mut inter_message_lower_bounds = vec![Duration::new(); size];
+        for (index, bound) in policy.inter_message_lower_bounds {
+            if let Some(value) = inter_message_lower_bounds.get_mut(index) {
+                *value = bound;
+            }
+        }
+        Self {
+            queue_size: policy.queue_size,
+            max_interval_duration: policy.max_interval_duration,
+            age_penalty: policy.age_penalty,
+            inter_message_lower_bounds,
+            deques: vec![VecDeque::new(); size],
+            past: vec![vec![]; size],
+            has_dropped_messages: vec![false; size],
+            num_non_empty_deques: 0,
+            candidate: None,
+            pivot: None,
+            published: vec![],
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.deques.len()
+    }
+
+    /// Whether a candidate ending at `end_time` can't be better than the current one,
+    /// against which `start_time` is compared.
+    fn is_not_better(&self, candidate: &Candidate, start_time: Time, end_time: Time) -> bool {
+        let age = (end_time - candidate.end).nanos() as f64 * (1.0 + self.age_penalty);
+        age >= (start_time - candidate.start).nanos() as f64
+    }
+
+    fn process(&mut self) {
+        while self.num_non_empty_deques == self.size() {
+            let (start_index, start_time) = self.candidate_boundary(false);
+            let (end_index, end_time) = self.candidate_boundary(true);
+            for (index, dropped) in self.has_dropped_messages.iter_mut().enumerate() {
+                if index != end_index {
+                    *dropped = false;
+                }
+            }
+            let not_better = self
+                .candidate
+                .as_ref()
+                .map(|candidate| self.is_not_better(candidate, start_time, end_time));
+            match not_better {
+                None => {
+                    let too_long = matches!(
+                        self.max_interval_duration,
+                        Some(max) if end_time - start_time > max
+                    );
+                    // A topic that dropped messages might still have had a better match
+                    if too_long || self.has_dropped_messages[end_index] {
+                        self.deque_delete_front(start_index);
+                        continue;
+                    }
+                    self.make_candidate(start_time, end_time);
+                    self.pivot = Some(Pivot {
+                        index: end_index,
+                        time: end_time,
+                    });
+                    self.deque_move_front_to_past(start_index);
+                }
+                Some(not_better) => {
+                    if !not_better {
+                        // Keep the same pivot
+                        self.make_candidate(start_time, end_time);
+                    }
+                    self.deque_move_front_to_past(start_index);
+                }
+            }
+            let (pivot_index, pivot_time) = match &self.pivot {
+                Some(pivot) => (pivot.index, pivot.time),
+                None => unreachable!("Candidate must have a pivot"),
+            };
+            let candidate = self
+                .candidate
+                .as_ref()
+                .expect("Pivot must have a candidate");
+            if start_index == pivot_index {
+                // All possible candidates for this pivot have been checked
+                self.publish_candidate();
+            } else if self.is_not_better(candidate, pivot_time, end_time) {
+                // Any future candidate must contain the interval from the pivot time to the
+                // end time, which is already too long
+                self.publish_candidate();
+            } else if self.num_non_empty_deques < self.size() {
+                self.search_virtual_candidates(pivot_index, pivot_time);
+            }
+        }
+    }
+
+    /// Uses the inter message lower bounds to try proving the candidate is optimal.
+    fn search_virtual_candidates(&mut self, pivot_index: usize, pivot_time: Time) {
+        let mut num_virtual_moves = vec![0; self.size()];
+        loop {
+            let (start_index, start_time) = self.virtual_candidate_boundary(false, pivot_time);
+            let (_, end_time) = self.virtual_candidate_boundary(true, pivot_time);
+            let candidate = self
+                .candidate
+                .as_ref()
+                .expect("Pivot must have a candidate");
+            if self.is_not_better(candidate, pivot_time, end_time) {
+                self.publish_candidate();
+                return;
+            }
+            if !self.is_not_better(candidate, start_time, end_time) {
+                // An optimistic candidate is better, so optimality can't be proven yet
+                self.num_non_empty_deques = 0;
+                for (index, count) in num_virtual_moves.into_iter().enumerate() {
+                    self.recover(index, count);
+                }
+                return;
+            }
+            // The start can't be the pivot, since its time would equal the pivot time, making
+            // one of the checks above succeed
+            debug_assert_ne!(start_index, pivot_index);
+            self.deque_move_front_to_past(start_index);
+            num_virtual_moves[start_index] += 1;
+        }
+    }
+
+    fn candidate_boundary(&self, end: bool) -> (usize, Time) {
+        let times = self.deques.iter().map(|deque| deque[0].stamp);
+        boundary(times, end)
+    }
+
+    fn virtual_candidate_boundary(&self, end: bool, pivot_time: Time) -> (usize, Time) {
+        let times = (0..self.size()).map(|index| self.virtual_time(index, pivot_time));
+        boundary(times, end)
+    }
+
+    fn virtual_time(&self, index: usize, pivot_time: Time) -> Time {
+        if let Some(item) = self.deques[index].front() {
+            return item.stamp;
+        }
+        let last = self.past[index]
+            .last()
+            .expect("Input of a candidate must have past messages");
+        let lower_bound = last.stamp + self.inter_message_lower_bounds[index];
+        lower_bound.max(pivot_time)
+    }
+
+    fn make_candidate(&mut self, start: Time, end: Time) {
+        let items = self.deques.iter().map(|deque| deque[0].clone()).collect();
+        self.candidate = Some(Candidate { items, start, end });
+        for past in &mut self.past {
+            past.clear();
+        }
+    }
+
+    fn publish_candidate(&mut self) {
+        if let Some(candidate) = self.candidate.take() {
+            self.published.push(candidate.items);
+        }
+        self.pivot = None;
+        self.num_non_empty_deques = 0;
+        for index in 0..self.size() {
+            self.recover_all(index);
+            self.deques[index].pop_front();
+            if !self.deques[index].is_empty() {
+                self.num_non_empty_deques += 1;
+            }
+        }
+    }
+
+    fn deque_delete_front(&mut self, index: usize) {
+        self.deques[index].pop_front();
+        if self.deques[index].is_empty() {
+            self.num_non_empty_deques -= 1;
+        }
+    }
+
+    fn deque_move_front_to_past(&mut self, index: usize) {
+        if let Some(item) = self.deques[index].pop_front() {
+            self.past[index].push(item);
+        }
+        if self.deques[index].is_empty() {
+            self.num_non_empty_deques -= 1;
+        }
+    }
+
+    /// Moves the newest `count` past messages back into the deque.
+    fn recover(&mut self, index: usize, count: usize) {
+        for _ in 0..count {
+            if let Some(item) = self.past[index].pop() {
+                self.deques[index].push_front(item);
+            }
+        }
+        if !self.deques[index].is_empty() {
+            self.num_non_empty_deques += 1;
+        }
+    }
+
+    fn recover_all(&mut self, index: usize) {
+        while let Some(item) = self.past[index].pop() {
+            self.deques[index].push_front(item);
+        }
+    }
+}
+
+impl Matcher for ApproximateTimeMatcher {
+    fn add(&mut self, index: usize, item: Item) -> Vec<Vec<Item>> {
+        self.deques[index].push_back(item);
+        if self.deques[index].len() == 1 {
+            self.num_non_empty_deques += 1;
+            if self.num_non_empty_deques == self.size() {
+                self.process();
+            }
+        }
+        if self.deques[index].len() + self.past[index].len() > self.queue_size {
+            // Cancel the ongoing candidate search, and drop the input's oldest message
+            self.num_non_empty_deques = 0;
+            for other in 0..self.size() {
+                self.recover_all(other);
+                if !self.deques[other].is_empty() {
+                    self.num_non_empty_deques += 1;
+                }
+            }
+            self.deques[index].pop_front();
+            if self.deques[index].is_empty() {
+                self.num_non_empty_deques -= 1;
+            }
+            self.has_dropped_messages[index] = true;
+            if self.pivot.is_some() {
+                self.candidate = None;
+                self.pivot = None;
+                self.process();
+            }
+        }
+        std::mem::take(&mut self.published)
+    }
+}
+
+/// Index and time of the earliest, or the latest, of the times.
+fn boundary(times: impl Iterator<Item = Time>, end: bool) -> (usize, Time) {
+    let mut times = times.enumerate();
+    let first = times.next().expect("Synchronizer must have inputs");
+    times.fold(first, |best, (index, time)| {
+        if (end && time >= best.1) || (!end && time < best.1) {
+            (index, time)
+        } else {
+            best
+        }
+    })
+}
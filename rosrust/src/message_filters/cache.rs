@@ -0,0 +1,144 @@
+use super::{Signal, Sink, Source};
+use crate::util::FAILED_TO_LOCK;
+use crate::Stamped;
+use ros_message::Time;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Filter that keeps the most recent messages, sorted by stamp, and passes every message on.
+///
+/// Cloning the cache shares the stored messages.
+pub struct Cache<M> {
+    inner: Arc<CacheInner<M>>,
+}
+
+impl<M> Clone for Cache<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+struct CacheInner<M> {
+    size: usize,
+    messages: Mutex<VecDeque<Arc<M>>>,
+    signal: Signal<Arc<M>>,
+}
+
+impl<M> Cache<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    /// Creates a cache that holds up to `size` messages, dropping the oldest ones.
+    pub fn new(size: usize) -> Self {
+        Self {
+            inner: Arc::new(CacheInner {
+                size,
+                messages: Mutex::new(VecDeque::with_capacity(size)),
+                signal: Signal::new(),
+            }),
+        }
+    }
+
+    #[inline]
+    fn messages(&self) -> MutexGuard<'_, VecDeque<Arc<M>>> {
+        self.inner.messages.lock().expect(FAILED_TO_LOCK)
+    }
+
+    /// Messages with stamps within the inclusive range.
+    pub fn interval(&self, start: Time, end: Time) -> Vec<Arc<M>> {
+        self.messages()
+            .iter()
+            .filter(|message| message.stamp() >= start && message.stamp() <= end)
+            .cloned()
+            .collect()
+    }
+
+    /// Messages within the range, plus the closest messages right before and right after it.
+    ///
+    /// This covers the whole range, as long as the cache holds messages from around it.
+    pub fn surroundings(&self, start: Time, end: Time) -> Vec<Arc<M>> {
+        let messages = self.messages();
+        let first = messages
+            .iter()
+            .rposition(|message| message.stamp() <= start)
+            .unwrap_or(0);
+        let last = messages
+            .iter()
+            .position(|message| message.stamp() >= end)
+            .unwrap_or_else(|| messages.len().saturating_sub(1));
+        messages
+            .iter()
+            .skip(first)
+            .take((last + 1).saturating_sub(first))
+            .cloned()
+            .collect()
+    }
+
+    /// Newest message with a stamp at or before the time.
+    pub fn elem_before_time(&self, time: Time) -> Option<Arc<M>> {
+        self.messages()
+            .iter()
+            .rev()
+            .find(|message| message.stamp() <= time)
+            .cloned()
+    }
+
+    /// Oldest message with a stamp at or after the time.
+    pub fn elem_after_time(&self, time: Time) -> Option<Arc<M>> {
+        self.messages()
+            .iter()
+            .find(|message| message.stamp() >= time)
+            .cloned()
+    }
+
+    pub fn oldest_time(&self) -> Option<Time> {
+        self.messages().front().map(|message| message.stamp())
+    }
+
+    pub fn latest_time(&self) -> Option<Time> {
+        self.messages().back().map(|message| message.stamp())
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages().is_empty()
+    }
+}
+
+impl<M> Sink<Arc<M>> for Cache<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn add(&self, message: Arc<M>) {
+        {
+            let mut messages = self.messages();
+            let stamp = message.stamp();
+            let position = messages
+                .iter()
+                .rposition(|item| item.stamp() <= stamp)
+                .map_or(0, |index| index + 1);
+            messages.insert(position, Arc::clone(&message));
+            while messages.len() > self.inner.size {
+                messages.pop_front();
+            }
+        }
+        self.inner.signal.emit(message);
+    }
+}
+
+impl<M> Source<Arc<M>> for Cache<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(Arc<M>) + Send + Sync + 'static,
+    {
+        self.inner.signal.connect(callback)
+    }
+}
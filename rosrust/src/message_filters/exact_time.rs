@@ -0,0 +1,54 @@
+use super::sync::{Item, Matcher};
+use ros_message::Time;
+use std::collections::BTreeMap;
+
+/// Synchronizer policy that matches messages with exactly the same stamp.
+///
+/// Once a set gets matched, incomplete sets with older stamps are dropped.
+#[derive(Clone, Debug)]
+pub struct ExactTime {
+    queue_size: usize,
+}
+
+impl ExactTime {
+    /// Creates the policy, keeping up to `queue_size` incomplete sets.
+    pub fn new(queue_size: usize) -> Self {
+        Self { queue_size }
+    }
+}
+
+pub(crate) struct ExactTimeMatcher {
+    queue_size: usize,
+    size: usize,
+    sets: BTreeMap<Time, Vec<Option<Item>>>,
+}
+
+impl ExactTimeMatcher {
+    pub fn new(policy: ExactTime, size: usize) -> Self {
+        Self {
+            queue_size: policy.queue_size,
+            size,
+            sets: BTreeMap::new(),
+        }
+    }
+}
+
+impl Matcher for ExactTimeMatcher {
+    fn add(&mut self, index: usize, item: Item) -> Vec<Vec<Item>> {
+        let stamp = item.stamp;
+        let size = self.size;
+        let set = self.sets.entry(stamp).or_insert_with(|| vec![None; size]);
+        set[index] = Some(item);
+        if set.iter().all(Option::is_some) {
+            let set = self.sets.remove(&stamp).unwrap_or_default();
+            let set = set.into_iter().flatten().collect();
+            self.sets = self.sets.split_off(&stamp);
+            return vec![set];
+        }
+        while self.sets.len() > self.queue_size {
+            let oldest = *self.sets.keys().next().unwrap();
+            self.sets.remove(&oldest);
+        }
+        vec![]
+    }
+}
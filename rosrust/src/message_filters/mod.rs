@@ -0,0 +1,100 @@
+//! Filters for processing messages in chains, modeled after ROS [message_filters].
+//!
+//! Filters pass messages on to the callbacks registered with them, and accept messages from
+//! any [`Source`] they get connected to. A typical chain subscribes to topics, and feeds the
+//! messages into a [`Synchronizer`] that matches them by their header stamps:
+//!
+//! ```no_run
+//! use rosrust::message_filters::{ApproximateTime, Sink, Source, Subscriber, Synchronizer};
+//! use std::sync::Arc;
+//!
+//! mod msg {
+//!     rosrust::rosmsg_include!(sensor_msgs / Image, sensor_msgs / CameraInfo);
+//! }
+//! use msg::sensor_msgs::{CameraInfo, Image};
+//!
+//! rosrust::init("synchronizer");
+//! let images = Subscriber::<Image>::new("/camera/image", 10).unwrap();
+//! let infos = Subscriber::<CameraInfo>::new("/camera/camera_info", 10).unwrap();
+//! let sync = Synchronizer::<(Arc<Image>, Arc<CameraInfo>)>::new(ApproximateTime::new(10));
+//! sync.input0().connect_input(&images);
+//! sync.input1().connect_input(&infos);
+//! sync.register_callback(|(image, info)| {
+//!     println!("{}x{} image, with {}x{} info", image.width, image.height, info.width, info.height);
+//! });
+//! rosrust::spin();
+//! ```
+//!
+//! Callbacks are called on the thread that passed in the message, after any of the filter's
+//! own locks have been released.
+//!
+//! [message_filters]: http://wiki.ros.org/message_filters
+
+pub use self::approximate_time::ApproximateTime;
+pub use self::cache::Cache;
+pub use self::exact_time::ExactTime;
+pub use self::subscriber::Subscriber;
+pub use self::sync::{MessageTuple, Policy, SyncInput, Synchronizer};
+pub use self::time_sequencer::TimeSequencer;
+
+use crate::util::FAILED_TO_LOCK;
+use std::sync::{Arc, Mutex};
+
+mod approximate_time;
+mod cache;
+mod exact_time;
+mod subscriber;
+mod sync;
+mod time_sequencer;
+
+/// Output of a filter, passing each item on to the registered callbacks.
+pub trait Source<T> {
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(T) + Send + Sync + 'static;
+}
+
+/// Input of a filter.
+pub trait Sink<T>: Clone + Send + Sync + 'static {
+    fn add(&self, item: T);
+
+    /// Feeds every item of the source into this filter.
+    fn connect_input<S>(&self, source: &S)
+    where
+        S: Source<T>,
+    {
+        let sink = self.clone();
+        source.register_callback(move |item| sink.add(item));
+    }
+}
+
+type Callback<T> = Arc<dyn Fn(T) + Send + Sync>;
+
+pub(crate) struct Signal<T> {
+    callbacks: Mutex<Vec<Callback<T>>>,
+}
+
+impl<T: Clone> Signal<T> {
+    pub fn new() -> Self {
+        Self {
+            callbacks: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn connect<F>(&self, callback: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .push(Arc::new(callback));
+    }
+
+    pub fn emit(&self, item: T) {
+        let callbacks = self.callbacks.lock().expect(FAILED_TO_LOCK).clone();
+        for callback in callbacks {
+            callback(item.clone());
+        }
+    }
+}
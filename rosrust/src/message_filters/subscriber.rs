@@ -0,0 +1,50 @@
+use super::{Signal, Source};
+use crate::api::error::Result;
+use crate::api::raii;
+use crate::api::Ros;
+use crate::Message;
+use std::sync::Arc;
+
+/// Filter that passes on the messages received on a topic.
+///
+/// Messages stop coming once the subscriber gets dropped.
+pub struct Subscriber<M> {
+    signal: Arc<Signal<Arc<M>>>,
+    _subscriber: raii::Subscriber,
+}
+
+impl<M: Message> Subscriber<M> {
+    /// Subscribes on the global node.
+    pub fn new(topic: &str, queue_size: usize) -> Result<Self> {
+        let signal = Arc::new(Signal::new());
+        let callback_signal = Arc::clone(&signal);
+        let subscriber = crate::subscribe(topic, queue_size, move |message: M| {
+            callback_signal.emit(Arc::new(message))
+        })?;
+        Ok(Self {
+            signal,
+            _subscriber: subscriber,
+        })
+    }
+
+    pub fn with_node(ros: &Ros, topic: &str, queue_size: usize) -> Result<Self> {
+        let signal = Arc::new(Signal::new());
+        let callback_signal = Arc::clone(&signal);
+        let subscriber = ros.subscribe(topic, queue_size, move |message: M| {
+            callback_signal.emit(Arc::new(message))
+        })?;
+        Ok(Self {
+            signal,
+            _subscriber: subscriber,
+        })
+    }
+}
+
+impl<M: Message> Source<Arc<M>> for Subscriber<M> {
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(Arc<M>) + Send + Sync + 'static,
+    {
+        self.signal.connect(callback)
+    }
+}
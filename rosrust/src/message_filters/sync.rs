@@ -0,0 +1,216 @@
+use super::approximate_time::{ApproximateTime, ApproximateTimeMatcher};
+use super::exact_time::{ExactTime, ExactTimeMatcher};
+use super::{Signal, Sink, Source};
+use crate::util::FAILED_TO_LOCK;
+use crate::Stamped;
+use ros_message::Time;
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+static WRONG_MESSAGE_TYPE: &str = "Synchronizer input received a message of the wrong type";
+
+/// Message waiting to be matched, with its type erased.
+#[derive(Clone)]
+pub(crate) struct Item {
+    pub stamp: Time,
+    pub message: Arc<dyn Any + Send + Sync>,
+}
+
+/// Matching algorithm of a synchronizer.
+pub(crate) trait Matcher: Send {
+    /// Stores the item for the input, and returns the sets of items that got matched.
+    fn add(&mut self, index: usize, item: Item) -> Vec<Vec<Item>>;
+}
+
+/// Policy that decides which messages a [`Synchronizer`] matches into sets.
+#[derive(Clone, Debug)]
+pub struct Policy {
+    kind: PolicyKind,
+}
+
+#[derive(Clone, Debug)]
+enum PolicyKind {
+    Exact(ExactTime),
+    Approximate(ApproximateTime),
+}
+
+impl Policy {
+    fn into_matcher(self, size: usize) -> Box<dyn Matcher> {
+        match self.kind {
+            PolicyKind::Exact(policy) => Box::new(ExactTimeMatcher::new(policy, size)),
+            PolicyKind::Approximate(policy) => Box::new(ApproximateTimeMatcher::new(policy, size)),
+        }
+    }
+}
+
+impl From<ExactTime> for Policy {
+    fn from(policy: ExactTime) -> Self {
+        Self {
+            kind: PolicyKind::Exact(policy),
+        }
+    }
+}
+
+impl From<ApproximateTime> for Policy {
+    fn from(policy: ApproximateTime) -> Self {
+        Self {
+            kind: PolicyKind::Approximate(policy),
+        }
+    }
+}
+
+/// Tuple of messages that a [`Synchronizer`] outputs, like `(Arc<Image>, Arc<CameraInfo>)`.
+///
+/// Implemented for tuples of two up to nine stamped messages.
+pub trait MessageTuple: Clone + Send + Sync + 'static {
+    #[doc(hidden)]
+    const SIZE: usize;
+
+    #[doc(hidden)]
+    fn from_messages(messages: Vec<Arc<dyn Any + Send + Sync>>) -> Self;
+}
+
+/// Filter that matches messages from several inputs by their stamps, and passes each
+/// matched set on as a tuple.
+///
+/// Messages go in through the inputs, like `input0()` for the first tuple element. Cloning
+/// the synchronizer shares the messages waiting to be matched.
+pub struct Synchronizer<T> {
+    core: Arc<SyncCore<T>>,
+}
+
+impl<T> Clone for Synchronizer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            core: Arc::clone(&self.core),
+        }
+    }
+}
+
+struct SyncCore<T> {
+    matcher: Mutex<Box<dyn Matcher>>,
+    signal: Signal<T>,
+}
+
+trait ItemSink: Send + Sync {
+    fn add_item(&self, index: usize, item: Item);
+}
+
+impl<T: MessageTuple> ItemSink for SyncCore<T> {
+    fn add_item(&self, index: usize, item: Item) {
+        let sets = self.matcher.lock().expect(FAILED_TO_LOCK).add(index, item);
+        for set in sets {
+            let messages = set.into_iter().map(|item| item.message).collect();
+            self.signal.emit(T::from_messages(messages));
+        }
+    }
+}
+
+impl<T: MessageTuple> Synchronizer<T> {
+    pub fn new(policy: impl Into<Policy>) -> Self {
+        Self {
+            core: Arc::new(SyncCore {
+                matcher: Mutex::new(policy.into().into_matcher(T::SIZE)),
+                signal: Signal::new(),
+            }),
+        }
+    }
+
+    fn input<M>(&self, index: usize) -> SyncInput<M> {
+        SyncInput {
+            core: Arc::clone(&self.core) as Arc<dyn ItemSink>,
+            index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: MessageTuple> Source<T> for Synchronizer<T> {
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.core.signal.connect(callback)
+    }
+}
+
+/// Input of a [`Synchronizer`], accepting messages for one element of its tuples.
+pub struct SyncInput<M> {
+    core: Arc<dyn ItemSink>,
+    index: usize,
+    _phantom: PhantomData<fn(M)>,
+}
+
+impl<M> Clone for SyncInput<M> {
+    fn clone(&self) -> Self {
+        Self {
+            core: Arc::clone(&self.core),
+            index: self.index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<M> Sink<Arc<M>> for SyncInput<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn add(&self, message: Arc<M>) {
+        let item = Item {
+            stamp: message.stamp(),
+            message,
+        };
+        self.core.add_item(self.index, item);
+    }
+}
+
+macro_rules! impl_message_tuple {
+    ($size:expr; $($index:tt $input:ident $message:ident),+) => {
+        impl<$($message),+> MessageTuple for ($(Arc<$message>,)+)
+        where
+            $($message: Stamped + Send + Sync + 'static),+
+        {
+            const SIZE: usize = $size;
+
+            fn from_messages(messages: Vec<Arc<dyn Any + Send + Sync>>) -> Self {
+                let mut messages = messages.into_iter();
+                ($(
+                    messages
+                        .next()
+                        .and_then(|message| message.downcast::<$message>().ok())
+                        .expect(WRONG_MESSAGE_TYPE),
+                )+)
+            }
+        }
+
+        impl<$($message),+> Synchronizer<($(Arc<$message>,)+)>
+        where
+            $($message: Stamped + Send + Sync + 'static),+
+        {
+            $(
+                #[doc = concat!("Input for element ", stringify!($index), " of the tuples.")]
+                pub fn $input(&self) -> SyncInput<$message> {
+                    self.input($index)
+                }
+            )+
+        }
+    };
+}
+
+impl_message_tuple!(2; 0 input0 A, 1 input1 B);
+impl_message_tuple!(3; 0 input0 A, 1 input1 B, 2 input2 C);
+impl_message_tuple!(4; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D);
+impl_message_tuple!(5; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D, 4 input4 E);
+impl_message_tuple!(6; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D, 4 input4 E, 5 input5 F);
+impl_message_tuple!(
+    7; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D, 4 input4 E, 5 input5 F, 6 input6 G
+);
+impl_message_tuple!(
+    8; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D, 4 input4 E, 5 input5 F, 6 input6 G,
+    7 input7 H
+);
+impl_message_tuple!(
+    9; 0 input0 A, 1 input1 B, 2 input2 C, 3 input3 D, 4 input4 E, 5 input5 F, 6 input6 G,
+    7 input7 H, 8 input8 I
+);
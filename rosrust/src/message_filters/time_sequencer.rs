@@ -0,0 +1,141 @@
+use super::{Signal, Sink, Source};
+use crate::api::Ros;
+use crate::util::FAILED_TO_LOCK;
+use crate::{Stamped, Timer};
+use ros_message::{Duration, Time};
+use std::sync::{Arc, Mutex};
+
+/// Filter that delays messages, and passes them on in the order of their stamps.
+///
+/// Each message is held until the node's clock reaches its stamp plus the delay. Messages
+/// arriving after a newer one has already been passed on get dropped, as do the oldest ones
+/// once more than the queue size are waiting.
+pub struct TimeSequencer<M> {
+    inner: Arc<SequencerInner<M>>,
+    _timer: Timer,
+}
+
+impl<M> Clone for TimeSequencer<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            _timer: self._timer.clone(),
+        }
+    }
+}
+
+struct SequencerInner<M> {
+    delay: Duration,
+    queue_size: usize,
+    state: Mutex<SequencerState<M>>,
+    signal: Signal<Arc<M>>,
+}
+
+struct SequencerState<M> {
+    waiting: Vec<Arc<M>>,
+    last_released: Option<Time>,
+}
+
+impl<M> TimeSequencer<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    /// Creates a sequencer on the global node, checking for due messages every update period.
+    pub fn new(delay: Duration, update_period: Duration, queue_size: usize) -> Self {
+        Self::with_timer(delay, queue_size, |callback| {
+            crate::create_timer(update_period, callback)
+        })
+    }
+
+    pub fn with_node(
+        ros: &Ros,
+        delay: Duration,
+        update_period: Duration,
+        queue_size: usize,
+    ) -> Self {
+        Self::with_timer(delay, queue_size, |callback| {
+            ros.create_timer(update_period, callback)
+        })
+    }
+
+    fn with_timer<F>(delay: Duration, queue_size: usize, create_timer: F) -> Self
+    where
+        F: FnOnce(Box<dyn FnMut(crate::TimerEvent) + Send>) -> Timer,
+    {
+        let inner = Arc::new(SequencerInner {
+            delay,
+            queue_size,
+            state: Mutex::new(SequencerState {
+                waiting: vec![],
+                last_released: None,
+            }),
+            signal: Signal::new(),
+        });
+        let timer_inner = Arc::clone(&inner);
+        let timer = create_timer(Box::new(move |event| {
+            timer_inner.dispatch(event.current_real)
+        }));
+        Self {
+            inner,
+            _timer: timer,
+        }
+    }
+}
+
+impl<M> SequencerInner<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn dispatch(&self, now: Time) {
+        let ready = {
+            let mut state = self.state.lock().expect(FAILED_TO_LOCK);
+            let count = state
+                .waiting
+                .iter()
+                .take_while(|message| message.stamp() + self.delay <= now)
+                .count();
+            let ready = state.waiting.drain(..count).collect::<Vec<_>>();
+            if let Some(last) = ready.last() {
+                state.last_released = Some(last.stamp());
+            }
+            ready
+        };
+        for message in ready {
+            self.signal.emit(message);
+        }
+    }
+}
+
+impl<M> Sink<Arc<M>> for TimeSequencer<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn add(&self, message: Arc<M>) {
+        let mut state = self.inner.state.lock().expect(FAILED_TO_LOCK);
+        let stamp = message.stamp();
+        if matches!(state.last_released, Some(last) if stamp < last) {
+            return;
+        }
+        let position = state
+            .waiting
+            .iter()
+            .rposition(|item| item.stamp() <= stamp)
+            .map_or(0, |index| index + 1);
+        state.waiting.insert(position, message);
+        if state.waiting.len() > self.inner.queue_size {
+            state.waiting.remove(0);
+        }
+    }
+}
+
+impl<M> Source<Arc<M>> for TimeSequencer<M>
+where
+    M: Stamped + Send + Sync + 'static,
+{
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(Arc<M>) + Send + Sync + 'static,
+    {
+        self.inner.signal.connect(callback)
+    }
+}
@@ -1,6 +1,5 @@
 use super::error::{ErrorKind, Result, ResultExt};
 use super::{Response, ResponseError, ResponseInfo};
-use serde::{Deserialize, Serialize};
 use xml_rpc::{self, Params, Url, Value};
 
 pub struct Client {
@@ -33,23 +32,6 @@ impl Client {
 
         ResponseInfo::from_array(response_parameters)?.into()
     }
-
-    pub fn request_tree<S>(&self, name: &str, params: &S) -> Response<Value>
-    where
-        S: Serialize,
-    {
-        let params = xml_rpc::into_params(params).map_err(bad_request_structure)?;
-        self.request_tree_with_tree(name, params)
-    }
-
-    pub fn request<'a, S, D>(&self, name: &str, params: &S) -> Response<D>
-    where
-        S: Serialize,
-        D: Deserialize<'a>,
-    {
-        let data = self.request_tree(name, params)?;
-        Deserialize::deserialize(data).map_err(bad_response_structure)
-    }
 }
 
 fn remove_array_wrappers(mut data: &[Value]) -> &[Value] {
@@ -59,7 +41,7 @@ fn remove_array_wrappers(mut data: &[Value]) -> &[Value] {
     data
 }
 
-fn bad_request_structure<T: ::std::fmt::Display>(err: T) -> ResponseError {
+pub(crate) fn bad_request_structure<T: ::std::fmt::Display>(err: T) -> ResponseError {
     ResponseError::Client(format!("Failed to serialize parameters: {}", err))
 }
 
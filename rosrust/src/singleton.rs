@@ -1,6 +1,12 @@
-use crate::api::raii::{Publisher, Service, Subscriber};
+use crate::api::raii::{ParamWatch, Publisher, Service, Subscriber};
 use crate::api::resolve::get_unused_args;
-use crate::api::{Delay, Parameter, Rate, Ros, SystemState, Topic};
+#[cfg(feature = "testing")]
+use crate::api::testing::TestKit;
+use crate::api::{
+    ClockServer, ClockServerOptions, Delay, JumpHandle, JumpThreshold, LogCallSite, LogFileOptions,
+    LogFilter, LogSite, NodeHandle, ParamValue, Parameter, Rate, Ros, RosLogger, SystemState,
+    TimeJump, Timer, TimerEvent, Topic,
+};
 use crate::error::{ErrorKind, Result};
 use crate::rosxmlrpc::Response;
 use crate::tcpros::{Client, Message, ServicePair, ServiceResult};
@@ -11,7 +17,9 @@ use ctrlc;
 use error_chain::bail;
 use lazy_static::lazy_static;
 use ros_message::{Duration, Time};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time;
 
@@ -57,6 +65,18 @@ pub fn try_init_with_options(name: &str, capture_sigint: bool) -> Result<()> {
     Ok(())
 }
 
+/// Initializes the global node inside a test kit's in-memory graph, instead of connecting to
+/// the ROS master. Like with `init`, this can only happen once per process.
+#[cfg(feature = "testing")]
+pub fn try_init_with_test_kit(name: &str, test_kit: &TestKit) -> Result<()> {
+    let mut ros = ROS.write().expect(FAILED_TO_LOCK);
+    if ros.is_some() {
+        bail!(ErrorKind::MultipleInitialization);
+    }
+    *ros = Some(test_kit.node(name)?);
+    Ok(())
+}
+
 pub fn is_initialized() -> bool {
     ROS.read().expect(FAILED_TO_LOCK).is_some()
 }
@@ -110,6 +130,51 @@ pub fn rate(rate: f64) -> Rate {
     ros!().rate(rate)
 }
 
+#[inline]
+pub fn clock_server(options: ClockServerOptions) -> Result<ClockServer> {
+    ros!().clock_server(options)
+}
+
+#[inline]
+pub fn add_jump_callback<F>(threshold: JumpThreshold, callback: F) -> JumpHandle
+where
+    F: Fn(&TimeJump) + Send + Sync + 'static,
+{
+    ros!().add_jump_callback(threshold, callback)
+}
+
+#[inline]
+pub fn create_timer<F>(period: Duration, callback: F) -> Timer
+where
+    F: FnMut(TimerEvent) + Send + 'static,
+{
+    ros!().create_timer(period, callback)
+}
+
+#[inline]
+pub fn create_oneshot_timer<F>(period: Duration, callback: F) -> Timer
+where
+    F: FnMut(TimerEvent) + Send + 'static,
+{
+    ros!().create_oneshot_timer(period, callback)
+}
+
+#[inline]
+pub fn create_wall_timer<F>(period: Duration, callback: F) -> Timer
+where
+    F: FnMut(TimerEvent) + Send + 'static,
+{
+    ros!().create_wall_timer(period, callback)
+}
+
+#[inline]
+pub fn create_oneshot_wall_timer<F>(period: Duration, callback: F) -> Timer
+where
+    F: FnMut(TimerEvent) + Send + 'static,
+{
+    ros!().create_oneshot_wall_timer(period, callback)
+}
+
 #[inline]
 pub fn is_ok() -> bool {
     ros!().is_ok()
@@ -126,11 +191,33 @@ pub fn shutdown() {
     ros!().shutdown_sender().shutdown()
 }
 
+#[inline]
+pub fn node_handle(namespace: &str) -> Result<NodeHandle> {
+    ros!().node_handle(namespace)
+}
+
 #[inline]
 pub fn param(name: &str) -> Option<Parameter> {
     ros!().param(name)
 }
 
+#[inline]
+pub fn watch_param<F>(name: &str, callback: F) -> Result<ParamWatch>
+where
+    F: Fn(Option<ParamValue>) + Send + Sync + 'static,
+{
+    ros!().watch_param(name, callback)
+}
+
+#[inline]
+pub fn watch_param_typed<T, F>(name: &str, callback: F) -> Result<ParamWatch>
+where
+    T: DeserializeOwned,
+    F: Fn(Option<T>) + Send + Sync + 'static,
+{
+    ros!().watch_param_typed::<T, F>(name, callback)
+}
+
 #[inline]
 pub fn parameters() -> Response<Vec<String>> {
     ros!().parameters()
@@ -232,19 +319,127 @@ pub fn log(level: i8, msg: String, file: &str, line: u32) {
     ros!().log(level, msg, file, line)
 }
 
+#[inline]
+pub fn log_named(name: &str, level: i8, msg: String, file: &str, line: u32) {
+    ros!().log_named(name, level, msg, file, line)
+}
+
+#[deprecated(note = "use `log_once_at`, as calls on one line share their state")]
+#[allow(deprecated)]
 #[inline]
 pub fn log_once(level: i8, msg: String, file: &str, line: u32) {
     ros!().log_once(level, msg, file, line)
 }
 
+#[deprecated(note = "use `log_throttle_at`, as calls on one line share their state")]
+#[allow(deprecated)]
 #[inline]
 pub fn log_throttle(period: f64, level: i8, msg: String, file: &str, line: u32) {
     ros!().log_throttle(period, level, msg, file, line)
 }
 
+#[deprecated(note = "use `log_throttle_identical_at`, as calls on one line share their state")]
+#[allow(deprecated)]
 #[inline]
 pub fn log_throttle_identical(period: f64, level: i8, msg: String, file: &str, line: u32) {
     ros!().log_throttle_identical(period, level, msg, file, line)
 }
 
+#[doc(hidden)]
+#[inline]
+pub fn log_at(level: i8, msg: String, site: &LogSite<'_>) {
+    ros!().log_at(level, msg, site)
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn log_named_at(name: &str, level: i8, msg: String, site: &LogSite<'_>) {
+    ros!().log_named_at(name, level, msg, site)
+}
+
+#[inline]
+pub fn log_once_at(level: i8, msg: String, site: &LogSite<'_>, state: &LogCallSite) {
+    ros!().log_once_at(level, msg, site, state)
+}
+
+#[inline]
+pub fn log_throttle_at(
+    period: f64,
+    level: i8,
+    msg: String,
+    site: &LogSite<'_>,
+    state: &LogCallSite,
+) {
+    ros!().log_throttle_at(period, level, msg, site, state)
+}
+
+#[inline]
+pub fn log_delayed_throttle_at(
+    period: f64,
+    level: i8,
+    msg: String,
+    site: &LogSite<'_>,
+    state: &LogCallSite,
+) {
+    ros!().log_delayed_throttle_at(period, level, msg, site, state)
+}
+
+#[inline]
+pub fn log_throttle_identical_at(
+    period: f64,
+    level: i8,
+    msg: String,
+    site: &LogSite<'_>,
+    state: &LogCallSite,
+) {
+    ros!().log_throttle_identical_at(period, level, msg, site, state)
+}
+
+#[inline]
+pub fn log_skip_first_at(
+    count: u64,
+    level: i8,
+    msg: String,
+    site: &LogSite<'_>,
+    state: &LogCallSite,
+) {
+    ros!().log_skip_first_at(count, level, msg, site, state)
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn log_filter_at(filter: &dyn LogFilter, level: i8, msg: String, site: &LogSite<'static>) {
+    ros!().log_filter_at(filter, level, msg, site)
+}
+
+#[inline]
+pub fn logger_level(logger: &str) -> i8 {
+    ros!().logger_level(logger)
+}
+
+#[inline]
+pub fn set_logger_level(logger: &str, level: i8) {
+    ros!().set_logger_level(logger, level)
+}
+
+#[inline]
+pub fn log_to_file<P: AsRef<Path>>(path: P, options: LogFileOptions) -> Result<()> {
+    ros!().log_to_file(path, options)
+}
+
+#[inline]
+pub fn stop_log_file() {
+    ros!().stop_log_file()
+}
+
+#[inline]
+pub fn log_file_path() -> Option<PathBuf> {
+    ros!().log_file_path()
+}
+
+#[inline]
+pub fn rosout_logger() -> RosLogger {
+    ros!().rosout_logger()
+}
+
 static UNINITIALIZED: &str = "ROS uninitialized. Please run ros::init(name) first!";
@@ -1,6 +1,8 @@
 use super::error::{ErrorKind, Result, ResultExt};
 use super::header::{decode, encode};
 use super::{ServicePair, ServiceResult};
+#[cfg(feature = "testing")]
+use crate::api::testing::MemoryBus;
 use crate::api::Master;
 use crate::rosmsg::RosMsg;
 use crate::util::FAILED_TO_LOCK;
@@ -69,6 +71,8 @@ impl UriCache {
 pub struct Client<T: ServicePair> {
     info: std::sync::Arc<ClientInfo>,
     uri_cache: std::sync::Arc<UriCache>,
+    #[cfg(feature = "testing")]
+    memory_bus: Option<Arc<MemoryBus>>,
     phantom: std::marker::PhantomData<T>,
 }
 
@@ -143,10 +147,25 @@ impl<T: ServicePair> Client<T> {
                 data: Mutex::new(None),
                 service: String::from(service),
             }),
+            #[cfg(feature = "testing")]
+            memory_bus: None,
             phantom: std::marker::PhantomData,
         }
     }
 
+    #[cfg(feature = "testing")]
+    pub(crate) fn new_in_memory(
+        master: Arc<Master>,
+        memory_bus: Arc<MemoryBus>,
+        caller_id: &str,
+        service: &str,
+    ) -> Client<T> {
+        Client {
+            memory_bus: Some(memory_bus),
+            ..Self::new(master, caller_id, service)
+        }
+    }
+
     fn probe_inner(&self, timeout: std::time::Duration) -> Result<()> {
         let mut stream = connect_to_tcp_attempt(&self.uri_cache, Some(timeout))?;
         exchange_probe_headers(&mut stream, &self.info.caller_id, &self.info.service)?;
@@ -154,6 +173,10 @@ impl<T: ServicePair> Client<T> {
     }
 
     pub fn probe(&self, timeout: std::time::Duration) -> Result<()> {
+        #[cfg(feature = "testing")]
+        if let Some(memory_bus) = &self.memory_bus {
+            return memory_bus.probe_service(&self.info.service);
+        }
         let probe_result = self.probe_inner(timeout);
         if probe_result.is_err() {
             self.uri_cache.clear();
@@ -162,6 +185,10 @@ impl<T: ServicePair> Client<T> {
     }
 
     pub fn req(&self, args: &T::Request) -> Result<ServiceResult<T::Response>> {
+        #[cfg(feature = "testing")]
+        if let Some(memory_bus) = &self.memory_bus {
+            return memory_bus.call_service::<T>(&self.info.service, args);
+        }
         Self::request_body(
             args,
             &self.uri_cache,
@@ -173,8 +200,14 @@ impl<T: ServicePair> Client<T> {
     pub fn req_async(&self, args: T::Request) -> ClientResponse<T::Response> {
         let info = Arc::clone(&self.info);
         let uri_cache = Arc::clone(&self.uri_cache);
+        #[cfg(feature = "testing")]
+        let memory_bus = self.memory_bus.clone();
         ClientResponse {
             handle: thread::spawn(move || {
+                #[cfg(feature = "testing")]
+                if let Some(memory_bus) = memory_bus {
+                    return memory_bus.call_service::<T>(&info.service, &args);
+                }
                 Self::request_body(&args, &uri_cache, &info.caller_id, &info.service)
             }),
         }
@@ -3,9 +3,11 @@ pub use self::error::Error;
 pub use self::publisher::{Publisher, PublisherStream};
 pub use self::service::Service;
 pub use self::subscriber::SubscriberRosConnection;
+pub(crate) use self::subscriber::{handle_data, MessageInfo};
 
 use crate::rosmsg::RosMsg;
 use crate::Clock;
+use ros_message::Time;
 use std::fmt::Debug;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
@@ -27,6 +29,13 @@ pub trait Message: Clone + Debug + Default + PartialEq + RosMsg + Send + Sync +
     fn set_header(&mut self, _clock: &Arc<dyn Clock>, _seq: &Arc<AtomicUsize>) {}
 }
 
+/// Data that belongs to a point in time, like messages with a `std_msgs/Header`.
+///
+/// Generated messages implement it whenever they have a `header` field.
+pub trait Stamped {
+    fn stamp(&self) -> Time;
+}
+
 pub trait ServicePair: Clone + Debug + Default + PartialEq + Message {
     type Request: RosMsg + Send + 'static;
     type Response: RosMsg + Send + 'static;
@@ -172,7 +172,7 @@ impl SubscriberRosConnection {
     }
 }
 
-fn handle_data<T, H>(
+pub fn handle_data<T, H>(
     data: LossyReceiver<MessageInfo>,
     connections: Receiver<HashMap<String, String>>,
     mut handler: H,
@@ -419,13 +419,13 @@ fn package_to_vector<R: std::io::Read>(stream: &mut R) -> std::io::Result<Vec<u8
 }
 
 #[derive(Clone)]
-struct MessageInfo {
+pub struct MessageInfo {
     caller_id: Arc<String>,
     data: Vec<u8>,
 }
 
 impl MessageInfo {
-    fn new(caller_id: Arc<String>, data: Vec<u8>) -> Self {
+    pub fn new(caller_id: Arc<String>, data: Vec<u8>) -> Self {
         Self { caller_id, data }
     }
 }
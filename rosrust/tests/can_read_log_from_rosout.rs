@@ -12,6 +12,7 @@ fn can_read_log_from_rosout() {
     let _roscore = util::run_roscore_for(util::TestVariant::CanReadLogFromRosout);
 
     rosrust::init("rosout_agg_listener");
+    rosrust::set_logger_level("ros", msg::rosgraph_msgs::Log::DEBUG);
 
     let (tx, rx) = unbounded();
 
@@ -0,0 +1,110 @@
+use rosrust::api::testing::TestKit;
+use rosrust::api::ClockServerOptions;
+use rosrust::error::ErrorKind;
+use rosrust::{Duration, Time};
+use std::time;
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+rosrust::rosmsg_include!(rosgraph_msgs / Clock);
+
+fn paused_at(seconds: u32) -> ClockServerOptions {
+    ClockServerOptions {
+        start: Time::from_seconds(seconds),
+        paused: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn steps_paused_time() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("simulator").unwrap();
+    let clock = kit.capture::<rosgraph_msgs::Clock>("/clock").unwrap();
+
+    let server = node.clock_server(paused_at(100)).unwrap();
+    assert!(server.is_paused());
+    assert_eq!(
+        Time::from_seconds(100),
+        clock.recv_timeout(TIMEOUT).unwrap().clock
+    );
+    // The node's own clock follows without waiting for the message
+    assert_eq!(Time::from_seconds(100), node.now());
+
+    server.step(Duration::from_seconds(2));
+    assert_eq!(Time::from_seconds(102), server.now());
+    assert_eq!(Time::from_seconds(102), node.now());
+    let deadline = time::Instant::now() + TIMEOUT;
+    while clock.recv_timeout(TIMEOUT).unwrap().clock != Time::from_seconds(102) {
+        assert!(time::Instant::now() < deadline, "Timed out waiting");
+    }
+
+    std::thread::sleep(NOTHING);
+    assert_eq!(Time::from_seconds(102), server.now());
+    assert_eq!(Time::from_seconds(102), node.now());
+
+    server.set_time(Time::from_seconds(5));
+    assert_eq!(Time::from_seconds(5), node.now());
+
+    server.step(Duration::from_seconds(-10));
+    assert_eq!(Time::from_seconds(0), server.now());
+    assert_eq!(Time::from_seconds(0), node.now());
+}
+
+#[test]
+fn runs_with_real_time_factor() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("simulator").unwrap();
+    let server = node.clock_server(paused_at(10)).unwrap();
+
+    server.set_real_time_factor(20.0).unwrap();
+    assert_eq!(20.0, server.real_time_factor());
+    server.resume();
+    std::thread::sleep(time::Duration::from_millis(100));
+    server.pause();
+    let paused = server.now();
+    assert!(paused >= Time::from_seconds(12), "{} is too early", paused);
+    assert!(paused < Time::from_seconds(30), "{} is too late", paused);
+
+    std::thread::sleep(NOTHING);
+    assert_eq!(paused, server.now());
+    let deadline = time::Instant::now() + TIMEOUT;
+    while node.now() != paused {
+        assert!(time::Instant::now() < deadline, "Timed out waiting");
+        std::thread::sleep(time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn rejects_bad_publish_rates() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("simulator").unwrap();
+    let options = ClockServerOptions {
+        publish_rate: 0.0,
+        ..Default::default()
+    };
+    let err = node.clock_server(options).err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::InvalidPublishRate(..)));
+}
+
+#[test]
+fn rejects_bad_real_time_factors() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("simulator").unwrap();
+    for &factor in &[-1.0, f64::NAN, f64::INFINITY] {
+        let options = ClockServerOptions {
+            real_time_factor: factor,
+            ..Default::default()
+        };
+        let err = node.clock_server(options).err().unwrap();
+        assert!(matches!(err.kind(), ErrorKind::InvalidRealTimeFactor(..)));
+    }
+
+    let server = node.clock_server(paused_at(10)).unwrap();
+    let err = server.set_real_time_factor(-2.0).err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::InvalidRealTimeFactor(..)));
+    server.set_real_time_factor(0.0).unwrap();
+    assert_eq!(0.0, server.real_time_factor());
+}
@@ -0,0 +1,63 @@
+use rosrust::api::testing::TestKit;
+use rosrust::api::LogFileOptions;
+use rosrust::Time;
+use std::{env, fs, process};
+
+mod msg {
+    rosrust::rosmsg_include!(rosgraph_msgs / Log);
+}
+use msg::rosgraph_msgs::Log;
+
+#[test]
+fn writes_structured_lines_to_log_file() {
+    let dir = env::temp_dir().join(format!("rosrust_log_file_{}", process::id()));
+    let path = dir.join("nested").join("node.log");
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    assert_eq!(None, node.log_file_path());
+
+    node.log_to_file(&path, LogFileOptions::default()).unwrap();
+    assert_eq!(Some(path.clone()), node.log_file_path());
+    kit.set_time(Time::from_nanos(3_250_000_000));
+    node.log(Log::INFO, "first\nsecond".into(), "src/main.rs", 7);
+    node.log_named("planner", Log::ERROR, "no path".into(), "src/plan.rs", 42);
+    node.stop_log_file();
+    node.log(Log::INFO, "not written".into(), "src/main.rs", 8);
+
+    let content = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        "[INFO] [3.250000000] [/node] [ros] [src/main.rs:7]: first\\nsecond\n\
+         [ERROR] [3.250000000] [/node] [ros.planner] [src/plan.rs:42]: no path\n",
+        content
+    );
+}
+
+#[test]
+fn rotates_log_file_by_size() {
+    let dir = env::temp_dir().join(format!("rosrust_log_rotation_{}", process::id()));
+    let path = dir.join("node.log");
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let options = LogFileOptions {
+        max_size: 100,
+        max_backups: 2,
+    };
+    node.log_to_file(&path, options).unwrap();
+
+    // Each line takes over half of the limit, so every file fits a single one
+    for index in 0..4 {
+        node.log(Log::INFO, format!("message {}", index), "src/main.rs", 1);
+    }
+
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+    let current = read("node.log");
+    let first_backup = read("node.log.1");
+    let second_backup = read("node.log.2");
+    let third_backup = read("node.log.3");
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(current.unwrap().ends_with("message 3\n"));
+    assert!(first_backup.unwrap().ends_with("message 2\n"));
+    assert!(second_backup.unwrap().ends_with("message 1\n"));
+    assert_eq!(None, third_backup);
+}
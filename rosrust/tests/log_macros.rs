@@ -0,0 +1,106 @@
+use rosrust::api::testing::TestKit;
+use rosrust::{FilterParams, LogCallSite, LogSite, Time};
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+mod msg {
+    rosrust::rosmsg_include!(rosgraph_msgs / Log);
+}
+use msg::rosgraph_msgs::Log;
+
+macro_rules! log_once_twice_on_one_line {
+    () => {
+        rosrust::ros_info_once!("first");
+        rosrust::ros_info_once!("second");
+    };
+}
+
+#[test]
+fn logs_through_rosconsole_style_macros() {
+    let kit = TestKit::new().unwrap();
+    rosrust::try_init_with_test_kit("node", &kit).unwrap();
+    let rosout = kit.capture::<Log>("/rosout").unwrap();
+    let next = || rosout.recv_timeout(TIMEOUT).unwrap();
+    kit.set_time(Time::from_nanos(10_000_000_000));
+
+    for _ in 0..3 {
+        log_once_twice_on_one_line!();
+    }
+    assert_eq!("first", next().msg);
+    assert_eq!("second", next().msg);
+
+    for value in 0..4 {
+        rosrust::ros_warn_cond!(value % 2 == 1, "odd {}", value);
+    }
+    assert_eq!("odd 1", next().msg);
+    assert_eq!("odd 3", next().msg);
+
+    rosrust::set_logger_level("ros.planner", Log::WARN);
+    rosrust::ros_info_named!("planner", "filtered");
+    rosrust::ros_err_named!("planner", "no path");
+    let record = next();
+    assert_eq!("no path", record.msg);
+    assert_eq!(Log::ERROR, record.level);
+
+    let delayed = |index| rosrust::ros_info_delayed_throttle!(1.0, "delayed {}", index);
+    for index in 0..5 {
+        delayed(index);
+        kit.advance(rosrust::Duration::from_nanos(500_000_000));
+    }
+    assert_eq!("delayed 2", next().msg);
+    assert_eq!("delayed 4", next().msg);
+
+    rosrust::set_logger_level("ros", Log::DEBUG);
+    let skipping = |index| rosrust::ros_debug_skip_first!(2, "skipping {}", index);
+    for index in 0..4 {
+        skipping(index);
+    }
+    assert_eq!("skipping 2", next().msg);
+    assert_eq!("skipping 3", next().msg);
+
+    let redact = |params: &mut FilterParams| {
+        params.message = params.message.replace("secret", "******");
+        params.logger = "ros.auth".into();
+        !params.message.contains("ignored")
+    };
+    rosrust::ros_info_filter!(redact, "ignored {}", "secret");
+    rosrust::ros_info_filter!(redact, "logging in with {}", "secret");
+    let record = next();
+    assert_eq!("logging in with ******", record.msg);
+    assert!(rosrust::logger_level("ros.auth") <= Log::INFO);
+
+    rosrust::ros_info_stream!("x = ", 1 + 2, ", ok: ", true);
+    let record = next();
+    assert_eq!("x = 3, ok: true", record.msg);
+    assert_eq!(
+        "log_macros::logs_through_rosconsole_style_macros",
+        record.function
+    );
+
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+}
+
+#[test]
+fn keeps_function_log_state_per_call_site_state() {
+    static FIRST: LogCallSite = LogCallSite::new();
+    static SECOND: LogCallSite = LogCallSite::new();
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let rosout = kit.capture::<Log>("/rosout").unwrap();
+
+    // Both calls report the same line, but keep their own state
+    let site = LogSite {
+        file: file!(),
+        line: line!(),
+        function: "",
+    };
+    for _ in 0..3 {
+        node.log_once_at(Log::INFO, "first".into(), &site, &FIRST);
+        node.log_once_at(Log::INFO, "second".into(), &site, &SECOND);
+    }
+    assert_eq!("first", rosout.recv_timeout(TIMEOUT).unwrap().msg);
+    assert_eq!("second", rosout.recv_timeout(TIMEOUT).unwrap().msg);
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+}
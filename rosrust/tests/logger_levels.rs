@@ -0,0 +1,120 @@
+use rosrust::api::testing::TestKit;
+use rosrust::{LogCallSite, LogSite};
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+mod msg {
+    rosrust::rosmsg_include!(
+        rosgraph_msgs / Log,
+        roscpp / GetLoggers,
+        roscpp / SetLoggerLevel
+    );
+}
+use msg::rosgraph_msgs::Log;
+
+#[test]
+fn filters_logs_by_logger_level() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let rosout = kit.capture::<Log>("/rosout").unwrap();
+
+    assert_eq!(Log::INFO, node.logger_level("ros"));
+    assert_eq!(Log::INFO, node.logger_level("ros.planner"));
+    node.set_logger_level("ros", Log::WARN);
+    assert_eq!(Log::WARN, node.logger_level("ros.planner"));
+    node.set_logger_level("ros.planner", Log::DEBUG);
+
+    node.log(Log::INFO, "filtered".into(), file!(), line!());
+    let site = LogSite {
+        file: file!(),
+        line: line!(),
+        function: "",
+    };
+    node.log_once_at(
+        Log::INFO,
+        "filtered once".into(),
+        &site,
+        &LogCallSite::new(),
+    );
+    node.log_named(
+        "mapper",
+        Log::INFO,
+        "filtered by parent".into(),
+        file!(),
+        line!(),
+    );
+    node.log_named("planner", Log::DEBUG, "planning".into(), file!(), line!());
+    node.log(Log::ERROR, "failed".into(), file!(), line!());
+
+    assert_eq!("planning", rosout.recv_timeout(TIMEOUT).unwrap().msg);
+    assert_eq!("failed", rosout.recv_timeout(TIMEOUT).unwrap().msg);
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+}
+
+#[test]
+fn reads_levels_from_private_parameter() {
+    let kit = TestKit::new().unwrap();
+    kit.param("/node/log_level")
+        .unwrap()
+        .set(&"error, ros.planner=INFO")
+        .unwrap();
+    let node = kit.node("node").unwrap();
+
+    assert_eq!(Log::ERROR, node.logger_level("ros"));
+    assert_eq!(Log::INFO, node.logger_level("ros.planner.global"));
+}
+
+#[test]
+fn rejects_invalid_level_parameter() {
+    let kit = TestKit::new().unwrap();
+    kit.param("/node/log_level")
+        .unwrap()
+        .set(&"ros.planner=loud")
+        .unwrap();
+
+    let error = kit.node("node").err().expect("Invalid level must fail");
+    assert!(error.to_string().contains("ros.planner=loud"), "{}", error);
+}
+
+#[test]
+fn serves_logger_level_services() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let caller = kit.node("rqt_logger_level").unwrap();
+    node.log_named("planner", Log::DEBUG, "registers".into(), file!(), line!());
+
+    let set_level = caller
+        .client::<msg::roscpp::SetLoggerLevel>("/node/set_logger_level")
+        .unwrap();
+    set_level
+        .req(&msg::roscpp::SetLoggerLevelReq {
+            logger: "ros.planner".into(),
+            level: "WARN".into(),
+        })
+        .unwrap()
+        .unwrap();
+    let error = set_level
+        .req(&msg::roscpp::SetLoggerLevelReq {
+            logger: "ros".into(),
+            level: "loud".into(),
+        })
+        .unwrap()
+        .unwrap_err();
+    assert!(error.contains("loud"), "{}", error);
+    assert_eq!(Log::WARN, node.logger_level("ros.planner"));
+
+    let loggers = caller
+        .client::<msg::roscpp::GetLoggers>("/node/get_loggers")
+        .unwrap()
+        .req(&msg::roscpp::GetLoggersReq {})
+        .unwrap()
+        .unwrap()
+        .loggers
+        .into_iter()
+        .map(|logger| (logger.name, logger.level))
+        .collect::<Vec<_>>();
+    assert!(loggers.contains(&("ros".into(), "info".into())));
+    assert!(loggers.contains(&("ros.planner".into(), "warn".into())));
+}
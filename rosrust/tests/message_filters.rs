@@ -0,0 +1,271 @@
+use crossbeam::channel::{unbounded, Receiver};
+use rosrust::api::testing::TestKit;
+use rosrust::message_filters::{
+    ApproximateTime, Cache, ExactTime, Sink, Source, Subscriber, Synchronizer, TimeSequencer,
+};
+use rosrust::{Duration, Stamped, Time};
+use std::sync::Arc;
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+mod msg {
+    rosrust::rosmsg_include!(
+        std_msgs / Header,
+        geometry_msgs / PointStamped,
+        rospy_tutorials / HeaderString
+    );
+}
+use msg::geometry_msgs::PointStamped;
+use msg::rospy_tutorials::HeaderString;
+
+fn millis(millis: i64) -> Duration {
+    Duration::from_nanos(millis * 1_000_000)
+}
+
+fn stamp(millis: i64) -> Time {
+    Time::from_nanos(millis * 1_000_000)
+}
+
+fn point(millis: i64) -> Arc<PointStamped> {
+    let mut message = PointStamped::default();
+    message.header.stamp = stamp(millis);
+    Arc::new(message)
+}
+
+fn string(millis: i64) -> Arc<HeaderString> {
+    let mut message = HeaderString::default();
+    message.header.stamp = stamp(millis);
+    message.data = millis.to_string();
+    Arc::new(message)
+}
+
+fn collect_pairs(
+    sync: &Synchronizer<(Arc<PointStamped>, Arc<HeaderString>)>,
+) -> Receiver<(Time, Time)> {
+    let (tx, rx) = unbounded();
+    sync.register_callback(move |(point, string)| {
+        tx.send((point.stamp(), string.stamp())).unwrap();
+    });
+    rx
+}
+
+fn received<T>(rx: &Receiver<T>) -> Vec<T> {
+    rx.try_iter().collect()
+}
+
+#[test]
+fn generated_messages_with_headers_are_stamped() {
+    assert_eq!(stamp(1500), point(1500).stamp());
+    assert_eq!(stamp(2500), string(2500).stamp());
+}
+
+#[test]
+fn exact_time_matches_identical_stamps() {
+    let sync = Synchronizer::new(ExactTime::new(10));
+    let pairs = collect_pairs(&sync);
+
+    sync.input0().add(point(100));
+    sync.input1().add(string(200));
+    sync.input0().add(point(200));
+    sync.input1().add(string(300));
+    sync.input1().add(string(100));
+
+    // The set at 100 is older than the matched set at 200, so it got dropped
+    assert_eq!(vec![(stamp(200), stamp(200))], received(&pairs));
+
+    sync.input0().add(point(300));
+    assert_eq!(vec![(stamp(300), stamp(300))], received(&pairs));
+}
+
+#[test]
+fn exact_time_drops_oldest_incomplete_sets() {
+    let sync = Synchronizer::new(ExactTime::new(2));
+    let pairs = collect_pairs(&sync);
+
+    sync.input0().add(point(100));
+    sync.input0().add(point(200));
+    sync.input0().add(point(300));
+    sync.input1().add(string(100));
+    sync.input1().add(string(200));
+
+    assert_eq!(vec![(stamp(200), stamp(200))], received(&pairs));
+}
+
+#[test]
+fn approximate_time_matches_closest_stamps() {
+    let sync = Synchronizer::new(ApproximateTime::new(10));
+    let pairs = collect_pairs(&sync);
+
+    for millis in &[1000, 2000, 3000, 4000] {
+        sync.input0().add(point(*millis));
+        sync.input1().add(string(*millis + 30));
+    }
+
+    assert_eq!(
+        vec![
+            (stamp(1000), stamp(1030)),
+            (stamp(2000), stamp(2030)),
+            (stamp(3000), stamp(3030)),
+        ],
+        received(&pairs)
+    );
+}
+
+#[test]
+fn approximate_time_skips_messages_without_close_match() {
+    let sync = Synchronizer::new(ApproximateTime::new(10));
+    let pairs = collect_pairs(&sync);
+
+    for millis in &[1000, 1100, 1200, 1300, 1400] {
+        sync.input0().add(point(*millis));
+    }
+    sync.input1().add(string(1210));
+    sync.input1().add(string(2000));
+    sync.input0().add(point(2500));
+
+    // Each message is part of one set at most, so 2000 gets matched with the later 2500
+    assert_eq!(
+        vec![(stamp(1200), stamp(1210)), (stamp(2500), stamp(2000))],
+        received(&pairs)
+    );
+}
+
+#[test]
+fn approximate_time_respects_max_interval_duration() {
+    let sync = Synchronizer::new(ApproximateTime::new(10).with_max_interval_duration(millis(100)));
+    let pairs = collect_pairs(&sync);
+
+    sync.input0().add(point(1000));
+    sync.input1().add(string(1500));
+    sync.input0().add(point(2000));
+    sync.input1().add(string(2050));
+    sync.input0().add(point(3000));
+    sync.input1().add(string(3050));
+
+    assert_eq!(vec![(stamp(2000), stamp(2050))], received(&pairs));
+}
+
+#[test]
+fn approximate_time_uses_inter_message_lower_bounds() {
+    let sync = Synchronizer::new(
+        ApproximateTime::new(10)
+            .with_inter_message_lower_bound(0, millis(1000))
+            .with_inter_message_lower_bound(1, millis(1000)),
+    );
+    let pairs = collect_pairs(&sync);
+
+    sync.input0().add(point(1000));
+    sync.input1().add(string(1030));
+
+    // No later message can make a better match, so there's no need to wait for one
+    assert_eq!(vec![(stamp(1000), stamp(1030))], received(&pairs));
+}
+
+#[test]
+fn synchronizes_three_inputs() {
+    let sync = Synchronizer::<(Arc<PointStamped>, Arc<HeaderString>, Arc<PointStamped>)>::new(
+        ExactTime::new(10),
+    );
+    let (tx, rx) = unbounded();
+    sync.register_callback(move |(first, second, third)| {
+        tx.send((first.stamp(), second.data.clone(), third.stamp()))
+            .unwrap();
+    });
+
+    sync.input0().add(point(100));
+    sync.input2().add(point(100));
+    assert!(received(&rx).is_empty());
+    sync.input1().add(string(100));
+
+    assert_eq!(vec![(stamp(100), "100".into(), stamp(100))], received(&rx));
+}
+
+#[test]
+fn cache_answers_time_range_queries() {
+    let cache = Cache::new(4);
+    let (tx, rx) = unbounded();
+    cache.register_callback(move |message: Arc<PointStamped>| tx.send(message.stamp()).unwrap());
+
+    for millis in &[100, 300, 200, 500, 400] {
+        cache.add(point(*millis));
+    }
+
+    let stamps =
+        |messages: Vec<Arc<PointStamped>>| messages.iter().map(|m| m.stamp()).collect::<Vec<_>>();
+    assert_eq!(5, received(&rx).len());
+    assert_eq!(4, cache.len());
+    assert_eq!(Some(stamp(200)), cache.oldest_time());
+    assert_eq!(Some(stamp(500)), cache.latest_time());
+    assert_eq!(
+        vec![stamp(300), stamp(400)],
+        stamps(cache.interval(stamp(250), stamp(450)))
+    );
+    assert_eq!(
+        vec![stamp(200), stamp(300), stamp(400), stamp(500)],
+        stamps(cache.surroundings(stamp(250), stamp(450)))
+    );
+    assert_eq!(
+        Some(stamp(300)),
+        cache.elem_before_time(stamp(350)).map(|m| m.stamp())
+    );
+    assert_eq!(
+        Some(stamp(400)),
+        cache.elem_after_time(stamp(350)).map(|m| m.stamp())
+    );
+    assert!(cache.elem_before_time(stamp(100)).is_none());
+}
+
+#[test]
+fn time_sequencer_releases_messages_in_order_after_delay() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(stamp(1000));
+
+    let sequencer = TimeSequencer::with_node(&node, millis(500), millis(100), 10);
+    let (tx, rx) = unbounded();
+    sequencer
+        .register_callback(move |message: Arc<PointStamped>| tx.send(message.stamp()).unwrap());
+
+    sequencer.add(point(900));
+    sequencer.add(point(700));
+    sequencer.add(point(1200));
+    kit.advance(millis(300));
+    assert_eq!(stamp(700), rx.recv_timeout(TIMEOUT).unwrap());
+    assert!(rx.recv_timeout(NOTHING).is_err());
+
+    // Older than what was already passed on
+    sequencer.add(point(600));
+    kit.advance(millis(300));
+    assert_eq!(stamp(900), rx.recv_timeout(TIMEOUT).unwrap());
+    assert!(rx.recv_timeout(NOTHING).is_err());
+
+    kit.advance(millis(200));
+    assert_eq!(stamp(1200), rx.recv_timeout(TIMEOUT).unwrap());
+    assert!(rx.recv_timeout(NOTHING).is_err());
+}
+
+#[test]
+fn chains_subscribers_into_synchronizer() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("listener").unwrap();
+
+    let points = Subscriber::<PointStamped>::with_node(&node, "points", 10).unwrap();
+    let strings = Subscriber::<HeaderString>::with_node(&node, "strings", 10).unwrap();
+    let sync = Synchronizer::<(Arc<PointStamped>, Arc<HeaderString>)>::new(ExactTime::new(10));
+    sync.input0().connect_input(&points);
+    sync.input1().connect_input(&strings);
+    let (tx, rx) = unbounded();
+    sync.register_callback(move |(point, string)| {
+        tx.send((point.stamp(), string.data.clone())).unwrap();
+    });
+
+    kit.inject("/points", (*point(100)).clone()).unwrap();
+    kit.inject("/strings", (*string(100)).clone()).unwrap();
+
+    assert_eq!(
+        (stamp(100), "100".to_owned()),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+}
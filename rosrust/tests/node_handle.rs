@@ -0,0 +1,125 @@
+use crossbeam::channel::unbounded;
+use rosrust::api::testing::TestKit;
+
+mod util;
+
+use util::TIMEOUT;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, roscpp_tutorials / TwoInts);
+}
+use msg::roscpp_tutorials::{TwoInts, TwoIntsReq, TwoIntsRes};
+
+fn string(data: &str) -> msg::std_msgs::String {
+    msg::std_msgs::String { data: data.into() }
+}
+
+#[test]
+fn resolves_names_within_handle_namespaces() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+
+    let arm = node.node_handle("arm").unwrap();
+    assert_eq!("/arm", arm.namespace());
+    assert_eq!("/arm/state", arm.resolve_name("state").unwrap());
+    assert_eq!("/state", arm.resolve_name("/state").unwrap());
+    assert_eq!("/node/state", arm.resolve_name("~state").unwrap());
+
+    let gripper = arm.child("gripper").unwrap();
+    assert_eq!("/arm/gripper", gripper.namespace());
+    assert_eq!(
+        "/node/planner",
+        node.node_handle("~planner").unwrap().namespace()
+    );
+    assert_eq!("/", node.node_handle("/").unwrap().namespace());
+    assert!(node.node_handle("bad$name").is_err());
+}
+
+#[test]
+fn publishes_subscribes_and_reads_params_within_namespace() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let arm = node.node_handle("arm").unwrap();
+
+    let state = kit.capture::<msg::std_msgs::String>("/arm/state").unwrap();
+    let publisher = arm.publish::<msg::std_msgs::String>("state", 10).unwrap();
+    publisher.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    publisher.send(string("moving")).unwrap();
+    assert_eq!("moving", state.recv_timeout(TIMEOUT).unwrap().data);
+
+    let (tx, rx) = unbounded();
+    let _subscriber = arm
+        .subscribe::<msg::std_msgs::String, _>("goal", 10, move |data| {
+            tx.send(data.data).unwrap();
+        })
+        .unwrap();
+    kit.inject("/arm/goal", string("home")).unwrap();
+    assert_eq!("home", rx.recv_timeout(TIMEOUT).unwrap());
+
+    kit.param("/arm/speed").unwrap().set(&2.5).unwrap();
+    assert_eq!(2.5, arm.param("speed").unwrap().get::<f64>().unwrap());
+    let private = node.node_handle("~").unwrap();
+    private.param("gain").unwrap().set(&3).unwrap();
+    assert_eq!(3, kit.param("/node/gain").unwrap().get::<i32>().unwrap());
+}
+
+#[test]
+fn serves_and_calls_services_within_namespace() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let arm = node.node_handle("arm").unwrap();
+
+    let _service = arm
+        .service::<TwoInts, _>("add", |req| Ok(TwoIntsRes { sum: req.a + req.b }))
+        .unwrap();
+    arm.wait_for_service("add", Some(TIMEOUT)).unwrap();
+    let client = node.client::<TwoInts>("/arm/add").unwrap();
+    let response = client.req(&TwoIntsReq { a: 2, b: 3 }).unwrap().unwrap();
+    assert_eq!(5, response.sum);
+
+    let response = arm
+        .client::<TwoInts>("add")
+        .unwrap()
+        .req(&TwoIntsReq { a: 4, b: 5 })
+        .unwrap()
+        .unwrap();
+    assert_eq!(9, response.sum);
+}
+
+#[test]
+fn remaps_names_of_handle_before_node_remappings() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let arm = node
+        .node_handle("arm")
+        .unwrap()
+        .with_remap("command", "/safety/command")
+        .unwrap()
+        .with_remap("~log", "debug_log")
+        .unwrap();
+
+    assert_eq!("/safety/command", arm.resolve_name("command").unwrap());
+    assert_eq!("/arm/debug_log", arm.resolve_name("~log").unwrap());
+    assert_eq!("/arm/state", arm.resolve_name("state").unwrap());
+
+    let gripper = arm.child("gripper").unwrap();
+    assert_eq!(
+        "/arm/gripper/command",
+        gripper.resolve_name("command").unwrap()
+    );
+    assert_eq!(
+        "/arm/command",
+        node.node_handle("arm")
+            .unwrap()
+            .resolve_name("command")
+            .unwrap()
+    );
+
+    let commands = kit
+        .capture::<msg::std_msgs::String>("/safety/command")
+        .unwrap();
+    let publisher = arm.publish::<msg::std_msgs::String>("command", 10).unwrap();
+    publisher.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    publisher.send(string("stop")).unwrap();
+    assert_eq!("stop", commands.recv_timeout(TIMEOUT).unwrap().data);
+}
@@ -0,0 +1,140 @@
+use crossbeam::channel::unbounded;
+use rosrust::api::testing::TestKit;
+use rosrust::ParamValue;
+use xml_rpc::Value;
+
+mod util;
+
+use util::value_tree;
+
+#[test]
+fn subscribes_to_cached_keys_only() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.param("/gain").unwrap().set(&1).unwrap();
+    kit.param("/other").unwrap().set(&1).unwrap();
+
+    let gain = node.param("gain").unwrap();
+    assert_eq!(1, gain.get::<i32>().unwrap());
+    assert_eq!(vec!["/node"], kit.param_subscribers("/gain"));
+    assert!(kit.param_subscribers("/").is_empty());
+    assert!(kit.param_subscribers("/other").is_empty());
+
+    // Updates from other nodes refresh the cached value in place
+    kit.param("/gain").unwrap().set(&2).unwrap();
+    assert_eq!(2, gain.get::<i32>().unwrap());
+    kit.param("/")
+        .unwrap()
+        .set_raw(value_tree(&[("gain", Value::Int(3))]))
+        .unwrap();
+    assert_eq!(3, gain.get::<i32>().unwrap());
+    kit.param("/gain").unwrap().delete().unwrap();
+    assert!(gain.get::<i32>().is_err());
+    assert_eq!(vec!["/node"], kit.param_subscribers("/gain"));
+}
+
+#[test]
+fn unsubscribes_with_the_last_handle() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.param("/gain").unwrap().set(&1).unwrap();
+
+    let gain = node.param("gain").unwrap();
+    assert_eq!(1, node.param("gain").unwrap().get::<i32>().unwrap());
+    assert_eq!(vec!["/node"], kit.param_subscribers("/gain"));
+    assert_eq!(1, gain.get::<i32>().unwrap());
+
+    drop(gain);
+    assert!(kit.param_subscribers("/gain").is_empty());
+
+    // Handles read once and dropped right away don't keep the subscription either
+    assert_eq!(1, node.param("gain").unwrap().get::<i32>().unwrap());
+    assert!(kit.param_subscribers("/gain").is_empty());
+    let gain = node.param("gain").unwrap().uncached();
+    assert_eq!(1, gain.get::<i32>().unwrap());
+    assert!(kit.param_subscribers("/gain").is_empty());
+}
+
+#[test]
+fn caches_namespaces_with_child_updates() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let controller = node.param("/controller").unwrap();
+    controller
+        .set_raw(value_tree(&[("p", Value::Int(1)), ("i", Value::Int(2))]))
+        .unwrap();
+    assert_eq!(
+        value_tree(&[("p", Value::Int(1)), ("i", Value::Int(2))]),
+        controller.get_raw().unwrap()
+    );
+
+    kit.param("/controller/p").unwrap().set(&5).unwrap();
+    assert_eq!(
+        value_tree(&[("p", Value::Int(5)), ("i", Value::Int(2))]),
+        controller.get_raw().unwrap()
+    );
+}
+
+#[test]
+fn unsubscribes_after_local_changes() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let param = node.param("/rate").unwrap();
+    param.set(&10).unwrap();
+    assert_eq!(10, param.get::<i32>().unwrap());
+    assert_eq!(vec!["/node"], kit.param_subscribers("/rate"));
+
+    param.set(&20).unwrap();
+    assert!(kit.param_subscribers("/rate").is_empty());
+    assert_eq!(20, param.get::<i32>().unwrap());
+
+    // Changes of a namespace drop the cached parameters within it
+    node.param("/")
+        .unwrap()
+        .set_raw(value_tree(&[("rate", Value::Int(30))]))
+        .unwrap();
+    assert!(kit.param_subscribers("/rate").is_empty());
+    assert_eq!(30, param.get::<i32>().unwrap());
+}
+
+#[test]
+fn keeps_subscriptions_shared_with_watches() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let param = node.param("/mode").unwrap();
+    param.set(&1).unwrap();
+
+    let (tx, rx) = unbounded();
+    let watch = node
+        .watch_param("/mode", move |value| tx.send(value).unwrap())
+        .unwrap();
+    assert_eq!(1, param.get::<i32>().unwrap());
+
+    // Dropping the cached value keeps the watch's subscription
+    param.set(&2).unwrap();
+    assert_eq!(Some(ParamValue::Int(2)), rx.try_recv().unwrap());
+    assert_eq!(vec!["/node"], kit.param_subscribers("/mode"));
+
+    // Dropping the watch keeps the cache's subscription
+    assert_eq!(2, param.get::<i32>().unwrap());
+    drop(watch);
+    assert_eq!(vec!["/node"], kit.param_subscribers("/mode"));
+    kit.param("/mode").unwrap().set(&3).unwrap();
+    assert_eq!(3, param.get::<i32>().unwrap());
+}
+
+#[test]
+fn uncached_parameters_skip_subscriptions() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.param("/gain").unwrap().set(&1).unwrap();
+
+    let param = node.param("gain").unwrap().uncached();
+    assert_eq!(1, param.get::<i32>().unwrap());
+    assert!(kit.param_subscribers("/gain").is_empty());
+    kit.param("/gain").unwrap().set(&2).unwrap();
+    assert_eq!(2, param.get::<i32>().unwrap());
+    assert!(param.get_raw().is_ok());
+    assert!(node.param("missing").unwrap().uncached().get_raw().is_err());
+    assert!(kit.param_subscribers("/missing").is_empty());
+}
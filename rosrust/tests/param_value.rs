@@ -0,0 +1,144 @@
+use rosrust::api::testing::TestKit;
+use rosrust::error::ErrorKind;
+use rosrust::ParamValue;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use yaml_rust::YamlLoader;
+
+mod util;
+
+use util::param_tree;
+
+fn sample() -> ParamValue {
+    param_tree(&[
+        ("flag", true.into()),
+        ("count", 3.into()),
+        ("gain", 0.25.into()),
+        ("name", "robot".into()),
+        ("blob", ParamValue::Binary(vec![0, 1, 254])),
+        ("stamp", ParamValue::DateTime("20230401T12:30:00".into())),
+        (
+            "list",
+            vec![ParamValue::from(1), "two".into(), param_tree(&[])].into(),
+        ),
+    ])
+}
+
+#[test]
+fn converts_to_and_from_xmlrpc() {
+    let value = sample();
+    let xmlrpc = xml_rpc::Value::from(value.clone());
+    match &xmlrpc {
+        xml_rpc::Value::Struct(fields) => {
+            assert_eq!(Some(&xml_rpc::Value::Int(3)), fields.get("count"));
+            assert_eq!(
+                Some(&xml_rpc::Value::Base64(vec![0, 1, 254])),
+                fields.get("blob")
+            );
+            assert_eq!(
+                Some(&xml_rpc::Value::DateTime("20230401T12:30:00".into())),
+                fields.get("stamp")
+            );
+        }
+        other => panic!("Expected struct, got {:?}", other),
+    }
+    assert_eq!(value, ParamValue::from(xmlrpc));
+}
+
+#[test]
+fn stores_large_integers_without_truncation() {
+    assert_eq!(
+        xml_rpc::Value::Int(i32::MIN),
+        ParamValue::Int(i32::MIN.into()).into()
+    );
+    assert_eq!(
+        xml_rpc::Value::Double(-(1i64 << 40) as f64),
+        ParamValue::Int(-(1 << 40)).into()
+    );
+    assert_eq!(
+        xml_rpc::Value::String(i64::MIN.to_string()),
+        ParamValue::Int(i64::MIN).into()
+    );
+    assert_eq!(
+        xml_rpc::Value::String("9007199254740993".into()),
+        ParamValue::Int((1 << 53) + 1).into()
+    );
+}
+
+#[test]
+fn converts_yaml() {
+    let value = sample();
+    assert_eq!(value, ParamValue::from_yaml(&value.to_yaml()).unwrap());
+
+    let yaml = YamlLoader::load_from_str("a: 6000000000\nb: [1.5, x]\n3: true\n")
+        .unwrap()
+        .remove(0);
+    assert_eq!(
+        param_tree(&[
+            ("a", ParamValue::Int(6_000_000_000)),
+            ("b", vec![ParamValue::from(1.5), "x".into()].into()),
+            ("3", true.into()),
+        ]),
+        ParamValue::try_from(yaml).unwrap()
+    );
+
+    let null = YamlLoader::load_from_str("a: ~\n").unwrap().remove(0);
+    let err = ParamValue::try_from(null).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::UnsupportedParamValue(..)));
+    assert!(err.to_string().contains("null values"));
+
+    let err = ParamValue::from_yaml("[1, ~]").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::UnsupportedParamValue(..)));
+}
+
+#[test]
+fn converts_with_serde() {
+    let json = serde_json::to_value(param_tree(&[
+        ("count", ParamValue::Int(1 << 40)),
+        ("items", vec![ParamValue::from(true), 1.5.into()].into()),
+    ]))
+    .unwrap();
+    assert_eq!(
+        serde_json::json!({"count": 1u64 << 40, "items": [true, 1.5]}),
+        json
+    );
+
+    let value: ParamValue =
+        serde_json::from_str(r#"{"name": "robot", "ids": [1, -2], "nested": {"x": 0.5}}"#).unwrap();
+    assert_eq!(
+        param_tree(&[
+            ("name", "robot".into()),
+            ("ids", vec![ParamValue::from(1), (-2).into()].into()),
+            ("nested", param_tree(&[("x", 0.5.into())])),
+        ]),
+        value
+    );
+
+    let err = serde_json::from_str::<ParamValue>("[null]").unwrap_err();
+    assert!(err.to_string().contains("null values"));
+    let err = serde_json::from_str::<ParamValue>("18446744073709551615").unwrap_err();
+    assert!(err.to_string().contains("too large"));
+}
+
+#[test]
+fn gets_and_sets_parameters() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/robot").unwrap();
+    param.set_value(sample()).unwrap();
+    assert_eq!(sample(), param.get_value().unwrap());
+
+    let count = kit.param("/robot/count").unwrap();
+    count.set_value(ParamValue::Int(1 << 40)).unwrap();
+    assert_eq!(
+        ParamValue::Double((1i64 << 40) as f64),
+        count.get_value().unwrap()
+    );
+    assert_eq!(
+        xml_rpc::Value::Array(vec![
+            xml_rpc::Value::Int(1),
+            xml_rpc::Value::String("two".into()),
+            xml_rpc::Value::Struct(HashMap::new()),
+        ]),
+        kit.param("/robot/list").unwrap().get_raw().unwrap()
+    );
+}
@@ -0,0 +1,253 @@
+use rosrust::api::testing::TestKit;
+use std::f64::consts::PI;
+use xml_rpc::Value;
+
+mod util;
+
+use util::value_tree;
+
+fn assert_close(expected: f64, actual: f64) {
+    assert!(
+        (expected - actual).abs() < 1e-9,
+        "{} != {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn loads_into_namespace() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let param = node.param("~").unwrap();
+    param
+        .load_yaml_str(
+            "gain: 2\n\
+             name: robot\n\
+             quoted: \"1.5\"\n\
+             limits:\n  min: -1.5\n  max: .inf\n\
+             list: [1, two, true]\n\
+             /global: yes\n",
+        )
+        .unwrap();
+
+    assert_eq!(2, kit.param("/node/gain").unwrap().get::<i32>().unwrap());
+    assert_eq!(
+        "robot",
+        kit.param("/node/name").unwrap().get::<String>().unwrap()
+    );
+    assert_eq!(
+        "1.5",
+        kit.param("/node/quoted").unwrap().get::<String>().unwrap()
+    );
+    assert_eq!(
+        value_tree(&[
+            ("min", Value::Double(-1.5)),
+            ("max", Value::Double(f64::INFINITY)),
+        ]),
+        kit.param("/node/limits").unwrap().get_raw().unwrap()
+    );
+    assert_eq!(
+        Value::Array(vec![
+            Value::Int(1),
+            Value::String("two".into()),
+            Value::Bool(true),
+        ]),
+        kit.param("/node/list").unwrap().get_raw().unwrap()
+    );
+    assert!(kit.param("/global").unwrap().get::<bool>().unwrap());
+}
+
+#[test]
+fn merges_mappings_and_deletes_nulls() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/robot").unwrap();
+    param
+        .set_raw(value_tree(&[
+            ("keep", Value::Int(1)),
+            ("change", Value::Int(2)),
+            ("remove", Value::Int(3)),
+        ]))
+        .unwrap();
+
+    param
+        .load_yaml_str("change: 5\nremove: ~\nmissing: null\nadded:\n  nested: x\n")
+        .unwrap();
+
+    assert_eq!(
+        value_tree(&[
+            ("keep", Value::Int(1)),
+            ("change", Value::Int(5)),
+            (
+                "added",
+                value_tree(&[("nested", Value::String("x".into()))])
+            ),
+        ]),
+        param.get_raw().unwrap()
+    );
+    assert!(!kit.param("/robot/remove").unwrap().exists().unwrap());
+}
+
+#[test]
+fn loads_multiple_documents_in_order() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/").unwrap();
+    param.load_yaml_str("a: 1\nb: 1\n---\nb: 2\n").unwrap();
+    assert_eq!(1, kit.param("/a").unwrap().get::<i32>().unwrap());
+    assert_eq!(2, kit.param("/b").unwrap().get::<i32>().unwrap());
+}
+
+#[test]
+fn converts_angles() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/angles").unwrap();
+    param
+        .load_yaml_str(
+            "tagged_degrees: !degrees 180\n\
+             tagged_radians: !radians pi/2\n\
+             expression: !degrees -(45 + 45) * 2\n\
+             deg_call: deg(90)\n\
+             rad_call: rad(2*pi)\n",
+        )
+        .unwrap();
+    let angle = |name: &str| {
+        kit.param(&format!("/angles/{}", name))
+            .unwrap()
+            .get::<f64>()
+            .unwrap()
+    };
+    assert_close(PI, angle("tagged_degrees"));
+    assert_close(PI / 2.0, angle("tagged_radians"));
+    assert_close(-PI, angle("expression"));
+    assert_close(PI / 2.0, angle("deg_call"));
+    assert_close(2.0 * PI, angle("rad_call"));
+
+    assert!(param.load_yaml_str("bad: !degrees 90 + x\n").is_err());
+    assert!(param.load_yaml_str("bad: !radians (pi\n").is_err());
+}
+
+#[test]
+fn keeps_large_integers() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/big").unwrap();
+    param
+        .load_yaml_str("exact: 5000000000\nhuge: 9007199254740993\n")
+        .unwrap();
+    assert_eq!(
+        Value::Double(5_000_000_000.0),
+        kit.param("/big/exact").unwrap().get_raw().unwrap()
+    );
+    assert_eq!(
+        Value::String("9007199254740993".into()),
+        kit.param("/big/huge").unwrap().get_raw().unwrap()
+    );
+}
+
+#[test]
+fn loads_binary_data() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/blob").unwrap();
+    param
+        .load_yaml_str("data: !!binary |\n  AAEC\n  /w==\n")
+        .unwrap();
+    assert_eq!(
+        Value::Base64(vec![0, 1, 2, 255]),
+        kit.param("/blob/data").unwrap().get_raw().unwrap()
+    );
+}
+
+#[test]
+fn rejects_bad_documents_without_changes() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/bad").unwrap();
+    assert!(param.load_yaml_str("a: 1\nb: [1, ~]\n").is_err());
+    assert!(param.load_yaml_str("a: 1\n? [1, 2]\n: 3\n").is_err());
+    assert!(param.load_yaml_str("a: 1\nb: !custom 3\n").is_err());
+    assert!(param.load_yaml_str("a: [1\n").is_err());
+    assert!(!kit.param("/bad/a").unwrap().exists().unwrap());
+}
+
+#[test]
+fn loads_files() {
+    let kit = TestKit::new().unwrap();
+    let path = std::env::temp_dir().join(format!("rosrust_params_{}.yaml", std::process::id()));
+    std::fs::write(&path, "rate: 10.0\n").unwrap();
+    let result = kit.param("/camera").unwrap().load_yaml_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+    assert_close(
+        10.0,
+        kit.param("/camera/rate").unwrap().get::<f64>().unwrap(),
+    );
+    assert!(kit
+        .param("/camera")
+        .unwrap()
+        .load_yaml_file("/nonexistent/params.yaml")
+        .is_err());
+}
+
+#[test]
+fn dumps_subtrees() {
+    let kit = TestKit::new().unwrap();
+    let param = kit.param("/robot").unwrap();
+    param
+        .set_raw(value_tree(&[
+            ("name", Value::String("r2".into())),
+            ("version", Value::String("1.0".into())),
+            ("answer", Value::String("yes".into())),
+            ("gain", Value::Double(0.5)),
+            ("count", Value::Int(3)),
+            ("enabled", Value::Bool(true)),
+            ("blob", Value::Base64(vec![0, 1, 2])),
+            ("empty", value_tree(&[])),
+            (
+                "joints",
+                Value::Array(vec![
+                    value_tree(&[("name", Value::String("a b".into())), ("id", Value::Int(1))]),
+                    Value::Array(vec![Value::Int(1), Value::Int(2)]),
+                ]),
+            ),
+            (
+                "limits",
+                value_tree(&[("max", Value::Double(f64::INFINITY))]),
+            ),
+        ]))
+        .unwrap();
+
+    let dump = param.dump_yaml().unwrap();
+    assert_eq!(
+        "answer: \"yes\"\n\
+         blob: !!binary AAEC\n\
+         count: 3\n\
+         empty: {}\n\
+         enabled: true\n\
+         gain: 0.5\n\
+         joints:\n\
+         - id: 1\n  \
+           name: a b\n\
+         - - 1\n  \
+           - 2\n\
+         limits:\n  \
+           max: .inf\n\
+         name: r2\n\
+         version: \"1.0\"\n",
+        dump
+    );
+    assert_eq!(
+        "3\n",
+        kit.param("/robot/count").unwrap().dump_yaml().unwrap()
+    );
+
+    // Dumped documents load back into the same values, except for empty namespaces,
+    // which set nothing when loaded
+    let original = match param.get_raw().unwrap() {
+        Value::Struct(mut fields) => {
+            fields.remove("empty");
+            Value::Struct(fields)
+        }
+        value => panic!("Expected a namespace, got {:?}", value),
+    };
+    param.delete().unwrap();
+    param.load_yaml_str(&dump).unwrap();
+    assert_eq!(original, param.get_raw().unwrap());
+}
@@ -0,0 +1,120 @@
+use crossbeam::channel::unbounded;
+use rosrust::api::testing::TestKit;
+use rosrust::api::OverrunPolicy;
+use rosrust::{Duration, Time};
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+rosrust::rosmsg_include!(rosgraph_msgs / Log);
+
+fn millis(millis: i64) -> Duration {
+    Duration::from_nanos(millis * 1_000_000)
+}
+
+#[test]
+fn catches_up_after_overruns() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let rate = node.rate(1.0);
+    assert_eq!(Duration::from_seconds(1), rate.expected_cycle_time());
+    kit.advance(millis(3500));
+
+    // The cycles ending at 11, 12 and 13 were missed, so they run right away
+    for (overruns, cycle_time) in &[(1, 3500), (2, 2500), (3, 1500)] {
+        rate.sleep();
+        assert_eq!(*overruns, rate.overruns());
+        assert_eq!(millis(*cycle_time), rate.cycle_time());
+    }
+
+    let (tx, rx) = unbounded();
+    let clock = kit.clone();
+    std::thread::spawn(move || {
+        rate.sleep();
+        tx.send((clock.now(), rate.overruns())).unwrap();
+    });
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(millis(500));
+    assert_eq!(
+        (Time::from_seconds(14), 3),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+}
+
+#[test]
+fn skips_missed_cycles() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let rate = node
+        .rate(1.0)
+        .with_overrun_policy(OverrunPolicy::SkipMissed);
+    kit.advance(millis(3500));
+
+    let (tx, rx) = unbounded();
+    let clock = kit.clone();
+    std::thread::spawn(move || {
+        rate.sleep();
+        tx.send((clock.now(), rate.overruns(), rate.cycle_time()))
+            .unwrap();
+    });
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(millis(500));
+    assert_eq!(
+        (Time::from_seconds(14), 1, millis(3500)),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+}
+
+#[test]
+fn resets_schedule() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let rate = node.rate(2.0);
+    kit.advance(Duration::from_seconds(3));
+    rate.reset();
+
+    let (tx, rx) = unbounded();
+    let clock = kit.clone();
+    std::thread::spawn(move || {
+        rate.sleep();
+        tx.send((clock.now(), rate.overruns())).unwrap();
+    });
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(millis(500));
+    assert_eq!(
+        (Time::from_nanos(13_500_000_000), 0),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+}
+
+#[test]
+fn warns_about_overruns_on_rosout() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let rosout = kit.capture::<rosgraph_msgs::Log>("/rosout").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let rate = node.rate(1.0).warn_on_overrun(Duration::from_seconds(10));
+    kit.advance(Duration::from_seconds(3));
+    rate.sleep();
+    rate.sleep();
+
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(rosgraph_msgs::Log::WARN, log.level);
+    assert_eq!("/node", log.name);
+    assert!(
+        log.msg.contains("missed its deadline by 2.000000s"),
+        "{}",
+        log.msg
+    );
+    assert!(log.msg.contains("1 missed so far"), "{}", log.msg);
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+    assert_eq!(2, rate.overruns());
+}
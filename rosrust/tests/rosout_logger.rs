@@ -0,0 +1,84 @@
+use rosrust::api::testing::TestKit;
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+rosrust::rosmsg_include!(rosgraph_msgs / Log);
+
+// The `log` facade takes a single logger per process, so everything happens in one test
+#[test]
+fn forwards_log_records_to_rosout() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let rosout = kit.capture::<rosgraph_msgs::Log>("/rosout").unwrap();
+    node.set_logger_level("ros.chatty", rosgraph_msgs::Log::DEBUG);
+
+    let logger = node
+        .rosout_logger()
+        .with_target_level("chatty", log::LevelFilter::Warn)
+        .with_target_level("chatty::verbose", log::LevelFilter::Debug)
+        .with_throttle_identical(10.0);
+    assert_eq!(log::LevelFilter::Debug, logger.max_level());
+    logger.install().unwrap();
+
+    log::info!("hello {}", 42);
+    let line = line!() - 1;
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(rosgraph_msgs::Log::INFO, log.level);
+    assert_eq!("hello 42", log.msg);
+    assert_eq!("/node", log.name);
+    assert_eq!(file!(), log.file);
+    assert_eq!(line, log.line);
+    assert_eq!(module_path!(), log.function);
+
+    log::debug!("filtered by the default level");
+    log::info!(target: "chatty", "filtered by the target level");
+    log::info!(target: "chatty_other", "sibling of a filtered target");
+    log::debug!(target: "chatty::verbose", "reaching /rosout");
+    log::trace!(target: "chatty::verbose", "too verbose");
+
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!("[chatty_other] sibling of a filtered target", log.msg);
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(rosgraph_msgs::Log::DEBUG, log.level);
+    assert_eq!("[chatty::verbose] reaching /rosout", log.msg);
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+
+    for _ in 0..3 {
+        log::warn!("repeated");
+    }
+    assert_eq!(
+        rosgraph_msgs::Log::WARN,
+        rosout.recv_timeout(TIMEOUT).unwrap().level
+    );
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn forwards_tracing_events_to_rosout() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let rosout = kit.capture::<rosgraph_msgs::Log>("/rosout").unwrap();
+
+    let layer = node.rosout_logger().into_layer();
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!(attempt = 3, path = "/map", "planning failed");
+        tracing::debug!("filtered by the default level");
+        tracing::error!(target: "planner", code = 7);
+    });
+
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(rosgraph_msgs::Log::WARN, log.level);
+    assert_eq!("planning failed attempt=3 path=\"/map\"", log.msg);
+    assert_eq!(file!(), log.file);
+    assert_eq!(module_path!(), log.function);
+    let log = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(rosgraph_msgs::Log::ERROR, log.level);
+    assert_eq!("[planner] code=7", log.msg);
+    assert!(rosout.recv_timeout(NOTHING).is_none());
+}
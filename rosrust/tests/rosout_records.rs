@@ -0,0 +1,41 @@
+use rosrust::api::testing::TestKit;
+use rosrust::Time;
+
+mod util;
+
+use util::TIMEOUT;
+
+mod msg {
+    rosrust::rosmsg_include!(rosgraph_msgs / Log);
+}
+use msg::rosgraph_msgs::Log;
+
+#[test]
+fn stamps_records_and_captures_calling_function() {
+    let kit = TestKit::new().unwrap();
+    rosrust::try_init_with_test_kit("node", &kit).unwrap();
+    let rosout = kit.capture::<Log>("/rosout").unwrap();
+    kit.set_time(Time::from_nanos(42_500_000_000));
+
+    rosrust::ros_warn!("stamped");
+    let record = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!("stamped", record.msg);
+    assert_eq!(Time::from_nanos(42_500_000_000), record.header.stamp);
+    assert_eq!(Log::WARN, record.level);
+    assert!(record.file.ends_with("rosout_records.rs"));
+    assert_eq!(
+        "rosout_records::stamps_records_and_captures_calling_function",
+        record.function,
+    );
+
+    let log_from_closure = || {
+        rosrust::ros_info_once!("from closure");
+    };
+    log_from_closure();
+    let record = rosout.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!("from closure", record.msg);
+    assert_eq!(
+        "rosout_records::stamps_records_and_captures_calling_function",
+        record.function,
+    );
+}
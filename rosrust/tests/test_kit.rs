@@ -0,0 +1,274 @@
+use crossbeam::channel::unbounded;
+use rosrust::api::testing::TestKit;
+use rosrust::{Duration, Time};
+use std::time;
+
+mod util;
+
+use util::TIMEOUT;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        std_msgs / String,
+        std_msgs / Header,
+        roscpp_tutorials / TwoInts
+    );
+}
+
+fn string(data: &str) -> msg::std_msgs::String {
+    msg::std_msgs::String { data: data.into() }
+}
+
+#[test]
+fn publisher_reaches_subscriber_of_other_node() {
+    let kit = TestKit::new().unwrap();
+    let talker = kit.node("talker").unwrap();
+    let listener = kit.node("listener").unwrap();
+
+    let (tx, rx) = unbounded();
+    let subscriber = listener
+        .subscribe_with_ids::<msg::std_msgs::String, _>("chatter", 10, move |data, caller_id| {
+            tx.send((data.data, caller_id.to_owned())).unwrap();
+        })
+        .unwrap();
+    let publisher = talker
+        .publish::<msg::std_msgs::String>("chatter", 10)
+        .unwrap();
+    publisher.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+
+    publisher.send(string("hello")).unwrap();
+
+    assert_eq!(
+        ("hello".to_owned(), "/talker".to_owned()),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert_eq!(vec!["/listener"], publisher.subscriber_names());
+    assert_eq!(vec!["memory://talker"], subscriber.publisher_uris());
+}
+
+#[test]
+fn captures_published_messages() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("talker").unwrap();
+    let chatter = kit.capture::<msg::std_msgs::String>("/chatter").unwrap();
+
+    let publisher = node
+        .publish::<msg::std_msgs::String>("chatter", 10)
+        .unwrap();
+    assert_eq!(1, publisher.subscriber_count());
+    publisher.send(string("first")).unwrap();
+    publisher.send(string("second")).unwrap();
+
+    assert_eq!("first", chatter.recv_timeout(TIMEOUT).unwrap().data);
+    assert_eq!("second", chatter.recv_timeout(TIMEOUT).unwrap().data);
+    assert!(chatter.try_recv().is_none());
+}
+
+#[test]
+fn injects_messages_with_connection_headers() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("listener").unwrap();
+
+    let (tx, rx) = unbounded();
+    let (headers_tx, headers_rx) = unbounded();
+    let _subscriber = node
+        .subscribe_with_ids_and_headers::<msg::std_msgs::String, _, _>(
+            "chatter",
+            10,
+            move |data, _| tx.send(data.data).unwrap(),
+            move |headers| headers_tx.send(headers).unwrap(),
+        )
+        .unwrap();
+
+    kit.inject("/chatter", string("injected")).unwrap();
+
+    assert_eq!("injected", rx.recv_timeout(TIMEOUT).unwrap());
+    let headers = headers_rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!("/test_kit", headers["callerid"]);
+    assert_eq!("std_msgs/String", headers["type"]);
+    assert_eq!("string data", headers["message_definition"].trim());
+}
+
+#[test]
+fn injects_different_message_types_on_one_topic() {
+    let kit = TestKit::new().unwrap();
+    let strings = kit.capture::<msg::std_msgs::String>("/mixed").unwrap();
+
+    kit.inject("/mixed", string("first")).unwrap();
+    // The topic already carries strings, so other types get rejected without replacing them
+    assert!(kit
+        .inject("/mixed", msg::std_msgs::Header::default())
+        .is_err());
+    kit.inject("/mixed", string("second")).unwrap();
+
+    assert_eq!("first", strings.recv_timeout(TIMEOUT).unwrap().data);
+    assert_eq!("second", strings.recv_timeout(TIMEOUT).unwrap().data);
+}
+
+#[test]
+fn delivers_latched_messages_to_late_subscribers() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("talker").unwrap();
+
+    let mut publisher = node
+        .publish::<msg::std_msgs::String>("chatter", 10)
+        .unwrap();
+    publisher.set_latching(true);
+    publisher.send(string("latched")).unwrap();
+
+    let chatter = kit.capture::<msg::std_msgs::String>("/chatter").unwrap();
+    assert_eq!("latched", chatter.recv_timeout(TIMEOUT).unwrap().data);
+}
+
+#[test]
+fn rejects_subscription_with_mismatched_type() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("listener").unwrap();
+
+    let _subscriber = node
+        .subscribe::<msg::std_msgs::String, _>("chatter", 10, |_| {})
+        .unwrap();
+    assert!(node
+        .subscribe::<msg::std_msgs::Header, _>("chatter", 10, |_| {})
+        .is_err());
+}
+
+#[test]
+fn calls_stubbed_services() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("client").unwrap();
+
+    let client = node
+        .client::<msg::roscpp_tutorials::TwoInts>("add_two_ints")
+        .unwrap();
+    let request = msg::roscpp_tutorials::TwoIntsReq { a: 40, b: 2 };
+    assert!(client.req(&request).is_err());
+
+    let _stub = kit
+        .stub_service::<msg::roscpp_tutorials::TwoInts, _>("/add_two_ints", |req| {
+            if req.a < 0 {
+                return Err("Negative input".into());
+            }
+            Ok(msg::roscpp_tutorials::TwoIntsRes { sum: req.a + req.b })
+        })
+        .unwrap();
+
+    node.wait_for_service("add_two_ints", Some(TIMEOUT))
+        .unwrap();
+    assert_eq!(42, client.req(&request).unwrap().unwrap().sum);
+    assert_eq!(
+        Err("Negative input".into()),
+        client
+            .req(&msg::roscpp_tutorials::TwoIntsReq { a: -1, b: 2 })
+            .unwrap()
+    );
+    assert_eq!(42, client.req_async(request).read().unwrap().unwrap().sum);
+}
+
+#[test]
+fn serves_node_services_to_other_nodes() {
+    let kit = TestKit::new().unwrap();
+    let server = kit.node("server").unwrap();
+    let caller = kit.node("caller").unwrap();
+
+    let _service = server
+        .service::<msg::roscpp_tutorials::TwoInts, _>("add_two_ints", |req| {
+            Ok(msg::roscpp_tutorials::TwoIntsRes { sum: req.a + req.b })
+        })
+        .unwrap();
+    assert!(server
+        .service::<msg::roscpp_tutorials::TwoInts, _>("add_two_ints", |_| Err("".into()))
+        .is_err());
+
+    let client = caller
+        .client::<msg::roscpp_tutorials::TwoInts>("/add_two_ints")
+        .unwrap();
+    let response = client
+        .req(&msg::roscpp_tutorials::TwoIntsReq { a: 1, b: 2 })
+        .unwrap()
+        .unwrap();
+    assert_eq!(3, response.sum);
+}
+
+#[test]
+fn drops_services_whose_handlers_own_publishers() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("server").unwrap();
+
+    let publisher = node.publish::<msg::std_msgs::String>("calls", 10).unwrap();
+    let service = node
+        .service::<msg::roscpp_tutorials::TwoInts, _>("add_two_ints", move |req| {
+            publisher.send(string("called")).unwrap();
+            Ok(msg::roscpp_tutorials::TwoIntsRes { sum: req.a + req.b })
+        })
+        .unwrap();
+
+    let (tx, rx) = unbounded();
+    std::thread::spawn(move || {
+        drop(service);
+        tx.send(()).unwrap();
+    });
+    rx.recv_timeout(TIMEOUT).unwrap();
+}
+
+#[test]
+fn shares_parameters_between_nodes() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+
+    kit.param("/gain").unwrap().set(&2.5).unwrap();
+    assert_eq!(2.5, node.param("gain").unwrap().get::<f64>().unwrap());
+
+    kit.param("/gain").unwrap().set(&4.0).unwrap();
+    assert_eq!(4.0, node.param("gain").unwrap().get::<f64>().unwrap());
+
+    node.param("~private").unwrap().set(&"value").unwrap();
+    assert_eq!(
+        "value",
+        kit.param("/node/private").unwrap().get::<String>().unwrap()
+    );
+    assert_eq!(vec!["/gain", "/node/private"], node.parameters().unwrap());
+
+    kit.param("/gain").unwrap().delete().unwrap();
+    assert!(node.param("gain").unwrap().get::<f64>().is_err());
+}
+
+#[test]
+fn drives_clock_manually() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("sleeper").unwrap();
+    kit.set_time(Time::from_seconds(10));
+    assert_eq!(Time::from_seconds(10), node.now());
+
+    let (tx, rx) = unbounded();
+    let rate = node.rate(0.2);
+    std::thread::spawn(move || {
+        rate.sleep();
+        tx.send(()).unwrap();
+    });
+
+    kit.advance(Duration::from_seconds(4));
+    assert!(rx.recv_timeout(time::Duration::from_millis(100)).is_err());
+    kit.advance(Duration::from_seconds(1));
+    rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(Time::from_seconds(15), node.now());
+}
+
+#[test]
+fn reports_graph_state() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("talker").unwrap();
+    let _publisher = node
+        .publish::<msg::std_msgs::String>("chatter", 10)
+        .unwrap();
+
+    let topics = node.topics().unwrap();
+    assert!(topics
+        .iter()
+        .any(|t| t.name == "/chatter" && t.datatype == "std_msgs/String"));
+    let state = node.state().unwrap();
+    assert!(state
+        .publishers
+        .iter()
+        .any(|t| t.name == "/chatter" && t.connections == vec!["/talker"]));
+}
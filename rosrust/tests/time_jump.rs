@@ -0,0 +1,110 @@
+use crossbeam::channel::unbounded;
+use rosrust::api::testing::TestKit;
+use rosrust::api::{JumpThreshold, TimeJump};
+use rosrust::{Duration, Time};
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+#[test]
+fn calls_jump_callbacks_above_threshold() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let threshold = JumpThreshold {
+        min_forward: Some(Duration::from_seconds(5)),
+        min_backward: Some(Duration::from_seconds(1)),
+    };
+    let handle = node.add_jump_callback(threshold, move |jump| tx.send(*jump).unwrap());
+
+    kit.advance(Duration::from_seconds(1));
+    kit.set_time(Time::from_nanos(10_500_000_000));
+    assert!(rx.try_recv().is_err());
+
+    kit.advance(Duration::from_seconds(5));
+    let jump = rx.try_recv().unwrap();
+    assert_eq!(Duration::from_seconds(5), jump.delta());
+    assert!(!jump.is_backward());
+
+    kit.set_time(Time::from_seconds(3));
+    assert_eq!(
+        TimeJump {
+            previous: Time::from_nanos(15_500_000_000),
+            current: Time::from_seconds(3),
+        },
+        rx.try_recv().unwrap()
+    );
+
+    drop(handle);
+    kit.set_time(Time::from_seconds(1));
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn restarts_rate_after_backward_jump() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(100));
+
+    let (tx, rx) = unbounded();
+    let rate = node.rate(1.0);
+    let clock = kit.clone();
+    std::thread::spawn(move || loop {
+        rate.sleep();
+        if tx.send(clock.now()).is_err() {
+            break;
+        }
+    });
+
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(Time::from_seconds(101), rx.recv_timeout(TIMEOUT).unwrap());
+    assert!(rx.recv_timeout(NOTHING).is_err());
+
+    // The sleep for the cycle ending at 102 ends right away, and the next one ends at 51
+    kit.set_time(Time::from_seconds(50));
+    assert_eq!(Time::from_seconds(50), rx.recv_timeout(TIMEOUT).unwrap());
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(Time::from_seconds(51), rx.recv_timeout(TIMEOUT).unwrap());
+}
+
+#[test]
+fn ends_delay_after_backward_jump() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(100));
+
+    let (tx, rx) = unbounded();
+    let delay = node.delay(Duration::from_seconds(10));
+    std::thread::spawn(move || {
+        delay.sleep();
+        tx.send(()).unwrap();
+    });
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.set_time(Time::from_seconds(20));
+    rx.recv_timeout(TIMEOUT).unwrap();
+}
+
+#[test]
+fn restarts_timer_after_backward_jump() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let _timer = node.create_timer(Duration::from_seconds(2), move |event| {
+        tx.send(event).unwrap()
+    });
+    kit.set_time(Time::from_seconds(5));
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(Duration::from_seconds(1));
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(7),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+}
@@ -0,0 +1,218 @@
+use crossbeam::channel::{unbounded, Receiver};
+use rosrust::api::testing::TestKit;
+use rosrust::{Duration, Time, TimerEvent};
+
+mod util;
+
+use util::{NOTHING, TIMEOUT};
+
+fn assert_no_event(rx: &Receiver<TimerEvent>) {
+    assert!(rx.recv_timeout(NOTHING).is_err());
+}
+
+#[test]
+fn calls_periodically() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_timer(Duration::from_seconds(2), move |event| {
+        tx.send(event).unwrap()
+    });
+    assert!(timer.is_running());
+    kit.advance(Duration::from_seconds(1));
+    assert_no_event(&rx);
+
+    kit.advance(Duration::from_seconds(1));
+    let first = rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(Time::default(), first.last_expected);
+    assert_eq!(Time::from_seconds(12), first.current_expected);
+    assert_eq!(Time::from_seconds(12), first.current_real);
+
+    kit.advance(Duration::from_seconds(2));
+    let second = rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(Time::from_seconds(12), second.last_expected);
+    assert_eq!(Time::from_seconds(12), second.last_real);
+    assert_eq!(Time::from_seconds(14), second.current_expected);
+
+    // Missed calls are skipped instead of being made up for
+    kit.advance(Duration::from_seconds(5));
+    let late = rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(Time::from_seconds(16), late.current_expected);
+    assert_eq!(Time::from_seconds(19), late.current_real);
+    assert_no_event(&rx);
+    kit.advance(Duration::from_seconds(2));
+    assert_eq!(
+        Time::from_seconds(21),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+}
+
+#[test]
+fn calls_oneshot_timers_once() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_oneshot_timer(Duration::from_seconds(1), move |event| {
+        tx.send(event).unwrap()
+    });
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(11),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+    kit.advance(Duration::from_seconds(3));
+    assert_no_event(&rx);
+    assert!(!timer.is_running());
+
+    timer.start();
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(15),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+}
+
+#[test]
+fn calls_timers_without_positive_period_once() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_timer(Duration::from_seconds(0), move |event| {
+        tx.send(event).unwrap()
+    });
+    assert_eq!(
+        Time::from_seconds(10),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+    kit.advance(Duration::from_seconds(1));
+    assert_no_event(&rx);
+    assert!(!timer.is_running());
+
+    timer.set_period(Duration::from_seconds(1), true);
+    timer.start();
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(12),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+    // The next call was due at 13, so it moves back to 11, which already passed
+    timer.set_period(Duration::from_seconds(-1), false);
+    assert_eq!(
+        Time::from_seconds(11),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+    kit.advance(Duration::from_seconds(1));
+    assert_no_event(&rx);
+    assert!(!timer.is_running());
+}
+
+#[test]
+fn stops_and_changes_period() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_timer(Duration::from_seconds(1), move |event| {
+        tx.send(event).unwrap()
+    });
+    timer.stop();
+    kit.advance(Duration::from_seconds(2));
+    assert_no_event(&rx);
+
+    timer.start();
+    timer.set_period(Duration::from_seconds(3), false);
+    assert_eq!(Duration::from_seconds(3), timer.period());
+    kit.advance(Duration::from_seconds(2));
+    assert_no_event(&rx);
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(15),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+
+    timer.set_period(Duration::from_seconds(1), true);
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(
+        Time::from_seconds(16),
+        rx.recv_timeout(TIMEOUT).unwrap().current_expected
+    );
+
+    drop(timer);
+    kit.advance(Duration::from_seconds(2));
+    assert_no_event(&rx);
+}
+
+#[test]
+fn controls_timer_from_its_own_callback() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_timer(Duration::from_seconds(1), move |event| {
+        let timer = event.timer.upgrade().unwrap();
+        if event.current_expected == Time::from_seconds(11) {
+            timer.set_period(Duration::from_seconds(2), false);
+        } else {
+            timer.stop();
+        }
+        tx.send(event.current_expected).unwrap()
+    });
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(Time::from_seconds(11), rx.recv_timeout(TIMEOUT).unwrap());
+    kit.advance(Duration::from_seconds(1));
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    kit.advance(Duration::from_seconds(1));
+    assert_eq!(Time::from_seconds(13), rx.recv_timeout(TIMEOUT).unwrap());
+    kit.advance(Duration::from_seconds(2));
+    assert!(rx.recv_timeout(NOTHING).is_err());
+    assert!(!timer.is_running());
+}
+
+#[test]
+fn stops_when_callback_holds_weak_handle() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let timer = node.create_timer(Duration::from_seconds(1), move |event| {
+        tx.send(event).unwrap()
+    });
+    kit.advance(Duration::from_seconds(1));
+    let event = rx.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(timer.downgrade(), event.timer);
+    assert!(event.timer.upgrade().is_some());
+
+    drop(timer);
+    assert!(event.timer.upgrade().is_none());
+    kit.advance(Duration::from_seconds(2));
+    assert_no_event(&rx);
+}
+
+#[test]
+fn wall_timers_ignore_simulated_time() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.set_time(Time::from_seconds(10));
+
+    let (tx, rx) = unbounded();
+    let _timer = node.create_wall_timer(Duration::from_nanos(100_000_000), move |event| {
+        tx.send(event).unwrap()
+    });
+    let first = rx.recv_timeout(TIMEOUT).unwrap();
+    let second = rx.recv_timeout(TIMEOUT).unwrap();
+    assert!(first.current_real > Time::from_seconds(1_000_000_000));
+    assert_eq!(first.current_expected, second.last_expected);
+    assert_eq!(
+        Duration::from_nanos(100_000_000),
+        second.current_expected - first.current_expected
+    );
+}
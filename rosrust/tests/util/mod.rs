@@ -1,6 +1,7 @@
 #[allow(clippy::module_inception)]
 mod util;
 pub use util::{
-    assert_success_and_output_containing, bytes_contain, run_roscore_for, test_publisher,
-    test_subscriber, test_subscriber_detailed, ChildProcessTerminator, TestVariant,
+    assert_success_and_output_containing, bytes_contain, param_tree, run_roscore_for,
+    test_publisher, test_subscriber, test_subscriber_detailed, value_tree, ChildProcessTerminator,
+    TestVariant, NOTHING, TIMEOUT,
 };
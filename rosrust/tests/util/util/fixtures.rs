@@ -0,0 +1,34 @@
+use rosrust::ParamValue;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use xml_rpc::Value;
+
+/// How long to wait for something that should happen.
+#[allow(dead_code)]
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait before deciding that something didn't happen.
+#[allow(dead_code)]
+pub const NOTHING: Duration = Duration::from_millis(50);
+
+/// Builds a raw parameter struct out of keys and values.
+#[allow(dead_code)]
+pub fn value_tree(items: &[(&str, Value)]) -> Value {
+    Value::Struct(
+        items
+            .iter()
+            .map(|(key, value)| (String::from(*key), value.clone()))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+/// Builds a parameter struct out of keys and values.
+#[allow(dead_code)]
+pub fn param_tree(items: &[(&str, ParamValue)]) -> ParamValue {
+    ParamValue::Struct(
+        items
+            .iter()
+            .map(|(key, value)| (String::from(*key), value.clone()))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
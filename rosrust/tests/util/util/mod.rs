@@ -1,4 +1,5 @@
 pub use child_process_terminator::ChildProcessTerminator;
+pub use fixtures::{param_tree, value_tree, NOTHING, TIMEOUT};
 use std::env;
 use std::process::{Command, Output};
 use std::str::from_utf8;
@@ -8,6 +9,7 @@ pub use subscriber_test::{test_publisher, test_subscriber, test_subscriber_detai
 pub use test_variant::TestVariant;
 
 mod child_process_terminator;
+mod fixtures;
 mod subscriber_test;
 mod test_variant;
 
@@ -35,6 +37,7 @@ fn run_roscore(port: u32) -> ChildProcessTerminator {
     roscore
 }
 
+#[allow(dead_code)]
 pub fn run_roscore_for(test_variant: TestVariant) -> ChildProcessTerminator {
     run_roscore(test_variant.port())
 }
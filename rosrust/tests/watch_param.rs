@@ -0,0 +1,159 @@
+use crossbeam::channel::{unbounded, Receiver};
+use rosrust::api::testing::TestKit;
+use rosrust::ParamValue;
+
+mod util;
+
+use util::{param_tree, NOTHING, TIMEOUT};
+
+fn assert_no_update<T: std::fmt::Debug>(rx: &Receiver<T>) {
+    assert!(rx.recv_timeout(NOTHING).is_err());
+}
+
+#[test]
+fn delivers_new_values() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    kit.param("/gain").unwrap().set(&1).unwrap();
+
+    let (tx, rx) = unbounded();
+    let _watch = node
+        .watch_param("gain", move |value| tx.send(value).unwrap())
+        .unwrap();
+    assert_no_update(&rx);
+
+    kit.param("/gain").unwrap().set(&2).unwrap();
+    assert_eq!(Some(ParamValue::Int(2)), rx.recv_timeout(TIMEOUT).unwrap());
+
+    // Setting the same value again is not a change
+    kit.param("/gain").unwrap().set(&2).unwrap();
+    assert_no_update(&rx);
+
+    kit.param("/gain").unwrap().delete().unwrap();
+    assert_eq!(None, rx.recv_timeout(TIMEOUT).unwrap());
+}
+
+#[test]
+fn delivers_subtree_updates() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+
+    let (namespace_tx, namespace_rx) = unbounded();
+    let _namespace_watch = node
+        .watch_param("/controller", move |value| {
+            namespace_tx.send(value).unwrap()
+        })
+        .unwrap();
+    let (leaf_tx, leaf_rx) = unbounded();
+    let _leaf_watch = node
+        .watch_param("/controller/p", move |value| leaf_tx.send(value).unwrap())
+        .unwrap();
+
+    kit.param("/controller/p").unwrap().set(&1.5).unwrap();
+    assert_eq!(
+        Some(param_tree(&[("p", ParamValue::Double(1.5))])),
+        namespace_rx.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert_eq!(
+        Some(ParamValue::Double(1.5)),
+        leaf_rx.recv_timeout(TIMEOUT).unwrap()
+    );
+
+    kit.param("/controller/i").unwrap().set(&0.5).unwrap();
+    assert_eq!(
+        Some(param_tree(&[
+            ("p", ParamValue::Double(1.5)),
+            ("i", ParamValue::Double(0.5))
+        ])),
+        namespace_rx.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert_no_update(&leaf_rx);
+
+    kit.param("/controller")
+        .unwrap()
+        .set_value(param_tree(&[("p", ParamValue::Double(3.0))]))
+        .unwrap();
+    assert_eq!(
+        Some(param_tree(&[("p", ParamValue::Double(3.0))])),
+        namespace_rx.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert_eq!(
+        Some(ParamValue::Double(3.0)),
+        leaf_rx.recv_timeout(TIMEOUT).unwrap()
+    );
+
+    kit.param("/controller/p").unwrap().delete().unwrap();
+    assert_eq!(None, namespace_rx.recv_timeout(TIMEOUT).unwrap());
+    assert_eq!(None, leaf_rx.recv_timeout(TIMEOUT).unwrap());
+}
+
+#[test]
+fn deserializes_typed_values() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+
+    let (tx, rx) = unbounded();
+    let _watch = node
+        .watch_param_typed::<Vec<String>, _>("~frames", move |value| tx.send(value).unwrap())
+        .unwrap();
+
+    kit.param("/node/frames")
+        .unwrap()
+        .set(&vec!["base", "odom"])
+        .unwrap();
+    assert_eq!(
+        Some(vec!["base".to_owned(), "odom".to_owned()]),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+
+    // Values of the wrong type are skipped
+    kit.param("/node/frames").unwrap().set(&5).unwrap();
+    assert_no_update(&rx);
+}
+
+#[test]
+fn callbacks_can_access_parameters() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+    let reader = kit.node("reader").unwrap();
+
+    let (tx, rx) = unbounded();
+    let _watch = node
+        .watch_param("/rate", move |_| {
+            tx.send(reader.param("/rate").unwrap().get::<i32>().unwrap())
+                .unwrap()
+        })
+        .unwrap();
+
+    kit.param("/rate").unwrap().set(&10).unwrap();
+    assert_eq!(10, rx.recv_timeout(TIMEOUT).unwrap());
+}
+
+#[test]
+fn stops_watching_when_dropped() {
+    let kit = TestKit::new().unwrap();
+    let node = kit.node("node").unwrap();
+
+    let (tx, rx) = unbounded();
+    let first = node
+        .watch_param("/mode", {
+            let tx = tx.clone();
+            move |value| tx.send(("first", value)).unwrap()
+        })
+        .unwrap();
+    let second = node
+        .watch_param("/mode", move |value| tx.send(("second", value)).unwrap())
+        .unwrap();
+
+    drop(first);
+    kit.param("/mode").unwrap().set(&"fast").unwrap();
+    assert_eq!(
+        ("second", Some(ParamValue::String("fast".into()))),
+        rx.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert_no_update(&rx);
+
+    drop(second);
+    kit.param("/mode").unwrap().set(&"slow").unwrap();
+    assert_no_update(&rx);
+}
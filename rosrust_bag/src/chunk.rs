@@ -0,0 +1,80 @@
+use crate::compression::Compression;
+use crate::error::{ErrorKind, Result};
+use crate::record::{self, op, Record};
+use byteorder::{LittleEndian, ReadBytesExt};
+use error_chain::bail;
+use ros_message::Time;
+use std::collections::HashMap;
+
+/// Summary of a chunk, as stored in the bag's index section.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkInfo {
+    /// Offset of the chunk record from the start of the file.
+    pub position: u64,
+    pub start_time: Time,
+    pub end_time: Time,
+    /// Number of messages in the chunk, per connection ID.
+    pub message_counts: HashMap<u32, u32>,
+}
+
+impl ChunkInfo {
+    pub(crate) fn from_record(record: &Record) -> Result<ChunkInfo> {
+        let mut data = record.data.as_slice();
+        let count = record.header.u32("count")?;
+        let mut message_counts = HashMap::new();
+        for _ in 0..count {
+            let connection = data.read_u32::<LittleEndian>()?;
+            let messages = data.read_u32::<LittleEndian>()?;
+            message_counts.insert(connection, messages);
+        }
+        Ok(ChunkInfo {
+            position: record.header.u64("chunk_pos")?,
+            start_time: record.header.time("start_time")?,
+            end_time: record.header.time("end_time")?,
+            message_counts,
+        })
+    }
+
+    #[inline]
+    pub fn message_count(&self) -> u64 {
+        self.message_counts
+            .values()
+            .map(|&count| u64::from(count))
+            .sum()
+    }
+}
+
+/// Location of a single message within its chunk.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct IndexEntry {
+    pub time: Time,
+    pub connection: u32,
+    /// Offset of the message record within the uncompressed chunk data.
+    pub offset: u32,
+}
+
+pub(crate) fn read_index(record: &Record) -> Result<Vec<IndexEntry>> {
+    let connection = record.header.u32("conn")?;
+    let count = record.header.u32("count")?;
+    let mut data = record.data.as_slice();
+    (0..count)
+        .map(|_| {
+            let time = record::read_time(&mut data)?;
+            let offset = data.read_u32::<LittleEndian>()?;
+            Ok(IndexEntry {
+                time,
+                connection,
+                offset,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn decompress(record: Record) -> Result<Vec<u8>> {
+    if record.header.op()? != op::CHUNK {
+        bail!(ErrorKind::UnexpectedRecord(record.header.op()?));
+    }
+    let compression = Compression::from_name(&record.header.string("compression")?)?;
+    let size = u64::from(record.header.u32("size")?);
+    compression.decompress(record.data, size)
+}
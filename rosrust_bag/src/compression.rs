@@ -0,0 +1,64 @@
+use crate::error::{ErrorKind, Result};
+use crate::record::read_limited;
+use error_chain::bail;
+use std::io::Write;
+
+/// Compression applied to the records inside a chunk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Bz2,
+    Lz4,
+}
+
+impl Compression {
+    /// Name used for the compression in chunk headers.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Bz2 => "bz2",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Compression> {
+        Ok(match name {
+            "none" => Compression::None,
+            "bz2" => Compression::Bz2,
+            "lz4" => Compression::Lz4,
+            _ => bail!(ErrorKind::UnsupportedCompression(name.into())),
+        })
+    }
+
+    /// Decompresses chunk records, checking that they have the size stated in the chunk.
+    pub(crate) fn decompress(self, data: Vec<u8>, size: u64) -> Result<Vec<u8>> {
+        let output = match self {
+            Compression::None => data,
+            Compression::Bz2 => read_limited(bzip2::read::BzDecoder::new(data.as_slice()), size)?,
+            Compression::Lz4 => {
+                read_limited(lz4_flex::frame::FrameDecoder::new(data.as_slice()), size)?
+            }
+        };
+        if output.len() as u64 != size {
+            bail!(ErrorKind::BadSize("chunk".into()));
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn compress(self, data: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(match self {
+            Compression::None => data,
+            Compression::Bz2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::best());
+                encoder.write_all(&data)?;
+                encoder.finish()?
+            }
+            Compression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+                encoder.write_all(&data)?;
+                encoder.finish().map_err(std::io::Error::from)?
+            }
+        })
+    }
+}
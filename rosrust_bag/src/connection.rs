@@ -0,0 +1,110 @@
+use crate::error::{ErrorKind, Result};
+use crate::record::{self, op, Header, Record};
+use error_chain::bail;
+use rosrust::{DynamicMsg, RawMessageDescription};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Publisher of a topic, as recorded in the bag.
+///
+/// Every message in the bag refers to the connection it was received on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection {
+    pub id: u32,
+    pub topic: String,
+    pub message_type: String,
+    pub md5sum: String,
+    pub message_definition: String,
+    pub caller_id: Option<String>,
+    pub latching: bool,
+}
+
+impl Connection {
+    /// Creates a connection for writing, with its ID to be assigned by the writer.
+    pub fn new(topic: &str, description: RawMessageDescription) -> Self {
+        Connection {
+            id: 0,
+            topic: topic.into(),
+            message_type: description.msg_type,
+            md5sum: description.md5sum,
+            message_definition: description.msg_definition,
+            caller_id: None,
+            latching: false,
+        }
+    }
+
+    /// Creates a connection from the headers that a publisher sends on connecting.
+    pub fn from_headers(topic: &str, headers: &HashMap<String, String>) -> Result<Self> {
+        let field = |name: &str| -> Result<String> {
+            match headers.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => bail!(ErrorKind::MissingField(name.into())),
+            }
+        };
+        Ok(Connection {
+            id: 0,
+            topic: topic.into(),
+            message_type: field("type")?,
+            md5sum: field("md5sum")?,
+            message_definition: field("message_definition")?,
+            caller_id: headers.get("callerid").cloned(),
+            latching: headers.get("latching").map(String::as_str) == Some("1"),
+        })
+    }
+
+    pub(crate) fn from_record(record: &Record) -> Result<Connection> {
+        let id = record.header.u32("conn")?;
+        let topic = record.header.string("topic")?;
+        let data = Header::parse(&record.data)?;
+        Ok(Connection {
+            id,
+            topic,
+            message_type: data.string("type")?,
+            md5sum: data.string("md5sum")?,
+            message_definition: data.string("message_definition")?,
+            caller_id: data.optional_string("callerid")?,
+            latching: data.get("latching") == Some(b"1"),
+        })
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<u64> {
+        let mut fields = vec![
+            ("topic", self.topic.as_bytes()),
+            ("type", self.message_type.as_bytes()),
+            ("md5sum", self.md5sum.as_bytes()),
+            ("message_definition", self.message_definition.as_bytes()),
+        ];
+        if let Some(caller_id) = &self.caller_id {
+            fields.push(("callerid", caller_id.as_bytes()));
+        }
+        if self.latching {
+            fields.push(("latching", b"1"));
+        }
+        record::write_record(
+            writer,
+            &[
+                ("op", &[op::CONNECTION]),
+                ("conn", &self.id.to_le_bytes()),
+                ("topic", self.topic.as_bytes()),
+            ],
+            &record::encode_fields(&fields),
+        )
+    }
+
+    /// Description of the message type, as needed for publishing raw messages.
+    pub fn description(&self) -> RawMessageDescription {
+        RawMessageDescription {
+            msg_definition: self.message_definition.clone(),
+            md5sum: self.md5sum.clone(),
+            msg_type: self.message_type.clone(),
+        }
+    }
+
+    /// Creates a decoder of this connection's messages from its message definition.
+    pub fn dynamic_msg(&self) -> Result<DynamicMsg> {
+        Ok(DynamicMsg::new(
+            &self.message_type,
+            &self.message_definition,
+        )?)
+    }
+}
@@ -0,0 +1,59 @@
+error_chain::error_chain! {
+    foreign_links {
+        Io(::std::io::Error);
+        FromUTF8(::std::string::FromUtf8Error);
+    }
+    links {
+        Ros(rosrust::error::Error, rosrust::error::ErrorKind);
+    }
+    errors {
+        UnsupportedVersion(version: String) {
+            description("Unsupported bag format version")
+            display("Unsupported bag format version: {}", version)
+        }
+        Unindexed {
+            description("Bag has no index")
+            display("Bag has no index, it needs to be reindexed")
+        }
+        MissingField(name: String) {
+            description("Missing field in record header")
+            display("Missing field in record header: {}", name)
+        }
+        BadField(name: String) {
+            description("Malformed field in record header")
+            display("Malformed field in record header: {}", name)
+        }
+        UnexpectedRecord(op: u8) {
+            description("Unexpected record type")
+            display("Unexpected record type: 0x{:02x}", op)
+        }
+        UnsupportedCompression(name: String) {
+            description("Unsupported chunk compression")
+            display("Unsupported chunk compression: {}", name)
+        }
+        BadChecksum(section: String) {
+            description("Checksum mismatch")
+            display("Checksum mismatch in {}", section)
+        }
+        BadSize(section: String) {
+            description("Size mismatch")
+            display("Size mismatch in {}", section)
+        }
+        UnknownConnection(id: u32) {
+            description("Reference to unknown connection")
+            display("Reference to unknown connection: {}", id)
+        }
+        InvalidRate(rate: f64) {
+            description("Playback rate must be positive")
+            display("Playback rate must be positive, but got {}", rate)
+        }
+        InvalidClockFrequency(frequency: f64) {
+            description("Clock frequency must be positive")
+            display("Clock frequency must be positive, but got {}", frequency)
+        }
+        MismatchedType(topic: String, actual_type: String, attempted_type: String) {
+            description("Attempted to read message with wrong message type")
+            display("Attempted to read {} message on topic '{}' as {}", actual_type, topic, attempted_type)
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/*!
+This crate provides reading, writing, recording and playback of ROS [bag files], in the 2.0
+format.
+
+The bag's index is used to only touch the chunks that a query needs, and chunks can be
+uncompressed or compressed with bz2 or lz4. Messages can be read as raw data, decoded into
+generated message types, or decoded dynamically based on the recorded message definitions:
+
+```no_run
+# rosrust::rosmsg_include!(std_msgs / String);
+use rosrust_bag::{Bag, Query};
+
+let mut bag = Bag::open("recording.bag").unwrap();
+let query = Query::new().topic("/chatter");
+for message in bag.read(&query).unwrap().typed::<std_msgs::String>() {
+    let message = message.unwrap();
+    println!("{}: {}", message.time, message.message.data);
+}
+```
+
+Live topics can be recorded with a [`Recorder`], which uses the global node:
+
+```no_run
+use rosrust_bag::{Compression, Recorder, RecorderOptions};
+
+rosrust::init("recorder");
+let options = RecorderOptions::new("recording.bag")
+    .topic("/chatter")
+    .compression(Compression::Lz4);
+let recorder = Recorder::start(options).unwrap();
+rosrust::spin();
+recorder.stop().unwrap();
+```
+
+Recordings can be played back with a [`Player`], which also drives `/clock` if asked to:
+
+```no_run
+use rosrust_bag::{Bag, Player, PlayerOptions};
+
+rosrust::init("player");
+let bag = Bag::open("recording.bag").unwrap();
+let options = PlayerOptions::new().rate(2.0).publish_clock(true);
+Player::start(bag, options).unwrap().wait().unwrap();
+```
+
+The same messages can be stored in [MCAP] files instead, through the [`mcap`] module.
+
+[bag files]: http://wiki.ros.org/Bags/Format/2.0
+[MCAP]: https://mcap.dev
+*/
+
+pub use chunk::ChunkInfo;
+pub use compression::Compression;
+pub use connection::Connection;
+pub use error::{Error, ErrorKind, Result};
+pub use message::{BagMessage, DynamicMessages, TypedMessages};
+pub use player::{Player, PlayerOptions};
+pub use query::Query;
+pub use reader::{Bag, Messages};
+pub use recorder::{Recorder, RecorderOptions};
+pub use writer::{BagWriter, DEFAULT_CHUNK_THRESHOLD};
+
+mod chunk;
+mod compression;
+mod connection;
+pub mod error;
+pub mod mcap;
+mod message;
+mod player;
+mod query;
+mod reader;
+mod record;
+mod recorder;
+mod writer;
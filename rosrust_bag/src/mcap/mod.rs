@@ -0,0 +1,105 @@
+/*!
+Reading and writing of [MCAP] files, holding ROS1 messages.
+
+Files follow the `ros1` profile: every schema holds the full message definition with the
+`ros1msg` encoding, and messages are stored in their ROS1 serialization. Messages are grouped
+into optionally compressed chunks, with a summary section at the end of the file, which lets
+readers only touch the chunks that a query needs.
+
+```no_run
+# rosrust::rosmsg_include!(std_msgs / String);
+use rosrust::RawMessageDescription;
+use rosrust_bag::mcap::{Compression, Mcap, McapWriter};
+use rosrust_bag::{Connection, Query};
+
+let mut writer = McapWriter::create("recording.mcap").unwrap();
+writer.set_compression(Compression::Zstd);
+let description = RawMessageDescription::from_message::<std_msgs::String>();
+let chatter = writer
+    .add_connection(Connection::new("/chatter", description))
+    .unwrap();
+let message = std_msgs::String { data: "hello".into() };
+writer.write(chatter, rosrust::Time::new(), &message).unwrap();
+writer.close().unwrap();
+
+let mut mcap = Mcap::open("recording.mcap").unwrap();
+for message in mcap.read(&Query::new()).unwrap().typed::<std_msgs::String>() {
+    println!("{}", message.unwrap().message.data);
+}
+```
+
+[MCAP]: https://mcap.dev/spec
+*/
+
+pub use self::reader::{Mcap, Messages};
+pub use self::writer::{McapWriter, DEFAULT_CHUNK_SIZE};
+
+use crate::error::{ErrorKind, Result};
+use crate::record::read_limited;
+use error_chain::bail;
+use std::io::Write;
+
+mod reader;
+mod records;
+mod writer;
+
+/// Compression applied to the records inside a chunk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    /// Name used for the compression in chunk records.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Lz4 => "lz4",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Compression> {
+        Ok(match name {
+            "" => Compression::None,
+            "lz4" => Compression::Lz4,
+            "zstd" => Compression::Zstd,
+            _ => bail!(ErrorKind::UnsupportedCompression(name.into())),
+        })
+    }
+
+    /// Decompresses chunk records, checking that they have the size stated in the chunk.
+    ///
+    /// The size comes straight from the file, so it only limits decoding, and never gets
+    /// allocated up front.
+    pub(crate) fn decompress(self, data: Vec<u8>, size: u64) -> Result<Vec<u8>> {
+        let output = match self {
+            Compression::None => data,
+            Compression::Lz4 => {
+                read_limited(lz4_flex::frame::FrameDecoder::new(data.as_slice()), size)?
+            }
+            Compression::Zstd => {
+                read_limited(zstd::stream::read::Decoder::new(data.as_slice())?, size)?
+            }
+        };
+        if output.len() as u64 != size {
+            bail!(ErrorKind::BadSize("chunk".into()));
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn compress(self, data: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(match self {
+            Compression::None => data,
+            Compression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+                encoder.write_all(&data)?;
+                encoder.finish().map_err(std::io::Error::from)?
+            }
+            Compression::Zstd => zstd::stream::encode_all(data.as_slice(), 0)?,
+        })
+    }
+}
@@ -0,0 +1,334 @@
+use super::records::{
+    self, op, ChunkIndex, Fields, Footer, MessageHeader, Schema, Statistics, FOOTER_LENGTH, MAGIC,
+    MESSAGE_ENCODING, SCHEMA_ENCODING,
+};
+use super::Compression;
+use crate::connection::Connection;
+use crate::error::{ErrorKind, Result};
+use crate::message::{BagMessage, DynamicMessages, TypedMessages};
+use crate::query::Query;
+use error_chain::bail;
+use ros_message::Time;
+use rosrust::{Message, RawMessage};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Reader of MCAP files with ROS1 messages.
+///
+/// Opening a file only loads its summary section, so files without one are rejected. Messages
+/// are read chunk by chunk, as queries get iterated over. Only channels with ROS1 encoded
+/// messages and `ros1msg` schemas are exposed, as connections with the channel's ID.
+pub struct Mcap<R> {
+    reader: R,
+    connections: BTreeMap<u32, Arc<Connection>>,
+    chunks: Vec<ChunkIndex>,
+    statistics: Option<Statistics>,
+}
+
+impl Mcap<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> Mcap<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        check_magic(&mut reader)?;
+        let footer_position = reader.seek(SeekFrom::End(-((FOOTER_LENGTH as i64) + 8)))?;
+        let footer = match records::read_record(&mut reader)? {
+            Some(record) if record.op == op::FOOTER => Footer::parse(&record.data)?,
+            Some(record) => bail!(ErrorKind::UnexpectedRecord(record.op)),
+            None => bail!(ErrorKind::MissingField("footer".into())),
+        };
+        check_magic(&mut reader)?;
+        if footer.summary_start == 0 {
+            bail!(ErrorKind::Unindexed);
+        }
+
+        reader.seek(SeekFrom::Start(footer.summary_start))?;
+        // The CRC covers the footer up to the CRC itself
+        let mut summary = vec![];
+        (&mut reader)
+            .take(footer_position + FOOTER_LENGTH - 4 - footer.summary_start)
+            .read_to_end(&mut summary)?;
+        if footer.summary_crc != 0 && crc32fast::hash(&summary) != footer.summary_crc {
+            bail!(ErrorKind::BadChecksum("summary".into()));
+        }
+        let summary_end = match footer.summary_offset_start {
+            0 => footer_position,
+            summary_offset_start => summary_offset_start,
+        };
+        let mut summary = summary
+            .get(..(summary_end - footer.summary_start) as usize)
+            .unwrap_or_default();
+
+        let mut schemas = HashMap::new();
+        let mut channels = vec![];
+        let mut chunks = vec![];
+        let mut statistics = None;
+        while let Some(record) = records::read_record(&mut summary)? {
+            match record.op {
+                op::SCHEMA => {
+                    let schema = Schema::parse(&record.data)?;
+                    schemas.insert(schema.id, schema);
+                }
+                op::CHANNEL => channels.push(records::Channel::parse(&record.data)?),
+                op::CHUNK_INDEX => chunks.push(ChunkIndex::parse(&record.data)?),
+                op::STATISTICS => statistics = Some(Statistics::parse(&record.data)?),
+                // Records for attachments and metadata aren't exposed
+                _ => {}
+            }
+        }
+        chunks.sort_by_key(|chunk| chunk.chunk_start_offset);
+
+        let mut connections = BTreeMap::new();
+        for channel in channels {
+            let schema = match schemas.get(&channel.schema_id) {
+                Some(schema) => schema,
+                None => continue,
+            };
+            if channel.message_encoding != MESSAGE_ENCODING || schema.encoding != SCHEMA_ENCODING {
+                continue;
+            }
+            let connection = Connection {
+                id: u32::from(channel.id),
+                topic: channel.topic,
+                message_type: schema.name.clone(),
+                md5sum: channel
+                    .metadata
+                    .get("md5sum")
+                    .cloned()
+                    .unwrap_or_else(|| "*".into()),
+                message_definition: String::from_utf8(schema.data.clone())?,
+                caller_id: channel.metadata.get("callerid").cloned(),
+                latching: matches!(
+                    channel.metadata.get("latching").map(String::as_str),
+                    Some("1") | Some("true")
+                ),
+            };
+            connections.insert(connection.id, Arc::new(connection));
+        }
+
+        Ok(Mcap {
+            reader,
+            connections,
+            chunks,
+            statistics,
+        })
+    }
+
+    /// Reads the messages selected by the query, in order of their log time.
+    pub fn read(&mut self, query: &Query) -> Result<Messages<'_, R>> {
+        let selected = self
+            .connections
+            .values()
+            .filter(|connection| query.matches_topic(&connection.topic))
+            .map(|connection| connection.id as u16)
+            .collect::<HashSet<u16>>();
+
+        let mut entries = vec![];
+        for index in 0..self.chunks.len() {
+            let chunk = &self.chunks[index];
+            let start = records::nanos_time(chunk.message_start_time);
+            let end = records::nanos_time(chunk.message_end_time);
+            if !query.overlaps(start, end) {
+                continue;
+            }
+            if chunk.message_index_offsets.is_empty() {
+                // Without message indexes, the chunk itself needs to be scanned
+                let data = self.load_chunk(index)?;
+                let mut offset = 0;
+                let mut reader = data.as_slice();
+                while let Some(record) = records::read_record(&mut reader)? {
+                    if record.op == op::MESSAGE {
+                        let (header, _) = MessageHeader::parse(&record.data)?;
+                        entries.push((index, header.channel_id, header.log_time, offset));
+                    }
+                    offset = (data.len() - reader.len()) as u64;
+                }
+                continue;
+            }
+            let offsets = chunk
+                .message_index_offsets
+                .iter()
+                .filter(|(channel_id, _)| selected.contains(channel_id))
+                .map(|(_, &offset)| offset)
+                .collect::<Vec<_>>();
+            for offset in offsets {
+                self.reader.seek(SeekFrom::Start(offset))?;
+                let record = match records::read_record(&mut self.reader)? {
+                    Some(record) if record.op == op::MESSAGE_INDEX => record,
+                    Some(record) => bail!(ErrorKind::UnexpectedRecord(record.op)),
+                    None => bail!(ErrorKind::MissingField("message_index".into())),
+                };
+                let mut fields = Fields::new(&record.data);
+                let channel_id = fields.u16("channel_id")?;
+                let mut records = Fields::new(fields.bytes("records")?);
+                while !records.rest_is_empty() {
+                    let log_time = records.u64("log_time")?;
+                    let offset = records.u64("offset")?;
+                    entries.push((index, channel_id, log_time, offset));
+                }
+            }
+        }
+        entries.retain(|&(_, channel_id, log_time, _)| {
+            selected.contains(&channel_id) && query.matches_time(records::nanos_time(log_time))
+        });
+        entries.sort_by_key(|&(index, _, log_time, offset)| (log_time, index, offset));
+
+        Ok(Messages {
+            mcap: self,
+            entries: entries
+                .into_iter()
+                .map(|(index, _, _, offset)| (index, offset))
+                .collect::<Vec<_>>()
+                .into_iter(),
+            chunk: None,
+        })
+    }
+
+    fn read_message(
+        &mut self,
+        chunk: &mut Option<(usize, Vec<u8>)>,
+        index: usize,
+        offset: u64,
+    ) -> Result<BagMessage> {
+        let data = match chunk {
+            Some((loaded, data)) if *loaded == index => data,
+            _ => &mut chunk.insert((index, self.load_chunk(index)?)).1,
+        };
+
+        let mut reader = data.get(offset as usize..).unwrap_or_default();
+        let record = match records::read_record(&mut reader)? {
+            Some(record) if record.op == op::MESSAGE => record,
+            Some(record) => bail!(ErrorKind::UnexpectedRecord(record.op)),
+            None => bail!(ErrorKind::MissingField("message".into())),
+        };
+        let (header, data) = MessageHeader::parse(&record.data)?;
+        let id = u32::from(header.channel_id);
+        let connection = match self.connections.get(&id) {
+            Some(connection) => Arc::clone(connection),
+            None => bail!(ErrorKind::UnknownConnection(id)),
+        };
+        Ok(BagMessage {
+            connection,
+            time: records::nanos_time(header.log_time),
+            message: RawMessage(data.to_vec()),
+        })
+    }
+
+    fn load_chunk(&mut self, index: usize) -> Result<Vec<u8>> {
+        self.reader
+            .seek(SeekFrom::Start(self.chunks[index].chunk_start_offset))?;
+        let record = match records::read_record(&mut self.reader)? {
+            Some(record) if record.op == op::CHUNK => record,
+            Some(record) => bail!(ErrorKind::UnexpectedRecord(record.op)),
+            None => bail!(ErrorKind::MissingField("chunk".into())),
+        };
+        let mut fields = Fields::new(&record.data);
+        let _message_start_time = fields.u64("message_start_time")?;
+        let _message_end_time = fields.u64("message_end_time")?;
+        let uncompressed_size = fields.u64("uncompressed_size")?;
+        let uncompressed_crc = fields.u32("uncompressed_crc")?;
+        let compression = Compression::from_name(&fields.string("compression")?)?;
+        let data = fields.long_bytes("records")?.to_vec();
+        let data = compression.decompress(data, uncompressed_size)?;
+        if uncompressed_crc != 0 && crc32fast::hash(&data) != uncompressed_crc {
+            bail!(ErrorKind::BadChecksum("chunk".into()));
+        }
+        Ok(data)
+    }
+}
+
+impl<R> Mcap<R> {
+    pub fn connections(&self) -> impl Iterator<Item = &Arc<Connection>> {
+        self.connections.values()
+    }
+
+    /// Names of all recorded topics, sorted alphabetically.
+    pub fn topics(&self) -> Vec<&str> {
+        let mut topics = self
+            .connections
+            .values()
+            .map(|connection| connection.topic.as_str())
+            .collect::<Vec<_>>();
+        topics.sort_unstable();
+        topics.dedup();
+        topics
+    }
+
+    pub fn start_time(&self) -> Option<Time> {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.message_start_time)
+            .min()
+            .map(records::nanos_time)
+    }
+
+    pub fn end_time(&self) -> Option<Time> {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.message_end_time)
+            .max()
+            .map(records::nanos_time)
+    }
+
+    /// Number of messages in the file, if the summary contains statistics.
+    pub fn message_count(&self) -> Option<u64> {
+        self.statistics
+            .as_ref()
+            .map(|statistics| statistics.message_count)
+    }
+}
+
+/// Iterator over raw messages selected by a query.
+pub struct Messages<'a, R> {
+    mcap: &'a mut Mcap<R>,
+    entries: std::vec::IntoIter<(usize, u64)>,
+    chunk: Option<(usize, Vec<u8>)>,
+}
+
+impl<'a, R: Read + Seek> Messages<'a, R> {
+    /// Decodes all messages into the given type.
+    ///
+    /// Messages of any other type result in errors, so the query should only select topics
+    /// of that type.
+    pub fn typed<T: Message>(self) -> TypedMessages<Self, T> {
+        TypedMessages::new(self)
+    }
+
+    /// Decodes all messages based on the message definitions stored in their schemas.
+    pub fn dynamic(self) -> DynamicMessages<Self> {
+        DynamicMessages::new(self)
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for Messages<'a, R> {
+    type Item = Result<BagMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, offset) = self.entries.next()?;
+        Some(self.mcap.read_message(&mut self.chunk, index, offset))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, R: Read + Seek> ExactSizeIterator for Messages<'a, R> {}
+
+fn check_magic<R: Read>(reader: &mut R) -> Result<()> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        bail!(ErrorKind::UnsupportedVersion(
+            String::from_utf8_lossy(&magic).trim().into()
+        ));
+    }
+    Ok(())
+}
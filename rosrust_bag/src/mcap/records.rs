@@ -0,0 +1,382 @@
+use crate::error::{ErrorKind, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use error_chain::bail;
+use ros_message::Time;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+/// Magic bytes at the start and the end of every MCAP file.
+pub const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+/// Length of the footer record, including its opcode and length.
+pub const FOOTER_LENGTH: u64 = 1 + 8 + 20;
+
+pub const PROFILE: &str = "ros1";
+pub const SCHEMA_ENCODING: &str = "ros1msg";
+pub const MESSAGE_ENCODING: &str = "ros1";
+
+pub mod op {
+    pub const HEADER: u8 = 0x01;
+    pub const FOOTER: u8 = 0x02;
+    pub const SCHEMA: u8 = 0x03;
+    pub const CHANNEL: u8 = 0x04;
+    pub const MESSAGE: u8 = 0x05;
+    pub const CHUNK: u8 = 0x06;
+    pub const MESSAGE_INDEX: u8 = 0x07;
+    pub const CHUNK_INDEX: u8 = 0x08;
+    pub const STATISTICS: u8 = 0x0B;
+    pub const SUMMARY_OFFSET: u8 = 0x0E;
+    pub const DATA_END: u8 = 0x0F;
+}
+
+pub struct Record {
+    pub op: u8,
+    pub data: Vec<u8>,
+}
+
+/// Reads the next record, returning `None` at the end of the stream.
+pub fn read_record<R: Read>(reader: &mut R) -> Result<Option<Record>> {
+    let op = match reader.read_u8() {
+        Ok(op) => op,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let length = reader.read_u64::<LittleEndian>()?;
+    let mut data = vec![];
+    reader.take(length).read_to_end(&mut data)?;
+    if data.len() as u64 != length {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(Some(Record { op, data }))
+}
+
+/// Writes a record, returning the number of bytes written.
+pub fn write_record<W: Write>(writer: &mut W, op: u8, data: &[u8]) -> io::Result<u64> {
+    writer.write_u8(op)?;
+    writer.write_u64::<LittleEndian>(data.len() as u64)?;
+    writer.write_all(data)?;
+    Ok(9 + data.len() as u64)
+}
+
+/// Decoder of the fields in a record's content.
+///
+/// Fields are named, so malformed records can be reported by the field that didn't fit.
+pub struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Fields { data }
+    }
+
+    fn take(&mut self, name: &str, length: usize) -> Result<&'a [u8]> {
+        if length > self.data.len() {
+            bail!(ErrorKind::BadField(name.into()));
+        }
+        let (value, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(value)
+    }
+
+    pub fn u16(&mut self, name: &str) -> Result<u16> {
+        Ok(self.take(name, 2)?.read_u16::<LittleEndian>()?)
+    }
+
+    pub fn u32(&mut self, name: &str) -> Result<u32> {
+        Ok(self.take(name, 4)?.read_u32::<LittleEndian>()?)
+    }
+
+    pub fn u64(&mut self, name: &str) -> Result<u64> {
+        Ok(self.take(name, 8)?.read_u64::<LittleEndian>()?)
+    }
+
+    /// Reads a byte array prefixed by its `u32` length.
+    pub fn bytes(&mut self, name: &str) -> Result<&'a [u8]> {
+        let length = self.u32(name)? as usize;
+        self.take(name, length)
+    }
+
+    /// Reads a byte array prefixed by its `u64` length.
+    pub fn long_bytes(&mut self, name: &str) -> Result<&'a [u8]> {
+        let length = self.u64(name)?;
+        if length > self.data.len() as u64 {
+            bail!(ErrorKind::BadField(name.into()));
+        }
+        self.take(name, length as usize)
+    }
+
+    pub fn string(&mut self, name: &str) -> Result<String> {
+        Ok(String::from_utf8(self.bytes(name)?.to_vec())?)
+    }
+
+    pub fn string_map(&mut self, name: &str) -> Result<BTreeMap<String, String>> {
+        let mut entries = Fields::new(self.bytes(name)?);
+        let mut map = BTreeMap::new();
+        while !entries.data.is_empty() {
+            let key = entries.string(name)?;
+            map.insert(key, entries.string(name)?);
+        }
+        Ok(map)
+    }
+
+    pub fn u16_u64_map(&mut self, name: &str) -> Result<BTreeMap<u16, u64>> {
+        let mut entries = Fields::new(self.bytes(name)?);
+        let mut map = BTreeMap::new();
+        while !entries.data.is_empty() {
+            let key = entries.u16(name)?;
+            map.insert(key, entries.u64(name)?);
+        }
+        Ok(map)
+    }
+
+    #[inline]
+    pub fn rest_is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Takes all remaining data, for fields that span to the end of the record.
+    pub fn rest(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+pub fn put_bytes(output: &mut Vec<u8>, value: &[u8]) {
+    output.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    output.extend_from_slice(value);
+}
+
+#[inline]
+pub fn put_string(output: &mut Vec<u8>, value: &str) {
+    put_bytes(output, value.as_bytes());
+}
+
+pub fn put_string_map(output: &mut Vec<u8>, map: &BTreeMap<String, String>) {
+    let mut entries = vec![];
+    for (key, value) in map {
+        put_string(&mut entries, key);
+        put_string(&mut entries, value);
+    }
+    put_bytes(output, &entries);
+}
+
+pub fn put_u16_u64_map(output: &mut Vec<u8>, map: &BTreeMap<u16, u64>) {
+    let mut entries = Vec::with_capacity(map.len() * 10);
+    for (key, value) in map {
+        entries.extend_from_slice(&key.to_le_bytes());
+        entries.extend_from_slice(&value.to_le_bytes());
+    }
+    put_bytes(output, &entries);
+}
+
+#[derive(Clone, Debug)]
+pub struct Schema {
+    pub id: u16,
+    pub name: String,
+    pub encoding: String,
+    pub data: Vec<u8>,
+}
+
+impl Schema {
+    pub fn parse(data: &[u8]) -> Result<Schema> {
+        let mut fields = Fields::new(data);
+        Ok(Schema {
+            id: fields.u16("id")?,
+            name: fields.string("name")?,
+            encoding: fields.string("encoding")?,
+            data: fields.bytes("data")?.to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = self.id.to_le_bytes().to_vec();
+        put_string(&mut output, &self.name);
+        put_string(&mut output, &self.encoding);
+        put_bytes(&mut output, &self.data);
+        output
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pub id: u16,
+    pub schema_id: u16,
+    pub topic: String,
+    pub message_encoding: String,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Channel {
+    pub fn parse(data: &[u8]) -> Result<Channel> {
+        let mut fields = Fields::new(data);
+        Ok(Channel {
+            id: fields.u16("id")?,
+            schema_id: fields.u16("schema_id")?,
+            topic: fields.string("topic")?,
+            message_encoding: fields.string("message_encoding")?,
+            metadata: fields.string_map("metadata")?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = self.id.to_le_bytes().to_vec();
+        output.extend_from_slice(&self.schema_id.to_le_bytes());
+        put_string(&mut output, &self.topic);
+        put_string(&mut output, &self.message_encoding);
+        put_string_map(&mut output, &self.metadata);
+        output
+    }
+}
+
+/// Message record, with its data left out.
+#[derive(Clone, Copy, Debug)]
+pub struct MessageHeader {
+    pub channel_id: u16,
+    pub sequence: u32,
+    pub log_time: u64,
+    pub publish_time: u64,
+}
+
+impl MessageHeader {
+    pub fn parse(data: &[u8]) -> Result<(MessageHeader, &[u8])> {
+        let mut fields = Fields::new(data);
+        let header = MessageHeader {
+            channel_id: fields.u16("channel_id")?,
+            sequence: fields.u32("sequence")?,
+            log_time: fields.u64("log_time")?,
+            publish_time: fields.u64("publish_time")?,
+        };
+        Ok((header, fields.rest()))
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(22 + data.len());
+        output.extend_from_slice(&self.channel_id.to_le_bytes());
+        output.extend_from_slice(&self.sequence.to_le_bytes());
+        output.extend_from_slice(&self.log_time.to_le_bytes());
+        output.extend_from_slice(&self.publish_time.to_le_bytes());
+        output.extend_from_slice(data);
+        output
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChunkIndex {
+    pub message_start_time: u64,
+    pub message_end_time: u64,
+    pub chunk_start_offset: u64,
+    pub chunk_length: u64,
+    pub message_index_offsets: BTreeMap<u16, u64>,
+    pub message_index_length: u64,
+    pub compression: String,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+impl ChunkIndex {
+    pub fn parse(data: &[u8]) -> Result<ChunkIndex> {
+        let mut fields = Fields::new(data);
+        Ok(ChunkIndex {
+            message_start_time: fields.u64("message_start_time")?,
+            message_end_time: fields.u64("message_end_time")?,
+            chunk_start_offset: fields.u64("chunk_start_offset")?,
+            chunk_length: fields.u64("chunk_length")?,
+            message_index_offsets: fields.u16_u64_map("message_index_offsets")?,
+            message_index_length: fields.u64("message_index_length")?,
+            compression: fields.string("compression")?,
+            compressed_size: fields.u64("compressed_size")?,
+            uncompressed_size: fields.u64("uncompressed_size")?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.extend_from_slice(&self.message_start_time.to_le_bytes());
+        output.extend_from_slice(&self.message_end_time.to_le_bytes());
+        output.extend_from_slice(&self.chunk_start_offset.to_le_bytes());
+        output.extend_from_slice(&self.chunk_length.to_le_bytes());
+        put_u16_u64_map(&mut output, &self.message_index_offsets);
+        output.extend_from_slice(&self.message_index_length.to_le_bytes());
+        put_string(&mut output, &self.compression);
+        output.extend_from_slice(&self.compressed_size.to_le_bytes());
+        output.extend_from_slice(&self.uncompressed_size.to_le_bytes());
+        output
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    pub message_count: u64,
+    pub schema_count: u16,
+    pub channel_count: u32,
+    pub chunk_count: u32,
+    pub message_start_time: u64,
+    pub message_end_time: u64,
+    pub channel_message_counts: BTreeMap<u16, u64>,
+}
+
+impl Statistics {
+    pub fn parse(data: &[u8]) -> Result<Statistics> {
+        let mut fields = Fields::new(data);
+        let message_count = fields.u64("message_count")?;
+        let schema_count = fields.u16("schema_count")?;
+        let channel_count = fields.u32("channel_count")?;
+        let _attachment_count = fields.u32("attachment_count")?;
+        let _metadata_count = fields.u32("metadata_count")?;
+        Ok(Statistics {
+            message_count,
+            schema_count,
+            channel_count,
+            chunk_count: fields.u32("chunk_count")?,
+            message_start_time: fields.u64("message_start_time")?,
+            message_end_time: fields.u64("message_end_time")?,
+            channel_message_counts: fields.u16_u64_map("channel_message_counts")?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.extend_from_slice(&self.message_count.to_le_bytes());
+        output.extend_from_slice(&self.schema_count.to_le_bytes());
+        output.extend_from_slice(&self.channel_count.to_le_bytes());
+        // Attachments and metadata records are never written
+        output.extend_from_slice(&0u32.to_le_bytes());
+        output.extend_from_slice(&0u32.to_le_bytes());
+        output.extend_from_slice(&self.chunk_count.to_le_bytes());
+        output.extend_from_slice(&self.message_start_time.to_le_bytes());
+        output.extend_from_slice(&self.message_end_time.to_le_bytes());
+        put_u16_u64_map(&mut output, &self.channel_message_counts);
+        output
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Footer {
+    pub summary_start: u64,
+    pub summary_offset_start: u64,
+    pub summary_crc: u32,
+}
+
+impl Footer {
+    pub fn parse(data: &[u8]) -> Result<Footer> {
+        let mut fields = Fields::new(data);
+        Ok(Footer {
+            summary_start: fields.u64("summary_start")?,
+            summary_offset_start: fields.u64("summary_offset_start")?,
+            summary_crc: fields.u32("summary_crc")?,
+        })
+    }
+}
+
+/// Converts a time into the nanoseconds since the epoch that MCAP timestamps hold.
+pub fn time_nanos(time: Time) -> u64 {
+    u64::from(time.sec) * 1_000_000_000 + u64::from(time.nsec)
+}
+
+pub fn nanos_time(nanos: u64) -> Time {
+    Time {
+        sec: (nanos / 1_000_000_000) as u32,
+        nsec: (nanos % 1_000_000_000) as u32,
+    }
+}
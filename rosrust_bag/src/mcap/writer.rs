@@ -0,0 +1,375 @@
+use super::records::{
+    self, op, ChunkIndex, MessageHeader, Schema, Statistics, MAGIC, MESSAGE_ENCODING, PROFILE,
+    SCHEMA_ENCODING,
+};
+use super::Compression;
+use crate::connection::Connection;
+use crate::error::{ErrorKind, Result};
+use error_chain::bail;
+use ros_message::Time;
+use rosrust::RosMsg;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Uncompressed chunk size at which chunks get written out.
+pub const DEFAULT_CHUNK_SIZE: usize = 768 * 1024;
+
+/// Writer of MCAP files with ROS1 messages.
+///
+/// The file is written in a single pass, so any writer works, including ones that can't seek.
+/// Messages are collected into chunks, which get compressed and indexed once they reach the
+/// chunk size. The summary section gets written when the file is closed, either explicitly
+/// with `close`, or when the writer is dropped.
+pub struct McapWriter<W: Write> {
+    writer: Option<CountingWriter<W>>,
+    compression: Compression,
+    chunk_size: usize,
+    schemas: Vec<Schema>,
+    channels: Vec<records::Channel>,
+    sequences: Vec<u32>,
+    chunk: ChunkBuffer,
+    chunk_indexes: Vec<ChunkIndex>,
+    statistics: Statistics,
+}
+
+#[derive(Default)]
+struct ChunkBuffer {
+    data: Vec<u8>,
+    index: BTreeMap<u16, Vec<(u64, u64)>>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+}
+
+impl McapWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> McapWriter<W> {
+    pub fn new(writer: W) -> Result<Self> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        let mut header = vec![];
+        records::put_string(&mut header, PROFILE);
+        records::put_string(
+            &mut header,
+            concat!("rosrust_bag ", env!("CARGO_PKG_VERSION")),
+        );
+        records::write_record(&mut writer, op::HEADER, &header)?;
+        Ok(McapWriter {
+            writer: Some(writer),
+            compression: Compression::None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            schemas: vec![],
+            channels: vec![],
+            sequences: vec![],
+            chunk: ChunkBuffer::default(),
+            chunk_indexes: vec![],
+            statistics: Statistics::default(),
+        })
+    }
+
+    #[inline]
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    #[inline]
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size;
+    }
+
+    /// Approximate size of the file, counting buffered messages as uncompressed.
+    pub fn size(&self) -> u64 {
+        let position = self.writer.as_ref().map_or(0, |writer| writer.position);
+        position + self.chunk.data.len() as u64
+    }
+
+    /// Adds a connection that messages can be written to, returning the connection's ID.
+    ///
+    /// Connections become MCAP channels, with the message definition stored as their schema.
+    /// Schemas are shared between connections of the same message type. The ID in the passed
+    /// connection gets ignored.
+    pub fn add_connection(&mut self, connection: Connection) -> Result<u32> {
+        let id = self.channels.len();
+        if id > usize::from(u16::MAX) {
+            bail!("Too many connections for an MCAP file");
+        }
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => unreachable!("Writer is only taken on close"),
+        };
+
+        let definition = connection.message_definition.as_bytes();
+        let existing = self
+            .schemas
+            .iter()
+            .find(|schema| schema.name == connection.message_type && schema.data == definition);
+        let schema_id = match existing {
+            Some(schema) => schema.id,
+            None => {
+                let schema = Schema {
+                    // Schema ID zero is reserved for channels without a schema
+                    id: self.schemas.len() as u16 + 1,
+                    name: connection.message_type.clone(),
+                    encoding: SCHEMA_ENCODING.into(),
+                    data: definition.to_vec(),
+                };
+                records::write_record(writer, op::SCHEMA, &schema.encode())?;
+                self.schemas.push(schema);
+                self.schemas.len() as u16
+            }
+        };
+
+        let mut metadata = BTreeMap::new();
+        metadata.insert("md5sum".into(), connection.md5sum.clone());
+        if let Some(caller_id) = &connection.caller_id {
+            metadata.insert("callerid".into(), caller_id.clone());
+        }
+        if connection.latching {
+            metadata.insert("latching".into(), "1".into());
+        }
+        let channel = records::Channel {
+            id: id as u16,
+            schema_id,
+            topic: connection.topic,
+            message_encoding: MESSAGE_ENCODING.into(),
+            metadata,
+        };
+        records::write_record(writer, op::CHANNEL, &channel.encode())?;
+        self.channels.push(channel);
+        self.sequences.push(0);
+        Ok(id as u32)
+    }
+
+    /// Writes a message received at the given time on the given connection.
+    ///
+    /// Raw messages can be written as well, since their encoding is their data.
+    pub fn write<T: RosMsg>(&mut self, connection: u32, time: Time, message: &T) -> Result<()> {
+        let sequence = match self.sequences.get_mut(connection as usize) {
+            Some(sequence) => sequence,
+            None => bail!(ErrorKind::UnknownConnection(connection)),
+        };
+        let channel_id = connection as u16;
+        let log_time = records::time_nanos(time);
+        let header = MessageHeader {
+            channel_id,
+            sequence: *sequence,
+            log_time,
+            publish_time: log_time,
+        };
+        *sequence = sequence.wrapping_add(1);
+
+        let mut data = vec![];
+        message.encode(&mut data)?;
+        let chunk = &mut self.chunk;
+        chunk
+            .index
+            .entry(channel_id)
+            .or_default()
+            .push((log_time, chunk.data.len() as u64));
+        records::write_record(&mut chunk.data, op::MESSAGE, &header.encode(&data))?;
+        chunk.start_time = Some(
+            chunk
+                .start_time
+                .map_or(log_time, |start| start.min(log_time)),
+        );
+        chunk.end_time = Some(chunk.end_time.map_or(log_time, |end| end.max(log_time)));
+
+        let statistics = &mut self.statistics;
+        if statistics.message_count == 0 || log_time < statistics.message_start_time {
+            statistics.message_start_time = log_time;
+        }
+        statistics.message_end_time = statistics.message_end_time.max(log_time);
+        statistics.message_count += 1;
+        *statistics
+            .channel_message_counts
+            .entry(channel_id)
+            .or_default() += 1;
+
+        if chunk.data.len() >= self.chunk_size {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered messages, the summary, and returns the underlying writer.
+    pub fn close(mut self) -> Result<W> {
+        let result = self.finish();
+        let writer = self.writer.take();
+        result?;
+        match writer {
+            Some(writer) => Ok(writer.inner),
+            None => unreachable!("Writer is only taken on close"),
+        }
+    }
+
+    fn flush_chunk(&mut self) -> Result<()> {
+        let chunk = std::mem::take(&mut self.chunk);
+        let (start_time, end_time) = match (chunk.start_time, chunk.end_time) {
+            (Some(start_time), Some(end_time)) => (start_time, end_time),
+            _ => return Ok(()),
+        };
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let uncompressed_size = chunk.data.len() as u64;
+        let uncompressed_crc = crc32fast::hash(&chunk.data);
+        let data = self.compression.compress(chunk.data)?;
+        let mut record = vec![];
+        record.extend_from_slice(&start_time.to_le_bytes());
+        record.extend_from_slice(&end_time.to_le_bytes());
+        record.extend_from_slice(&uncompressed_size.to_le_bytes());
+        record.extend_from_slice(&uncompressed_crc.to_le_bytes());
+        records::put_string(&mut record, self.compression.name());
+        record.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        record.extend_from_slice(&data);
+        let chunk_start_offset = writer.position;
+        let chunk_length = records::write_record(writer, op::CHUNK, &record)?;
+
+        let mut message_index_offsets = BTreeMap::new();
+        for (channel_id, entries) in &chunk.index {
+            message_index_offsets.insert(*channel_id, writer.position);
+            let mut index = channel_id.to_le_bytes().to_vec();
+            index.extend_from_slice(&((entries.len() * 16) as u32).to_le_bytes());
+            for (log_time, offset) in entries {
+                index.extend_from_slice(&log_time.to_le_bytes());
+                index.extend_from_slice(&offset.to_le_bytes());
+            }
+            records::write_record(writer, op::MESSAGE_INDEX, &index)?;
+        }
+
+        self.chunk_indexes.push(ChunkIndex {
+            message_start_time: start_time,
+            message_end_time: end_time,
+            chunk_start_offset,
+            chunk_length,
+            message_index_offsets,
+            message_index_length: writer.position - chunk_start_offset - chunk_length,
+            compression: self.compression.name().into(),
+            compressed_size: data.len() as u64,
+            uncompressed_size,
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.flush_chunk()?;
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let data_crc = writer.take_crc();
+        records::write_record(writer, op::DATA_END, &data_crc.to_le_bytes())?;
+
+        let summary_start = writer.position;
+        writer.take_crc();
+        let mut offsets = vec![];
+        let mut write_group = |writer: &mut CountingWriter<W>,
+                               group_op: u8,
+                               records: Vec<Vec<u8>>|
+         -> io::Result<()> {
+            if records.is_empty() {
+                return Ok(());
+            }
+            let start = writer.position;
+            for record in records {
+                records::write_record(writer, group_op, &record)?;
+            }
+            offsets.push((group_op, start, writer.position - start));
+            Ok(())
+        };
+        let statistics = Statistics {
+            schema_count: self.schemas.len() as u16,
+            channel_count: self.channels.len() as u32,
+            chunk_count: self.chunk_indexes.len() as u32,
+            ..self.statistics.clone()
+        };
+        write_group(
+            writer,
+            op::SCHEMA,
+            self.schemas.iter().map(Schema::encode).collect(),
+        )?;
+        write_group(
+            writer,
+            op::CHANNEL,
+            self.channels.iter().map(records::Channel::encode).collect(),
+        )?;
+        write_group(writer, op::STATISTICS, vec![statistics.encode()])?;
+        write_group(
+            writer,
+            op::CHUNK_INDEX,
+            self.chunk_indexes.iter().map(ChunkIndex::encode).collect(),
+        )?;
+
+        let summary_offset_start = writer.position;
+        for (group_op, start, length) in offsets {
+            let mut record = vec![group_op];
+            record.extend_from_slice(&start.to_le_bytes());
+            record.extend_from_slice(&length.to_le_bytes());
+            records::write_record(writer, op::SUMMARY_OFFSET, &record)?;
+        }
+
+        // The summary CRC covers the footer up to the CRC itself
+        writer.write_all(&[op::FOOTER])?;
+        writer.write_all(&20u64.to_le_bytes())?;
+        writer.write_all(&summary_start.to_le_bytes())?;
+        writer.write_all(&summary_offset_start.to_le_bytes())?;
+        let summary_crc = writer.take_crc();
+        writer.write_all(&summary_crc.to_le_bytes())?;
+        writer.write_all(MAGIC)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for McapWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            // Errors can only be handled by closing the file explicitly
+            let _ = self.finish();
+        }
+    }
+}
+
+/// Writer that keeps track of its position, and of the CRC of data written since the last
+/// time the CRC was taken.
+struct CountingWriter<W> {
+    inner: W,
+    position: u64,
+    crc: crc32fast::Hasher,
+}
+
+impl<W> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        CountingWriter {
+            inner,
+            position: 0,
+            crc: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn take_crc(&mut self) -> u32 {
+        std::mem::take(&mut self.crc).finalize()
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        self.crc.update(&buf[..written]);
+        Ok(written)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
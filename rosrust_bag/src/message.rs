@@ -0,0 +1,122 @@
+use crate::connection::Connection;
+use crate::error::{ErrorKind, Result};
+use error_chain::bail;
+use ros_message::{MessageValue, Time};
+use rosrust::{DynamicMsg, Message, RawMessage};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Message read from a bag, together with the connection it was recorded from.
+#[derive(Clone, Debug)]
+pub struct BagMessage<T = RawMessage> {
+    pub connection: Arc<Connection>,
+    /// Time at which the message was received by the recorder.
+    pub time: Time,
+    pub message: T,
+}
+
+impl BagMessage {
+    /// Decodes the message into the given type, if the recorded MD5 sum matches it.
+    pub fn decode<T: Message>(self) -> Result<BagMessage<T>> {
+        let md5sum = T::md5sum();
+        if md5sum != "*" && self.connection.md5sum != "*" && md5sum != self.connection.md5sum {
+            bail!(ErrorKind::MismatchedType(
+                self.connection.topic.clone(),
+                self.connection.message_type.clone(),
+                T::msg_type(),
+            ));
+        }
+        let message = T::decode(self.message.0.as_slice())?;
+        Ok(BagMessage {
+            connection: self.connection,
+            time: self.time,
+            message,
+        })
+    }
+
+    /// Decodes the message with a decoder built from the connection's message definition.
+    pub fn decode_dynamic(self, decoder: &DynamicMsg) -> Result<BagMessage<MessageValue>> {
+        let message = decoder.decode(self.message.0.as_slice())?;
+        Ok(BagMessage {
+            connection: self.connection,
+            time: self.time,
+            message,
+        })
+    }
+}
+
+/// Iterator over raw messages, decoded into a message type.
+pub struct TypedMessages<I, T> {
+    messages: I,
+    phantom: PhantomData<T>,
+}
+
+impl<I, T> TypedMessages<I, T> {
+    pub(crate) fn new(messages: I) -> Self {
+        Self {
+            messages,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, T> Iterator for TypedMessages<I, T>
+where
+    I: Iterator<Item = Result<BagMessage>>,
+    T: Message,
+{
+    type Item = Result<BagMessage<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let message = self.messages.next()?;
+        Some(message.and_then(|message| message.decode::<T>()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.messages.size_hint()
+    }
+}
+
+/// Iterator over raw messages, decoded into dynamic message values.
+///
+/// Decoders are built from the message definitions recorded with each connection.
+pub struct DynamicMessages<I> {
+    messages: I,
+    decoders: HashMap<u32, DynamicMsg>,
+}
+
+impl<I> DynamicMessages<I> {
+    pub(crate) fn new(messages: I) -> Self {
+        Self {
+            messages,
+            decoders: HashMap::new(),
+        }
+    }
+}
+
+impl<I> Iterator for DynamicMessages<I>
+where
+    I: Iterator<Item = Result<BagMessage>>,
+{
+    type Item = Result<BagMessage<MessageValue>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoders = &mut self.decoders;
+        let message = self.messages.next()?;
+        Some(message.and_then(|message| {
+            let decoder = match decoders.entry(message.connection.id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(message.connection.dynamic_msg()?),
+            };
+            message.decode_dynamic(decoder)
+        }))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.messages.size_hint()
+    }
+}
@@ -0,0 +1,417 @@
+use crate::error::{ErrorKind, Result};
+use crate::query::Query;
+use crate::reader::Bag;
+use error_chain::bail;
+use ros_message::{Duration, Time};
+use rosrust::msg::rosgraph_msgs::Clock;
+use rosrust::{Publisher, RawMessage};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// Settings of a bag playback.
+#[derive(Clone, Debug)]
+pub struct PlayerOptions {
+    topics: Option<HashSet<String>>,
+    rate: f64,
+    start_offset: Duration,
+    looping: bool,
+    publish_clock: bool,
+    clock_frequency: f64,
+    start_paused: bool,
+    delay: std::time::Duration,
+    queue_size: usize,
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self {
+            topics: None,
+            rate: 1.0,
+            start_offset: Duration::new(),
+            looping: false,
+            publish_clock: false,
+            clock_frequency: 100.0,
+            start_paused: false,
+            delay: std::time::Duration::from_millis(200),
+            queue_size: 100,
+        }
+    }
+}
+
+impl PlayerOptions {
+    /// Creates options for playing all topics at the recorded speed.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a topic to play. Once a topic is added, other topics are skipped.
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topics
+            .get_or_insert_with(HashSet::new)
+            .insert(topic.into());
+        self
+    }
+
+    pub fn topics<I, S>(self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        topics
+            .into_iter()
+            .fold(self, |options, topic| options.topic(topic.as_ref()))
+    }
+
+    /// Multiplies the playback speed, so `2.0` plays twice as fast as recorded.
+    ///
+    /// The rate must be positive, otherwise `Player::start` fails.
+    #[inline]
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Skips the given duration from the start of the bag.
+    #[inline]
+    pub fn start_offset(mut self, start_offset: Duration) -> Self {
+        self.start_offset = start_offset;
+        self
+    }
+
+    /// Starts over once the end of the bag is reached.
+    #[inline]
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Publishes bag time on `/clock`, for nodes running with `/use_sim_time`.
+    #[inline]
+    pub fn publish_clock(mut self, publish_clock: bool) -> Self {
+        self.publish_clock = publish_clock;
+        self
+    }
+
+    /// Frequency of `/clock` messages between played messages, which must be positive.
+    #[inline]
+    pub fn clock_frequency(mut self, clock_frequency: f64) -> Self {
+        self.clock_frequency = clock_frequency;
+        self
+    }
+
+    #[inline]
+    pub fn start_paused(mut self, start_paused: bool) -> Self {
+        self.start_paused = start_paused;
+        self
+    }
+
+    /// Time to wait after advertising topics, so subscribers can connect before playback.
+    #[inline]
+    pub fn delay(mut self, delay: std::time::Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    #[inline]
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+}
+
+/// Plays back a bag through the global node.
+///
+/// Each recorded topic is advertised with its recorded message type, MD5 sum and message
+/// definition, and messages are published with their recorded timing. Playback happens in a
+/// background thread, which stops when the player gets dropped.
+pub struct Player {
+    control: Arc<Control>,
+    thread: Option<thread::JoinHandle<Result<()>>>,
+}
+
+impl Player {
+    pub fn start<R>(mut bag: Bag<R>, options: PlayerOptions) -> Result<Player>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        if !(options.rate > 0.0 && options.rate.is_finite()) {
+            bail!(ErrorKind::InvalidRate(options.rate));
+        }
+        if !(options.clock_frequency > 0.0 && options.clock_frequency.is_finite()) {
+            bail!(ErrorKind::InvalidClockFrequency(options.clock_frequency));
+        }
+
+        let mut query = Query::new();
+        if let Some(topics) = &options.topics {
+            query = query.topics(topics);
+        }
+        if let Some(start_time) = bag.start_time() {
+            query = query.start(start_time + options.start_offset);
+        }
+
+        let mut publishers = HashMap::new();
+        for connection in bag.connections() {
+            if !query.matches_topic(&connection.topic) || publishers.contains_key(&connection.topic)
+            {
+                continue;
+            }
+            let mut publisher = rosrust::publish_with_description::<RawMessage>(
+                &connection.topic,
+                options.queue_size,
+                connection.description(),
+            )?;
+            publisher.set_latching(connection.latching);
+            publishers.insert(connection.topic.clone(), publisher);
+        }
+        let clock = if options.publish_clock {
+            Some(rosrust::publish::<Clock>("/clock", 1)?)
+        } else {
+            None
+        };
+
+        let control = Arc::new(Control {
+            state: Mutex::new(ControlState {
+                paused: options.start_paused,
+                steps: 0,
+                stopped: false,
+                finished: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let playback = Playback {
+            options,
+            query,
+            publishers,
+            clock,
+            control: Arc::clone(&control),
+        };
+        let thread = thread::spawn(move || {
+            let result = playback.run(&mut bag);
+            playback.control.lock().finished = true;
+            playback.control.changed.notify_all();
+            if let Err(err) = &result {
+                log::error!("Bag playback failed: {}", err);
+            }
+            result
+        });
+
+        Ok(Player {
+            control,
+            thread: Some(thread),
+        })
+    }
+
+    #[inline]
+    pub fn pause(&self) {
+        self.control.update(|state| state.paused = true);
+    }
+
+    #[inline]
+    pub fn resume(&self) {
+        self.control.update(|state| state.paused = false);
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.control.lock().paused
+    }
+
+    /// Publishes the next message right away, while paused.
+    #[inline]
+    pub fn step(&self) {
+        self.control.update(|state| state.steps += 1);
+    }
+
+    /// Returns `true` once all messages have been played, or playback failed.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.control.lock().finished
+    }
+
+    /// Waits for playback to finish. With looping enabled, this only happens on failures.
+    pub fn wait(mut self) -> Result<()> {
+        self.join()
+    }
+
+    /// Stops playback, waiting for the currently published message to go out.
+    pub fn stop(mut self) -> Result<()> {
+        self.control.update(|state| state.stopped = true);
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<()> {
+        match self.thread.take() {
+            Some(thread) => match thread.join() {
+                Ok(result) => result,
+                Err(_) => Err("Bag playback thread panicked".into()),
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.control.update(|state| state.stopped = true);
+        // Failures are already logged by the playback thread
+        let _ = self.join();
+    }
+}
+
+struct Control {
+    state: Mutex<ControlState>,
+    changed: Condvar,
+}
+
+struct ControlState {
+    paused: bool,
+    steps: usize,
+    stopped: bool,
+    finished: bool,
+}
+
+impl Control {
+    fn lock(&self) -> MutexGuard<'_, ControlState> {
+        self.state.lock().expect(FAILED_TO_LOCK)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ControlState)) {
+        f(&mut self.lock());
+        self.changed.notify_all();
+    }
+}
+
+/// Mapping between bag time and wall time, at the given playback rate.
+struct Timeline {
+    bag_start: Time,
+    wall_start: Instant,
+    rate: f64,
+}
+
+impl Timeline {
+    fn wall_time(&self, time: Time) -> Instant {
+        let offset = (time - self.bag_start).seconds() / self.rate;
+        self.wall_start + std::time::Duration::from_secs_f64(offset.max(0.0))
+    }
+
+    fn bag_time(&self, wall_time: Instant) -> Time {
+        let offset = wall_time.saturating_duration_since(self.wall_start);
+        self.bag_start + Duration::from_nanos((offset.as_nanos() as f64 * self.rate) as i64)
+    }
+}
+
+struct Playback {
+    options: PlayerOptions,
+    query: Query,
+    publishers: HashMap<String, Publisher<RawMessage>>,
+    clock: Option<Publisher<Clock>>,
+    control: Arc<Control>,
+}
+
+impl Playback {
+    fn run<R: Read + Seek>(&self, bag: &mut Bag<R>) -> Result<()> {
+        thread::sleep(self.options.delay);
+        loop {
+            let mut timeline: Option<Timeline> = None;
+            // Bag time reached before pausing, so playback can resume from there
+            let mut paused_at: Option<Time> = None;
+            for message in bag.read(&self.query)? {
+                let message = message?;
+                let timeline = timeline.get_or_insert_with(|| Timeline {
+                    bag_start: message.time,
+                    wall_start: Instant::now(),
+                    rate: self.options.rate,
+                });
+                if !self.wait_for(message.time, timeline, &mut paused_at) {
+                    return Ok(());
+                }
+                self.publish_clock(message.time);
+                if let Some(publisher) = self.publishers.get(&message.connection.topic) {
+                    publisher.send(message.message)?;
+                }
+            }
+            if !self.options.looping || timeline.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Waits until it's time to publish a message, returning `false` if playback got stopped.
+    fn wait_for(&self, time: Time, timeline: &mut Timeline, paused_at: &mut Option<Time>) -> bool {
+        let clock_period = std::time::Duration::from_secs_f64(1.0 / self.options.clock_frequency);
+        let mut state = self.control.lock();
+        loop {
+            if state.stopped {
+                return false;
+            }
+            let now = Instant::now();
+            if state.paused {
+                let paused_time =
+                    *paused_at.get_or_insert_with(|| timeline.bag_time(now).min(time));
+                if state.steps > 0 {
+                    state.steps -= 1;
+                    *paused_at = Some(time);
+                    return true;
+                }
+                drop(state);
+                self.publish_clock(paused_time);
+                state = self.control.lock();
+                if !state.paused || state.steps > 0 || state.stopped {
+                    continue;
+                }
+                state = self.wait(state, clock_period);
+                continue;
+            }
+            if let Some(paused_time) = paused_at.take() {
+                *timeline = Timeline {
+                    bag_start: paused_time,
+                    wall_start: now,
+                    rate: self.options.rate,
+                };
+            }
+
+            let target = timeline.wall_time(time);
+            if now >= target {
+                return true;
+            }
+            drop(state);
+            self.publish_clock(timeline.bag_time(now));
+            state = self.control.lock();
+            if state.paused || state.stopped {
+                continue;
+            }
+            let timeout = if self.clock.is_some() {
+                (target - now).min(clock_period)
+            } else {
+                target - now
+            };
+            state = self.wait(state, timeout);
+        }
+    }
+
+    fn wait<'a>(
+        &self,
+        state: MutexGuard<'a, ControlState>,
+        timeout: std::time::Duration,
+    ) -> MutexGuard<'a, ControlState> {
+        self.control
+            .changed
+            .wait_timeout(state, timeout)
+            .expect(FAILED_TO_LOCK)
+            .0
+    }
+
+    fn publish_clock(&self, time: Time) {
+        if let Some(clock) = &self.clock {
+            if let Err(err) = clock.send(Clock { clock: time }) {
+                log::error!("Failed to publish clock: {}", err);
+            }
+        }
+    }
+}
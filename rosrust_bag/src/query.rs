@@ -0,0 +1,66 @@
+use ros_message::Time;
+use std::collections::HashSet;
+
+/// Selection of messages to read from a bag.
+///
+/// By default, all messages are selected. Time bounds are inclusive.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    topics: Option<HashSet<String>>,
+    start: Option<Time>,
+    end: Option<Time>,
+}
+
+impl Query {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a topic to the selection. Once a topic is added, other topics are skipped.
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topics
+            .get_or_insert_with(HashSet::new)
+            .insert(topic.into());
+        self
+    }
+
+    pub fn topics<I, S>(self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        topics
+            .into_iter()
+            .fold(self, |query, topic| query.topic(topic.as_ref()))
+    }
+
+    #[inline]
+    pub fn start(mut self, start: Time) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    #[inline]
+    pub fn end(mut self, end: Time) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn matches_topic(&self, topic: &str) -> bool {
+        match &self.topics {
+            Some(topics) => topics.contains(topic),
+            None => true,
+        }
+    }
+
+    pub fn matches_time(&self, time: Time) -> bool {
+        !matches!(self.start, Some(start) if time < start)
+            && !matches!(self.end, Some(end) if time > end)
+    }
+
+    /// Returns `true` if the given time range overlaps the selected time range.
+    pub fn overlaps(&self, start: Time, end: Time) -> bool {
+        !matches!(self.start, Some(s) if end < s) && !matches!(self.end, Some(e) if start > e)
+    }
+}
@@ -0,0 +1,272 @@
+use crate::chunk::{self, ChunkInfo, IndexEntry};
+use crate::connection::Connection;
+use crate::error::{ErrorKind, Result};
+use crate::message::{BagMessage, DynamicMessages, TypedMessages};
+use crate::query::Query;
+use crate::record::{self, op, Header};
+use error_chain::bail;
+use ros_message::Time;
+use rosrust::{Message, RawMessage};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+/// First line of every bag file in the supported format version.
+pub(crate) const MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+
+/// Reader of bag files in the 2.0 format.
+///
+/// Opening a bag only loads its index section. Messages are read chunk by chunk, as queries get
+/// iterated over, so large bags can be processed without loading them into memory.
+pub struct Bag<R> {
+    reader: R,
+    connections: BTreeMap<u32, Arc<Connection>>,
+    chunks: Vec<ChunkInfo>,
+}
+
+impl Bag<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> Bag<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut version = vec![0; MAGIC.len()];
+        reader.read_exact(&mut version)?;
+        if version != MAGIC {
+            bail!(ErrorKind::UnsupportedVersion(
+                String::from_utf8_lossy(&version).trim().into()
+            ));
+        }
+
+        let header = match record::read_record(&mut reader)? {
+            Some(record) => record.header,
+            None => bail!(ErrorKind::MissingField("op".into())),
+        };
+        if header.op()? != op::BAG_HEADER {
+            bail!(ErrorKind::UnexpectedRecord(header.op()?));
+        }
+        let index_position = header.u64("index_pos")?;
+        if index_position == 0 {
+            bail!(ErrorKind::Unindexed);
+        }
+
+        let mut connections = BTreeMap::new();
+        let mut chunks = vec![];
+        reader.seek(SeekFrom::Start(index_position))?;
+        while let Some(record) = record::read_record(&mut reader)? {
+            match record.header.op()? {
+                op::CONNECTION => {
+                    let connection = Connection::from_record(&record)?;
+                    connections.insert(connection.id, Arc::new(connection));
+                }
+                op::CHUNK_INFO => chunks.push(ChunkInfo::from_record(&record)?),
+                op => bail!(ErrorKind::UnexpectedRecord(op)),
+            }
+        }
+        chunks.sort_by_key(|chunk| chunk.position);
+
+        Ok(Bag {
+            reader,
+            connections,
+            chunks,
+        })
+    }
+
+    /// Reads the messages selected by the query, in order of their recording time.
+    pub fn read(&mut self, query: &Query) -> Result<Messages<'_, R>> {
+        let selected = self
+            .connections
+            .values()
+            .filter(|connection| query.matches_topic(&connection.topic))
+            .map(|connection| connection.id)
+            .collect::<HashSet<u32>>();
+
+        let mut entries = vec![];
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let has_selected = chunk.message_counts.keys().any(|id| selected.contains(id));
+            if !has_selected || !query.overlaps(chunk.start_time, chunk.end_time) {
+                continue;
+            }
+            // Index records for each connection in the chunk follow right after it
+            self.reader.seek(SeekFrom::Start(chunk.position))?;
+            record::skip_record(&mut self.reader)?;
+            for _ in 0..chunk.message_counts.len() {
+                let record = match record::read_record(&mut self.reader)? {
+                    Some(record) => record,
+                    None => break,
+                };
+                if record.header.op()? != op::INDEX_DATA {
+                    break;
+                }
+                entries.extend(
+                    chunk::read_index(&record)?
+                        .into_iter()
+                        .filter(|entry| {
+                            selected.contains(&entry.connection) && query.matches_time(entry.time)
+                        })
+                        .map(|entry| (index, entry)),
+                );
+            }
+        }
+        entries.sort_by_key(|(index, entry)| (entry.time, *index, entry.offset));
+
+        let mut remaining = HashMap::new();
+        for (index, _) in &entries {
+            *remaining.entry(*index).or_default() += 1;
+        }
+        Ok(Messages {
+            bag: self,
+            entries: entries.into_iter(),
+            chunks: LoadedChunks {
+                remaining,
+                data: HashMap::new(),
+            },
+        })
+    }
+
+    fn read_message(
+        &mut self,
+        chunks: &mut LoadedChunks,
+        index: usize,
+        entry: IndexEntry,
+    ) -> Result<BagMessage> {
+        let data = match chunks.data.entry(index) {
+            Entry::Occupied(chunk) => chunk.into_mut(),
+            Entry::Vacant(chunk) => chunk.insert(self.load_chunk(index)?),
+        };
+        let mut reader = data.get(entry.offset as usize..).unwrap_or_default();
+        let record = record::read_record(&mut reader)?;
+        chunks.release(index);
+
+        let record = match record {
+            Some(record) => record,
+            None => bail!(ErrorKind::MissingField("op".into())),
+        };
+        if record.header.op()? != op::MESSAGE_DATA {
+            bail!(ErrorKind::UnexpectedRecord(record.header.op()?));
+        }
+        let connection = self.connection_of(&record.header)?;
+        Ok(BagMessage {
+            connection,
+            time: record.header.time("time")?,
+            message: RawMessage(record.data),
+        })
+    }
+
+    fn load_chunk(&mut self, index: usize) -> Result<Vec<u8>> {
+        self.reader
+            .seek(SeekFrom::Start(self.chunks[index].position))?;
+        match record::read_record(&mut self.reader)? {
+            Some(record) => chunk::decompress(record),
+            None => bail!(ErrorKind::MissingField("op".into())),
+        }
+    }
+
+    fn connection_of(&self, header: &Header) -> Result<Arc<Connection>> {
+        let id = header.u32("conn")?;
+        match self.connections.get(&id) {
+            Some(connection) => Ok(Arc::clone(connection)),
+            None => bail!(ErrorKind::UnknownConnection(id)),
+        }
+    }
+}
+
+impl<R> Bag<R> {
+    pub fn connections(&self) -> impl Iterator<Item = &Arc<Connection>> {
+        self.connections.values()
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> &[ChunkInfo] {
+        &self.chunks
+    }
+
+    /// Names of all recorded topics, sorted alphabetically.
+    pub fn topics(&self) -> Vec<&str> {
+        let mut topics = self
+            .connections
+            .values()
+            .map(|connection| connection.topic.as_str())
+            .collect::<Vec<_>>();
+        topics.sort_unstable();
+        topics.dedup();
+        topics
+    }
+
+    pub fn start_time(&self) -> Option<Time> {
+        self.chunks.iter().map(|chunk| chunk.start_time).min()
+    }
+
+    pub fn end_time(&self) -> Option<Time> {
+        self.chunks.iter().map(|chunk| chunk.end_time).max()
+    }
+
+    pub fn message_count(&self) -> u64 {
+        self.chunks.iter().map(ChunkInfo::message_count).sum()
+    }
+}
+
+/// Decompressed chunks that selected messages are still read from.
+///
+/// Chunks can overlap in time, so reading in time order can alternate between them. Each chunk
+/// stays loaded until its last selected message is read, so it only gets decompressed once.
+struct LoadedChunks {
+    /// Number of selected messages left to read, per chunk index.
+    remaining: HashMap<usize, usize>,
+    data: HashMap<usize, Vec<u8>>,
+}
+
+impl LoadedChunks {
+    fn release(&mut self, index: usize) {
+        if let Some(count) = self.remaining.get_mut(&index) {
+            *count -= 1;
+            if *count == 0 {
+                self.remaining.remove(&index);
+                self.data.remove(&index);
+            }
+        }
+    }
+}
+
+/// Iterator over raw messages selected by a query.
+pub struct Messages<'a, R> {
+    bag: &'a mut Bag<R>,
+    entries: std::vec::IntoIter<(usize, IndexEntry)>,
+    chunks: LoadedChunks,
+}
+
+impl<'a, R: Read + Seek> Messages<'a, R> {
+    /// Decodes all messages into the given type.
+    ///
+    /// Messages of any other type result in errors, so the query should only select topics
+    /// of that type.
+    pub fn typed<T: Message>(self) -> TypedMessages<Self, T> {
+        TypedMessages::new(self)
+    }
+
+    /// Decodes all messages based on the message definitions recorded with their connections.
+    pub fn dynamic(self) -> DynamicMessages<Self> {
+        DynamicMessages::new(self)
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for Messages<'a, R> {
+    type Item = Result<BagMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, entry) = self.entries.next()?;
+        Some(self.bag.read_message(&mut self.chunks, index, entry))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, R: Read + Seek> ExactSizeIterator for Messages<'a, R> {}
@@ -0,0 +1,201 @@
+use crate::error::{ErrorKind, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use error_chain::bail;
+use ros_message::Time;
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+pub mod op {
+    pub const MESSAGE_DATA: u8 = 0x02;
+    pub const BAG_HEADER: u8 = 0x03;
+    pub const INDEX_DATA: u8 = 0x04;
+    pub const CHUNK: u8 = 0x05;
+    pub const CHUNK_INFO: u8 = 0x06;
+    pub const CONNECTION: u8 = 0x07;
+}
+
+/// Fields of a record header, or of any other `name=value` field list.
+#[derive(Clone, Debug, Default)]
+pub struct Header {
+    fields: HashMap<String, Vec<u8>>,
+}
+
+impl Header {
+    pub fn parse(mut data: &[u8]) -> Result<Header> {
+        let mut fields = HashMap::new();
+        while !data.is_empty() {
+            let length = data.read_u32::<LittleEndian>()? as usize;
+            if length > data.len() {
+                bail!(ErrorKind::BadField("<field length>".into()));
+            }
+            let (field, rest) = data.split_at(length);
+            data = rest;
+            let separator = match field.iter().position(|&c| c == b'=') {
+                Some(separator) => separator,
+                None => bail!(ErrorKind::BadField(
+                    String::from_utf8_lossy(field).into_owned()
+                )),
+            };
+            let name = String::from_utf8(field[..separator].to_vec())?;
+            fields.insert(name, field[separator + 1..].to_vec());
+        }
+        Ok(Header { fields })
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.fields.get(name).map(Vec::as_slice)
+    }
+
+    pub fn field(&self, name: &str) -> Result<&[u8]> {
+        match self.get(name) {
+            Some(value) => Ok(value),
+            None => bail!(ErrorKind::MissingField(name.into())),
+        }
+    }
+
+    pub fn op(&self) -> Result<u8> {
+        match self.field("op")? {
+            [op] => Ok(*op),
+            _ => bail!(ErrorKind::BadField("op".into())),
+        }
+    }
+
+    pub fn u32(&self, name: &str) -> Result<u32> {
+        let mut value = self.field(name)?;
+        if value.len() != 4 {
+            bail!(ErrorKind::BadField(name.into()));
+        }
+        Ok(value.read_u32::<LittleEndian>()?)
+    }
+
+    pub fn u64(&self, name: &str) -> Result<u64> {
+        let mut value = self.field(name)?;
+        if value.len() != 8 {
+            bail!(ErrorKind::BadField(name.into()));
+        }
+        Ok(value.read_u64::<LittleEndian>()?)
+    }
+
+    pub fn time(&self, name: &str) -> Result<Time> {
+        let mut value = self.field(name)?;
+        if value.len() != 8 {
+            bail!(ErrorKind::BadField(name.into()));
+        }
+        read_time(&mut value).map_err(Into::into)
+    }
+
+    pub fn string(&self, name: &str) -> Result<String> {
+        Ok(String::from_utf8(self.field(name)?.to_vec())?)
+    }
+
+    pub fn optional_string(&self, name: &str) -> Result<Option<String>> {
+        match self.get(name) {
+            Some(value) => Ok(Some(String::from_utf8(value.to_vec())?)),
+            None => Ok(None),
+        }
+    }
+}
+
+pub struct Record {
+    pub header: Header,
+    pub data: Vec<u8>,
+}
+
+/// Reads the next record, returning `None` at the end of the stream.
+pub fn read_record<R: Read>(reader: &mut R) -> Result<Option<Record>> {
+    let header = match read_header(reader)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let data = read_block(reader)?;
+    Ok(Some(Record { header, data }))
+}
+
+/// Reads the header of the next record and skips over its data.
+pub fn skip_record<R: Read + Seek>(reader: &mut R) -> Result<Option<Header>> {
+    let header = match read_header(reader)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let data_length = reader.read_u32::<LittleEndian>()?;
+    reader.seek(SeekFrom::Current(i64::from(data_length)))?;
+    Ok(Some(header))
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<Option<Header>> {
+    let length = match reader.read_u32::<LittleEndian>() {
+        Ok(length) => length,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let data = read_exact_length(reader, u64::from(length))?;
+    Header::parse(&data).map(Some)
+}
+
+fn read_block<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let length = reader.read_u32::<LittleEndian>()?;
+    read_exact_length(reader, u64::from(length))
+}
+
+/// Reads exactly the given number of bytes.
+///
+/// Lengths come straight from the file, so the data grows as it is read, instead of the whole
+/// length being allocated up front.
+fn read_exact_length<R: Read>(reader: &mut R, length: u64) -> io::Result<Vec<u8>> {
+    let mut data = vec![];
+    reader.take(length).read_to_end(&mut data)?;
+    if (data.len() as u64) < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+/// Reads at most one byte more than expected, which is enough to detect oversized data.
+pub(crate) fn read_limited(reader: impl Read, size: u64) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    reader
+        .take(size.saturating_add(1))
+        .read_to_end(&mut output)?;
+    Ok(output)
+}
+
+pub fn read_time<R: Read>(reader: &mut R) -> io::Result<Time> {
+    let sec = reader.read_u32::<LittleEndian>()?;
+    let nsec = reader.read_u32::<LittleEndian>()?;
+    Ok(Time { sec, nsec })
+}
+
+/// Encodes a list of `name=value` fields, as used by record headers.
+pub fn encode_fields(fields: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut output = vec![];
+    for (name, value) in fields {
+        let length = name.len() + 1 + value.len();
+        output.extend_from_slice(&(length as u32).to_le_bytes());
+        output.extend_from_slice(name.as_bytes());
+        output.push(b'=');
+        output.extend_from_slice(value);
+    }
+    output
+}
+
+/// Writes a record, returning the number of bytes written.
+pub fn write_record<W: Write>(
+    writer: &mut W,
+    fields: &[(&str, &[u8])],
+    data: &[u8],
+) -> io::Result<u64> {
+    let header = encode_fields(fields);
+    writer.write_u32::<LittleEndian>(header.len() as u32)?;
+    writer.write_all(&header)?;
+    writer.write_u32::<LittleEndian>(data.len() as u32)?;
+    writer.write_all(data)?;
+    Ok(8 + header.len() as u64 + data.len() as u64)
+}
+
+pub fn time_bytes(time: Time) -> [u8; 8] {
+    let mut output = [0; 8];
+    output[..4].copy_from_slice(&time.sec.to_le_bytes());
+    output[4..].copy_from_slice(&time.nsec.to_le_bytes());
+    output
+}
@@ -0,0 +1,415 @@
+use crate::compression::Compression;
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::writer::{BagWriter, DEFAULT_CHUNK_THRESHOLD};
+use regex::Regex;
+use ros_message::{Duration, Time};
+use rosrust::{RawMessage, Subscriber};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// Settings of a bag recording.
+#[derive(Clone, Debug)]
+pub struct RecorderOptions {
+    path: PathBuf,
+    topics: Vec<String>,
+    topic_regex: Option<Regex>,
+    all_topics: bool,
+    compression: Compression,
+    chunk_threshold: usize,
+    split_size: Option<u64>,
+    split_duration: Option<Duration>,
+    discovery_period: std::time::Duration,
+    queue_size: usize,
+}
+
+impl RecorderOptions {
+    /// Creates options for recording into the given file, with no topics selected.
+    ///
+    /// If the recording gets split, the files get numbered, so `out.bag` would be split
+    /// into `out_0.bag`, `out_1.bag`, and so on.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().into(),
+            topics: vec![],
+            topic_regex: None,
+            all_topics: false,
+            compression: Compression::None,
+            chunk_threshold: DEFAULT_CHUNK_THRESHOLD,
+            split_size: None,
+            split_duration: None,
+            discovery_period: std::time::Duration::from_secs(1),
+            queue_size: 100,
+        }
+    }
+
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topics.push(topic.into());
+        self
+    }
+
+    pub fn topics<I, S>(self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        topics
+            .into_iter()
+            .fold(self, |options, topic| options.topic(topic.as_ref()))
+    }
+
+    /// Records all published topics with names matching the regex.
+    ///
+    /// New topics are discovered by polling the master.
+    #[inline]
+    pub fn topic_regex(mut self, regex: Regex) -> Self {
+        self.topic_regex = Some(regex);
+        self
+    }
+
+    /// Records all published topics, discovered by polling the master.
+    #[inline]
+    pub fn all_topics(mut self) -> Self {
+        self.all_topics = true;
+        self
+    }
+
+    #[inline]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    #[inline]
+    pub fn chunk_threshold(mut self, chunk_threshold: usize) -> Self {
+        self.chunk_threshold = chunk_threshold;
+        self
+    }
+
+    /// Starts a new file once the current one reaches the given size in bytes.
+    #[inline]
+    pub fn split_size(mut self, split_size: u64) -> Self {
+        self.split_size = Some(split_size);
+        self
+    }
+
+    /// Starts a new file once the current one spans the given duration.
+    #[inline]
+    pub fn split_duration(mut self, split_duration: Duration) -> Self {
+        self.split_duration = Some(split_duration);
+        self
+    }
+
+    /// Time between polls of the master for new topics.
+    #[inline]
+    pub fn discovery_period(mut self, discovery_period: std::time::Duration) -> Self {
+        self.discovery_period = discovery_period;
+        self
+    }
+
+    #[inline]
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    fn is_split(&self) -> bool {
+        self.split_size.is_some() || self.split_duration.is_some()
+    }
+
+    fn is_discovering(&self) -> bool {
+        self.all_topics || self.topic_regex.is_some()
+    }
+
+    fn matches(&self, topic: &str) -> bool {
+        self.all_topics
+            || self
+                .topic_regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(topic))
+    }
+
+    fn file_path(&self, index: usize) -> PathBuf {
+        if !self.is_split() {
+            return self.path.clone();
+        }
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut file_name = format!("{}_{}", stem, index);
+        if let Some(extension) = self.path.extension() {
+            file_name.push('.');
+            file_name.push_str(&extension.to_string_lossy());
+        }
+        self.path.with_file_name(file_name)
+    }
+}
+
+/// Records live topics into bag files, using the global node.
+///
+/// Messages are recorded as raw data, with their types, MD5 sums and message definitions
+/// taken from the headers of each publisher connection. Every publisher of a topic gets its
+/// own connection in the bag, and messages are stamped with the node's time of receiving them.
+///
+/// Recording stops when the recorder gets dropped or stopped.
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+    subscribers: Arc<Mutex<HashMap<String, Subscriber>>>,
+    discovery: Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>,
+}
+
+impl Recorder {
+    pub fn start(options: RecorderOptions) -> Result<Recorder> {
+        let options = Arc::new(options);
+        let mut state = RecorderState {
+            options: Arc::clone(&options),
+            writer: None,
+            file_start: None,
+            paths: vec![],
+            connections: HashMap::new(),
+            connection_ids: HashMap::new(),
+            pending: HashMap::new(),
+        };
+        state.open_next()?;
+        let state = Arc::new(Mutex::new(state));
+
+        let subscribers = Arc::new(Mutex::new(HashMap::new()));
+        for topic in &options.topics {
+            subscribe_topic(&state, &subscribers, &options, topic)?;
+        }
+
+        let mut discovery = None;
+        if options.is_discovering() {
+            discover_topics(&state, &subscribers, &options)?;
+            let (stop_tx, stop_rx) = mpsc::channel();
+            let thread_state = Arc::clone(&state);
+            let thread_subscribers = Arc::clone(&subscribers);
+            let thread = thread::spawn(move || {
+                // Stop on either a stop request or the recorder going away
+                while let Err(RecvTimeoutError::Timeout) =
+                    stop_rx.recv_timeout(options.discovery_period)
+                {
+                    if let Err(err) = discover_topics(&thread_state, &thread_subscribers, &options)
+                    {
+                        log::error!("Failed to discover topics for recording: {}", err);
+                    }
+                }
+            });
+            discovery = Some((stop_tx, thread));
+        }
+
+        Ok(Recorder {
+            state,
+            subscribers,
+            discovery,
+        })
+    }
+
+    /// Names of the topics being recorded.
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics = self
+            .subscribers
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        topics.sort();
+        topics
+    }
+
+    /// Files written by the recording so far, including the one being written to.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.state.lock().expect(FAILED_TO_LOCK).paths.clone()
+    }
+
+    /// Stops recording and closes the current file, returning all files that got written.
+    pub fn stop(mut self) -> Result<Vec<PathBuf>> {
+        self.stop_recording()?;
+        Ok(self.paths())
+    }
+
+    fn stop_recording(&mut self) -> Result<()> {
+        if let Some((stop_tx, thread)) = self.discovery.take() {
+            // The thread stops on either a message or a disconnect, so errors are irrelevant
+            let _ = stop_tx.send(());
+            if thread.join().is_err() {
+                log::error!("Topic discovery thread panicked");
+            }
+        }
+        self.subscribers.lock().expect(FAILED_TO_LOCK).clear();
+        self.state.lock().expect(FAILED_TO_LOCK).close()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop_recording() {
+            log::error!("Failed to close recording: {}", err);
+        }
+    }
+}
+
+fn discover_topics(
+    state: &Arc<Mutex<RecorderState>>,
+    subscribers: &Arc<Mutex<HashMap<String, Subscriber>>>,
+    options: &RecorderOptions,
+) -> Result<()> {
+    let topics = rosrust::topics().map_err(|err| format!("Failed to fetch topics: {}", err))?;
+    for topic in topics {
+        if options.matches(&topic.name) {
+            subscribe_topic(state, subscribers, options, &topic.name)?;
+        }
+    }
+    Ok(())
+}
+
+fn subscribe_topic(
+    state: &Arc<Mutex<RecorderState>>,
+    subscribers: &Arc<Mutex<HashMap<String, Subscriber>>>,
+    options: &RecorderOptions,
+    topic: &str,
+) -> Result<()> {
+    let mut subscribers = subscribers.lock().expect(FAILED_TO_LOCK);
+    if subscribers.contains_key(topic) {
+        return Ok(());
+    }
+
+    let message_state = Arc::clone(state);
+    let message_topic = topic.to_owned();
+    let connection_state = Arc::clone(state);
+    let connection_topic = topic.to_owned();
+    let subscriber = rosrust::subscribe_with_ids_and_headers::<RawMessage, _, _>(
+        topic,
+        options.queue_size,
+        move |message, caller_id| {
+            let time = rosrust::now();
+            let mut state = message_state.lock().expect(FAILED_TO_LOCK);
+            if let Err(err) = state.record(&message_topic, caller_id, time, message) {
+                log::error!("Failed to record message on {}: {}", message_topic, err);
+            }
+        },
+        move |headers| {
+            let mut state = connection_state.lock().expect(FAILED_TO_LOCK);
+            if let Err(err) = state.connect(&connection_topic, &headers) {
+                log::error!(
+                    "Failed to record connection on {}: {}",
+                    connection_topic,
+                    err
+                );
+            }
+        },
+    )?;
+    subscribers.insert(topic.into(), subscriber);
+    Ok(())
+}
+
+/// Publisher connection, identified by its topic and caller ID.
+type ConnectionKey = (String, String);
+
+struct RecorderState {
+    options: Arc<RecorderOptions>,
+    writer: Option<BagWriter<BufWriter<File>>>,
+    file_start: Option<Time>,
+    paths: Vec<PathBuf>,
+    connections: HashMap<ConnectionKey, Connection>,
+    /// IDs of connections within the current file.
+    connection_ids: HashMap<ConnectionKey, u32>,
+    /// Messages that arrived before the headers of their connection.
+    pending: HashMap<ConnectionKey, Vec<(Time, RawMessage)>>,
+}
+
+impl RecorderState {
+    fn connect(&mut self, topic: &str, headers: &HashMap<String, String>) -> Result<()> {
+        let connection = Connection::from_headers(topic, headers)?;
+        let caller_id = connection.caller_id.clone().unwrap_or_default();
+        let key = (topic.to_owned(), caller_id);
+        self.connection_ids.remove(&key);
+        self.connections.insert(key.clone(), connection);
+        for (time, message) in self.pending.remove(&key).unwrap_or_default() {
+            self.write(&key, time, &message)?;
+        }
+        Ok(())
+    }
+
+    fn record(
+        &mut self,
+        topic: &str,
+        caller_id: &str,
+        time: Time,
+        message: RawMessage,
+    ) -> Result<()> {
+        let key = (topic.to_owned(), caller_id.to_owned());
+        if !self.connections.contains_key(&key) {
+            self.pending.entry(key).or_default().push((time, message));
+            return Ok(());
+        }
+        self.write(&key, time, &message)
+    }
+
+    fn write(&mut self, key: &ConnectionKey, time: Time, message: &RawMessage) -> Result<()> {
+        if self.should_split(time) {
+            self.close()?;
+            self.open_next()?;
+        }
+        self.file_start.get_or_insert(time);
+
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let id = match self.connection_ids.get(key) {
+            Some(id) => *id,
+            None => {
+                let id = writer.add_connection(self.connections[key].clone());
+                self.connection_ids.insert(key.clone(), id);
+                id
+            }
+        };
+        writer.write(id, time, message)
+    }
+
+    fn should_split(&self, time: Time) -> bool {
+        let writer = match &self.writer {
+            Some(writer) => writer,
+            None => return false,
+        };
+        let too_large = self
+            .options
+            .split_size
+            .is_some_and(|split_size| writer.size() >= split_size);
+        let too_long = match (self.options.split_duration, self.file_start) {
+            (Some(split_duration), Some(file_start)) => time - file_start >= split_duration,
+            _ => false,
+        };
+        too_large || too_long
+    }
+
+    fn open_next(&mut self) -> Result<()> {
+        let path = self.options.file_path(self.paths.len());
+        let mut writer = BagWriter::create(&path)?;
+        writer.set_compression(self.options.compression);
+        writer.set_chunk_threshold(self.options.chunk_threshold);
+        self.writer = Some(writer);
+        self.file_start = None;
+        self.connection_ids.clear();
+        self.paths.push(path);
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        match self.writer.take() {
+            Some(writer) => writer.close().map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
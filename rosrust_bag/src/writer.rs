@@ -0,0 +1,267 @@
+use crate::chunk::ChunkInfo;
+use crate::compression::Compression;
+use crate::connection::Connection;
+use crate::error::{ErrorKind, Result};
+use crate::reader::MAGIC;
+use crate::record::{self, op};
+use error_chain::bail;
+use ros_message::Time;
+use rosrust::RosMsg;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Uncompressed chunk size at which chunks get written out, matching `rosbag record`.
+pub const DEFAULT_CHUNK_THRESHOLD: usize = 768 * 1024;
+
+/// Total size of the bag header record, which gets overwritten once the bag is closed.
+const BAG_HEADER_LENGTH: usize = 4096;
+
+/// Writer of bag files in the 2.0 format.
+///
+/// Messages are collected into chunks, which get compressed and indexed once they reach the
+/// chunk threshold. The index section gets written when the bag is closed, either explicitly
+/// with `close`, or when the writer is dropped.
+pub struct BagWriter<W: Write + Seek> {
+    writer: Option<W>,
+    position: u64,
+    compression: Compression,
+    chunk_threshold: usize,
+    connections: Vec<Connection>,
+    chunk: ChunkBuffer,
+    chunk_infos: Vec<ChunkInfo>,
+}
+
+#[derive(Default)]
+struct ChunkBuffer {
+    data: Vec<u8>,
+    index: BTreeMap<u32, Vec<(Time, u32)>>,
+    start_time: Option<Time>,
+    end_time: Option<Time>,
+}
+
+impl BagWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> BagWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        let position = MAGIC.len() as u64 + write_bag_header(&mut writer, 0, 0, 0)?;
+        Ok(BagWriter {
+            writer: Some(writer),
+            position,
+            compression: Compression::None,
+            chunk_threshold: DEFAULT_CHUNK_THRESHOLD,
+            connections: vec![],
+            chunk: ChunkBuffer::default(),
+            chunk_infos: vec![],
+        })
+    }
+
+    #[inline]
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    #[inline]
+    pub fn set_chunk_threshold(&mut self, chunk_threshold: usize) {
+        self.chunk_threshold = chunk_threshold;
+    }
+
+    /// Approximate size of the bag, counting buffered messages as uncompressed.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.position + self.chunk.data.len() as u64
+    }
+
+    /// Adds a connection that messages can be written to, returning the connection's ID.
+    ///
+    /// The ID in the passed connection gets ignored.
+    pub fn add_connection(&mut self, mut connection: Connection) -> u32 {
+        let id = self.connections.len() as u32;
+        connection.id = id;
+        self.connections.push(connection);
+        id
+    }
+
+    /// Writes a message received at the given time on the given connection.
+    ///
+    /// Raw messages can be written as well, since their encoding is their data.
+    pub fn write<T: RosMsg>(&mut self, connection: u32, time: Time, message: &T) -> Result<()> {
+        let chunk = &mut self.chunk;
+        let entries = match chunk.index.get_mut(&connection) {
+            Some(entries) => entries,
+            None => {
+                // Chunks need to be readable on their own, so they repeat connection records
+                match self.connections.get(connection as usize) {
+                    Some(info) => info.write(&mut chunk.data)?,
+                    None => bail!(ErrorKind::UnknownConnection(connection)),
+                };
+                chunk.index.entry(connection).or_default()
+            }
+        };
+
+        let mut data = vec![];
+        message.encode(&mut data)?;
+        entries.push((time, chunk.data.len() as u32));
+        record::write_record(
+            &mut chunk.data,
+            &[
+                ("op", &[op::MESSAGE_DATA]),
+                ("conn", &connection.to_le_bytes()),
+                ("time", &record::time_bytes(time)),
+            ],
+            &data,
+        )?;
+        chunk.start_time = Some(chunk.start_time.map_or(time, |start| start.min(time)));
+        chunk.end_time = Some(chunk.end_time.map_or(time, |end| end.max(time)));
+
+        if chunk.data.len() >= self.chunk_threshold {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered messages, the index, and returns the underlying writer.
+    pub fn close(mut self) -> Result<W> {
+        let result = self.finish();
+        let writer = self.writer.take();
+        result?;
+        match writer {
+            Some(writer) => Ok(writer),
+            None => unreachable!("Writer is only taken on close"),
+        }
+    }
+
+    fn flush_chunk(&mut self) -> Result<()> {
+        let chunk = std::mem::take(&mut self.chunk);
+        let (start_time, end_time) = match (chunk.start_time, chunk.end_time) {
+            (Some(start_time), Some(end_time)) => (start_time, end_time),
+            _ => return Ok(()),
+        };
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let position = self.position;
+        let size = chunk.data.len() as u32;
+        let data = self.compression.compress(chunk.data)?;
+        self.position += record::write_record(
+            writer,
+            &[
+                ("op", &[op::CHUNK]),
+                ("compression", self.compression.name().as_bytes()),
+                ("size", &size.to_le_bytes()),
+            ],
+            &data,
+        )?;
+
+        let mut message_counts = HashMap::new();
+        for (connection, entries) in &chunk.index {
+            let mut data = Vec::with_capacity(entries.len() * 12);
+            for (time, offset) in entries {
+                data.extend_from_slice(&record::time_bytes(*time));
+                data.extend_from_slice(&offset.to_le_bytes());
+            }
+            let count = entries.len() as u32;
+            self.position += record::write_record(
+                writer,
+                &[
+                    ("op", &[op::INDEX_DATA]),
+                    ("ver", &1u32.to_le_bytes()),
+                    ("conn", &connection.to_le_bytes()),
+                    ("count", &count.to_le_bytes()),
+                ],
+                &data,
+            )?;
+            message_counts.insert(*connection, count);
+        }
+
+        self.chunk_infos.push(ChunkInfo {
+            position,
+            start_time,
+            end_time,
+            message_counts,
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.flush_chunk()?;
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let index_position = self.position;
+        for connection in &self.connections {
+            self.position += connection.write(writer)?;
+        }
+        for chunk_info in &self.chunk_infos {
+            let mut message_counts = chunk_info.message_counts.iter().collect::<Vec<_>>();
+            message_counts.sort_unstable();
+            let mut data = Vec::with_capacity(message_counts.len() * 8);
+            for (connection, count) in message_counts {
+                data.extend_from_slice(&connection.to_le_bytes());
+                data.extend_from_slice(&count.to_le_bytes());
+            }
+            self.position += record::write_record(
+                writer,
+                &[
+                    ("op", &[op::CHUNK_INFO]),
+                    ("ver", &1u32.to_le_bytes()),
+                    ("chunk_pos", &chunk_info.position.to_le_bytes()),
+                    ("start_time", &record::time_bytes(chunk_info.start_time)),
+                    ("end_time", &record::time_bytes(chunk_info.end_time)),
+                    (
+                        "count",
+                        &(chunk_info.message_counts.len() as u32).to_le_bytes(),
+                    ),
+                ],
+                &data,
+            )?;
+        }
+
+        writer.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        write_bag_header(
+            writer,
+            index_position,
+            self.connections.len() as u32,
+            self.chunk_infos.len() as u32,
+        )?;
+        writer.seek(SeekFrom::End(0))?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Drop for BagWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            // Errors can only be handled by closing the bag explicitly
+            let _ = self.finish();
+        }
+    }
+}
+
+fn write_bag_header<W: Write>(
+    writer: &mut W,
+    index_position: u64,
+    connection_count: u32,
+    chunk_count: u32,
+) -> Result<u64> {
+    let fields: &[(&str, &[u8])] = &[
+        ("op", &[op::BAG_HEADER]),
+        ("index_pos", &index_position.to_le_bytes()),
+        ("conn_count", &connection_count.to_le_bytes()),
+        ("chunk_count", &chunk_count.to_le_bytes()),
+    ];
+    // The header is padded, so it can be overwritten in place when the bag gets closed
+    let padding = BAG_HEADER_LENGTH - 8 - record::encode_fields(fields).len();
+    Ok(record::write_record(writer, fields, &vec![b' '; padding])?)
+}
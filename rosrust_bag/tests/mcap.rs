@@ -0,0 +1,189 @@
+use rosrust::{Message, MsgValue, RawMessageDescription, Time};
+use rosrust_bag::mcap::{Compression, Mcap, McapWriter};
+use rosrust_bag::{Connection, Query};
+use std::io::Cursor;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, std_msgs / Header);
+}
+
+fn string(data: &str) -> msg::std_msgs::String {
+    msg::std_msgs::String { data: data.into() }
+}
+
+fn write_sample_file(compression: Compression, chunk_size: usize) -> Vec<u8> {
+    let mut writer = McapWriter::new(Cursor::new(vec![])).unwrap();
+    writer.set_compression(compression);
+    writer.set_chunk_size(chunk_size);
+
+    let mut chatter = Connection::new(
+        "/chatter",
+        RawMessageDescription::from_message::<msg::std_msgs::String>(),
+    );
+    chatter.caller_id = Some("/talker".into());
+    chatter.latching = true;
+    let chatter = writer.add_connection(chatter).unwrap();
+    let header = writer
+        .add_connection(Connection::new(
+            "/header",
+            RawMessageDescription::from_message::<msg::std_msgs::Header>(),
+        ))
+        .unwrap();
+    let other_chatter = writer
+        .add_connection(Connection::new(
+            "/other_chatter",
+            RawMessageDescription::from_message::<msg::std_msgs::String>(),
+        ))
+        .unwrap();
+
+    for i in 0..20 {
+        writer
+            .write(chatter, Time::from_seconds(i * 2), &string(&i.to_string()))
+            .unwrap();
+        let message = msg::std_msgs::Header {
+            seq: i,
+            stamp: Time::from_seconds(i),
+            frame_id: "base".into(),
+        };
+        writer
+            .write(header, Time::from_seconds(i * 2 + 1), &message)
+            .unwrap();
+    }
+    writer
+        .write(other_chatter, Time::from_seconds(100), &string("other"))
+        .unwrap();
+
+    writer.close().unwrap().into_inner()
+}
+
+#[test]
+fn reads_back_written_files() {
+    for &compression in &[Compression::None, Compression::Lz4, Compression::Zstd] {
+        let data = write_sample_file(compression, 256);
+        assert_eq!(b"\x89MCAP0\r\n", &data[..8]);
+        assert_eq!(b"\x89MCAP0\r\n", &data[data.len() - 8..]);
+        let mut mcap = Mcap::new(Cursor::new(data)).unwrap();
+
+        assert_eq!(Some(41), mcap.message_count());
+        assert_eq!(Some(Time::from_seconds(0)), mcap.start_time());
+        assert_eq!(Some(Time::from_seconds(100)), mcap.end_time());
+        assert_eq!(vec!["/chatter", "/header", "/other_chatter"], mcap.topics());
+
+        let times = mcap
+            .read(&Query::new().end(Time::from_seconds(50)))
+            .unwrap()
+            .map(|message| message.unwrap().time.sec)
+            .collect::<Vec<_>>();
+        assert_eq!((0..40).collect::<Vec<_>>(), times);
+
+        let query = Query::new().topic("/chatter").start(Time::from_seconds(30));
+        let messages = mcap
+            .read(&query)
+            .unwrap()
+            .typed::<msg::std_msgs::String>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let data = messages
+            .iter()
+            .map(|message| message.message.data.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["15", "16", "17", "18", "19"], data);
+
+        let connection = &messages[0].connection;
+        assert_eq!(Some("/talker".into()), connection.caller_id);
+        assert!(connection.latching);
+        assert_eq!("std_msgs/String", connection.message_type);
+        assert_eq!(msg::std_msgs::String::md5sum(), connection.md5sum);
+    }
+}
+
+#[test]
+fn decodes_messages_dynamically() {
+    let data = write_sample_file(Compression::Zstd, usize::MAX);
+    let mut mcap = Mcap::new(Cursor::new(data)).unwrap();
+
+    let query = Query::new().topic("/header").end(Time::from_seconds(5));
+    let messages = mcap
+        .read(&query)
+        .unwrap()
+        .dynamic()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(3, messages.len());
+    assert_eq!(Some(&MsgValue::U32(2)), messages[2].message.get("seq"),);
+    assert_eq!(
+        Some(&MsgValue::String("base".into())),
+        messages[2].message.get("frame_id"),
+    );
+}
+
+#[test]
+fn shares_schemas_between_channels() {
+    let data = write_sample_file(Compression::None, usize::MAX);
+    let mut mcap = Mcap::new(Cursor::new(data)).unwrap();
+    let definitions = mcap
+        .connections()
+        .map(|connection| connection.message_definition.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(definitions[0], definitions[2]);
+
+    let messages = mcap
+        .read(&Query::new().topics(["/chatter", "/other_chatter"]))
+        .unwrap();
+    assert_eq!(21, messages.len());
+}
+
+#[test]
+fn writes_empty_files() {
+    let writer = McapWriter::new(Cursor::new(vec![])).unwrap();
+    let data = writer.close().unwrap().into_inner();
+    let mut mcap = Mcap::new(Cursor::new(data)).unwrap();
+    assert_eq!(Some(0), mcap.message_count());
+    assert_eq!(None, mcap.start_time());
+    assert_eq!(0, mcap.read(&Query::new()).unwrap().len());
+}
+
+#[test]
+fn detects_corrupted_summaries() {
+    let mut data = write_sample_file(Compression::Lz4, 256);
+    let length = data.len();
+    // Flip a byte of the last summary offset record
+    data[length - 8 - 29 - 1] ^= 0xff;
+    assert!(Mcap::new(Cursor::new(data)).is_err());
+}
+
+/// Offset of the first chunk's uncompressed size, found by walking the records.
+fn first_chunk_size_offset(data: &[u8]) -> usize {
+    let mut offset = 8;
+    loop {
+        let op = data[offset];
+        let mut length = [0; 8];
+        length.copy_from_slice(&data[offset + 1..offset + 9]);
+        if op == 0x06 {
+            return offset + 9 + 16;
+        }
+        offset += 9 + u64::from_le_bytes(length) as usize;
+    }
+}
+
+#[test]
+fn detects_corrupted_chunk_sizes() {
+    for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+        for size in [u64::MAX, 1] {
+            let mut data = write_sample_file(compression, 256);
+            let offset = first_chunk_size_offset(&data);
+            data[offset..offset + 8].copy_from_slice(&size.to_le_bytes());
+            let mut mcap = Mcap::new(Cursor::new(data)).unwrap();
+            let messages = mcap.read(&Query::new()).unwrap();
+            assert!(messages.collect::<Result<Vec<_>, _>>().is_err());
+        }
+    }
+}
+
+#[test]
+fn rejects_unknown_connections() {
+    let mut writer = McapWriter::new(Cursor::new(vec![])).unwrap();
+    assert!(writer
+        .write(3, Time::from_seconds(1), &string("data"))
+        .is_err());
+}
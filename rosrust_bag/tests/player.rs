@@ -0,0 +1,228 @@
+use lazy_static::lazy_static;
+use rosrust::api::testing::{Capture, TestKit};
+use rosrust::{Duration, RawMessageDescription, Time};
+use rosrust_bag::{Bag, BagWriter, Connection, ErrorKind, Player, PlayerOptions};
+use std::io::Cursor;
+use std::thread;
+use std::time;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, rosgraph_msgs / Clock);
+}
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+lazy_static! {
+    static ref KIT: TestKit = {
+        let kit = TestKit::new().unwrap();
+        rosrust::try_init_with_test_kit("player", &kit).unwrap();
+        kit
+    };
+}
+
+fn millis(value: i64) -> Time {
+    Time::from_nanos(value * 1_000_000)
+}
+
+/// Creates a bag with string messages, given as topic, time and data.
+fn make_bag(messages: &[(&str, Time, &str)]) -> Bag<Cursor<Vec<u8>>> {
+    let mut writer = BagWriter::new(Cursor::new(vec![])).unwrap();
+    let mut connections = std::collections::HashMap::new();
+    for &(topic, time, data) in messages {
+        let connection = *connections.entry(topic).or_insert_with(|| {
+            writer.add_connection(Connection::new(
+                topic,
+                RawMessageDescription::from_message::<msg::std_msgs::String>(),
+            ))
+        });
+        let message = msg::std_msgs::String { data: data.into() };
+        writer.write(connection, time, &message).unwrap();
+    }
+    Bag::new(Cursor::new(writer.close().unwrap().into_inner())).unwrap()
+}
+
+fn options() -> PlayerOptions {
+    PlayerOptions::new().delay(time::Duration::from_millis(0))
+}
+
+fn capture(topic: &str) -> Capture<msg::std_msgs::String> {
+    // Make sure the shared node exists before anything gets published
+    lazy_static::initialize(&KIT);
+    KIT.capture::<msg::std_msgs::String>(topic).unwrap()
+}
+
+fn data(capture: &Capture<msg::std_msgs::String>) -> Vec<String> {
+    capture
+        .drain()
+        .into_iter()
+        .map(|message| message.data)
+        .collect()
+}
+
+/// Gives subscriber threads time to process all delivered messages.
+fn settle() {
+    thread::sleep(time::Duration::from_millis(100));
+}
+
+#[test]
+fn plays_messages_with_recorded_timing() {
+    let chatter = capture("/timing_chatter");
+    let bag = make_bag(&[
+        ("/timing_chatter", millis(10_000), "a"),
+        ("/timing_chatter", millis(10_200), "b"),
+        ("/timing_chatter", millis(10_400), "c"),
+    ]);
+
+    let start = time::Instant::now();
+    let player = Player::start(bag, options()).unwrap();
+    let topics = rosrust::topics().unwrap();
+    assert!(topics
+        .iter()
+        .any(|t| t.name == "/timing_chatter" && t.datatype == "std_msgs/String"));
+    player.wait().unwrap();
+    assert!(start.elapsed() >= time::Duration::from_millis(380));
+    settle();
+    assert_eq!(vec!["a", "b", "c"], data(&chatter));
+}
+
+#[test]
+fn scales_timing_by_rate() {
+    let chatter = capture("/rate_chatter");
+    let bag = make_bag(&[
+        ("/rate_chatter", millis(0), "a"),
+        ("/rate_chatter", millis(400), "b"),
+        ("/rate_chatter", millis(800), "c"),
+    ]);
+
+    let start = time::Instant::now();
+    Player::start(bag, options().rate(4.0))
+        .unwrap()
+        .wait()
+        .unwrap();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= time::Duration::from_millis(180));
+    assert!(elapsed < time::Duration::from_millis(600));
+    settle();
+    assert_eq!(vec!["a", "b", "c"], data(&chatter));
+}
+
+#[test]
+fn rejects_non_positive_rates() {
+    for &rate in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let bag = make_bag(&[("/bad_rate", millis(0), "a")]);
+        match Player::start(bag, options().rate(rate)) {
+            Err(err) => match err.kind() {
+                ErrorKind::InvalidRate(_) => {}
+                kind => panic!("Unexpected error for rate {}: {}", rate, kind),
+            },
+            Ok(_) => panic!("Started playback with rate {}", rate),
+        }
+    }
+}
+
+#[test]
+fn rejects_non_positive_clock_frequencies() {
+    for &frequency in &[0.0, -10.0, f64::NAN, f64::INFINITY] {
+        let bag = make_bag(&[("/bad_frequency", millis(0), "a")]);
+        let options = options().publish_clock(true).clock_frequency(frequency);
+        match Player::start(bag, options) {
+            Err(err) => match err.kind() {
+                ErrorKind::InvalidClockFrequency(_) => {}
+                kind => panic!("Unexpected error for frequency {}: {}", frequency, kind),
+            },
+            Ok(_) => panic!("Started playback with clock frequency {}", frequency),
+        }
+    }
+}
+
+#[test]
+fn filters_topics_and_skips_start() {
+    let first = capture("/filter_first");
+    let second = capture("/filter_second");
+    let bag = make_bag(&[
+        ("/filter_first", millis(0), "a0"),
+        ("/filter_second", millis(0), "b0"),
+        ("/filter_first", millis(50), "a1"),
+        ("/filter_second", millis(50), "b1"),
+        ("/filter_first", millis(100), "a2"),
+    ]);
+
+    let options = options()
+        .topic("/filter_first")
+        .start_offset(Duration::from_nanos(50_000_000));
+    Player::start(bag, options).unwrap().wait().unwrap();
+    settle();
+    assert_eq!(vec!["a1", "a2"], data(&first));
+    assert!(data(&second).is_empty());
+}
+
+#[test]
+fn publishes_bag_time_on_clock() {
+    let chatter = capture("/clock_chatter");
+    let clock = KIT.capture::<msg::rosgraph_msgs::Clock>("/clock").unwrap();
+    let bag = make_bag(&[
+        ("/clock_chatter", millis(5_000), "a"),
+        ("/clock_chatter", millis(5_200), "b"),
+    ]);
+
+    let options = options().publish_clock(true).clock_frequency(50.0);
+    Player::start(bag, options).unwrap().wait().unwrap();
+    settle();
+    assert_eq!(vec!["a", "b"], data(&chatter));
+
+    let times = clock
+        .drain()
+        .into_iter()
+        .map(|message| message.clock)
+        .collect::<Vec<_>>();
+    assert!(times.len() > 3);
+    assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(times.contains(&millis(5_000)));
+    assert_eq!(Some(&millis(5_200)), times.last());
+}
+
+#[test]
+fn pauses_and_steps() {
+    let chatter = capture("/pause_chatter");
+    let bag = make_bag(&[
+        ("/pause_chatter", millis(0), "a"),
+        ("/pause_chatter", millis(100), "b"),
+        ("/pause_chatter", millis(200), "c"),
+    ]);
+
+    let player = Player::start(bag, options().start_paused(true)).unwrap();
+    settle();
+    assert!(player.is_paused());
+    assert!(data(&chatter).is_empty());
+
+    player.step();
+    assert_eq!("a", chatter.recv_timeout(TIMEOUT).unwrap().data);
+    settle();
+    assert!(chatter.try_recv().is_none());
+
+    player.step();
+    assert_eq!("b", chatter.recv_timeout(TIMEOUT).unwrap().data);
+    settle();
+    assert!(chatter.try_recv().is_none());
+
+    player.resume();
+    assert_eq!("c", chatter.recv_timeout(TIMEOUT).unwrap().data);
+    player.wait().unwrap();
+}
+
+#[test]
+fn loops_until_stopped() {
+    let chatter = capture("/loop_chatter");
+    let bag = make_bag(&[
+        ("/loop_chatter", millis(0), "a"),
+        ("/loop_chatter", millis(10), "b"),
+    ]);
+
+    let player = Player::start(bag, options().looping(true)).unwrap();
+    let received = (0..6)
+        .map(|_| chatter.recv_timeout(TIMEOUT).unwrap().data)
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["a", "b", "a", "b", "a", "b"], received);
+    assert!(!player.is_finished());
+    player.stop().unwrap();
+}
@@ -0,0 +1,62 @@
+use rosrust::{RawMessageDescription, Time};
+use rosrust_bag::{Bag, BagWriter, Connection, Player, PlayerOptions};
+use rosrust_master::Master;
+use std::io::Cursor;
+use std::time;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String);
+}
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+fn make_bag(times: &[Time]) -> Bag<Cursor<Vec<u8>>> {
+    let mut writer = BagWriter::new(Cursor::new(vec![])).unwrap();
+    let connection = writer.add_connection(Connection::new(
+        "/sim_chatter",
+        RawMessageDescription::from_message::<msg::std_msgs::String>(),
+    ));
+    for &time in times {
+        let message = msg::std_msgs::String { data: "a".into() };
+        writer.write(connection, time, &message).unwrap();
+    }
+    Bag::new(Cursor::new(writer.close().unwrap().into_inner())).unwrap()
+}
+
+fn wait_for_time(time: Time) {
+    let deadline = time::Instant::now() + TIMEOUT;
+    while rosrust::now() < time {
+        assert!(
+            time::Instant::now() < deadline,
+            "Clock never reached {}",
+            time
+        );
+        std::thread::sleep(time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn playing_node_follows_its_own_clock() {
+    let master = Master::new_local().unwrap();
+    std::env::set_var("ROS_MASTER_URI", master.uri());
+    rosrust::api::Ros::new("sim_time_setup")
+        .unwrap()
+        .param("/use_sim_time")
+        .unwrap()
+        .set(&true)
+        .unwrap();
+    rosrust::init("sim_time_player");
+    assert_eq!(Time::default(), rosrust::now());
+
+    let bag = make_bag(&[Time::from_seconds(100), Time::from_seconds(103)]);
+    // Gives the node's own `/clock` subscriber time to connect
+    let options = PlayerOptions::new()
+        .publish_clock(true)
+        .delay(time::Duration::from_secs(1));
+    let player = Player::start(bag, options).unwrap();
+
+    wait_for_time(Time::from_seconds(100));
+    wait_for_time(Time::from_seconds(101));
+    player.stop().unwrap();
+    assert!(rosrust::now() <= Time::from_seconds(103));
+}
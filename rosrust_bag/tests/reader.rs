@@ -0,0 +1,408 @@
+use rosrust::{Message, MsgValue, RosMsg, Time};
+use rosrust_bag::{Bag, Compression, ErrorKind, Query};
+use std::io::{Cursor, Write};
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, std_msgs / Header);
+}
+
+struct TestConnection {
+    id: u32,
+    topic: &'static str,
+    message_type: String,
+    md5sum: String,
+    message_definition: String,
+}
+
+impl TestConnection {
+    fn new<T: Message>(id: u32, topic: &'static str) -> Self {
+        Self {
+            id,
+            topic,
+            message_type: T::msg_type(),
+            md5sum: T::md5sum(),
+            message_definition: T::msg_definition(),
+        }
+    }
+
+    fn record(&self) -> Vec<u8> {
+        let data = [
+            field("topic", self.topic.as_bytes()),
+            field("type", self.message_type.as_bytes()),
+            field("md5sum", self.md5sum.as_bytes()),
+            field("message_definition", self.message_definition.as_bytes()),
+        ]
+        .concat();
+        record(
+            &[
+                field("op", &[0x07]),
+                field("conn", &self.id.to_le_bytes()),
+                field("topic", self.topic.as_bytes()),
+            ],
+            &data,
+        )
+    }
+}
+
+fn field(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut data = ((name.len() + 1 + value.len()) as u32)
+        .to_le_bytes()
+        .to_vec();
+    data.extend_from_slice(name.as_bytes());
+    data.push(b'=');
+    data.extend_from_slice(value);
+    data
+}
+
+fn record(header: &[Vec<u8>], data: &[u8]) -> Vec<u8> {
+    let header = header.concat();
+    let mut output = (header.len() as u32).to_le_bytes().to_vec();
+    output.extend(header);
+    output.extend((data.len() as u32).to_le_bytes().iter());
+    output.extend_from_slice(data);
+    output
+}
+
+fn time_bytes(time: Time) -> Vec<u8> {
+    [time.sec.to_le_bytes(), time.nsec.to_le_bytes()].concat()
+}
+
+fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+    match compression {
+        Compression::None => data.to_vec(),
+        Compression::Bz2 => {
+            let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+    }
+}
+
+fn bag_header(index_position: u64, connection_count: u32, chunk_count: u32) -> Vec<u8> {
+    let header = [
+        field("op", &[0x03]),
+        field("index_pos", &index_position.to_le_bytes()),
+        field("conn_count", &connection_count.to_le_bytes()),
+        field("chunk_count", &chunk_count.to_le_bytes()),
+    ];
+    // The bag header record always takes up 4096 bytes, padded with spaces
+    let padding = 4096 - record(&header, &[]).len();
+    record(&header, &vec![b' '; padding])
+}
+
+/// Builds a bag with one chunk per group of messages, each message given as its
+/// connection ID, receive time and serialized data.
+fn build_bag(
+    connections: &[TestConnection],
+    chunks: &[Vec<(u32, Time, Vec<u8>)>],
+    compression: Compression,
+) -> Vec<u8> {
+    let mut body = vec![];
+    let mut chunk_infos = vec![];
+    let body_start = (b"#ROSBAG V2.0\n".len() + bag_header(0, 0, 0).len()) as u64;
+
+    for messages in chunks {
+        let mut chunk_data = vec![];
+        let mut index: Vec<(u32, Vec<u8>, u32)> = vec![];
+        for connection in connections {
+            if messages.iter().any(|(id, _, _)| *id == connection.id) {
+                chunk_data.extend(connection.record());
+                index.push((connection.id, vec![], 0));
+            }
+        }
+        for (id, time, data) in messages {
+            let offset = chunk_data.len() as u32;
+            chunk_data.extend(record(
+                &[
+                    field("op", &[0x02]),
+                    field("conn", &id.to_le_bytes()),
+                    field("time", &time_bytes(*time)),
+                ],
+                data,
+            ));
+            let entry = index.iter_mut().find(|(conn, _, _)| conn == id).unwrap();
+            entry.1.extend(time_bytes(*time));
+            entry.1.extend(offset.to_le_bytes().iter());
+            entry.2 += 1;
+        }
+
+        let position = body_start + body.len() as u64;
+        body.extend(record(
+            &[
+                field("op", &[0x05]),
+                field("compression", compression.name().as_bytes()),
+                field("size", &(chunk_data.len() as u32).to_le_bytes()),
+            ],
+            &compress(compression, &chunk_data),
+        ));
+        for (id, data, count) in &index {
+            body.extend(record(
+                &[
+                    field("op", &[0x04]),
+                    field("ver", &1u32.to_le_bytes()),
+                    field("conn", &id.to_le_bytes()),
+                    field("count", &count.to_le_bytes()),
+                ],
+                data,
+            ));
+        }
+
+        let counts = index
+            .iter()
+            .flat_map(|(id, _, count)| [id.to_le_bytes(), count.to_le_bytes()].concat())
+            .collect::<Vec<u8>>();
+        let start_time = messages.iter().map(|(_, time, _)| *time).min().unwrap();
+        let end_time = messages.iter().map(|(_, time, _)| *time).max().unwrap();
+        chunk_infos.push(record(
+            &[
+                field("op", &[0x06]),
+                field("ver", &1u32.to_le_bytes()),
+                field("chunk_pos", &position.to_le_bytes()),
+                field("start_time", &time_bytes(start_time)),
+                field("end_time", &time_bytes(end_time)),
+                field("count", &(index.len() as u32).to_le_bytes()),
+            ],
+            &counts,
+        ));
+    }
+
+    let index_position = body_start + body.len() as u64;
+    let mut bag = b"#ROSBAG V2.0\n".to_vec();
+    bag.extend(bag_header(
+        index_position,
+        connections.len() as u32,
+        chunks.len() as u32,
+    ));
+    bag.extend(body);
+    for connection in connections {
+        bag.extend(connection.record());
+    }
+    for chunk_info in chunk_infos {
+        bag.extend(chunk_info);
+    }
+    bag
+}
+
+fn string(data: &str) -> Vec<u8> {
+    let mut output = vec![];
+    msg::std_msgs::String { data: data.into() }
+        .encode(&mut output)
+        .unwrap();
+    output
+}
+
+fn header(seq: u32, frame_id: &str) -> Vec<u8> {
+    let mut output = vec![];
+    msg::std_msgs::Header {
+        seq,
+        stamp: Time::from_seconds(seq),
+        frame_id: frame_id.into(),
+    }
+    .encode(&mut output)
+    .unwrap();
+    output
+}
+
+fn sample_bag(compression: Compression) -> Bag<Cursor<Vec<u8>>> {
+    let connections = [
+        TestConnection::new::<msg::std_msgs::String>(0, "/chatter"),
+        TestConnection::new::<msg::std_msgs::Header>(1, "/header"),
+    ];
+    let chunks = [
+        vec![
+            (0, Time::from_seconds(1), string("one")),
+            (1, Time::from_seconds(2), header(2, "a")),
+            (0, Time::from_seconds(5), string("five")),
+        ],
+        vec![
+            (0, Time::from_seconds(3), string("three")),
+            (1, Time::from_seconds(4), header(4, "b")),
+            (0, Time::from_seconds(6), string("six")),
+        ],
+    ];
+    Bag::new(Cursor::new(build_bag(&connections, &chunks, compression))).unwrap()
+}
+
+fn seconds(times: impl Iterator<Item = Time>) -> Vec<u32> {
+    times.map(|time| time.sec).collect()
+}
+
+#[test]
+fn reads_bag_summary() {
+    let bag = sample_bag(Compression::None);
+    assert_eq!(vec!["/chatter", "/header"], bag.topics());
+    assert_eq!(2, bag.chunks().len());
+    assert_eq!(6, bag.message_count());
+    assert_eq!(Some(Time::from_seconds(1)), bag.start_time());
+    assert_eq!(Some(Time::from_seconds(6)), bag.end_time());
+
+    let connection = bag.connections().next().unwrap();
+    assert_eq!("/chatter", connection.topic);
+    assert_eq!("std_msgs/String", connection.message_type);
+    assert_eq!(msg::std_msgs::String::md5sum(), connection.md5sum);
+}
+
+#[test]
+fn reads_messages_across_chunks_in_time_order() {
+    let mut bag = sample_bag(Compression::None);
+    let messages = bag
+        .read(&Query::new())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        vec![1, 2, 3, 4, 5, 6],
+        seconds(messages.iter().map(|message| message.time))
+    );
+    assert_eq!("/header", messages[1].connection.topic);
+    assert_eq!(header(2, "a"), messages[1].message.0);
+}
+
+#[test]
+fn filters_by_topic_and_time() {
+    let mut bag = sample_bag(Compression::None);
+
+    let query = Query::new().topic("/chatter");
+    let messages = bag.read(&query).unwrap();
+    assert_eq!(4, messages.len());
+    assert_eq!(
+        vec![1, 3, 5, 6],
+        seconds(messages.map(|message| message.unwrap().time))
+    );
+
+    let query = Query::new()
+        .start(Time::from_seconds(2))
+        .end(Time::from_seconds(4));
+    let messages = bag.read(&query).unwrap();
+    assert_eq!(
+        vec![2, 3, 4],
+        seconds(messages.map(|message| message.unwrap().time))
+    );
+
+    let query = Query::new()
+        .topics(["/header"])
+        .start(Time::from_seconds(3));
+    let messages = bag.read(&query).unwrap();
+    assert_eq!(
+        vec![4],
+        seconds(messages.map(|message| message.unwrap().time))
+    );
+
+    let query = Query::new().topic("/missing");
+    assert_eq!(0, bag.read(&query).unwrap().len());
+}
+
+#[test]
+fn reads_compressed_chunks() {
+    for &compression in &[Compression::Bz2, Compression::Lz4] {
+        let mut bag = sample_bag(compression);
+        let query = Query::new().topic("/chatter");
+        let data = bag
+            .read(&query)
+            .unwrap()
+            .typed::<msg::std_msgs::String>()
+            .map(|message| message.unwrap().message.data)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["one", "three", "five", "six"], data);
+    }
+}
+
+#[test]
+fn decodes_typed_messages() {
+    let mut bag = sample_bag(Compression::None);
+    let query = Query::new().topic("/header");
+    let messages = bag
+        .read(&query)
+        .unwrap()
+        .typed::<msg::std_msgs::Header>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(2, messages.len());
+    assert_eq!(4, messages[1].message.seq);
+    assert_eq!("b", messages[1].message.frame_id);
+    assert_eq!(Time::from_seconds(4), messages[1].time);
+
+    let mut messages = bag.read(&query).unwrap().typed::<msg::std_msgs::String>();
+    match messages.next().unwrap() {
+        Err(err) => match err.kind() {
+            ErrorKind::MismatchedType(..) => {}
+            kind => panic!("Unexpected error: {}", kind),
+        },
+        Ok(message) => panic!("Unexpected message: {:?}", message),
+    }
+}
+
+#[test]
+fn decodes_dynamic_messages() {
+    let mut bag = sample_bag(Compression::Lz4);
+    let messages = bag
+        .read(&Query::new())
+        .unwrap()
+        .dynamic()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(6, messages.len());
+    assert_eq!(
+        Some(&MsgValue::String("one".into())),
+        messages[0].message.get("data")
+    );
+    assert_eq!(Some(&MsgValue::U32(2)), messages[1].message.get("seq"));
+    assert_eq!(
+        Some(&MsgValue::Time(Time::from_seconds(2))),
+        messages[1].message.get("stamp")
+    );
+}
+
+#[test]
+fn rejects_other_format_versions() {
+    let data = b"#ROSBAG V1.2\n".to_vec();
+    match Bag::new(Cursor::new(data)) {
+        Err(err) => match err.kind() {
+            ErrorKind::UnsupportedVersion(version) => assert_eq!("#ROSBAG V1.2", version),
+            kind => panic!("Unexpected error: {}", kind),
+        },
+        Ok(_) => panic!("Bag should be rejected"),
+    }
+}
+
+#[test]
+fn rejects_truncated_records() {
+    let mut data = b"#ROSBAG V2.0\n".to_vec();
+    data.extend(u32::MAX.to_le_bytes().iter());
+    data.extend(field("op", &[0x03]));
+    match Bag::new(Cursor::new(data)) {
+        Err(err) => match err.kind() {
+            ErrorKind::Io(err) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+            kind => panic!("Unexpected error: {}", kind),
+        },
+        Ok(_) => panic!("Bag should be rejected"),
+    }
+}
+
+#[test]
+fn detects_mismatched_chunk_sizes() {
+    let connections = [TestConnection::new::<msg::std_msgs::String>(0, "/chatter")];
+    let chunks = [vec![(0, Time::from_seconds(1), string("one"))]];
+    for &compression in &[Compression::None, Compression::Bz2, Compression::Lz4] {
+        let mut data = build_bag(&connections, &chunks, compression);
+        let position = data
+            .windows(5)
+            .position(|window| window == b"size=")
+            .unwrap();
+        data[position + 5] += 1;
+
+        let mut bag = Bag::new(Cursor::new(data)).unwrap();
+        match bag.read(&Query::new()).unwrap().next().unwrap() {
+            Err(err) => match err.kind() {
+                ErrorKind::BadSize(section) => assert_eq!("chunk", section),
+                kind => panic!("Unexpected error: {}", kind),
+            },
+            Ok(message) => panic!("Unexpected message: {:?}", message),
+        }
+    }
+}
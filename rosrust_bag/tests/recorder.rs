@@ -0,0 +1,171 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use rosrust::api::testing::TestKit;
+use rosrust::{Duration, Time};
+use rosrust_bag::{Bag, Query, Recorder, RecorderOptions};
+use std::path::PathBuf;
+use std::thread;
+use std::time;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String);
+}
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+lazy_static! {
+    static ref KIT: TestKit = {
+        let kit = TestKit::new().unwrap();
+        rosrust::try_init_with_test_kit("recorder", &kit).unwrap();
+        kit
+    };
+}
+
+fn bag_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rosrust_bag_{}_{}.bag", std::process::id(), name))
+}
+
+fn string(data: &str) -> msg::std_msgs::String {
+    msg::std_msgs::String { data: data.into() }
+}
+
+fn read_strings(path: &PathBuf) -> Vec<(String, String)> {
+    let mut bag = Bag::open(path).unwrap();
+    let messages = bag
+        .read(&Query::new())
+        .unwrap()
+        .typed::<msg::std_msgs::String>()
+        .map(|message| {
+            let message = message.unwrap();
+            (message.connection.topic.clone(), message.message.data)
+        })
+        .collect();
+    std::fs::remove_file(path).unwrap();
+    messages
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let deadline = time::Instant::now() + TIMEOUT;
+    while !condition() {
+        assert!(time::Instant::now() < deadline, "Timed out waiting");
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
+
+/// Gives subscriber threads time to process all delivered messages.
+fn settle() {
+    thread::sleep(time::Duration::from_millis(200));
+}
+
+#[test]
+fn records_selected_topics() {
+    let talker = KIT.node("explicit_talker").unwrap();
+    let path = bag_path("explicit");
+    let recorder = Recorder::start(RecorderOptions::new(&path).topic("/explicit_chatter")).unwrap();
+    assert_eq!(vec!["/explicit_chatter"], recorder.topics());
+
+    let chatter = talker
+        .publish::<msg::std_msgs::String>("explicit_chatter", 10)
+        .unwrap();
+    let ignored = talker
+        .publish::<msg::std_msgs::String>("explicit_ignored", 10)
+        .unwrap();
+    chatter.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    chatter.send(string("first")).unwrap();
+    chatter.send(string("second")).unwrap();
+    ignored.send(string("ignored")).unwrap();
+    settle();
+
+    assert_eq!(vec![path.clone()], recorder.stop().unwrap());
+    let mut bag = Bag::open(&path).unwrap();
+    let connection = bag.connections().next().unwrap().clone();
+    assert_eq!("std_msgs/String", connection.message_type);
+    assert_eq!(Some("/explicit_talker".into()), connection.caller_id);
+    assert_eq!(2, bag.read(&Query::new()).unwrap().len());
+    drop(bag);
+
+    let messages = read_strings(&path);
+    assert_eq!(
+        vec![
+            ("/explicit_chatter".into(), "first".into()),
+            ("/explicit_chatter".into(), "second".into()),
+        ],
+        messages
+    );
+}
+
+#[test]
+fn discovers_topics_matching_regex() {
+    let talker = KIT.node("discovery_talker").unwrap();
+    let path = bag_path("discovery");
+    let options = RecorderOptions::new(&path)
+        .topic_regex(Regex::new("^/discovered_").unwrap())
+        .discovery_period(time::Duration::from_millis(20));
+    let recorder = Recorder::start(options).unwrap();
+
+    let first = talker
+        .publish::<msg::std_msgs::String>("discovered_first", 10)
+        .unwrap();
+    let second = talker
+        .publish::<msg::std_msgs::String>("discovered_second", 10)
+        .unwrap();
+    let other = talker
+        .publish::<msg::std_msgs::String>("undiscovered", 10)
+        .unwrap();
+    wait_for(|| recorder.topics().len() == 2);
+    assert_eq!(
+        vec!["/discovered_first", "/discovered_second"],
+        recorder.topics()
+    );
+
+    first.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    second.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    first.send(string("a")).unwrap();
+    settle();
+    second.send(string("b")).unwrap();
+    other.send(string("c")).unwrap();
+    settle();
+
+    let paths = recorder.stop().unwrap();
+    let messages = read_strings(&paths[0]);
+    assert_eq!(
+        vec![
+            ("/discovered_first".into(), "a".into()),
+            ("/discovered_second".into(), "b".into()),
+        ],
+        messages
+    );
+}
+
+#[test]
+fn splits_recordings_by_duration() {
+    let talker = KIT.node("split_talker").unwrap();
+    let path = bag_path("split");
+    let options = RecorderOptions::new(&path)
+        .topic("/split_chatter")
+        .split_duration(Duration::from_seconds(10));
+    let recorder = Recorder::start(options).unwrap();
+
+    let chatter = talker
+        .publish::<msg::std_msgs::String>("split_chatter", 10)
+        .unwrap();
+    chatter.wait_for_subscribers(Some(TIMEOUT)).unwrap();
+    for &(second, data) in &[(100, "a"), (105, "b"), (111, "c"), (115, "d")] {
+        KIT.set_time(Time::from_seconds(second));
+        chatter.send(string(data)).unwrap();
+        settle();
+    }
+
+    let paths = recorder.stop().unwrap();
+    assert_eq!(2, paths.len());
+    assert!(paths[0].to_string_lossy().ends_with("_split_0.bag"));
+    assert!(paths[1].to_string_lossy().ends_with("_split_1.bag"));
+    let data = |path| {
+        read_strings(path)
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(vec!["a", "b"], data(&paths[0]));
+    assert_eq!(vec!["c", "d"], data(&paths[1]));
+}
@@ -0,0 +1,125 @@
+use rosrust::{Message, RawMessage, RawMessageDescription, RosMsg, Time};
+use rosrust_bag::{Bag, BagWriter, Compression, Connection, Query};
+use std::io::Cursor;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, std_msgs / Header);
+}
+
+fn string(data: &str) -> msg::std_msgs::String {
+    msg::std_msgs::String { data: data.into() }
+}
+
+fn write_sample_bag(compression: Compression, chunk_threshold: usize) -> Vec<u8> {
+    let mut writer = BagWriter::new(Cursor::new(vec![])).unwrap();
+    writer.set_compression(compression);
+    writer.set_chunk_threshold(chunk_threshold);
+
+    let mut chatter = Connection::new(
+        "/chatter",
+        RawMessageDescription::from_message::<msg::std_msgs::String>(),
+    );
+    chatter.caller_id = Some("/talker".into());
+    chatter.latching = true;
+    let chatter = writer.add_connection(chatter);
+    let header = writer.add_connection(Connection::new(
+        "/header",
+        RawMessageDescription::from_message::<msg::std_msgs::Header>(),
+    ));
+
+    for i in 0..20 {
+        writer
+            .write(chatter, Time::from_seconds(i * 2), &string(&i.to_string()))
+            .unwrap();
+        let mut data = vec![];
+        msg::std_msgs::Header {
+            seq: i,
+            stamp: Time::from_seconds(i),
+            frame_id: "base".into(),
+        }
+        .encode(&mut data)
+        .unwrap();
+        writer
+            .write(header, Time::from_seconds(i * 2 + 1), &RawMessage(data))
+            .unwrap();
+    }
+
+    writer.close().unwrap().into_inner()
+}
+
+#[test]
+fn reads_back_written_bags() {
+    for &compression in &[Compression::None, Compression::Bz2, Compression::Lz4] {
+        let data = write_sample_bag(compression, 256);
+        let mut bag = Bag::new(Cursor::new(data)).unwrap();
+
+        assert!(bag.chunks().len() > 1);
+        assert_eq!(40, bag.message_count());
+        assert_eq!(Some(Time::from_seconds(0)), bag.start_time());
+        assert_eq!(Some(Time::from_seconds(39)), bag.end_time());
+        assert_eq!(vec!["/chatter", "/header"], bag.topics());
+
+        let times = bag
+            .read(&Query::new())
+            .unwrap()
+            .map(|message| message.unwrap().time.sec)
+            .collect::<Vec<_>>();
+        assert_eq!((0..40).collect::<Vec<_>>(), times);
+
+        let query = Query::new().topic("/chatter").start(Time::from_seconds(30));
+        let messages = bag
+            .read(&query)
+            .unwrap()
+            .typed::<msg::std_msgs::String>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let data = messages
+            .iter()
+            .map(|message| message.message.data.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["15", "16", "17", "18", "19"], data);
+
+        let connection = &messages[0].connection;
+        assert_eq!(Some("/talker".into()), connection.caller_id);
+        assert!(connection.latching);
+        assert_eq!(msg::std_msgs::String::md5sum(), connection.md5sum);
+    }
+}
+
+#[test]
+fn writes_single_chunk_below_threshold() {
+    let data = write_sample_bag(Compression::Lz4, usize::MAX);
+    let mut bag = Bag::new(Cursor::new(data)).unwrap();
+    assert_eq!(1, bag.chunks().len());
+
+    let query = Query::new().topic("/header");
+    let messages = bag
+        .read(&query)
+        .unwrap()
+        .typed::<msg::std_msgs::Header>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(20, messages.len());
+    assert_eq!(7, messages[7].message.seq);
+    assert_eq!(Time::from_seconds(15), messages[7].time);
+}
+
+#[test]
+fn writes_empty_bags() {
+    let writer = BagWriter::new(Cursor::new(vec![])).unwrap();
+    let data = writer.close().unwrap().into_inner();
+    assert_eq!(13 + 4096, data.len());
+
+    let mut bag = Bag::new(Cursor::new(data)).unwrap();
+    assert_eq!(0, bag.message_count());
+    assert_eq!(None, bag.start_time());
+    assert_eq!(0, bag.read(&Query::new()).unwrap().len());
+}
+
+#[test]
+fn rejects_unknown_connections() {
+    let mut writer = BagWriter::new(Cursor::new(vec![])).unwrap();
+    assert!(writer
+        .write(3, Time::from_seconds(1), &string("data"))
+        .is_err());
+}
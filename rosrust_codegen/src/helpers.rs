@@ -195,6 +195,8 @@ enum MessageCase {
 lazy_static! {
     static ref IN_MEMORY_MESSAGES: HashMap<MessagePath, &'static str> =
         generate_in_memory_messages();
+    static ref IN_MEMORY_SERVICES: HashMap<MessagePath, &'static str> =
+        generate_in_memory_services();
 }
 
 fn generate_in_memory_messages() -> HashMap<MessagePath, &'static str> {
@@ -211,6 +213,23 @@ fn generate_in_memory_messages() -> HashMap<MessagePath, &'static str> {
         MessagePath::new("std_msgs", "Header").expect(MESSAGE_NAME_SHOULD_BE_VALID),
         include_str!("in_memory_messages/Header.msg"),
     );
+    output.insert(
+        MessagePath::new("roscpp", "Logger").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/Logger.msg"),
+    );
+    output
+}
+
+fn generate_in_memory_services() -> HashMap<MessagePath, &'static str> {
+    let mut output = HashMap::new();
+    output.insert(
+        MessagePath::new("roscpp", "GetLoggers").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/GetLoggers.srv"),
+    );
+    output.insert(
+        MessagePath::new("roscpp", "SetLoggerLevel").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/SetLoggerLevel.srv"),
+    );
     output
 }
 
@@ -237,26 +256,12 @@ fn get_message_or_service(
             f.read_to_string(&mut contents)
                 .chain_err(|| "Failed to read file to string!")?;
 
-            let service = ros_message::Srv::new(path.clone(), &contents)
-                .or_else(|err| {
-                    if ignore_bad_messages {
-                        ros_message::Srv::new(path.clone(), "\n\n---\n\n")
-                    } else {
-                        Err(err)
-                    }
-                })
-                .chain_err(|| "Failed to build service messages")?;
-
-            return Ok(MessageCase::Service(
-                Srv {
-                    path: service.path().clone(),
-                    source: service.source().into(),
-                },
-                Msg(service.request().clone()),
-                Msg(service.response().clone()),
-            ));
+            return create_service(path, &contents, ignore_bad_messages);
         }
     }
+    if let Some(contents) = IN_MEMORY_SERVICES.get(&path) {
+        return create_service(path, contents, ignore_bad_messages);
+    }
     if let Some(contents) = IN_MEMORY_MESSAGES.get(&path) {
         return Msg::new(path, contents).map(MessageCase::Message);
     }
@@ -269,6 +274,31 @@ fn get_message_or_service(
     ))
 }
 
+fn create_service(
+    path: MessagePath,
+    contents: &str,
+    ignore_bad_messages: bool,
+) -> Result<MessageCase> {
+    let service = ros_message::Srv::new(path.clone(), contents)
+        .or_else(|err| {
+            if ignore_bad_messages {
+                ros_message::Srv::new(path.clone(), "\n\n---\n\n")
+            } else {
+                Err(err)
+            }
+        })
+        .chain_err(|| "Failed to build service messages")?;
+
+    Ok(MessageCase::Service(
+        Srv {
+            path: service.path().clone(),
+            source: service.source().into(),
+        },
+        Msg(service.request().clone()),
+        Msg(service.response().clone()),
+    ))
+}
+
 fn create_message(message: MessagePath, contents: &str, ignore_bad_messages: bool) -> Result<Msg> {
     Msg::new(message.clone(), contents).or_else(|err| {
         if ignore_bad_messages {
@@ -432,6 +462,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn calculate_md5_works_for_in_memory_services() {
+        let message_map = get_message_map(
+            false,
+            &[],
+            &[
+                MessagePath::new("roscpp", "GetLoggers").unwrap(),
+                MessagePath::new("roscpp", "SetLoggerLevel").unwrap(),
+            ],
+        )
+        .unwrap();
+        let hashes = calculate_md5(&message_map).unwrap();
+        assert_eq!(
+            *hashes
+                .get(&MessagePath::new("roscpp", "Logger").unwrap())
+                .unwrap(),
+            "a6069a2ff40db7bd32143dd66e1f408e".to_owned()
+        );
+        assert_eq!(
+            *hashes
+                .get(&MessagePath::new("roscpp", "GetLoggers").unwrap())
+                .unwrap(),
+            "32e97e85527d4678a8f9279894bb64b0".to_owned()
+        );
+        assert_eq!(
+            *hashes
+                .get(&MessagePath::new("roscpp", "SetLoggerLevel").unwrap())
+                .unwrap(),
+            "51da076440d78ca1684d36c868df61ea".to_owned()
+        );
+    }
+
     #[test]
     fn generate_message_definition_works() {
         let message_map = get_message_map(
@@ -208,6 +208,21 @@ impl Msg {
             }
         }
     }
+
+    pub fn stamped_token_stream<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+        if !self.has_header() {
+            return quote! {};
+        }
+        let name = self.name_ident();
+        quote! {
+            impl #crate_prefix Stamped for #name {
+                #[inline]
+                fn stamp(&self) -> #crate_prefix Time {
+                    self.header.stamp
+                }
+            }
+        }
+    }
 }
 
 lazy_static! {
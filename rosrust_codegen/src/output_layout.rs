@@ -75,6 +75,7 @@ impl Message {
         let decode_message = message.token_stream_decode(crate_prefix);
         let name = message.name_ident();
         let header_tokens = message.header_token_stream(crate_prefix);
+        let stamped_tokens = message.stamped_token_stream(crate_prefix);
         quote! {
             #base_message
 
@@ -97,6 +98,8 @@ impl Message {
                 #header_tokens
             }
 
+            #stamped_tokens
+
             impl #crate_prefix rosmsg::RosMsg for #name {
                 fn encode<W: ::std::io::Write>(&self, mut w: W) -> ::std::io::Result<()> {
                     #encode_message
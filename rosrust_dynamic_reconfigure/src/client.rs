@@ -0,0 +1,115 @@
+use crate::description::config_msg;
+use crate::error::{ErrorKind, Result};
+use crate::value::{self, Values};
+use rosrust::Subscriber;
+use rosrust_msg::dynamic_reconfigure::{Config, ConfigDescription, Reconfigure, ReconfigureReq};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// Client for reading and changing the parameters of another node's server.
+pub struct Client {
+    updates: Arc<Latest<Config>>,
+    descriptions: Arc<Latest<ConfigDescription>>,
+    service: rosrust::Client<Reconfigure>,
+    _subscribers: [Subscriber; 2],
+}
+
+impl Client {
+    /// Connects to the server of the given node.
+    pub fn new(node: &str) -> Result<Self> {
+        let node = node.trim_end_matches('/');
+        let updates = Arc::new(Latest::default());
+        let descriptions = Arc::new(Latest::default());
+        let updates_subscriber = {
+            let updates = Arc::clone(&updates);
+            rosrust::subscribe(
+                &format!("{}/parameter_updates", node),
+                1,
+                move |config: Config| updates.set(config),
+            )?
+        };
+        let descriptions_subscriber = {
+            let descriptions = Arc::clone(&descriptions);
+            rosrust::subscribe(
+                &format!("{}/parameter_descriptions", node),
+                1,
+                move |description: ConfigDescription| descriptions.set(description),
+            )?
+        };
+        let service = rosrust::client::<Reconfigure>(&format!("{}/set_parameters", node))?;
+        Ok(Self {
+            updates,
+            descriptions,
+            service,
+            _subscribers: [updates_subscriber, descriptions_subscriber],
+        })
+    }
+
+    /// Latest values published by the server, waiting for them if none arrived yet.
+    pub fn values(&self, timeout: Duration) -> Result<Values> {
+        Ok(value::from_msg(&self.updates.get(timeout)?))
+    }
+
+    /// Latest config published by the server, waiting for it if none arrived yet.
+    pub fn config<T: DeserializeOwned>(&self, timeout: Duration) -> Result<T> {
+        value::to_config(&self.values(timeout)?)
+    }
+
+    /// Parameter descriptions published by the server, waiting for them if none arrived yet.
+    pub fn description(&self, timeout: Duration) -> Result<ConfigDescription> {
+        self.descriptions.get(timeout)
+    }
+
+    /// Requests changes of the given parameters, and returns all values after the change.
+    pub fn set_values(&self, values: &Values) -> Result<Values> {
+        let request = ReconfigureReq {
+            config: config_msg(values),
+        };
+        let response = self
+            .service
+            .req(&request)?
+            .map_err(ErrorKind::ServiceCall)?;
+        let values = value::from_msg(&response.config);
+        self.updates.set(response.config);
+        Ok(values)
+    }
+
+    /// Requests changes of all parameters within the config, and returns all values after the change.
+    pub fn set_config<T: Serialize>(&self, config: &T) -> Result<Values> {
+        self.set_values(&value::from_config(config)?)
+    }
+}
+
+struct Latest<T> {
+    value: Mutex<Option<T>>,
+    condvar: Condvar,
+}
+
+impl<T> Default for Latest<T> {
+    fn default() -> Self {
+        Self {
+            value: Mutex::new(None),
+            condvar: Condvar::new(),
+        }
+    }
+}
+
+impl<T: Clone> Latest<T> {
+    fn set(&self, value: T) {
+        *self.value.lock().expect(FAILED_TO_LOCK) = Some(value);
+        self.condvar.notify_all();
+    }
+
+    fn get(&self, timeout: Duration) -> Result<T> {
+        let guard = self.value.lock().expect(FAILED_TO_LOCK);
+        let (guard, _) = self
+            .condvar
+            .wait_timeout_while(guard, timeout, |value| value.is_none())
+            .expect(FAILED_TO_LOCK);
+        guard.clone().ok_or_else(|| ErrorKind::Timeout.into())
+    }
+}
@@ -0,0 +1,321 @@
+use crate::error::{ErrorKind, Result};
+use crate::value::{self, Value, Values};
+use error_chain::bail;
+use rosrust_msg::dynamic_reconfigure::{
+    Config, ConfigDescription, Group, GroupState, ParamDescription,
+};
+
+/// Description of all parameters that a server exposes.
+#[derive(Clone, Debug, Default)]
+pub struct Description {
+    params: Vec<Param>,
+}
+
+impl Description {
+    /// Creates a description without any parameters.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parameter to the description.
+    #[inline]
+    pub fn param(mut self, param: Param) -> Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Parameters in the order they were added.
+    #[inline]
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    /// Default values of all parameters.
+    pub fn defaults(&self) -> Values {
+        self.params
+            .iter()
+            .map(|param| (param.name.clone(), param.default.clone()))
+            .collect()
+    }
+
+    /// Checks a single value against its parameter's constraints.
+    ///
+    /// Numbers are clamped to the parameter's range, and integers are accepted for doubles.
+    pub fn validate(&self, name: &str, value: Value) -> Result<Value> {
+        let param = match self.params.iter().find(|param| param.name == name) {
+            Some(param) => param,
+            None => bail!(ErrorKind::UnknownParameter(name.into())),
+        };
+        param.validate(value)
+    }
+
+    /// Applies updates on top of the current values, and returns the levels of changed parameters.
+    ///
+    /// Names that aren't described are ignored, like they are in ROS.
+    pub(crate) fn apply(&self, values: &mut Values, updates: Values) -> Result<u32> {
+        let mut validated = Vec::with_capacity(updates.len());
+        for (name, value) in updates {
+            match self.params.iter().find(|param| param.name == name) {
+                Some(param) => validated.push((param, param.validate(value)?)),
+                None => log::debug!("Ignoring unknown parameter '{}'", name),
+            }
+        }
+        let mut level = 0;
+        for (param, value) in validated {
+            if values.get(&param.name) != Some(&value) {
+                level |= param.level;
+                values.insert(param.name.clone(), value);
+            }
+        }
+        Ok(level)
+    }
+
+    /// Creates the message published on `parameter_descriptions`.
+    pub fn to_msg(&self) -> ConfigDescription {
+        let pick = |f: fn(&Param) -> &Value| {
+            let values = self
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), f(param).clone()))
+                .collect();
+            config_msg(&values)
+        };
+        ConfigDescription {
+            groups: vec![Group {
+                name: DEFAULT_GROUP.into(),
+                type_: String::new(),
+                parameters: self.params.iter().map(Param::to_msg).collect(),
+                parent: 0,
+                id: 0,
+            }],
+            max: pick(|param| &param.max),
+            min: pick(|param| &param.min),
+            dflt: pick(|param| &param.default),
+        }
+    }
+}
+
+const DEFAULT_GROUP: &str = "Default";
+
+/// Creates a config message for the values, with the state of the default group.
+pub(crate) fn config_msg(values: &Values) -> Config {
+    let mut config = value::to_msg(values);
+    config.groups.push(GroupState {
+        name: DEFAULT_GROUP.into(),
+        state: true,
+        id: 0,
+        parent: 0,
+    });
+    config
+}
+
+/// Description of a single parameter, with its constraints.
+#[derive(Clone, Debug)]
+pub struct Param {
+    name: String,
+    description: String,
+    level: u32,
+    default: Value,
+    min: Value,
+    max: Value,
+    options: Vec<EnumValue>,
+    enum_description: String,
+}
+
+#[derive(Clone, Debug)]
+struct EnumValue {
+    name: String,
+    value: Value,
+    description: String,
+}
+
+impl Param {
+    fn new(name: &str, default: Value, min: Value, max: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            level: 0,
+            default,
+            min,
+            max,
+            options: Vec::new(),
+            enum_description: String::new(),
+        }
+    }
+
+    /// Creates a boolean parameter.
+    pub fn bool(name: &str, default: bool) -> Self {
+        Self::new(name, default.into(), false.into(), true.into())
+    }
+
+    /// Creates an integer parameter, which accepts any 32-bit value by default.
+    pub fn int(name: &str, default: i32) -> Self {
+        Self::new(name, default.into(), i32::MIN.into(), i32::MAX.into())
+    }
+
+    /// Creates a floating point parameter, which accepts any value by default.
+    pub fn double(name: &str, default: f64) -> Self {
+        Self::new(
+            name,
+            default.into(),
+            f64::NEG_INFINITY.into(),
+            f64::INFINITY.into(),
+        )
+    }
+
+    /// Creates a string parameter.
+    pub fn str(name: &str, default: &str) -> Self {
+        Self::new(name, default.into(), "".into(), "".into())
+    }
+
+    /// Sets the human readable description.
+    #[inline]
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Sets the level that gets passed to the callback when the parameter changes.
+    #[inline]
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Limits numeric values to a range, with values outside it getting clamped.
+    ///
+    /// The bounds are ignored for boolean and string parameters.
+    pub fn range<T: Into<Value>>(mut self, min: T, max: T) -> Self {
+        let (min, max) = (min.into(), max.into());
+        if let (Some(min), Some(max)) = (
+            min.coerce_like(&self.default),
+            max.coerce_like(&self.default),
+        ) {
+            if matches!(self.default, Value::Int(_) | Value::Double(_)) {
+                self.min = min;
+                self.max = max;
+            }
+        }
+        self
+    }
+
+    /// Adds a named option, limiting the parameter to the set of added options.
+    pub fn enum_value<T: Into<Value>>(mut self, name: &str, value: T, description: &str) -> Self {
+        if let Some(value) = value.into().coerce_like(&self.default) {
+            self.options.push(EnumValue {
+                name: name.into(),
+                value,
+                description: description.into(),
+            });
+        }
+        self
+    }
+
+    /// Sets the description of the parameter's set of options.
+    #[inline]
+    pub fn enum_description(mut self, description: &str) -> Self {
+        self.enum_description = description.into();
+        self
+    }
+
+    /// Name of the parameter.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Default value of the parameter.
+    #[inline]
+    pub fn default_value(&self) -> &Value {
+        &self.default
+    }
+
+    fn validate(&self, value: Value) -> Result<Value> {
+        let value = match value.coerce_like(&self.default) {
+            Some(value) => value,
+            None => bail!(ErrorKind::MismatchedType(
+                self.name.clone(),
+                self.default.type_name().into(),
+            )),
+        };
+        let value = match (value, &self.min, &self.max) {
+            (Value::Int(v), Value::Int(min), Value::Int(max)) => Value::Int(v.max(*min).min(*max)),
+            (Value::Double(v), Value::Double(min), Value::Double(max)) => {
+                Value::Double(v.max(*min).min(*max))
+            }
+            (value, _, _) => value,
+        };
+        if !self.options.is_empty() && !self.options.iter().any(|option| option.value == value) {
+            bail!(ErrorKind::InvalidEnumValue(
+                self.name.clone(),
+                value.to_string()
+            ));
+        }
+        Ok(value)
+    }
+
+    fn to_msg(&self) -> ParamDescription {
+        ParamDescription {
+            name: self.name.clone(),
+            type_: self.default.type_name().into(),
+            level: self.level,
+            description: self.description.clone(),
+            edit_method: self.edit_method(),
+        }
+    }
+
+    /// Enum options, in the Python literal format that rqt_reconfigure parses.
+    fn edit_method(&self) -> String {
+        if self.options.is_empty() {
+            return String::new();
+        }
+        let type_name = self.default.type_name();
+        let ctype = match type_name {
+            "str" => "std::string",
+            other => other,
+        };
+        let options = self
+            .options
+            .iter()
+            .map(|option| {
+                format!(
+                    "{{'name': {}, 'type': '{}', 'value': {}, 'srcline': 0, 'srcfile': '', \
+                     'cconsttype': 'const {}', 'ctype': '{}', 'description': {}}}",
+                    python_str(&option.name),
+                    type_name,
+                    python_literal(&option.value),
+                    ctype,
+                    ctype,
+                    python_str(&option.description),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{{'enum': [{}], 'enum_description': {}}}",
+            options,
+            python_str(&self.enum_description),
+        )
+    }
+}
+
+fn python_str(value: &str) -> String {
+    format!(
+        "'{}'",
+        value
+            .replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('\n', "\\n")
+    )
+}
+
+fn python_literal(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "True".into(),
+        Value::Bool(false) => "False".into(),
+        Value::Int(v) => v.to_string(),
+        Value::Double(v) => format!("{:?}", v),
+        Value::Str(v) => python_str(v),
+    }
+}
@@ -0,0 +1,35 @@
+error_chain::error_chain! {
+    links {
+        Ros(rosrust::error::Error, rosrust::error::ErrorKind);
+        Tcpros(rosrust::error::tcpros::Error, rosrust::error::tcpros::ErrorKind);
+    }
+    foreign_links {
+        Response(rosrust::error::ResponseError);
+    }
+    errors {
+        UnknownParameter(name: String) {
+            description("Unknown parameter")
+            display("Unknown parameter: {}", name)
+        }
+        MismatchedType(name: String, expected: String) {
+            description("Parameter value has the wrong type")
+            display("Parameter '{}' needs a value of type {}", name, expected)
+        }
+        InvalidEnumValue(name: String, value: String) {
+            description("Parameter value is not one of the enumerated values")
+            display("Value {} is not one of the enumerated values of parameter '{}'", value, name)
+        }
+        BadConfig(details: String) {
+            description("Config does not match the parameters")
+            display("Config does not match the parameters: {}", details)
+        }
+        ServiceCall(details: String) {
+            description("Reconfigure service call failed")
+            display("Reconfigure service call failed: {}", details)
+        }
+        Timeout {
+            description("Timed out waiting for config")
+            display("Timed out waiting for config")
+        }
+    }
+}
@@ -0,0 +1,63 @@
+/*!
+This crate provides [dynamic_reconfigure] servers and clients for `rosrust`.
+
+A [`Server`] exposes a node's parameters to tools like `rqt_reconfigure`. Parameters are
+described with their defaults and constraints, and changes are delivered to a callback as a
+typed config structure, with fields named like the parameters:
+
+```no_run
+use rosrust_dynamic_reconfigure::{Description, Param, Server};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Config {
+    speed: f64,
+    mode: i32,
+    verbose: bool,
+}
+
+rosrust::init("reconfigurable");
+let description = Description::new()
+    .param(Param::double("speed", 1.0).range(0.0, 5.0).level(1))
+    .param(
+        Param::int("mode", 0)
+            .enum_value("Slow", 0, "Slow mode")
+            .enum_value("Fast", 1, "Fast mode")
+            .level(2),
+    )
+    .param(Param::bool("verbose", false).description("Enables verbose output"));
+let _server = Server::new(description, |config: &mut Config, level| {
+    println!("Reconfigured with level {}: {:?}", level, config);
+})
+.unwrap();
+rosrust::spin();
+```
+
+A [`Client`] reads and changes the parameters of another node's server:
+
+```no_run
+use rosrust_dynamic_reconfigure::{Client, Value, Values};
+use std::time::Duration;
+
+rosrust::init("reconfigure_client");
+let client = Client::new("/reconfigurable").unwrap();
+println!("{:?}", client.values(Duration::from_secs(1)).unwrap());
+let mut changes = Values::new();
+changes.insert("speed".into(), Value::Double(2.5));
+client.set_values(&changes).unwrap();
+```
+
+[dynamic_reconfigure]: http://wiki.ros.org/dynamic_reconfigure
+*/
+
+pub use client::Client;
+pub use description::{Description, Param};
+pub use rosrust_msg::dynamic_reconfigure::{Config, ConfigDescription};
+pub use server::Server;
+pub use value::{Value, Values};
+
+mod client;
+mod description;
+pub mod error;
+mod server;
+mod value;
@@ -0,0 +1,178 @@
+use crate::description::{config_msg, Description};
+use crate::error::Result;
+use crate::value::{self, Value, Values};
+use rosrust::{Publisher, Service};
+use rosrust_msg::dynamic_reconfigure::{
+    Config, ConfigDescription, Reconfigure, ReconfigureReq, ReconfigureRes,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+type Callback<T> = Box<dyn FnMut(&mut T, u32) + Send>;
+
+/// Server exposing the node's reconfigurable parameters.
+///
+/// The server publishes the parameter descriptions on `~parameter_descriptions` and the
+/// current values on `~parameter_updates`, mirrors the values into the `~` parameters, and
+/// accepts changes through the `~set_parameters` service.
+///
+/// Every change gets validated against the description, converted into the config type
+/// `T`, and passed to the callback together with the OR of the changed parameters' levels.
+/// The callback can further modify the config before it gets applied.
+pub struct Server<T> {
+    inner: Arc<Inner<T>>,
+    _service: Service,
+}
+
+struct Inner<T> {
+    description: Description,
+    values: Mutex<Values>,
+    callback: Mutex<Callback<T>>,
+    updates: Publisher<Config>,
+    _descriptions: Publisher<ConfigDescription>,
+    _phantom: PhantomData<fn(T) -> T>,
+}
+
+impl<T> Server<T>
+where
+    T: Serialize + DeserializeOwned + 'static,
+{
+    /// Starts the server on the global node.
+    ///
+    /// Initial values are the description's defaults, overridden by any existing `~`
+    /// parameters. The callback is called once with them, with all level bits set.
+    pub fn new<F>(description: Description, callback: F) -> Result<Self>
+    where
+        F: FnMut(&mut T, u32) + Send + 'static,
+    {
+        let mut values = description.defaults();
+        for param in description.params() {
+            if let Some(value) = initial_value(&description, param.name()) {
+                values.insert(param.name().into(), value);
+            }
+        }
+
+        let mut descriptions = rosrust::publish("~parameter_descriptions", 1)?;
+        descriptions.set_latching(true);
+        descriptions.send(description.to_msg())?;
+        let mut updates = rosrust::publish("~parameter_updates", 1)?;
+        updates.set_latching(true);
+
+        let inner = Arc::new(Inner {
+            description,
+            values: Mutex::new(Values::new()),
+            callback: Mutex::new(Box::new(callback) as Callback<T>),
+            updates,
+            _descriptions: descriptions,
+            _phantom: PhantomData,
+        });
+        {
+            let mut callback = inner.callback.lock().expect(FAILED_TO_LOCK);
+            inner.reconfigure(&mut callback, values, !0)?;
+        }
+
+        let service_inner = Arc::clone(&inner);
+        let service = rosrust::service::<Reconfigure, _>(
+            "~set_parameters",
+            move |request: ReconfigureReq| {
+                let values = service_inner
+                    .request(value::from_msg(&request.config))
+                    .map_err(|err| err.to_string())?;
+                Ok(ReconfigureRes {
+                    config: config_msg(&values),
+                })
+            },
+        )?;
+
+        Ok(Self {
+            inner,
+            _service: service,
+        })
+    }
+
+    /// Current config.
+    pub fn config(&self) -> Result<T> {
+        value::to_config(&self.values())
+    }
+
+    /// Current values of all parameters.
+    pub fn values(&self) -> Values {
+        self.inner.values.lock().expect(FAILED_TO_LOCK).clone()
+    }
+
+    /// Description the server was started with.
+    #[inline]
+    pub fn description(&self) -> &Description {
+        &self.inner.description
+    }
+
+    /// Changes the config from within the node, without calling the callback.
+    ///
+    /// The config gets validated and published like changes requested by other nodes.
+    pub fn update_config(&self, config: &T) -> Result<()> {
+        let updates = value::from_config(config)?;
+        let _callback = self.inner.callback.lock().expect(FAILED_TO_LOCK);
+        let mut values = self.values();
+        self.inner.description.apply(&mut values, updates)?;
+        self.inner.publish(values)
+    }
+}
+
+impl<T> Inner<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn request(&self, updates: Values) -> Result<Values> {
+        let mut callback = self.callback.lock().expect(FAILED_TO_LOCK);
+        let mut values = self.values.lock().expect(FAILED_TO_LOCK).clone();
+        let level = self.description.apply(&mut values, updates)?;
+        self.reconfigure(&mut callback, values, level)
+    }
+
+    fn reconfigure(
+        &self,
+        callback: &mut Callback<T>,
+        mut values: Values,
+        level: u32,
+    ) -> Result<Values> {
+        let mut config = value::to_config(&values)?;
+        (callback)(&mut config, level);
+        self.description
+            .apply(&mut values, value::from_config(&config)?)?;
+        self.publish(values.clone())?;
+        Ok(values)
+    }
+
+    fn publish(&self, values: Values) -> Result<()> {
+        for (name, value) in &values {
+            if let Some(param) = rosrust::param(&format!("~{}", name)) {
+                param.set_raw(value.clone().into_xmlrpc())?;
+            }
+        }
+        self.updates.send(config_msg(&values))?;
+        *self.values.lock().expect(FAILED_TO_LOCK) = values;
+        Ok(())
+    }
+}
+
+fn initial_value(description: &Description, name: &str) -> Option<Value> {
+    let raw = rosrust::param(&format!("~{}", name))?.get_raw().ok()?;
+    let value = match Value::from_xmlrpc(raw) {
+        Some(value) => value,
+        None => {
+            log::warn!("Ignoring parameter '{}' of unsupported type", name);
+            return None;
+        }
+    };
+    match description.validate(name, value) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::warn!("Ignoring initial value of parameter '{}': {}", name, err);
+            None
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::error::{ErrorKind, Result};
+use error_chain::bail;
+use rosrust_msg::dynamic_reconfigure::{
+    BoolParameter, Config, DoubleParameter, IntParameter, StrParameter,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Values of a config, by parameter name.
+pub type Values = BTreeMap<String, Value>;
+
+/// Value of a single parameter, in one of the types dynamic_reconfigure supports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i32),
+    Double(f64),
+    Str(String),
+}
+
+impl Value {
+    /// Name of the value's type, as used in parameter descriptions.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Double(_) => "double",
+            Value::Str(_) => "str",
+        }
+    }
+
+    /// Converts the value to the type of the other value, if that can be done losslessly.
+    pub(crate) fn coerce_like(self, other: &Value) -> Option<Value> {
+        Some(match (self, other) {
+            (Value::Bool(v), Value::Bool(_)) => Value::Bool(v),
+            (Value::Int(v), Value::Int(_)) => Value::Int(v),
+            (Value::Int(v), Value::Double(_)) => Value::Double(f64::from(v)),
+            (Value::Double(v), Value::Double(_)) => Value::Double(v),
+            (Value::Str(v), Value::Str(_)) => Value::Str(v),
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn from_xmlrpc(value: xml_rpc::Value) -> Option<Value> {
+        Some(match value {
+            xml_rpc::Value::Bool(v) => Value::Bool(v),
+            xml_rpc::Value::Int(v) => Value::Int(v),
+            xml_rpc::Value::Double(v) => Value::Double(v),
+            xml_rpc::Value::String(v) => Value::Str(v),
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn into_xmlrpc(self) -> xml_rpc::Value {
+        match self {
+            Value::Bool(v) => xml_rpc::Value::Bool(v),
+            Value::Int(v) => xml_rpc::Value::Int(v),
+            Value::Double(v) => xml_rpc::Value::Double(v),
+            Value::Str(v) => xml_rpc::Value::String(v),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(v) => v.fmt(f),
+            Value::Int(v) => v.fmt(f),
+            Value::Double(v) => v.fmt(f),
+            Value::Str(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    #[inline]
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<i32> for Value {
+    #[inline]
+    fn from(v: i32) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<f64> for Value {
+    #[inline]
+    fn from(v: f64) -> Self {
+        Value::Double(v)
+    }
+}
+
+impl From<&str> for Value {
+    #[inline]
+    fn from(v: &str) -> Self {
+        Value::Str(v.into())
+    }
+}
+
+impl From<String> for Value {
+    #[inline]
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+
+/// Extracts all values from a config message.
+pub fn from_msg(config: &Config) -> Values {
+    let bools = config
+        .bools
+        .iter()
+        .map(|v| (v.name.clone(), Value::Bool(v.value)));
+    let ints = config
+        .ints
+        .iter()
+        .map(|v| (v.name.clone(), Value::Int(v.value)));
+    let doubles = config
+        .doubles
+        .iter()
+        .map(|v| (v.name.clone(), Value::Double(v.value)));
+    let strs = config
+        .strs
+        .iter()
+        .map(|v| (v.name.clone(), Value::Str(v.value.clone())));
+    bools.chain(ints).chain(doubles).chain(strs).collect()
+}
+
+/// Creates a config message holding the given values, without any group states.
+pub fn to_msg(values: &Values) -> Config {
+    let mut config = Config::default();
+    for (name, value) in values {
+        let name = name.clone();
+        match value.clone() {
+            Value::Bool(value) => config.bools.push(BoolParameter { name, value }),
+            Value::Int(value) => config.ints.push(IntParameter { name, value }),
+            Value::Double(value) => config.doubles.push(DoubleParameter { name, value }),
+            Value::Str(value) => config.strs.push(StrParameter { name, value }),
+        }
+    }
+    config
+}
+
+/// Serializes a config structure, or a map of values, into values by field name.
+pub fn from_config<T: Serialize>(config: &T) -> Result<Values> {
+    let mut params =
+        xml_rpc::into_params(config).map_err(|err| ErrorKind::BadConfig(err.to_string()))?;
+    let fields = match (params.pop(), params.is_empty()) {
+        (Some(xml_rpc::Value::Struct(fields)), true) => fields,
+        _ => bail!(ErrorKind::BadConfig(
+            "config needs to be a structure".into()
+        )),
+    };
+    fields
+        .into_iter()
+        .map(|(name, value)| match Value::from_xmlrpc(value) {
+            Some(value) => Ok((name, value)),
+            None => bail!(ErrorKind::BadConfig(format!(
+                "field '{}' has an unsupported type",
+                name
+            ))),
+        })
+        .collect()
+}
+
+/// Deserializes values into a config structure, with fields named like the parameters.
+pub fn to_config<T: DeserializeOwned>(values: &Values) -> Result<T> {
+    let fields = values
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone().into_xmlrpc()))
+        .collect::<HashMap<_, _>>();
+    T::deserialize(xml_rpc::Value::Struct(fields))
+        .map_err(|err| ErrorKind::BadConfig(err.to_string()).into())
+}
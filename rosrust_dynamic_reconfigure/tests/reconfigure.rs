@@ -0,0 +1,234 @@
+use crossbeam::channel::unbounded;
+use lazy_static::lazy_static;
+use rosrust::api::testing::TestKit;
+use rosrust_dynamic_reconfigure::error::ErrorKind;
+use rosrust_dynamic_reconfigure::{Client, Description, Param, Server, Value, Values};
+use serde_derive::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard};
+use std::time;
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+lazy_static! {
+    static ref KIT: TestKit = {
+        let kit = TestKit::new().unwrap();
+        rosrust::try_init_with_test_kit("reconfigurable", &kit).unwrap();
+        kit
+    };
+    static ref SERVER_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    speed: f64,
+    mode: i32,
+    verbose: bool,
+    label: String,
+}
+
+fn description() -> Description {
+    Description::new()
+        .param(Param::double("speed", 1.0).range(0.0, 5.0).level(1))
+        .param(
+            Param::int("mode", 0)
+                .enum_value("Slow", 0, "Slow mode")
+                .enum_value("Fast", 1, "Fast mode")
+                .enum_description("Speed mode")
+                .level(2),
+        )
+        .param(Param::bool("verbose", false).level(4))
+        .param(Param::str("label", "robot").description("Displayed name"))
+}
+
+/// Only one server can exist on the node at a time, and it should start without leftovers.
+fn exclusive_server() -> MutexGuard<'static, ()> {
+    let guard = SERVER_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    for name in &["speed", "mode", "verbose", "label"] {
+        let param = KIT.param(&format!("/reconfigurable/{}", name)).unwrap();
+        if param.exists().unwrap() {
+            param.delete().unwrap();
+        }
+    }
+    guard
+}
+
+fn values(items: &[(&str, Value)]) -> Values {
+    items
+        .iter()
+        .map(|(name, value)| (String::from(*name), value.clone()))
+        .collect()
+}
+
+#[test]
+fn validates_values() {
+    let description = description();
+    assert_eq!(
+        Value::Double(5.0),
+        description.validate("speed", Value::Double(7.5)).unwrap()
+    );
+    assert_eq!(
+        Value::Double(2.0),
+        description.validate("speed", Value::Int(2)).unwrap()
+    );
+    assert_eq!(
+        Value::Int(1),
+        description.validate("mode", Value::Int(1)).unwrap()
+    );
+    assert!(matches!(
+        description
+            .validate("mode", Value::Int(3))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidEnumValue(..)
+    ));
+    assert!(matches!(
+        description
+            .validate("verbose", Value::Int(1))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::MismatchedType(..)
+    ));
+    assert!(matches!(
+        description
+            .validate("missing", Value::Int(1))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnknownParameter(..)
+    ));
+}
+
+#[test]
+fn describes_parameters() {
+    let message = description().to_msg();
+    assert_eq!(1, message.groups.len());
+    let params = &message.groups[0].parameters;
+    assert_eq!(
+        vec!["speed", "mode", "verbose", "label"],
+        params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["double", "int", "bool", "str"],
+        params.iter().map(|p| p.type_.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!("Displayed name", params[3].description);
+    assert!(params[0].edit_method.is_empty());
+    assert!(params[1]
+        .edit_method
+        .starts_with("{'enum': [{'name': 'Slow'"));
+    assert!(params[1]
+        .edit_method
+        .ends_with("'enum_description': 'Speed mode'}"));
+    assert_eq!(5.0, message.max.doubles[0].value);
+    assert_eq!(0.0, message.min.doubles[0].value);
+    assert_eq!("robot", message.dflt.strs[0].value);
+}
+
+#[test]
+fn starts_with_defaults_and_parameter_overrides() {
+    let _guard = exclusive_server();
+    KIT.param("/reconfigurable/speed")
+        .unwrap()
+        .set(&9.0)
+        .unwrap();
+    KIT.param("/reconfigurable/mode").unwrap().set(&1).unwrap();
+
+    let (tx, rx) = unbounded();
+    let server = Server::new(description(), move |config: &mut Config, level| {
+        tx.send((config.clone(), level)).unwrap();
+    })
+    .unwrap();
+
+    let expected = Config {
+        speed: 5.0,
+        mode: 1,
+        verbose: false,
+        label: "robot".into(),
+    };
+    assert_eq!((expected.clone(), !0), rx.recv_timeout(TIMEOUT).unwrap());
+    assert_eq!(expected, server.config().unwrap());
+
+    let speed = KIT.param("/reconfigurable/speed").unwrap();
+    assert_eq!(5.0, speed.get::<f64>().unwrap());
+    let label = KIT.param("/reconfigurable/label").unwrap();
+    assert_eq!("robot", label.get::<String>().unwrap());
+}
+
+#[test]
+fn client_reads_and_changes_config() {
+    let _guard = exclusive_server();
+    let (tx, rx) = unbounded();
+    let server = Server::new(description(), move |config: &mut Config, level| {
+        if config.label.is_empty() {
+            config.label = "unnamed".into();
+        }
+        tx.send(level).unwrap();
+    })
+    .unwrap();
+    assert_eq!(!0, rx.recv_timeout(TIMEOUT).unwrap());
+
+    let client = Client::new("/reconfigurable").unwrap();
+    let description = client.description(TIMEOUT).unwrap();
+    assert_eq!(4, description.groups[0].parameters.len());
+    let config = client.config::<Config>(TIMEOUT).unwrap();
+    assert_eq!(1.0, config.speed);
+
+    let result = client
+        .set_values(&values(&[
+            ("speed", Value::Double(-1.0)),
+            ("verbose", Value::Bool(true)),
+            ("unknown", Value::Int(3)),
+        ]))
+        .unwrap();
+    assert_eq!(1 | 4, rx.recv_timeout(TIMEOUT).unwrap());
+    assert_eq!(Some(&Value::Double(0.0)), result.get("speed"));
+    assert_eq!(Some(&Value::Bool(true)), result.get("verbose"));
+    assert!(!result.contains_key("unknown"));
+    assert!(server.config().unwrap().verbose);
+    let verbose = KIT.param("/reconfigurable/verbose").unwrap();
+    assert!(verbose.get::<bool>().unwrap());
+
+    // Values changed by the callback are applied too
+    let result = client
+        .set_values(&values(&[("label", Value::Str(String::new()))]))
+        .unwrap();
+    assert_eq!(0, rx.recv_timeout(TIMEOUT).unwrap());
+    assert_eq!(Some(&Value::Str("unnamed".into())), result.get("label"));
+
+    // Invalid requests leave the config untouched
+    assert!(client
+        .set_values(&values(&[("mode", Value::Int(7))]))
+        .is_err());
+    assert!(rx.recv_timeout(time::Duration::from_millis(50)).is_err());
+    assert_eq!(0, server.config().unwrap().mode);
+
+    let mut config = server.config().unwrap();
+    config.mode = 1;
+    let result = client.set_config(&config).unwrap();
+    assert_eq!(2, rx.recv_timeout(TIMEOUT).unwrap());
+    assert_eq!(Some(&Value::Int(1)), result.get("mode"));
+}
+
+#[test]
+fn publishes_local_updates() {
+    let _guard = exclusive_server();
+    let (tx, rx) = unbounded();
+    let server = Server::new(description(), move |_: &mut Config, level| {
+        tx.send(level).unwrap();
+    })
+    .unwrap();
+    assert_eq!(!0, rx.recv_timeout(TIMEOUT).unwrap());
+
+    let mut config = server.config().unwrap();
+    config.speed = 3.5;
+    server.update_config(&config).unwrap();
+    assert!(rx.recv_timeout(time::Duration::from_millis(50)).is_err());
+
+    let client = Client::new("/reconfigurable").unwrap();
+    let deadline = time::Instant::now() + TIMEOUT;
+    while client.config::<Config>(TIMEOUT).unwrap().speed != 3.5 {
+        assert!(time::Instant::now() < deadline, "Timed out waiting");
+        std::thread::sleep(time::Duration::from_millis(10));
+    }
+    let speed = KIT.param("/reconfigurable/speed").unwrap();
+    assert_eq!(3.5, speed.get::<f64>().unwrap());
+}
@@ -0,0 +1,12 @@
+error_chain::error_chain! {
+    foreign_links {
+        Io(::std::io::Error);
+        XmlRpc(xml_rpc::error::Error);
+    }
+    errors {
+        BadAddress(address: String) {
+            description("Bad address provided")
+            display("Bad address provided: {}", address)
+        }
+    }
+}
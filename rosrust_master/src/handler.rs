@@ -0,0 +1,447 @@
+use crate::naming;
+use crate::notifier::Notifier;
+use crate::param_server::{ParamServer, ParamUpdate};
+use crate::registrations::{NodeRegistration, Registrations};
+use crate::FAILED_TO_LOCK;
+use log::info;
+use rosrust::error::ResponseError;
+use rosrust::XmlRpcServer;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use xml_rpc::{Params, Value};
+
+type Response<T> = Result<T, ResponseError>;
+
+/// Complete state of the master, shared between all XML-RPC handlers.
+#[derive(Default)]
+pub struct MasterState {
+    registrations: Registrations,
+    params: ParamServer,
+}
+
+/// Everything needed by the XML-RPC handlers.
+#[derive(Clone)]
+pub struct Context {
+    pub state: Arc<Mutex<MasterState>>,
+    pub notifier: Arc<Notifier>,
+    pub uri: Arc<Mutex<String>>,
+    pub stop_signal: Arc<AtomicBool>,
+}
+
+struct Arguments {
+    items: std::vec::IntoIter<Value>,
+}
+
+impl Arguments {
+    fn new(params: Params) -> Self {
+        let params = match params.first() {
+            Some(Value::Array(items)) => items.clone(),
+            _ => params,
+        };
+        Self {
+            items: params.into_iter(),
+        }
+    }
+
+    fn value(&mut self, name: &str) -> Response<Value> {
+        self.items
+            .next()
+            .ok_or_else(|| ResponseError::Client(format!("Missing argument '{}'", name)))
+    }
+
+    fn string(&mut self, name: &str) -> Response<String> {
+        match self.value(name)? {
+            Value::String(v) => Ok(v),
+            _ => Err(ResponseError::Client(format!(
+                "Argument '{}' needs to be a string",
+                name
+            ))),
+        }
+    }
+}
+
+fn string_array(items: impl IntoIterator<Item = String>) -> Value {
+    Value::Array(items.into_iter().map(Value::String).collect())
+}
+
+fn topic_pairs(items: Vec<(String, String)>) -> Value {
+    Value::Array(
+        items
+            .into_iter()
+            .map(|(name, datatype)| string_array(vec![name, datatype]))
+            .collect(),
+    )
+}
+
+fn registration_list(items: &BTreeMap<String, BTreeSet<String>>) -> Value {
+    Value::Array(
+        items
+            .iter()
+            .map(|(name, nodes)| {
+                Value::Array(vec![
+                    Value::String(name.clone()),
+                    string_array(nodes.iter().cloned()),
+                ])
+            })
+            .collect(),
+    )
+}
+
+impl Context {
+    fn lock(&self) -> std::sync::MutexGuard<'_, MasterState> {
+        self.state.lock().expect(FAILED_TO_LOCK)
+    }
+
+    /// Registers a node's API, shutting down any other node using the same name.
+    fn register_node(&self, state: &mut MasterState, caller_id: &str, caller_api: &str) {
+        let published_topics = state
+            .registrations
+            .publishers()
+            .iter()
+            .filter(|(_, nodes)| nodes.contains(caller_id))
+            .map(|(topic, _)| topic.clone())
+            .collect::<Vec<_>>();
+        if let NodeRegistration::Replaced(old_api) =
+            state.registrations.register_node(caller_id, caller_api)
+        {
+            info!(
+                "Node {} replaced by a new node with the same name",
+                caller_id
+            );
+            state.params.drop_node(caller_id);
+            self.notifier
+                .shutdown(&old_api, "new node registered with same name");
+            for topic in published_topics {
+                self.notify_subscribers(state, &topic);
+            }
+        }
+    }
+
+    fn prune_node(&self, state: &mut MasterState, caller_id: &str) {
+        let has_param_subscriptions = state.params.has_subscriptions(caller_id);
+        state
+            .registrations
+            .prune_node(caller_id, has_param_subscriptions);
+    }
+
+    fn is_caller(&self, state: &MasterState, caller_id: &str, caller_api: &str) -> bool {
+        state.registrations.lookup_node(caller_id) == Some(caller_api)
+    }
+
+    fn notify_subscribers(&self, state: &MasterState, topic: &str) {
+        let publishers = state.registrations.publisher_apis(topic);
+        for subscriber in state.registrations.subscriber_apis(topic) {
+            self.notifier
+                .publisher_update(&subscriber, topic, publishers.clone());
+        }
+    }
+
+    fn notify_param_updates(&self, updates: Vec<ParamUpdate>) {
+        for update in updates {
+            self.notifier
+                .param_update(&update.caller_api, &update.key, update.value);
+        }
+    }
+}
+
+pub fn register_master_api(server: &mut XmlRpcServer, context: &Context) {
+    let ctx = context.clone();
+    server.register_value("registerService", "Registered service", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let service = naming::resolve(&args.string("service")?, &caller_id);
+        let service_api = args.string("service_api")?;
+        let caller_api = args.string("caller_api")?;
+        let mut state = ctx.lock();
+        ctx.register_node(&mut state, &caller_id, &caller_api);
+        state
+            .registrations
+            .register_service(&service, &caller_id, &service_api);
+        Ok(Value::Int(1))
+    });
+
+    let ctx = context.clone();
+    server.register_value("unregisterService", "Unregistered service", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let service = naming::resolve(&args.string("service")?, &caller_id);
+        let service_api = args.string("service_api")?;
+        let mut state = ctx.lock();
+        let removed = state
+            .registrations
+            .unregister_service(&service, &service_api);
+        ctx.prune_node(&mut state, &caller_id);
+        Ok(Value::Int(removed as i32))
+    });
+
+    let ctx = context.clone();
+    server.register_value("registerSubscriber", "Subscribed to topic", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let topic = naming::resolve(&args.string("topic")?, &caller_id);
+        let topic_type = args.string("topic_type")?;
+        let caller_api = args.string("caller_api")?;
+        let mut state = ctx.lock();
+        ctx.register_node(&mut state, &caller_id, &caller_api);
+        state
+            .registrations
+            .register_subscriber(&topic, &topic_type, &caller_id);
+        Ok(string_array(state.registrations.publisher_apis(&topic)))
+    });
+
+    let ctx = context.clone();
+    server.register_value(
+        "unregisterSubscriber",
+        "Unsubscribed from topic",
+        move |args| {
+            let mut args = Arguments::new(args);
+            let caller_id = args.string("caller_id")?;
+            let topic = naming::resolve(&args.string("topic")?, &caller_id);
+            let caller_api = args.string("caller_api")?;
+            let mut state = ctx.lock();
+            if !ctx.is_caller(&state, &caller_id, &caller_api) {
+                return Ok(Value::Int(0));
+            }
+            let removed = state
+                .registrations
+                .unregister_subscriber(&topic, &caller_id);
+            ctx.prune_node(&mut state, &caller_id);
+            Ok(Value::Int(removed as i32))
+        },
+    );
+
+    let ctx = context.clone();
+    server.register_value("registerPublisher", "Registered publisher", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let topic = naming::resolve(&args.string("topic")?, &caller_id);
+        let topic_type = args.string("topic_type")?;
+        let caller_api = args.string("caller_api")?;
+        let mut state = ctx.lock();
+        ctx.register_node(&mut state, &caller_id, &caller_api);
+        state
+            .registrations
+            .register_publisher(&topic, &topic_type, &caller_id);
+        ctx.notify_subscribers(&state, &topic);
+        Ok(string_array(state.registrations.subscriber_apis(&topic)))
+    });
+
+    let ctx = context.clone();
+    server.register_value(
+        "unregisterPublisher",
+        "Unregistered publisher",
+        move |args| {
+            let mut args = Arguments::new(args);
+            let caller_id = args.string("caller_id")?;
+            let topic = naming::resolve(&args.string("topic")?, &caller_id);
+            let caller_api = args.string("caller_api")?;
+            let mut state = ctx.lock();
+            if !ctx.is_caller(&state, &caller_id, &caller_api) {
+                return Ok(Value::Int(0));
+            }
+            let removed = state.registrations.unregister_publisher(&topic, &caller_id);
+            if removed {
+                ctx.notify_subscribers(&state, &topic);
+            }
+            ctx.prune_node(&mut state, &caller_id);
+            Ok(Value::Int(removed as i32))
+        },
+    );
+
+    let ctx = context.clone();
+    server.register_value("lookupNode", "Node API", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let node_name = naming::resolve(&args.string("node_name")?, &caller_id);
+        ctx.lock()
+            .registrations
+            .lookup_node(&node_name)
+            .map(|api| Value::String(api.into()))
+            .ok_or_else(|| ResponseError::Client(format!("unknown node [{}]", node_name)))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getPublishedTopics", "Published topics", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let subgraph = args.string("subgraph")?;
+        let subgraph = if subgraph.is_empty() {
+            String::from("/")
+        } else {
+            naming::resolve(&subgraph, &caller_id)
+        };
+        let topics = ctx
+            .lock()
+            .registrations
+            .published_topics()
+            .into_iter()
+            .filter(|(topic, _)| naming::is_in_namespace(topic, &subgraph))
+            .collect();
+        Ok(topic_pairs(topics))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getTopicTypes", "Topic types", move |args| {
+        Arguments::new(args).string("caller_id")?;
+        Ok(topic_pairs(ctx.lock().registrations.topic_types()))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getSystemState", "System state", move |args| {
+        Arguments::new(args).string("caller_id")?;
+        let state = ctx.lock();
+        let services = state
+            .registrations
+            .services()
+            .map(|(name, provider)| {
+                Value::Array(vec![
+                    Value::String(name.clone()),
+                    string_array(vec![provider.caller_id.clone()]),
+                ])
+            })
+            .collect();
+        Ok(Value::Array(vec![
+            registration_list(state.registrations.publishers()),
+            registration_list(state.registrations.subscribers()),
+            Value::Array(services),
+        ]))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getUri", "Master URI", move |args| {
+        Arguments::new(args).string("caller_id")?;
+        Ok(Value::String(ctx.uri.lock().expect(FAILED_TO_LOCK).clone()))
+    });
+
+    let ctx = context.clone();
+    server.register_value("lookupService", "Service URI", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let service = naming::resolve(&args.string("service")?, &caller_id);
+        ctx.lock()
+            .registrations
+            .lookup_service(&service)
+            .map(|api| Value::String(api.into()))
+            .ok_or_else(|| ResponseError::Client(format!("no provider for [{}]", service)))
+    });
+
+    server.register_value("getPid", "PID", |_args| {
+        Ok(Value::Int(std::process::id() as i32))
+    });
+
+    let ctx = context.clone();
+    server.register_value("shutdown", "Shutdown", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let message = args.string("message").unwrap_or_default();
+        info!("Shutdown requested by {}: {}", caller_id, message);
+        ctx.stop_signal.store(true, Ordering::SeqCst);
+        Ok(Value::Int(0))
+    });
+}
+
+pub fn register_param_api(server: &mut XmlRpcServer, context: &Context) {
+    let ctx = context.clone();
+    server.register_value("deleteParam", "Deleted parameter", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let key = naming::resolve(&args.string("key")?, &caller_id);
+        let updates = ctx
+            .lock()
+            .params
+            .delete(&key)
+            .ok_or_else(|| ResponseError::Client(format!("parameter [{}] is not set", key)))?;
+        ctx.notify_param_updates(updates);
+        Ok(Value::Int(0))
+    });
+
+    let ctx = context.clone();
+    server.register_value("setParam", "Set parameter", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let key = naming::resolve(&args.string("key")?, &caller_id);
+        let value = args.value("value")?;
+        let updates = ctx
+            .lock()
+            .params
+            .set(&key, value)
+            .map_err(ResponseError::Client)?;
+        ctx.notify_param_updates(updates);
+        Ok(Value::Int(0))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getParam", "Parameter value", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let key = naming::resolve(&args.string("key")?, &caller_id);
+        ctx.lock()
+            .params
+            .get(&key)
+            .ok_or_else(|| ResponseError::Client(format!("Parameter [{}] is not set", key)))
+    });
+
+    let ctx = context.clone();
+    server.register_value("searchParam", "Found parameter", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let key = args.string("key")?;
+        if key.starts_with('~') {
+            return Err(ResponseError::Client(
+                "Private names cannot be searched for".into(),
+            ));
+        }
+        ctx.lock()
+            .params
+            .search(&caller_id, &key)
+            .map(Value::String)
+            .ok_or_else(|| {
+                ResponseError::Client(format!(
+                    "Cannot find parameter [{}] in an upwards search",
+                    key
+                ))
+            })
+    });
+
+    let ctx = context.clone();
+    server.register_value("subscribeParam", "Subscribed to parameter", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let caller_api = args.string("caller_api")?;
+        let key = naming::resolve(&args.string("key")?, &caller_id);
+        let mut state = ctx.lock();
+        ctx.register_node(&mut state, &caller_id, &caller_api);
+        Ok(state.params.subscribe(&key, &caller_id, &caller_api))
+    });
+
+    let ctx = context.clone();
+    server.register_value(
+        "unsubscribeParam",
+        "Unsubscribed from parameter",
+        move |args| {
+            let mut args = Arguments::new(args);
+            let caller_id = args.string("caller_id")?;
+            let caller_api = args.string("caller_api")?;
+            let key = naming::resolve(&args.string("key")?, &caller_id);
+            let mut state = ctx.lock();
+            let removed = state.params.unsubscribe(&key, &caller_id, &caller_api);
+            ctx.prune_node(&mut state, &caller_id);
+            Ok(Value::Int(removed as i32))
+        },
+    );
+
+    let ctx = context.clone();
+    server.register_value("hasParam", "Parameter existence", move |args| {
+        let mut args = Arguments::new(args);
+        let caller_id = args.string("caller_id")?;
+        let key = naming::resolve(&args.string("key")?, &caller_id);
+        Ok(Value::Bool(ctx.lock().params.has(&key)))
+    });
+
+    let ctx = context.clone();
+    server.register_value("getParamNames", "Parameter names", move |args| {
+        Arguments::new(args).string("caller_id")?;
+        Ok(string_array(ctx.lock().params.names()))
+    });
+}
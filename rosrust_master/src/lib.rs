@@ -0,0 +1,153 @@
+/*!
+This crate provides a pure Rust implementation of the ROS master and parameter server.
+
+It implements the [Master API] and [Parameter Server API] over XML-RPC, including
+`publisherUpdate` and `paramUpdate` callbacks towards registered nodes. It can be run as the
+standalone `rosrust_master` binary, which replaces `rosmaster`, or started in-process:
+
+```no_run
+let master = rosrust_master::Master::new_local().unwrap();
+std::env::set_var("ROS_MASTER_URI", master.uri());
+rosrust::init("node_under_test");
+```
+
+[Master API]: http://wiki.ros.org/ROS/Master_API
+[Parameter Server API]: http://wiki.ros.org/ROS/Parameter%20Server%20API
+*/
+
+pub use error::{Error, ErrorKind, Result};
+
+pub mod error;
+mod handler;
+mod naming;
+mod notifier;
+mod param_server;
+mod registrations;
+
+use error_chain::bail;
+use handler::{Context, MasterState};
+use notifier::Notifier;
+use rosrust::XmlRpcServer;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// Caller ID used by the master when calling node APIs.
+const MASTER_CALLER_ID: &str = "/master";
+
+/// Time between checks for incoming XML-RPC requests.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Running instance of the ROS master.
+///
+/// The master serves requests in a background thread until it is dropped, or until a `shutdown`
+/// request is received over XML-RPC.
+pub struct Master {
+    uri: String,
+    local_addr: SocketAddr,
+    stop_signal: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Master {
+    /// Starts a master on the given port, advertised under the given hostname.
+    ///
+    /// Like nodes, the master binds to all interfaces, unless the hostname refers to the
+    /// loopback interface. Passing port `0` picks an ephemeral port.
+    pub fn new(hostname: &str, port: u16) -> Result<Master> {
+        let bind_address = if hostname == "localhost" || hostname.starts_with("127.") {
+            hostname
+        } else {
+            "0.0.0.0"
+        };
+        Self::new_with_bind_address(hostname, bind_address, port)
+    }
+
+    /// Starts a master on an ephemeral port of the loopback interface.
+    ///
+    /// This is meant for tests, where many masters can run in parallel without clashing.
+    pub fn new_local() -> Result<Master> {
+        Self::new_with_bind_address("127.0.0.1", "127.0.0.1", 0)
+    }
+
+    /// Starts a master bound to the given address, advertised under the given hostname.
+    pub fn new_with_bind_address(hostname: &str, bind_address: &str, port: u16) -> Result<Master> {
+        let socket_addr = match (bind_address, port).to_socket_addrs()?.next() {
+            Some(socket_addr) => socket_addr,
+            None => bail!(ErrorKind::BadAddress(format!("{}:{}", bind_address, port))),
+        };
+
+        let context = Context {
+            state: Arc::new(Mutex::new(MasterState::default())),
+            notifier: Arc::new(Notifier::new(MASTER_CALLER_ID)),
+            uri: Arc::new(Mutex::new(String::new())),
+            stop_signal: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut server = XmlRpcServer::default();
+        handler::register_master_api(&mut server, &context);
+        handler::register_param_api(&mut server, &context);
+        let bound_server = server.bind(&socket_addr)?;
+
+        let local_addr = bound_server.local_addr();
+        let uri = format!("http://{}:{}/", hostname, local_addr.port());
+        *context.uri.lock().expect(FAILED_TO_LOCK) = uri.clone();
+
+        let stop_signal = Arc::clone(&context.stop_signal);
+        let thread = thread::spawn(move || {
+            while !context.stop_signal.load(Ordering::SeqCst) {
+                bound_server.poll();
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        Ok(Master {
+            uri,
+            local_addr,
+            stop_signal,
+            thread: Some(thread),
+        })
+    }
+
+    /// URI that nodes should use as their `ROS_MASTER_URI`.
+    #[inline]
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Address that the XML-RPC server is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns `false` once the master has been asked to shut down.
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        !self.stop_signal.load(Ordering::SeqCst)
+    }
+
+    /// Stops serving requests, and waits for the server thread to finish.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stop_signal.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("Master server thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for Master {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
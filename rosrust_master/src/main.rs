@@ -0,0 +1,69 @@
+use rosrust_master::Master;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const DEFAULT_PORT: u16 = 11311;
+
+fn print_usage() {
+    println!("Usage: rosrust_master [-p PORT] [--host HOSTNAME]");
+}
+
+fn main() {
+    env_logger::init();
+
+    let mut port = DEFAULT_PORT;
+    let mut hostname = rosrust::api::resolve::hostname();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "--port" => {
+                port = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(v) => v,
+                    None => {
+                        print_usage();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--host" => {
+                hostname = match args.next() {
+                    Some(v) => v,
+                    None => {
+                        print_usage();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            _ => {
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let master = match Master::new(&hostname, port) {
+        Ok(master) => master,
+        Err(err) => {
+            eprintln!("Failed to start master: {}", err);
+            std::process::exit(1);
+        }
+    };
+    println!("ROS_MASTER_URI={}", master.uri());
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = Arc::clone(&interrupted);
+        ctrlc::set_handler(move || interrupted.store(true, Ordering::SeqCst))
+            .expect("Failed to set SIGINT handler");
+    }
+
+    while master.is_running() && !interrupted.load(Ordering::SeqCst) {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    master.shutdown();
+}
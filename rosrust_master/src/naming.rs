@@ -0,0 +1,88 @@
+/// Returns the namespace that the given node name resides in, with a trailing slash.
+pub fn namespace(caller_id: &str) -> String {
+    let caller_id = canonicalize(caller_id);
+    match caller_id.rfind('/') {
+        Some(idx) => String::from(&caller_id[..=idx]),
+        None => String::from("/"),
+    }
+}
+
+/// Removes duplicate and trailing slashes, and makes the name global.
+pub fn canonicalize(name: &str) -> String {
+    let parts = split(name);
+    if parts.is_empty() {
+        return String::from("/");
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Splits a name into its non-empty components.
+pub fn split(name: &str) -> Vec<&str> {
+    name.split('/').filter(|v| !v.is_empty()).collect()
+}
+
+/// Resolves a name relative to the caller's namespace, like `rosmaster` does.
+pub fn resolve(name: &str, caller_id: &str) -> String {
+    if name.is_empty() {
+        return namespace(caller_id);
+    }
+    if name.starts_with('/') {
+        return canonicalize(name);
+    }
+    if let Some(private) = name.strip_prefix('~') {
+        return canonicalize(&format!("{}/{}", caller_id, private));
+    }
+    canonicalize(&format!("{}{}", namespace(caller_id), name))
+}
+
+/// Appends a trailing slash, used for comparing namespaces.
+pub fn with_trailing_slash(name: &str) -> String {
+    let name = canonicalize(name);
+    if name == "/" {
+        name
+    } else {
+        name + "/"
+    }
+}
+
+/// Checks whether `name` is equal to `parent`, or is located inside of it.
+pub fn is_in_namespace(name: &str, parent: &str) -> bool {
+    with_trailing_slash(name).starts_with(&with_trailing_slash(parent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_namespace_of_node() {
+        assert_eq!("/", namespace("/node"));
+        assert_eq!("/foo/", namespace("/foo/node"));
+        assert_eq!("/foo/bar/", namespace("/foo/bar/node"));
+    }
+
+    #[test]
+    fn canonicalizes_names() {
+        assert_eq!("/", canonicalize(""));
+        assert_eq!("/", canonicalize("//"));
+        assert_eq!("/foo/bar", canonicalize("/foo//bar/"));
+        assert_eq!("/foo", canonicalize("foo"));
+    }
+
+    #[test]
+    fn resolves_names() {
+        assert_eq!("/a/b", resolve("/a/b", "/ns/node"));
+        assert_eq!("/ns/a/b", resolve("a/b", "/ns/node"));
+        assert_eq!("/ns/node/a", resolve("~a", "/ns/node"));
+        assert_eq!("/ns/", resolve("", "/ns/node"));
+    }
+
+    #[test]
+    fn checks_namespace_membership() {
+        assert!(is_in_namespace("/a/b", "/a"));
+        assert!(is_in_namespace("/a", "/a/"));
+        assert!(is_in_namespace("/a/b", "/"));
+        assert!(!is_in_namespace("/ab", "/a"));
+        assert!(!is_in_namespace("/a", "/a/b"));
+    }
+}
@@ -0,0 +1,194 @@
+use crossbeam::channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use log::{debug, error};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use xml_rpc::{Url, Value};
+
+/// Time after which a worker without notifications exits.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Notification {
+    method: &'static str,
+    params: Vec<Value>,
+}
+
+struct Worker {
+    id: usize,
+    sender: Sender<Notification>,
+}
+
+#[derive(Default)]
+struct Workers {
+    next_id: usize,
+    by_api: HashMap<String, Worker>,
+}
+
+type SharedWorkers = Arc<Mutex<Workers>>;
+
+/// Sends callbacks to nodes' XML-RPC APIs without blocking the master.
+///
+/// Each node gets its own worker thread, so notifications to one node are delivered in order,
+/// and an unresponsive node cannot delay notifications to other nodes. Workers exit once they
+/// have been idle for a while, so nodes that went away do not keep their threads alive.
+pub struct Notifier {
+    caller_id: String,
+    idle_timeout: Duration,
+    workers: SharedWorkers,
+}
+
+impl Notifier {
+    pub fn new(caller_id: &str) -> Self {
+        Self::with_idle_timeout(caller_id, IDLE_TIMEOUT)
+    }
+
+    fn with_idle_timeout(caller_id: &str, idle_timeout: Duration) -> Self {
+        Self {
+            caller_id: caller_id.into(),
+            idle_timeout,
+            workers: Arc::new(Mutex::new(Workers::default())),
+        }
+    }
+
+    pub fn publisher_update(&self, caller_api: &str, topic: &str, publishers: Vec<String>) {
+        self.send(
+            caller_api,
+            "publisherUpdate",
+            vec![
+                Value::String(topic.into()),
+                Value::Array(publishers.into_iter().map(Value::String).collect()),
+            ],
+        );
+    }
+
+    pub fn param_update(&self, caller_api: &str, key: &str, value: Value) {
+        self.send(
+            caller_api,
+            "paramUpdate",
+            vec![Value::String(key.into()), value],
+        );
+    }
+
+    pub fn shutdown(&self, caller_api: &str, message: &str) {
+        self.send(caller_api, "shutdown", vec![Value::String(message.into())]);
+    }
+
+    fn send(&self, caller_api: &str, method: &'static str, mut params: Vec<Value>) {
+        params.insert(0, Value::String(self.caller_id.clone()));
+        let notification = Notification { method, params };
+        // Workers only remove themselves while holding this lock, so they cannot exit with
+        // the notification still queued
+        let mut workers = self.workers.lock().expect(crate::FAILED_TO_LOCK);
+        if !workers.by_api.contains_key(caller_api) {
+            let id = workers.next_id;
+            workers.next_id += 1;
+            let sender = spawn_worker(caller_api, id, Arc::clone(&self.workers), self.idle_timeout);
+            workers
+                .by_api
+                .insert(caller_api.into(), Worker { id, sender });
+        }
+        let worker = &workers.by_api[caller_api];
+        if worker.sender.send(notification).is_err() {
+            error!(
+                "Failed to notify {}, as its notification worker died",
+                caller_api
+            );
+            workers.by_api.remove(caller_api);
+        }
+    }
+
+    #[cfg(test)]
+    fn worker_count(&self) -> usize {
+        self.workers
+            .lock()
+            .expect(crate::FAILED_TO_LOCK)
+            .by_api
+            .len()
+    }
+}
+
+fn spawn_worker(
+    caller_api: &str,
+    id: usize,
+    workers: SharedWorkers,
+    idle_timeout: Duration,
+) -> Sender<Notification> {
+    let (tx, rx) = unbounded::<Notification>();
+    let caller_api = String::from(caller_api);
+    thread::spawn(move || match caller_api.parse::<Url>() {
+        Ok(uri) => deliver(&uri, &caller_api, &rx, &workers, id, idle_timeout),
+        Err(err) => {
+            error!("Cannot notify node at {}: {}", caller_api, err);
+            let mut workers = workers.lock().expect(crate::FAILED_TO_LOCK);
+            remove_worker(&mut workers, &caller_api, id);
+        }
+    });
+    tx
+}
+
+fn deliver(
+    uri: &Url,
+    caller_api: &str,
+    rx: &Receiver<Notification>,
+    workers: &SharedWorkers,
+    id: usize,
+    idle_timeout: Duration,
+) {
+    loop {
+        let notification = match rx.recv_timeout(idle_timeout) {
+            Ok(notification) => notification,
+            Err(RecvTimeoutError::Timeout) => {
+                let mut workers = workers.lock().expect(crate::FAILED_TO_LOCK);
+                if rx.is_empty() {
+                    remove_worker(&mut workers, caller_api, id);
+                    return;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        debug!("Calling {} on {}", notification.method, caller_api);
+        match xml_rpc::call_value(uri, notification.method, notification.params) {
+            Ok(Ok(_)) => {}
+            Ok(Err(fault)) => error!(
+                "Fault #{} calling {} on {}: {}",
+                fault.code, notification.method, caller_api, fault.message
+            ),
+            Err(err) => error!(
+                "Failed to call {} on {}: {}",
+                notification.method, caller_api, err
+            ),
+        }
+    }
+}
+
+/// Removes the worker, unless it was already replaced by a newer one.
+fn remove_worker(workers: &mut Workers, caller_api: &str, id: usize) {
+    if workers.by_api.get(caller_api).map(|worker| worker.id) == Some(id) {
+        workers.by_api.remove(caller_api);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_workers_exit() {
+        let notifier = Notifier::with_idle_timeout("/master", Duration::from_millis(20));
+        // Nothing listens on the discard port, so calls fail right away
+        notifier.shutdown("http://127.0.0.1:9/", "test");
+        notifier.shutdown("http://127.0.0.1:9/", "test");
+        assert_eq!(1, notifier.worker_count());
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while notifier.worker_count() > 0 {
+            assert!(std::time::Instant::now() < deadline, "Worker never exited");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        notifier.shutdown("http://127.0.0.1:9/", "test");
+        assert_eq!(1, notifier.worker_count());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::naming;
+use std::collections::{BTreeMap, HashMap};
+use xml_rpc::Value;
+
+/// Hierarchical storage of all parameters, with subscriptions to their changes.
+#[derive(Default)]
+pub struct ParamServer {
+    tree: HashMap<String, Value>,
+    subscriptions: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Notification that needs to be sent to a node's `paramUpdate` API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamUpdate {
+    pub caller_api: String,
+    pub key: String,
+    pub value: Value,
+}
+
+impl ParamServer {
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let mut parts = naming::split(key).into_iter();
+        let first = match parts.next() {
+            Some(v) => v,
+            None => return Some(Value::Struct(self.tree.clone())),
+        };
+        let mut value = self.tree.get(first)?;
+        for part in parts {
+            value = match value {
+                Value::Struct(children) => children.get(part)?,
+                _ => return None,
+            };
+        }
+        Some(value.clone())
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Sets the value of a parameter, and returns notifications for interested subscribers.
+    ///
+    /// Setting the root of the tree requires the value to be a struct.
+    pub fn set(&mut self, key: &str, value: Value) -> Result<Vec<ParamUpdate>, String> {
+        let parts = naming::split(key);
+        match parts.split_last() {
+            None => match value.clone() {
+                Value::Struct(tree) => self.tree = tree,
+                _ => return Err("Cannot set root of parameter tree to non-struct".into()),
+            },
+            Some((last, namespaces)) => {
+                let mut node = &mut self.tree;
+                for namespace in namespaces {
+                    let entry = node
+                        .entry(String::from(*namespace))
+                        .or_insert_with(|| Value::Struct(HashMap::new()));
+                    if !matches!(entry, Value::Struct(_)) {
+                        *entry = Value::Struct(HashMap::new());
+                    }
+                    node = match entry {
+                        Value::Struct(children) => children,
+                        _ => unreachable!(),
+                    };
+                }
+                node.insert(String::from(*last), value.clone());
+            }
+        }
+        Ok(self.compute_updates(key, &value))
+    }
+
+    /// Deletes a parameter, and returns notifications for interested subscribers.
+    ///
+    /// Returns `None` if the parameter did not exist.
+    pub fn delete(&mut self, key: &str) -> Option<Vec<ParamUpdate>> {
+        let parts = naming::split(key);
+        let (last, namespaces) = match parts.split_last() {
+            Some(v) => v,
+            None => {
+                self.tree.clear();
+                return Some(self.compute_updates(key, &Value::Struct(HashMap::new())));
+            }
+        };
+        let mut node = &mut self.tree;
+        for namespace in namespaces {
+            node = match node.get_mut(*namespace) {
+                Some(Value::Struct(children)) => children,
+                _ => return None,
+            };
+        }
+        node.remove(*last)?;
+        Some(self.compute_updates(key, &Value::Struct(HashMap::new())))
+    }
+
+    /// Lists the full names of all leaf parameters.
+    pub fn names(&self) -> Vec<String> {
+        let mut output = vec![];
+        collect_names("", &self.tree, &mut output);
+        output.sort();
+        output
+    }
+
+    /// Searches for a parameter upwards through the caller's namespaces.
+    ///
+    /// The first component of the key is looked up first, starting from the caller's namespace
+    /// and moving towards the root, with the rest of the key appended to the first match.
+    pub fn search(&self, caller_id: &str, key: &str) -> Option<String> {
+        if key.starts_with('/') {
+            return if self.has(key) {
+                Some(naming::canonicalize(key))
+            } else {
+                None
+            };
+        }
+        let key_parts = naming::split(key);
+        let (first, rest) = key_parts.split_first()?;
+        let namespace = naming::namespace(caller_id);
+        let namespace_parts = naming::split(&namespace);
+        for depth in (0..=namespace_parts.len()).rev() {
+            let mut search_key = namespace_parts[..depth].to_vec();
+            search_key.push(first);
+            let search_key = format!("/{}", search_key.join("/"));
+            if self.has(&search_key) {
+                let mut found = search_key;
+                for part in rest {
+                    found.push('/');
+                    found.push_str(part);
+                }
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// Subscribes a node to changes of a key, returning the key's current value.
+    pub fn subscribe(&mut self, key: &str, caller_id: &str, caller_api: &str) -> Value {
+        self.subscriptions
+            .entry(naming::with_trailing_slash(key))
+            .or_default()
+            .insert(caller_id.into(), caller_api.into());
+        self.get(key)
+            .unwrap_or_else(|| Value::Struct(HashMap::new()))
+    }
+
+    /// Unsubscribes a node from changes of a key, returning whether it was subscribed.
+    pub fn unsubscribe(&mut self, key: &str, caller_id: &str, caller_api: &str) -> bool {
+        let key = naming::with_trailing_slash(key);
+        let subscribers = match self.subscriptions.get_mut(&key) {
+            Some(v) => v,
+            None => return false,
+        };
+        let removed = match subscribers.get(caller_id) {
+            Some(api) if api == caller_api => subscribers.remove(caller_id).is_some(),
+            _ => false,
+        };
+        if subscribers.is_empty() {
+            self.subscriptions.remove(&key);
+        }
+        removed
+    }
+
+    pub fn has_subscriptions(&self, caller_id: &str) -> bool {
+        self.subscriptions
+            .values()
+            .any(|subscribers| subscribers.contains_key(caller_id))
+    }
+
+    /// Removes all subscriptions held by the given node.
+    pub fn drop_node(&mut self, caller_id: &str) {
+        for subscribers in self.subscriptions.values_mut() {
+            subscribers.remove(caller_id);
+        }
+        self.subscriptions.retain(|_, v| !v.is_empty());
+    }
+
+    fn compute_updates(&self, key: &str, value: &Value) -> Vec<ParamUpdate> {
+        let key = naming::with_trailing_slash(key);
+        let mut updates = vec![];
+        for (subscribed_key, subscribers) in &self.subscriptions {
+            let (update_key, update_value) = if key.starts_with(subscribed_key.as_str()) {
+                (key.clone(), value.clone())
+            } else if subscribed_key.starts_with(key.as_str()) {
+                let value = naming::split(&subscribed_key[key.len()..])
+                    .into_iter()
+                    .try_fold(value, |value, part| match value {
+                        Value::Struct(children) => children.get(part),
+                        _ => None,
+                    })
+                    .cloned()
+                    .unwrap_or_else(|| Value::Struct(HashMap::new()));
+                (subscribed_key.clone(), value)
+            } else {
+                continue;
+            };
+            for caller_api in subscribers.values() {
+                updates.push(ParamUpdate {
+                    caller_api: caller_api.clone(),
+                    key: update_key.clone(),
+                    value: update_value.clone(),
+                });
+            }
+        }
+        updates
+    }
+}
+
+fn collect_names(prefix: &str, tree: &HashMap<String, Value>, output: &mut Vec<String>) {
+    for (key, value) in tree {
+        let name = format!("{}/{}", prefix, key);
+        match value {
+            Value::Struct(children) if !children.is_empty() => {
+                collect_names(&name, children, output)
+            }
+            _ => output.push(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(items: &[(&str, Value)]) -> Value {
+        Value::Struct(
+            items
+                .iter()
+                .map(|(k, v)| (String::from(*k), v.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn sets_and_gets_nested_values() {
+        let mut server = ParamServer::default();
+        server.set("/a/b/c", Value::Int(5)).unwrap();
+        server.set("/a/d", Value::Bool(true)).unwrap();
+        assert_eq!(Some(Value::Int(5)), server.get("/a/b/c"));
+        assert_eq!(Some(tree(&[("c", Value::Int(5))])), server.get("/a/b/"));
+        assert_eq!(None, server.get("/a/b/c/d"));
+        assert_eq!(None, server.get("/x"));
+        assert_eq!(vec!["/a/b/c", "/a/d"], server.names());
+    }
+
+    #[test]
+    fn setting_replaces_subtrees_and_leaves() {
+        let mut server = ParamServer::default();
+        server.set("/a/b", Value::Int(5)).unwrap();
+        server.set("/a/b/c", Value::Int(6)).unwrap();
+        assert_eq!(Some(tree(&[("c", Value::Int(6))])), server.get("/a/b"));
+        server.set("/a", Value::Int(7)).unwrap();
+        assert_eq!(Some(Value::Int(7)), server.get("/a"));
+        assert!(server.set("/", Value::Int(7)).is_err());
+    }
+
+    #[test]
+    fn deletes_values() {
+        let mut server = ParamServer::default();
+        server.set("/a/b", Value::Int(5)).unwrap();
+        assert!(server.delete("/a/b").is_some());
+        assert!(server.delete("/a/b").is_none());
+        assert!(server.delete("/x/y").is_none());
+        assert!(server.has("/a"));
+        assert!(!server.has("/a/b"));
+    }
+
+    #[test]
+    fn searches_upwards() {
+        let mut server = ParamServer::default();
+        server.set("/a/x", Value::Int(1)).unwrap();
+        server.set("/a/b/y/z", Value::Int(2)).unwrap();
+        assert_eq!(
+            Some(String::from("/a/x")),
+            server.search("/a/b/c/node", "x")
+        );
+        assert_eq!(
+            Some(String::from("/a/b/y/z")),
+            server.search("/a/b/c/node", "y/z")
+        );
+        assert_eq!(
+            Some(String::from("/a/b/y/missing")),
+            server.search("/a/b/node", "y/missing")
+        );
+        assert_eq!(None, server.search("/node", "x"));
+        assert_eq!(Some(String::from("/a/x")), server.search("/node", "/a/x"));
+    }
+
+    #[test]
+    fn notifies_ancestor_and_descendant_subscribers() {
+        let mut server = ParamServer::default();
+        server.subscribe("/", "/root", "http://root");
+        server.subscribe("/a/b", "/exact", "http://exact");
+        server.subscribe("/a/b/c", "/child", "http://child");
+        server.subscribe("/x", "/other", "http://other");
+        let mut updates = server.set("/a/b", tree(&[("c", Value::Int(3))])).unwrap();
+        updates.sort_by(|a, b| a.caller_api.cmp(&b.caller_api));
+        assert_eq!(
+            vec![
+                ParamUpdate {
+                    caller_api: "http://child".into(),
+                    key: "/a/b/c/".into(),
+                    value: Value::Int(3),
+                },
+                ParamUpdate {
+                    caller_api: "http://exact".into(),
+                    key: "/a/b/".into(),
+                    value: tree(&[("c", Value::Int(3))]),
+                },
+                ParamUpdate {
+                    caller_api: "http://root".into(),
+                    key: "/a/b/".into(),
+                    value: tree(&[("c", Value::Int(3))]),
+                },
+            ],
+            updates
+        );
+    }
+
+    #[test]
+    fn notifies_deletions_with_empty_struct() {
+        let mut server = ParamServer::default();
+        server.set("/a/b", Value::Int(5)).unwrap();
+        server.subscribe("/a/b", "/node", "http://node");
+        let updates = server.delete("/a").unwrap();
+        assert_eq!(
+            vec![ParamUpdate {
+                caller_api: "http://node".into(),
+                key: "/a/b/".into(),
+                value: tree(&[]),
+            }],
+            updates
+        );
+    }
+
+    #[test]
+    fn unsubscribes_nodes() {
+        let mut server = ParamServer::default();
+        assert_eq!(tree(&[]), server.subscribe("/a", "/node", "http://node"));
+        assert!(!server.unsubscribe("/a", "/node", "http://other"));
+        assert!(server.unsubscribe("/a", "/node", "http://node"));
+        assert!(server.set("/a", Value::Int(1)).unwrap().is_empty());
+    }
+}
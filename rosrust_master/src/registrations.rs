@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Bookkeeping of all nodes, topics and services known to the master.
+#[derive(Default)]
+pub struct Registrations {
+    nodes: BTreeMap<String, String>,
+    publishers: BTreeMap<String, BTreeSet<String>>,
+    subscribers: BTreeMap<String, BTreeSet<String>>,
+    topic_types: BTreeMap<String, String>,
+    services: BTreeMap<String, ServiceProvider>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceProvider {
+    pub caller_id: String,
+    pub service_api: String,
+}
+
+/// Outcome of a node announcing itself to the master.
+pub enum NodeRegistration {
+    New,
+    Existing,
+    /// Another node was already using the same name with a different API.
+    ///
+    /// Contains the API of the replaced node, which should be told to shut down.
+    Replaced(String),
+}
+
+impl Registrations {
+    pub fn register_node(&mut self, caller_id: &str, caller_api: &str) -> NodeRegistration {
+        match self.nodes.insert(caller_id.into(), caller_api.into()) {
+            None => NodeRegistration::New,
+            Some(old_api) if old_api == caller_api => NodeRegistration::Existing,
+            Some(old_api) => {
+                self.drop_node_registrations(caller_id);
+                NodeRegistration::Replaced(old_api)
+            }
+        }
+    }
+
+    pub fn lookup_node(&self, caller_id: &str) -> Option<&str> {
+        self.nodes.get(caller_id).map(String::as_str)
+    }
+
+    fn drop_node_registrations(&mut self, caller_id: &str) {
+        for nodes in self.publishers.values_mut() {
+            nodes.remove(caller_id);
+        }
+        for nodes in self.subscribers.values_mut() {
+            nodes.remove(caller_id);
+        }
+        self.publishers.retain(|_, v| !v.is_empty());
+        self.subscribers.retain(|_, v| !v.is_empty());
+        self.services.retain(|_, v| v.caller_id != caller_id);
+    }
+
+    /// Removes nodes that have nothing registered anymore.
+    ///
+    /// Nodes with parameter subscriptions are kept, as they still need to be reachable.
+    pub fn prune_node(&mut self, caller_id: &str, has_param_subscriptions: bool) {
+        if has_param_subscriptions {
+            return;
+        }
+        let in_use = self.publishers.values().any(|v| v.contains(caller_id))
+            || self.subscribers.values().any(|v| v.contains(caller_id))
+            || self.services.values().any(|v| v.caller_id == caller_id);
+        if !in_use {
+            self.nodes.remove(caller_id);
+        }
+    }
+
+    fn set_topic_type(&mut self, topic: &str, topic_type: &str) {
+        if topic_type == "*" && self.topic_types.contains_key(topic) {
+            return;
+        }
+        self.topic_types.insert(topic.into(), topic_type.into());
+    }
+
+    pub fn register_publisher(&mut self, topic: &str, topic_type: &str, caller_id: &str) {
+        self.set_topic_type(topic, topic_type);
+        self.publishers
+            .entry(topic.into())
+            .or_default()
+            .insert(caller_id.into());
+    }
+
+    pub fn unregister_publisher(&mut self, topic: &str, caller_id: &str) -> bool {
+        remove_from(&mut self.publishers, topic, caller_id)
+    }
+
+    pub fn register_subscriber(&mut self, topic: &str, topic_type: &str, caller_id: &str) {
+        self.set_topic_type(topic, topic_type);
+        self.subscribers
+            .entry(topic.into())
+            .or_default()
+            .insert(caller_id.into());
+    }
+
+    pub fn unregister_subscriber(&mut self, topic: &str, caller_id: &str) -> bool {
+        remove_from(&mut self.subscribers, topic, caller_id)
+    }
+
+    pub fn register_service(&mut self, service: &str, caller_id: &str, service_api: &str) {
+        self.services.insert(
+            service.into(),
+            ServiceProvider {
+                caller_id: caller_id.into(),
+                service_api: service_api.into(),
+            },
+        );
+    }
+
+    pub fn unregister_service(&mut self, service: &str, service_api: &str) -> bool {
+        match self.services.get(service) {
+            Some(provider) if provider.service_api == service_api => {
+                self.services.remove(service);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn lookup_service(&self, service: &str) -> Option<&str> {
+        self.services
+            .get(service)
+            .map(|provider| provider.service_api.as_str())
+    }
+
+    fn apis_of(&self, nodes: Option<&BTreeSet<String>>) -> Vec<String> {
+        nodes
+            .into_iter()
+            .flatten()
+            .filter_map(|node| self.nodes.get(node))
+            .cloned()
+            .collect()
+    }
+
+    pub fn publisher_apis(&self, topic: &str) -> Vec<String> {
+        self.apis_of(self.publishers.get(topic))
+    }
+
+    pub fn subscriber_apis(&self, topic: &str) -> Vec<String> {
+        self.apis_of(self.subscribers.get(topic))
+    }
+
+    pub fn published_topics(&self) -> Vec<(String, String)> {
+        self.publishers
+            .keys()
+            .map(|topic| {
+                let topic_type = self.topic_types.get(topic).cloned().unwrap_or_default();
+                (topic.clone(), topic_type)
+            })
+            .collect()
+    }
+
+    pub fn topic_types(&self) -> Vec<(String, String)> {
+        self.topic_types
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    pub fn publishers(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.publishers
+    }
+
+    pub fn subscribers(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.subscribers
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = (&String, &ServiceProvider)> {
+        self.services.iter()
+    }
+}
+
+fn remove_from(mapping: &mut BTreeMap<String, BTreeSet<String>>, topic: &str, node: &str) -> bool {
+    let nodes = match mapping.get_mut(topic) {
+        Some(v) => v,
+        None => return false,
+    };
+    let removed = nodes.remove(node);
+    if nodes.is_empty() {
+        mapping.remove(topic);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_publishers_and_subscribers() {
+        let mut registrations = Registrations::default();
+        registrations.register_node("/pub", "http://pub");
+        registrations.register_node("/sub", "http://sub");
+        registrations.register_publisher("/chatter", "std_msgs/String", "/pub");
+        registrations.register_subscriber("/chatter", "*", "/sub");
+        assert_eq!(vec!["http://pub"], registrations.publisher_apis("/chatter"));
+        assert_eq!(
+            vec!["http://sub"],
+            registrations.subscriber_apis("/chatter")
+        );
+        assert_eq!(
+            vec![(String::from("/chatter"), String::from("std_msgs/String"))],
+            registrations.topic_types()
+        );
+        assert!(registrations.unregister_publisher("/chatter", "/pub"));
+        assert!(!registrations.unregister_publisher("/chatter", "/pub"));
+        assert!(registrations.publisher_apis("/chatter").is_empty());
+        assert!(registrations.published_topics().is_empty());
+    }
+
+    #[test]
+    fn replaces_nodes_with_same_name() {
+        let mut registrations = Registrations::default();
+        registrations.register_node("/node", "http://old");
+        registrations.register_publisher("/chatter", "std_msgs/String", "/node");
+        registrations.register_service("/srv", "/node", "rosrpc://old");
+        match registrations.register_node("/node", "http://new") {
+            NodeRegistration::Replaced(api) => assert_eq!("http://old", api),
+            _ => panic!("Node should have been replaced"),
+        }
+        assert!(registrations.publisher_apis("/chatter").is_empty());
+        assert_eq!(None, registrations.lookup_service("/srv"));
+        assert_eq!(Some("http://new"), registrations.lookup_node("/node"));
+    }
+
+    #[test]
+    fn unregisters_services_only_with_matching_api() {
+        let mut registrations = Registrations::default();
+        registrations.register_node("/node", "http://node");
+        registrations.register_service("/srv", "/node", "rosrpc://a");
+        assert!(!registrations.unregister_service("/srv", "rosrpc://b"));
+        assert_eq!(Some("rosrpc://a"), registrations.lookup_service("/srv"));
+        assert!(registrations.unregister_service("/srv", "rosrpc://a"));
+        registrations.prune_node("/node", false);
+        assert_eq!(None, registrations.lookup_node("/node"));
+    }
+}
@@ -0,0 +1,87 @@
+use crossbeam::channel::unbounded;
+use lazy_static::lazy_static;
+use rosrust_master::Master;
+use std::time::Duration;
+
+mod msg {
+    rosrust::rosmsg_include!(std_msgs / String, roscpp_tutorials / TwoInts);
+}
+
+fn global_init() -> Master {
+    let master = Master::new_local().unwrap();
+    std::env::set_var("ROS_MASTER_URI", master.uri());
+    rosrust::init("master_tester");
+    master
+}
+
+lazy_static! {
+    static ref MASTER: Master = global_init();
+}
+
+fn setup() {
+    assert!(MASTER.is_running());
+}
+
+#[test]
+fn publisher_reaches_subscriber() {
+    setup();
+    let (tx, rx) = unbounded();
+
+    let _subscriber = rosrust::subscribe::<msg::std_msgs::String, _>("chatter", 100, move |data| {
+        tx.send(data.data).unwrap();
+    })
+    .unwrap();
+
+    let publisher = rosrust::publish::<msg::std_msgs::String>("chatter", 100).unwrap();
+    publisher
+        .wait_for_subscribers(Some(Duration::from_secs(10)))
+        .unwrap();
+
+    let message = msg::std_msgs::String {
+        data: "hello world".into(),
+    };
+    for _ in 0..50 {
+        publisher.send(message.clone()).unwrap();
+        if let Ok(data) = rx.recv_timeout(Duration::from_millis(100)) {
+            assert_eq!("hello world", data);
+            let topics = rosrust::topics().unwrap();
+            assert!(topics
+                .iter()
+                .any(|t| t.name == "/chatter" && t.datatype == "std_msgs/String"));
+            return;
+        }
+    }
+    panic!("Message never arrived");
+}
+
+#[test]
+fn client_reaches_service() {
+    setup();
+    let _service = rosrust::service::<msg::roscpp_tutorials::TwoInts, _>("add_two_ints", |req| {
+        Ok(msg::roscpp_tutorials::TwoIntsRes { sum: req.a + req.b })
+    })
+    .unwrap();
+
+    rosrust::wait_for_service("add_two_ints", Some(Duration::from_secs(10))).unwrap();
+    let client = rosrust::client::<msg::roscpp_tutorials::TwoInts>("add_two_ints").unwrap();
+    let response = client
+        .req(&msg::roscpp_tutorials::TwoIntsReq { a: 40, b: 2 })
+        .unwrap()
+        .unwrap();
+    assert_eq!(42, response.sum);
+}
+
+#[test]
+fn parameters_round_trip() {
+    setup();
+    let parameter = rosrust::param("/master_test/nested/value").unwrap();
+    parameter.set(&vec![1, 2, 3]).unwrap();
+    assert_eq!(vec![1, 2, 3], parameter.get::<Vec<i32>>().unwrap());
+    assert!(parameter.exists().unwrap());
+    assert_eq!("/master_test/nested/value", parameter.search().unwrap());
+    assert!(rosrust::parameters()
+        .unwrap()
+        .contains(&String::from("/master_test/nested/value")));
+    parameter.delete().unwrap();
+    assert!(!parameter.exists().unwrap());
+}
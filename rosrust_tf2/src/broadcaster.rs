@@ -0,0 +1,77 @@
+use crate::error::Result;
+use rosrust::Publisher;
+use rosrust_msg::geometry_msgs::TransformStamped;
+use rosrust_msg::tf2_msgs::TFMessage;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// Publisher of changing transforms on `/tf`.
+///
+/// Transforms sent together are batched into a single message.
+pub struct TransformBroadcaster {
+    publisher: Publisher<TFMessage>,
+}
+
+impl TransformBroadcaster {
+    /// Starts publishing on the global node.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            publisher: rosrust::publish("/tf", 100)?,
+        })
+    }
+
+    pub fn send_transform(&self, transform: TransformStamped) -> Result<()> {
+        self.send_transforms(vec![transform])
+    }
+
+    pub fn send_transforms(&self, transforms: Vec<TransformStamped>) -> Result<()> {
+        self.publisher.send(TFMessage { transforms })?;
+        Ok(())
+    }
+}
+
+/// Publisher of fixed transforms on `/tf_static`.
+///
+/// Every transform sent so far is kept, with newer ones replacing older ones of the same child
+/// frame. The whole set gets republished latched on every change, so late subscribers receive
+/// all static transforms of the node, and not just the ones from the last call.
+pub struct StaticTransformBroadcaster {
+    publisher: Publisher<TFMessage>,
+    transforms: Mutex<BTreeMap<String, TransformStamped>>,
+}
+
+impl StaticTransformBroadcaster {
+    /// Starts publishing on the global node.
+    pub fn new() -> Result<Self> {
+        let mut publisher = rosrust::publish("/tf_static", 100)?;
+        publisher.set_latching(true);
+        Ok(Self {
+            publisher,
+            transforms: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn send_transform(&self, transform: TransformStamped) -> Result<()> {
+        self.send_transforms(vec![transform])
+    }
+
+    pub fn send_transforms(&self, transforms: Vec<TransformStamped>) -> Result<()> {
+        // Held while sending, so the latched message is always the most complete one
+        let mut stored = self.transforms.lock().expect(FAILED_TO_LOCK);
+        for transform in transforms {
+            stored.insert(transform.child_frame_id.clone(), transform);
+        }
+        self.publisher.send(TFMessage {
+            transforms: stored.values().cloned().collect(),
+        })?;
+        Ok(())
+    }
+
+    /// All transforms that are being published.
+    pub fn transforms(&self) -> Vec<TransformStamped> {
+        let stored = self.transforms.lock().expect(FAILED_TO_LOCK);
+        stored.values().cloned().collect()
+    }
+}
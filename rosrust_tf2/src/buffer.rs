@@ -0,0 +1,413 @@
+use crate::cache::{Entry, FrameCache};
+use crate::error::{ErrorKind, Result, ResultExt};
+use crate::math::Transform;
+use error_chain::bail;
+use rosrust::{Duration, Time};
+use rosrust_msg::geometry_msgs::TransformStamped;
+use rosrust_msg::std_msgs::Header;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time;
+
+static FAILED_TO_LOCK: &str = "Failed to acquire lock";
+
+/// How long transforms are kept by default, counting back from the newest one of each frame.
+pub const DEFAULT_CACHE_TIME: Duration = Duration::from_seconds(10);
+
+// Deeper frame graphs are treated as loops
+const MAX_GRAPH_DEPTH: usize = 1000;
+
+// How often waiting lookups check the node's clock, in case it is simulated
+const WAIT_POLL_PERIOD: time::Duration = time::Duration::from_millis(10);
+
+/// Time-bounded storage of transforms between frames, queryable between any two connected frames.
+///
+/// Frames form a tree, where each frame has one parent at a time. Transforms of dynamic frames
+/// are interpolated between the stored times, while static frames are valid at all times.
+///
+/// Lookups at `Time::new()` use the latest time at which all transforms along the path are
+/// available. Cloning the buffer shares the stored transforms.
+#[derive(Clone)]
+pub struct Buffer {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<BufferState>,
+    updated: Condvar,
+}
+
+struct BufferState {
+    cache_time: Duration,
+    frames: HashMap<String, FrameCache>,
+    parents: HashSet<String>,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Buffer {
+    /// Creates an empty buffer, with the default cache time.
+    pub fn new() -> Self {
+        Self::with_cache_time(DEFAULT_CACHE_TIME)
+    }
+
+    pub fn with_cache_time(cache_time: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(BufferState {
+                    cache_time,
+                    frames: HashMap::new(),
+                    parents: HashSet::new(),
+                }),
+                updated: Condvar::new(),
+            }),
+        }
+    }
+
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, BufferState> {
+        self.inner.state.lock().expect(FAILED_TO_LOCK)
+    }
+
+    pub fn cache_time(&self) -> Duration {
+        self.state().cache_time
+    }
+
+    /// Stores the transform of `child_frame_id` relative to the header's `frame_id`.
+    ///
+    /// Static transforms are valid at all times, and replace any previous transforms of the
+    /// child frame. Transforms older than the cache time get rejected.
+    pub fn set_transform(&self, transform: &TransformStamped, is_static: bool) -> Result<()> {
+        let parent = strip_frame(&transform.header.frame_id);
+        let child = strip_frame(&transform.child_frame_id);
+        if parent.is_empty() || child.is_empty() {
+            bail!(ErrorKind::InvalidTransform(format!(
+                "frames of transform from '{}' to '{}' must not be empty",
+                child, parent
+            )));
+        }
+        if parent == child {
+            bail!(ErrorKind::InvalidTransform(format!(
+                "frame '{}' can't be its own parent",
+                child
+            )));
+        }
+        let entry = Entry {
+            stamp: transform.header.stamp,
+            parent: parent.into(),
+            transform: Transform::from_msg(&transform.transform).ok_or_else(|| {
+                ErrorKind::InvalidTransform(format!(
+                    "transform of frame '{}' has invalid values",
+                    child
+                ))
+            })?,
+        };
+
+        let mut state = self.state();
+        if is_static {
+            state.frames.insert(child.into(), FrameCache::Static(entry));
+        } else {
+            let cache_time = state.cache_time;
+            state
+                .frames
+                .entry(child.into())
+                .or_insert_with(|| FrameCache::Dynamic(Default::default()))
+                .insert(child, entry, cache_time)?;
+        }
+        state.parents.insert(parent.into());
+        drop(state);
+        self.inner.updated.notify_all();
+        Ok(())
+    }
+
+    /// Drops all dynamic transforms, keeping the static ones.
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.frames.retain(|_, cache| cache.is_static());
+        let parents = state
+            .frames
+            .values()
+            .filter_map(FrameCache::latest_parent)
+            .map(String::from)
+            .collect();
+        state.parents = parents;
+    }
+
+    /// Names of all known frames, sorted.
+    pub fn frames(&self) -> Vec<String> {
+        let state = self.state();
+        let mut frames = state
+            .frames
+            .keys()
+            .chain(state.parents.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        frames.sort();
+        frames.dedup();
+        frames
+    }
+
+    /// Transform that maps data in the `source` frame into the `target` frame, at the given time.
+    pub fn lookup_transform(
+        &self,
+        target: &str,
+        source: &str,
+        time: Time,
+    ) -> Result<TransformStamped> {
+        self.state().lookup(target, source, time)
+    }
+
+    /// Transform of data from the `source` frame at `source_time` into the `target` frame at
+    /// `target_time`, assuming that the `fixed` frame does not move over time.
+    ///
+    /// For example, with the world as the fixed frame, this maps where an object was seen by a
+    /// moving robot a moment ago into where the robot is now.
+    pub fn lookup_transform_full(
+        &self,
+        target: &str,
+        target_time: Time,
+        source: &str,
+        source_time: Time,
+        fixed: &str,
+    ) -> Result<TransformStamped> {
+        self.state()
+            .lookup_full(target, target_time, source, source_time, fixed)
+    }
+
+    /// Like `lookup_transform`, but waits for the transform to become available.
+    ///
+    /// The timeout is measured with the node's clock, so this requires `rosrust` to be
+    /// initialized. On timeout, the error is chained to the reason of the last failed lookup.
+    pub fn lookup_transform_with_timeout(
+        &self,
+        target: &str,
+        source: &str,
+        time: Time,
+        timeout: Duration,
+    ) -> Result<TransformStamped> {
+        self.wait_for(target, source, timeout, |state| {
+            state.lookup(target, source, time)
+        })
+    }
+
+    /// Like `lookup_transform_full`, but waits for the transform to become available.
+    pub fn lookup_transform_full_with_timeout(
+        &self,
+        target: &str,
+        target_time: Time,
+        source: &str,
+        source_time: Time,
+        fixed: &str,
+        timeout: Duration,
+    ) -> Result<TransformStamped> {
+        self.wait_for(target, source, timeout, |state| {
+            state.lookup_full(target, target_time, source, source_time, fixed)
+        })
+    }
+
+    pub fn can_transform(&self, target: &str, source: &str, time: Time) -> bool {
+        self.lookup_transform(target, source, time).is_ok()
+    }
+
+    pub fn can_transform_full(
+        &self,
+        target: &str,
+        target_time: Time,
+        source: &str,
+        source_time: Time,
+        fixed: &str,
+    ) -> bool {
+        self.lookup_transform_full(target, target_time, source, source_time, fixed)
+            .is_ok()
+    }
+
+    pub fn can_transform_with_timeout(
+        &self,
+        target: &str,
+        source: &str,
+        time: Time,
+        timeout: Duration,
+    ) -> bool {
+        self.lookup_transform_with_timeout(target, source, time, timeout)
+            .is_ok()
+    }
+
+    fn wait_for<F>(
+        &self,
+        target: &str,
+        source: &str,
+        timeout: Duration,
+        lookup: F,
+    ) -> Result<TransformStamped>
+    where
+        F: Fn(&BufferState) -> Result<TransformStamped>,
+    {
+        let deadline = rosrust::now() + timeout;
+        let mut state = self.state();
+        loop {
+            let err = match lookup(&state) {
+                Ok(transform) => return Ok(transform),
+                Err(err) => err,
+            };
+            if rosrust::now() >= deadline || !rosrust::is_ok() {
+                return Err(err).chain_err(|| ErrorKind::Timeout(target.into(), source.into()));
+            }
+            state = self
+                .inner
+                .updated
+                .wait_timeout(state, WAIT_POLL_PERIOD)
+                .expect(FAILED_TO_LOCK)
+                .0;
+        }
+    }
+}
+
+impl BufferState {
+    fn lookup(&self, target: &str, source: &str, time: Time) -> Result<TransformStamped> {
+        let target = strip_frame(target);
+        let source = strip_frame(source);
+        let (transform, stamp) = self.resolve(target, source, time)?;
+        Ok(TransformStamped {
+            header: Header {
+                seq: 0,
+                stamp,
+                frame_id: target.into(),
+            },
+            child_frame_id: source.into(),
+            transform: transform.to_msg(),
+        })
+    }
+
+    fn lookup_full(
+        &self,
+        target: &str,
+        target_time: Time,
+        source: &str,
+        source_time: Time,
+        fixed: &str,
+    ) -> Result<TransformStamped> {
+        let target = strip_frame(target);
+        let source = strip_frame(source);
+        let fixed = strip_frame(fixed);
+        let (fixed_from_source, _) = self.resolve(fixed, source, source_time)?;
+        let (target_from_fixed, stamp) = self.resolve(target, fixed, target_time)?;
+        Ok(TransformStamped {
+            header: Header {
+                seq: 0,
+                stamp,
+                frame_id: target.into(),
+            },
+            child_frame_id: source.into(),
+            transform: target_from_fixed.then(fixed_from_source).to_msg(),
+        })
+    }
+
+    fn is_known(&self, frame: &str) -> bool {
+        self.frames.contains_key(frame) || self.parents.contains(frame)
+    }
+
+    /// Finds the transform from `source` to `target`, and the time it is valid at.
+    fn resolve(&self, target: &str, source: &str, time: Time) -> Result<(Transform, Time)> {
+        for frame in &[target, source] {
+            if !self.is_known(frame) {
+                bail!(ErrorKind::UnknownFrame(String::from(*frame)));
+            }
+        }
+        if target == source {
+            return Ok((Transform::IDENTITY, time));
+        }
+        let (transform, latest) = self.walk(target, source, time)?;
+        if time != Time::new() {
+            return Ok((transform, time));
+        }
+        match latest {
+            // Only static transforms are involved, so any time works
+            None => Ok((transform, time)),
+            Some(common) => Ok((self.walk(target, source, common)?.0, common)),
+        }
+    }
+
+    /// Chains transforms up the frame tree from both frames, until the paths meet.
+    ///
+    /// Also returns the oldest of the newest stamps of the dynamic frames along the path,
+    /// which is the latest time at which the whole path is available.
+    fn walk(&self, target: &str, source: &str, time: Time) -> Result<(Transform, Option<Time>)> {
+        // Frames above the source, each with the transform from the source, and the stamp
+        let mut source_path = vec![];
+        let mut current = source.to_string();
+        let mut current_from_source = Transform::IDENTITY;
+        let mut latest = None;
+        let mut source_error = None;
+        loop {
+            if current == target {
+                return Ok((current_from_source, latest));
+            }
+            if source_path.len() > MAX_GRAPH_DEPTH {
+                bail!(ErrorKind::FrameLoop(current));
+            }
+            source_path.push((current.clone(), current_from_source, latest));
+            let cache = match self.frames.get(&current) {
+                Some(cache) => cache,
+                None => break,
+            };
+            match cache.lookup(&current, time) {
+                Ok((parent, transform)) => {
+                    current_from_source = transform.then(current_from_source);
+                    latest = oldest(latest, cache.latest_stamp());
+                    current = parent;
+                }
+                // Might not be needed, if the target's path joins below this frame
+                Err(err) => {
+                    source_error = Some(err);
+                    break;
+                }
+            }
+        }
+
+        let mut current = target.to_string();
+        let mut current_from_target = Transform::IDENTITY;
+        let mut target_latest = None;
+        let mut depth = 0;
+        loop {
+            if let Some((_, joint_from_source, source_latest)) =
+                source_path.iter().find(|(frame, ..)| *frame == current)
+            {
+                let transform = current_from_target.inverse().then(*joint_from_source);
+                return Ok((transform, oldest(target_latest, *source_latest)));
+            }
+            depth += 1;
+            if depth > MAX_GRAPH_DEPTH {
+                bail!(ErrorKind::FrameLoop(current));
+            }
+            let cache = match self.frames.get(&current) {
+                Some(cache) => cache,
+                None => break,
+            };
+            let (parent, transform) = cache.lookup(&current, time)?;
+            current_from_target = transform.then(current_from_target);
+            target_latest = oldest(target_latest, cache.latest_stamp());
+            current = parent;
+        }
+
+        match source_error {
+            Some(err) => Err(err),
+            None => bail!(ErrorKind::NotConnected(target.into(), source.into())),
+        }
+    }
+}
+
+fn oldest(a: Option<Time>, b: Option<Time>) -> Option<Time> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Frame names are stored without the leading slash of old `tf` names.
+fn strip_frame(frame: &str) -> &str {
+    frame.strip_prefix('/').unwrap_or(frame)
+}
@@ -0,0 +1,124 @@
+use crate::error::{ErrorKind, Result};
+use crate::math::Transform;
+use error_chain::bail;
+use rosrust::{Duration, Time};
+use std::collections::VecDeque;
+
+/// Transform of a frame relative to its parent, at a point in time.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub stamp: Time,
+    pub parent: String,
+    pub transform: Transform,
+}
+
+/// History of a single frame's transforms to its parent.
+///
+/// Static frames hold a single transform that is valid at all times. Dynamic frames hold
+/// transforms sorted by time, covering the cache time before the latest one.
+pub(crate) enum FrameCache {
+    Static(Entry),
+    Dynamic(VecDeque<Entry>),
+}
+
+impl FrameCache {
+    pub fn is_static(&self) -> bool {
+        matches!(self, FrameCache::Static(..))
+    }
+
+    /// Stores the transform, dropping transforms older than the cache time.
+    pub fn insert(&mut self, frame: &str, entry: Entry, cache_time: Duration) -> Result<()> {
+        let entries = match self {
+            FrameCache::Dynamic(entries) => entries,
+            FrameCache::Static(..) => {
+                *self = FrameCache::Dynamic(VecDeque::new());
+                return self.insert(frame, entry, cache_time);
+            }
+        };
+        if let Some(latest) = entries.back() {
+            if entry.stamp + cache_time < latest.stamp {
+                bail!(ErrorKind::OldData(frame.into(), entry.stamp));
+            }
+        }
+        let position = entries
+            .iter()
+            .rposition(|item| item.stamp <= entry.stamp)
+            .map_or(0, |index| index + 1);
+        if position > 0 && entries[position - 1].stamp == entry.stamp {
+            entries[position - 1] = entry;
+        } else {
+            entries.insert(position, entry);
+        }
+        if let Some(latest) = entries.back().map(|item| item.stamp) {
+            while matches!(entries.front(), Some(item) if item.stamp + cache_time < latest) {
+                entries.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    /// Stamp of the newest transform, or `None` for static frames.
+    pub fn latest_stamp(&self) -> Option<Time> {
+        match self {
+            FrameCache::Static(..) => None,
+            FrameCache::Dynamic(entries) => entries.back().map(|entry| entry.stamp),
+        }
+    }
+
+    /// Parent of the frame, as of the newest transform.
+    pub fn latest_parent(&self) -> Option<&str> {
+        match self {
+            FrameCache::Static(entry) => Some(&entry.parent),
+            FrameCache::Dynamic(entries) => entries.back().map(|entry| entry.parent.as_str()),
+        }
+    }
+
+    /// Transform at the given time, interpolating between the surrounding transforms.
+    ///
+    /// Time zero means the newest available transform.
+    pub fn lookup(&self, frame: &str, time: Time) -> Result<(String, Transform)> {
+        let entries = match self {
+            FrameCache::Static(entry) => return Ok((entry.parent.clone(), entry.transform)),
+            FrameCache::Dynamic(entries) => entries,
+        };
+        let (first, last) = match (entries.front(), entries.back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => bail!(ErrorKind::UnknownFrame(frame.into())),
+        };
+        if time == Time::new() {
+            return Ok((last.parent.clone(), last.transform));
+        }
+        if time < first.stamp {
+            bail!(ErrorKind::ExtrapolationIntoPast(
+                frame.into(),
+                time,
+                first.stamp
+            ));
+        }
+        if time > last.stamp {
+            bail!(ErrorKind::ExtrapolationIntoFuture(
+                frame.into(),
+                time,
+                last.stamp
+            ));
+        }
+        let index = entries
+            .iter()
+            .position(|entry| entry.stamp >= time)
+            .unwrap_or(entries.len() - 1);
+        let after = &entries[index];
+        if after.stamp == time || index == 0 {
+            return Ok((after.parent.clone(), after.transform));
+        }
+        let before = &entries[index - 1];
+        if before.parent != after.parent {
+            // Transforms to different parents can't be blended
+            return Ok((before.parent.clone(), before.transform));
+        }
+        let ratio = (time - before.stamp).seconds() / (after.stamp - before.stamp).seconds();
+        Ok((
+            before.parent.clone(),
+            before.transform.interpolate(after.transform, ratio),
+        ))
+    }
+}
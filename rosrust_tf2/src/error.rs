@@ -0,0 +1,55 @@
+use rosrust::Time;
+
+error_chain::error_chain! {
+    links {
+        Ros(rosrust::error::Error, rosrust::error::ErrorKind);
+    }
+    errors {
+        InvalidTransform(details: String) {
+            description("Invalid transform")
+            display("Invalid transform: {}", details)
+        }
+        OldData(frame: String, stamp: Time) {
+            description("Transform is older than the cache time")
+            display("Transform of frame '{}' at time {} is older than the cache time", frame, stamp)
+        }
+        UnknownFrame(frame: String) {
+            description("Frame does not exist")
+            display("Frame '{}' does not exist", frame)
+        }
+        NotConnected(target: String, source: String) {
+            description("Frames are not part of the same tree")
+            display(
+                "Could not find a connection between '{}' and '{}' because they are not part of the same tree",
+                target,
+                source,
+            )
+        }
+        FrameLoop(frame: String) {
+            description("Frames form a loop")
+            display("Frame '{}' is part of a loop in the frame graph", frame)
+        }
+        ExtrapolationIntoPast(frame: String, requested: Time, earliest: Time) {
+            description("Lookup would require extrapolation into the past")
+            display(
+                "Lookup of frame '{}' would require extrapolation into the past: requested time {} but the earliest data is at time {}",
+                frame,
+                requested,
+                earliest,
+            )
+        }
+        ExtrapolationIntoFuture(frame: String, requested: Time, latest: Time) {
+            description("Lookup would require extrapolation into the future")
+            display(
+                "Lookup of frame '{}' would require extrapolation into the future: requested time {} but the latest data is at time {}",
+                frame,
+                requested,
+                latest,
+            )
+        }
+        Timeout(target: String, source: String) {
+            description("Timed out waiting for transform")
+            display("Timed out waiting for transform from '{}' to '{}'", source, target)
+        }
+    }
+}
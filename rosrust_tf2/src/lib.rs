@@ -0,0 +1,70 @@
+/*!
+This crate provides a [tf2] style transform buffer and listener for `rosrust`.
+
+A [`Buffer`] stores the transforms between coordinate frames for a limited time, and answers
+queries between any two connected frames, interpolating between the stored times. A
+[`TransformListener`] keeps a buffer updated with the transforms published on `/tf` and
+`/tf_static`:
+
+```no_run
+use rosrust_tf2::{Buffer, TransformListener};
+
+rosrust::init("listener");
+let listener = TransformListener::new(Buffer::new()).unwrap();
+let timeout = rosrust::Duration::from_seconds(1);
+while rosrust::is_ok() {
+    match listener.buffer().lookup_transform_with_timeout(
+        "map",
+        "base_link",
+        rosrust::Time::new(),
+        timeout,
+    ) {
+        Ok(transform) => println!("{:?}", transform.transform),
+        Err(err) => println!("{}", err),
+    }
+}
+```
+
+Failed lookups report the reason, like unknown frames, frames in unconnected trees, or
+times that would require extrapolation outside of the stored data.
+
+Transforms get published with a [`TransformBroadcaster`] for moving frames, and with a
+[`StaticTransformBroadcaster`] for frames that never move relative to their parents:
+
+```no_run
+use rosrust_msg::geometry_msgs::{Quaternion, Transform, TransformStamped, Vector3};
+use rosrust_msg::std_msgs::Header;
+use rosrust_tf2::StaticTransformBroadcaster;
+
+rosrust::init("static_broadcaster");
+let broadcaster = StaticTransformBroadcaster::new().unwrap();
+broadcaster
+    .send_transform(TransformStamped {
+        header: Header {
+            seq: 0,
+            stamp: rosrust::now(),
+            frame_id: "base_link".into(),
+        },
+        child_frame_id: "laser".into(),
+        transform: Transform {
+            translation: Vector3 { x: 0.2, y: 0.0, z: 0.1 },
+            rotation: Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+        },
+    })
+    .unwrap();
+rosrust::spin();
+```
+
+[tf2]: http://wiki.ros.org/tf2
+*/
+
+pub use broadcaster::{StaticTransformBroadcaster, TransformBroadcaster};
+pub use buffer::{Buffer, DEFAULT_CACHE_TIME};
+pub use listener::TransformListener;
+
+mod broadcaster;
+mod buffer;
+mod cache;
+pub mod error;
+mod listener;
+mod math;
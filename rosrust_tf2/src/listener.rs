@@ -0,0 +1,54 @@
+use crate::buffer::Buffer;
+use crate::error::Result;
+use rosrust::api::{JumpHandle, JumpThreshold};
+use rosrust::Subscriber;
+use rosrust_msg::tf2_msgs::TFMessage;
+
+/// Fills a buffer with the transforms published on `/tf` and `/tf_static`.
+///
+/// The buffer gets cleared of dynamic transforms when the node's time jumps backwards, like
+/// when a bag or a simulation restarts. Updates stop once the listener is dropped.
+pub struct TransformListener {
+    buffer: Buffer,
+    _tf: Subscriber,
+    _tf_static: Subscriber,
+    _jump: JumpHandle,
+}
+
+impl TransformListener {
+    /// Starts listening on the global node.
+    pub fn new(buffer: Buffer) -> Result<Self> {
+        let tf = subscribe(&buffer, "/tf", false)?;
+        let tf_static = subscribe(&buffer, "/tf_static", true)?;
+        let jump_buffer = buffer.clone();
+        let jump =
+            rosrust::add_jump_callback(JumpThreshold::backward(), move |_| jump_buffer.clear());
+        Ok(Self {
+            buffer,
+            _tf: tf,
+            _tf_static: tf_static,
+            _jump: jump,
+        })
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+fn subscribe(buffer: &Buffer, topic: &str, is_static: bool) -> Result<Subscriber> {
+    let buffer = buffer.clone();
+    let subscriber = rosrust::subscribe_with_ids(topic, 100, move |msg: TFMessage, caller_id| {
+        for transform in &msg.transforms {
+            if let Err(err) = buffer.set_transform(transform, is_static) {
+                rosrust::ros_warn_throttle!(
+                    5.0,
+                    "Ignoring transform published by {}: {}",
+                    caller_id,
+                    err
+                );
+            }
+        }
+    })?;
+    Ok(subscriber)
+}
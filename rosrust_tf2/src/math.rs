@@ -0,0 +1,158 @@
+use rosrust_msg::geometry_msgs;
+
+/// Rigid transform, mapping points from a child frame into its parent frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Transform {
+    pub translation: [f64; 3],
+    /// Unit quaternion, in `[x, y, z, w]` order.
+    pub rotation: [f64; 4],
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        translation: [0.0; 3],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+    };
+
+    /// Converts the message, normalizing the rotation.
+    ///
+    /// Returns `None` if any value is not finite, or the rotation is too close to zero.
+    pub fn from_msg(msg: &geometry_msgs::Transform) -> Option<Self> {
+        let t = &msg.translation;
+        let r = &msg.rotation;
+        let translation = [t.x, t.y, t.z];
+        let rotation = [r.x, r.y, r.z, r.w];
+        if !translation
+            .iter()
+            .chain(rotation.iter())
+            .all(|v| v.is_finite())
+        {
+            return None;
+        }
+        let norm = dot(rotation, rotation).sqrt();
+        if norm < 1e-6 {
+            return None;
+        }
+        Some(Self {
+            translation,
+            rotation: scale(rotation, 1.0 / norm),
+        })
+    }
+
+    pub fn to_msg(self) -> geometry_msgs::Transform {
+        let [x, y, z] = self.translation;
+        let [qx, qy, qz, qw] = self.rotation;
+        geometry_msgs::Transform {
+            translation: geometry_msgs::Vector3 { x, y, z },
+            rotation: geometry_msgs::Quaternion {
+                x: qx,
+                y: qy,
+                z: qz,
+                w: qw,
+            },
+        }
+    }
+
+    /// Transform that applies `rhs` first, and then `self`.
+    pub fn then(self, rhs: Transform) -> Transform {
+        let rotated = rotate(self.rotation, rhs.translation);
+        Transform {
+            translation: [
+                self.translation[0] + rotated[0],
+                self.translation[1] + rotated[1],
+                self.translation[2] + rotated[2],
+            ],
+            rotation: multiply(self.rotation, rhs.rotation),
+        }
+    }
+
+    pub fn inverse(self) -> Transform {
+        let [x, y, z, w] = self.rotation;
+        let rotation = [-x, -y, -z, w];
+        let [tx, ty, tz] = rotate(rotation, self.translation);
+        Transform {
+            translation: [-tx, -ty, -tz],
+            rotation,
+        }
+    }
+
+    /// Linear interpolation of the translation and SLERP of the rotation.
+    pub fn interpolate(self, other: Transform, ratio: f64) -> Transform {
+        let mut translation = [0.0; 3];
+        for (i, value) in translation.iter_mut().enumerate() {
+            *value = self.translation[i] + (other.translation[i] - self.translation[i]) * ratio;
+        }
+        Transform {
+            translation,
+            rotation: slerp(self.rotation, other.rotation, ratio),
+        }
+    }
+}
+
+fn dot(a: [f64; 4], b: [f64; 4]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3]
+}
+
+fn scale(q: [f64; 4], factor: f64) -> [f64; 4] {
+    [q[0] * factor, q[1] * factor, q[2] * factor, q[3] * factor]
+}
+
+fn multiply(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+fn rotate(q: [f64; 4], v: [f64; 3]) -> [f64; 3] {
+    // v' = v + 2w(u x v) + 2u x (u x v), for the quaternion (u, w)
+    let [x, y, z, w] = q;
+    let cross = |a: [f64; 3], b: [f64; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let u = [x, y, z];
+    let uv = cross(u, v);
+    let uuv = cross(u, uv);
+    [
+        v[0] + 2.0 * (w * uv[0] + uuv[0]),
+        v[1] + 2.0 * (w * uv[1] + uuv[1]),
+        v[2] + 2.0 * (w * uv[2] + uuv[2]),
+    ]
+}
+
+fn slerp(a: [f64; 4], b: [f64; 4], ratio: f64) -> [f64; 4] {
+    let mut cos_theta = dot(a, b);
+    // Take the shorter path around the sphere
+    let b = if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        scale(b, -1.0)
+    } else {
+        b
+    };
+    let (wa, wb) = if cos_theta > 0.9995 {
+        // Nearly parallel, where linear interpolation is accurate and numerically stable
+        (1.0 - ratio, ratio)
+    } else {
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        (
+            ((1.0 - ratio) * theta).sin() / sin_theta,
+            (ratio * theta).sin() / sin_theta,
+        )
+    };
+    let q = [
+        wa * a[0] + wb * b[0],
+        wa * a[1] + wb * b[1],
+        wa * a[2] + wb * b[2],
+        wa * a[3] + wb * b[3],
+    ];
+    scale(q, 1.0 / dot(q, q).sqrt())
+}
@@ -0,0 +1,93 @@
+use lazy_static::lazy_static;
+use rosrust::api::testing::TestKit;
+use rosrust::{Duration, Time};
+use rosrust_msg::geometry_msgs::{Quaternion, Transform, TransformStamped, Vector3};
+use rosrust_msg::std_msgs::Header;
+use rosrust_msg::tf2_msgs::TFMessage;
+use rosrust_tf2::{Buffer, StaticTransformBroadcaster, TransformBroadcaster, TransformListener};
+use std::time;
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+lazy_static! {
+    static ref KIT: TestKit = {
+        let kit = TestKit::new().unwrap();
+        rosrust::try_init_with_test_kit("broadcaster", &kit).unwrap();
+        kit
+    };
+}
+
+fn transform(parent: &str, child: &str, x: f64) -> TransformStamped {
+    TransformStamped {
+        header: Header {
+            seq: 0,
+            stamp: Time::from_seconds(10),
+            frame_id: parent.into(),
+        },
+        child_frame_id: child.into(),
+        transform: Transform {
+            translation: Vector3 { x, y: 0.0, z: 0.0 },
+            rotation: Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+        },
+    }
+}
+
+fn children(message: &TFMessage) -> Vec<(&str, f64)> {
+    message
+        .transforms
+        .iter()
+        .map(|t| (t.child_frame_id.as_str(), t.transform.translation.x))
+        .collect()
+}
+
+#[test]
+fn batches_transforms() {
+    let tf = KIT.capture::<TFMessage>("/tf").unwrap();
+    let broadcaster = TransformBroadcaster::new().unwrap();
+    broadcaster
+        .send_transforms(vec![
+            transform("world", "base", 1.0),
+            transform("base", "arm", 2.0),
+        ])
+        .unwrap();
+    broadcaster
+        .send_transform(transform("world", "base", 3.0))
+        .unwrap();
+
+    let first = tf.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(vec![("base", 1.0), ("arm", 2.0)], children(&first));
+    let second = tf.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(vec![("base", 3.0)], children(&second));
+}
+
+#[test]
+fn latches_all_static_transforms() {
+    let broadcaster = StaticTransformBroadcaster::new().unwrap();
+    broadcaster
+        .send_transform(transform("base", "laser", 1.0))
+        .unwrap();
+    broadcaster
+        .send_transforms(vec![
+            transform("base", "camera", 2.0),
+            transform("base", "laser", 3.0),
+        ])
+        .unwrap();
+    assert_eq!(2, broadcaster.transforms().len());
+
+    // Late subscribers get every transform, not just the ones from the last call
+    let tf_static = KIT.capture::<TFMessage>("/tf_static").unwrap();
+    let latched = tf_static.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(vec![("camera", 2.0), ("laser", 3.0)], children(&latched));
+
+    let listener = TransformListener::new(Buffer::new()).unwrap();
+    let camera = listener
+        .buffer()
+        .lookup_transform_with_timeout("laser", "camera", Time::new(), Duration::from_seconds(1))
+        .unwrap();
+    assert_eq!(-1.0, camera.transform.translation.x);
+}
@@ -0,0 +1,305 @@
+use rosrust::{Duration, Time};
+use rosrust_msg::geometry_msgs::{Quaternion, Transform, TransformStamped, Vector3};
+use rosrust_msg::std_msgs::Header;
+use rosrust_tf2::error::ErrorKind;
+use rosrust_tf2::Buffer;
+
+fn transform(parent: &str, child: &str, stamp: Time, xyz: [f64; 3], yaw: f64) -> TransformStamped {
+    TransformStamped {
+        header: Header {
+            seq: 0,
+            stamp,
+            frame_id: parent.into(),
+        },
+        child_frame_id: child.into(),
+        transform: Transform {
+            translation: Vector3 {
+                x: xyz[0],
+                y: xyz[1],
+                z: xyz[2],
+            },
+            rotation: Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: (yaw / 2.0).sin(),
+                w: (yaw / 2.0).cos(),
+            },
+        },
+    }
+}
+
+fn at(seconds: f64) -> Time {
+    Time::from_nanos((seconds * 1e9) as i64)
+}
+
+fn assert_transform(actual: &TransformStamped, xyz: [f64; 3], yaw: f64) {
+    let t = &actual.transform.translation;
+    let r = &actual.transform.rotation;
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    assert!(
+        close(t.x, xyz[0]) && close(t.y, xyz[1]) && close(t.z, xyz[2]),
+        "translation {:?} is not {:?}",
+        t,
+        xyz
+    );
+    // Both signs of a quaternion describe the same rotation
+    let sign = if r.w < 0.0 { -1.0 } else { 1.0 };
+    assert!(
+        close(r.x, 0.0)
+            && close(r.y, 0.0)
+            && close(sign * r.z, (yaw / 2.0).sin())
+            && close(sign * r.w, (yaw / 2.0).cos()),
+        "rotation {:?} is not a yaw of {}",
+        r,
+        yaw
+    );
+}
+
+fn error_kind(result: rosrust_tf2::error::Result<TransformStamped>) -> ErrorKind {
+    result.expect_err("Lookup should fail").0
+}
+
+const QUARTER: f64 = std::f64::consts::FRAC_PI_2;
+
+#[test]
+fn chains_transforms_through_the_tree() {
+    let buffer = Buffer::new();
+    let stamp = at(10.0);
+    buffer
+        .set_transform(
+            &transform("world", "base", stamp, [1.0, 0.0, 0.0], QUARTER),
+            false,
+        )
+        .unwrap();
+    buffer
+        .set_transform(
+            &transform("base", "laser", stamp, [0.5, 0.0, 0.2], 0.0),
+            false,
+        )
+        .unwrap();
+    buffer
+        .set_transform(
+            &transform("base", "camera", stamp, [0.0, 1.0, 0.0], QUARTER),
+            false,
+        )
+        .unwrap();
+
+    let laser = buffer.lookup_transform("world", "laser", stamp).unwrap();
+    assert_eq!("world", laser.header.frame_id);
+    assert_eq!("laser", laser.child_frame_id);
+    assert_eq!(stamp, laser.header.stamp);
+    assert_transform(&laser, [1.0, 0.5, 0.2], QUARTER);
+
+    let inverse = buffer.lookup_transform("laser", "world", stamp).unwrap();
+    assert_transform(&inverse, [-0.5, 1.0, -0.2], -QUARTER);
+
+    let sibling = buffer.lookup_transform("camera", "laser", stamp).unwrap();
+    assert_transform(&sibling, [-1.0, -0.5, 0.2], -QUARTER);
+
+    let identity = buffer.lookup_transform("/laser", "laser", stamp).unwrap();
+    assert_transform(&identity, [0.0; 3], 0.0);
+    assert_eq!(vec!["base", "camera", "laser", "world"], buffer.frames());
+}
+
+#[test]
+fn interpolates_between_stamps() {
+    let buffer = Buffer::new();
+    buffer
+        .set_transform(&transform("world", "base", at(10.0), [0.0; 3], 0.0), false)
+        .unwrap();
+    buffer
+        .set_transform(
+            &transform("world", "base", at(12.0), [2.0, 4.0, 0.0], QUARTER),
+            false,
+        )
+        .unwrap();
+
+    let middle = buffer.lookup_transform("world", "base", at(11.0)).unwrap();
+    assert_transform(&middle, [1.0, 2.0, 0.0], QUARTER / 2.0);
+    let quarter = buffer.lookup_transform("world", "base", at(10.5)).unwrap();
+    assert_transform(&quarter, [0.5, 1.0, 0.0], QUARTER / 4.0);
+}
+
+#[test]
+fn reports_lookup_failures() {
+    let buffer = Buffer::new();
+    buffer
+        .set_transform(&transform("world", "base", at(10.0), [0.0; 3], 0.0), false)
+        .unwrap();
+    buffer
+        .set_transform(&transform("world", "base", at(12.0), [0.0; 3], 0.0), false)
+        .unwrap();
+    buffer
+        .set_transform(&transform("map", "robot", at(10.0), [0.0; 3], 0.0), false)
+        .unwrap();
+
+    match error_kind(buffer.lookup_transform("world", "base", at(9.0))) {
+        ErrorKind::ExtrapolationIntoPast(frame, requested, earliest) => {
+            assert_eq!("base", frame);
+            assert_eq!(at(9.0), requested);
+            assert_eq!(at(10.0), earliest);
+        }
+        kind => panic!("Unexpected error: {}", kind),
+    }
+    match error_kind(buffer.lookup_transform("base", "world", at(12.5))) {
+        ErrorKind::ExtrapolationIntoFuture(frame, _, latest) => {
+            assert_eq!("base", frame);
+            assert_eq!(at(12.0), latest);
+        }
+        kind => panic!("Unexpected error: {}", kind),
+    }
+    assert!(matches!(
+        error_kind(buffer.lookup_transform("world", "odom", at(10.0))),
+        ErrorKind::UnknownFrame(frame) if frame == "odom"
+    ));
+    assert!(matches!(
+        error_kind(buffer.lookup_transform("robot", "base", at(10.0))),
+        ErrorKind::NotConnected(target, source) if target == "robot" && source == "base"
+    ));
+    assert!(!buffer.can_transform("world", "base", at(13.0)));
+    assert!(buffer.can_transform("world", "base", at(11.0)));
+}
+
+#[test]
+fn looks_up_latest_common_time() {
+    let buffer = Buffer::new();
+    for &stamp in &[10.0, 12.0] {
+        buffer
+            .set_transform(
+                &transform("world", "base", at(stamp), [stamp, 0.0, 0.0], 0.0),
+                false,
+            )
+            .unwrap();
+    }
+    for &stamp in &[10.0, 11.0] {
+        buffer
+            .set_transform(
+                &transform("base", "laser", at(stamp), [0.0, stamp, 0.0], 0.0),
+                false,
+            )
+            .unwrap();
+    }
+    buffer
+        .set_transform(
+            &transform("laser", "lens", Time::new(), [0.0, 0.0, 1.0], 0.0),
+            true,
+        )
+        .unwrap();
+
+    let latest = buffer
+        .lookup_transform("world", "lens", Time::new())
+        .unwrap();
+    assert_eq!(at(11.0), latest.header.stamp);
+    assert_transform(&latest, [11.0, 11.0, 1.0], 0.0);
+
+    let base = buffer
+        .lookup_transform("world", "base", Time::new())
+        .unwrap();
+    assert_eq!(at(12.0), base.header.stamp);
+
+    let fixed = buffer
+        .lookup_transform("laser", "lens", Time::new())
+        .unwrap();
+    assert_eq!(Time::new(), fixed.header.stamp);
+}
+
+#[test]
+fn keeps_static_transforms_for_all_times() {
+    let buffer = Buffer::new();
+    buffer
+        .set_transform(
+            &transform("base", "laser", at(5.0), [0.5, 0.0, 0.0], 0.0),
+            true,
+        )
+        .unwrap();
+    buffer
+        .set_transform(
+            &transform("world", "base", at(100.0), [1.0, 0.0, 0.0], 0.0),
+            false,
+        )
+        .unwrap();
+
+    let laser = buffer
+        .lookup_transform("world", "laser", at(100.0))
+        .unwrap();
+    assert_transform(&laser, [1.5, 0.0, 0.0], 0.0);
+    assert!(buffer.can_transform("base", "laser", at(1.0)));
+
+    buffer.clear();
+    assert!(buffer.can_transform("base", "laser", at(1.0)));
+    assert!(matches!(
+        error_kind(buffer.lookup_transform("world", "laser", at(100.0))),
+        ErrorKind::UnknownFrame(frame) if frame == "world"
+    ));
+}
+
+#[test]
+fn drops_transforms_past_cache_time() {
+    let buffer = Buffer::with_cache_time(Duration::from_seconds(1));
+    buffer
+        .set_transform(&transform("world", "base", at(10.0), [0.0; 3], 0.0), false)
+        .unwrap();
+    buffer
+        .set_transform(&transform("world", "base", at(12.0), [0.0; 3], 0.0), false)
+        .unwrap();
+    assert!(!buffer.can_transform("world", "base", at(10.0)));
+
+    let err = buffer
+        .set_transform(&transform("world", "base", at(10.5), [0.0; 3], 0.0), false)
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::OldData(..)));
+
+    // Late data within the cache time gets sorted in
+    buffer
+        .set_transform(
+            &transform("world", "base", at(11.5), [1.0, 0.0, 0.0], 0.0),
+            false,
+        )
+        .unwrap();
+    let late = buffer.lookup_transform("world", "base", at(11.75)).unwrap();
+    assert_transform(&late, [0.5, 0.0, 0.0], 0.0);
+}
+
+#[test]
+fn looks_up_through_fixed_frame() {
+    let buffer = Buffer::new();
+    buffer
+        .set_transform(
+            &transform("odom", "base", at(1.0), [1.0, 0.0, 0.0], 0.0),
+            false,
+        )
+        .unwrap();
+    buffer
+        .set_transform(
+            &transform("odom", "base", at(2.0), [3.0, 0.0, 0.0], QUARTER),
+            false,
+        )
+        .unwrap();
+
+    // Where something seen at the first time is, relative to the robot at the second time
+    let moved = buffer
+        .lookup_transform_full("base", at(2.0), "base", at(1.0), "odom")
+        .unwrap();
+    assert_eq!(at(2.0), moved.header.stamp);
+    assert_transform(&moved, [0.0, 2.0, 0.0], -QUARTER);
+    assert!(buffer.can_transform_full("base", at(2.0), "base", at(1.5), "odom"));
+    assert!(!buffer.can_transform_full("base", at(3.0), "base", at(1.0), "odom"));
+}
+
+#[test]
+fn rejects_invalid_transforms() {
+    let buffer = Buffer::new();
+    let mut zero_rotation = transform("world", "base", at(1.0), [0.0; 3], 0.0);
+    zero_rotation.transform.rotation.w = 0.0;
+    let invalid = vec![
+        zero_rotation,
+        transform("world", "base", at(1.0), [f64::NAN, 0.0, 0.0], 0.0),
+        transform("base", "/base", at(1.0), [0.0; 3], 0.0),
+        transform("", "base", at(1.0), [0.0; 3], 0.0),
+    ];
+    for transform in &invalid {
+        let err = buffer.set_transform(transform, false).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidTransform(..)));
+    }
+    assert!(buffer.frames().is_empty());
+}
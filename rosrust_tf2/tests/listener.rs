@@ -0,0 +1,110 @@
+use lazy_static::lazy_static;
+use rosrust::api::testing::TestKit;
+use rosrust::{Duration, Time};
+use rosrust_msg::geometry_msgs::{Quaternion, Transform, TransformStamped, Vector3};
+use rosrust_msg::std_msgs::Header;
+use rosrust_msg::tf2_msgs::TFMessage;
+use rosrust_tf2::error::ErrorKind;
+use rosrust_tf2::{Buffer, TransformListener};
+use std::error::Error as _;
+use std::sync::{Mutex, MutexGuard};
+use std::time;
+
+lazy_static! {
+    static ref KIT: TestKit = {
+        let kit = TestKit::new().unwrap();
+        rosrust::try_init_with_test_kit("listener", &kit).unwrap();
+        kit
+    };
+    // Tests change the shared simulated time
+    static ref TIME_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn exclusive_time() -> MutexGuard<'static, ()> {
+    TIME_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn transform(parent: &str, child: &str, stamp: Time, x: f64) -> TransformStamped {
+    TransformStamped {
+        header: Header {
+            seq: 0,
+            stamp,
+            frame_id: parent.into(),
+        },
+        child_frame_id: child.into(),
+        transform: Transform {
+            translation: Vector3 { x, y: 0.0, z: 0.0 },
+            rotation: Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+        },
+    }
+}
+
+#[test]
+fn fills_buffer_from_topics() {
+    let _time = exclusive_time();
+    KIT.set_time(Time::from_seconds(100));
+    let broadcaster = KIT.node("broadcaster").unwrap();
+    let mut tf_static = broadcaster.publish::<TFMessage>("/tf_static", 10).unwrap();
+    tf_static.set_latching(true);
+    tf_static
+        .send(TFMessage {
+            transforms: vec![transform("base", "laser", Time::new(), 0.5)],
+        })
+        .unwrap();
+
+    let listener = TransformListener::new(Buffer::new()).unwrap();
+    let tf = broadcaster.publish::<TFMessage>("/tf", 10).unwrap();
+    tf.wait_for_subscribers(Some(time::Duration::from_secs(5)))
+        .unwrap();
+    tf.send(TFMessage {
+        transforms: vec![transform("world", "base", Time::from_seconds(100), 1.0)],
+    })
+    .unwrap();
+
+    let laser = listener
+        .buffer()
+        .lookup_transform_with_timeout(
+            "world",
+            "laser",
+            Time::from_seconds(100),
+            Duration::from_seconds(1),
+        )
+        .unwrap();
+    assert_eq!(1.5, laser.transform.translation.x);
+
+    // Restarting time drops the dynamic transforms, but keeps the static ones
+    KIT.set_time(Time::from_seconds(1));
+    assert!(!listener
+        .buffer()
+        .can_transform("world", "base", Time::new()));
+    assert!(listener
+        .buffer()
+        .can_transform("base", "laser", Time::new()));
+}
+
+#[test]
+fn times_out_with_node_clock() {
+    let _time = exclusive_time();
+    KIT.set_time(Time::from_seconds(10));
+    let buffer = Buffer::new();
+    let kit = KIT.clone();
+    let advancer = std::thread::spawn(move || {
+        std::thread::sleep(time::Duration::from_millis(100));
+        kit.advance(Duration::from_seconds(3));
+    });
+
+    let started = time::Instant::now();
+    let err = buffer
+        .lookup_transform_with_timeout("world", "base", Time::new(), Duration::from_seconds(2))
+        .unwrap_err();
+    advancer.join().unwrap();
+    assert!(started.elapsed() >= time::Duration::from_millis(100));
+    assert!(matches!(err.kind(), ErrorKind::Timeout(..)));
+    let cause = err.source().unwrap().to_string();
+    assert!(cause.contains("does not exist"), "{}", cause);
+}